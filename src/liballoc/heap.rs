@@ -15,7 +15,7 @@
                       tracing garbage collector",
             issue = "27700")]
 
-use core::{isize, usize};
+use core::{cmp, isize, ptr, usize};
 #[cfg(not(test))]
 use core::intrinsics::{size_of, min_align_of};
 
@@ -120,6 +120,212 @@ pub fn usable_size(size: usize, align: usize) -> usize {
     unsafe { __rust_usable_size(size, align) }
 }
 
+/// A memory allocator that can be registered as the program's global
+/// allocator with the `#[global_allocator]` attribute.
+///
+/// A binary crate declares a `static` of a type implementing this trait and
+/// tags it with `#[global_allocator]`. Every allocation made through `Box`,
+/// `Vec` and the other standard collections is then routed through that
+/// value instead of the default allocator crate:
+///
+/// ```rust,ignore
+/// use std::heap::{GlobalAlloc, System};
+///
+/// struct MyAllocator;
+///
+/// unsafe impl GlobalAlloc for MyAllocator {
+///     unsafe fn alloc(&self, size: usize, align: usize) -> *mut u8 {
+///         System.alloc(size, align)
+///     }
+///
+///     unsafe fn dealloc(&self, ptr: *mut u8, size: usize, align: usize) {
+///         System.dealloc(ptr, size, align)
+///     }
+/// }
+///
+/// #[global_allocator]
+/// static GLOBAL: MyAllocator = MyAllocator;
+/// ```
+///
+/// Only one global allocator may be linked into a program, so a crate
+/// declaring one cannot also link an `#![allocator]` crate such as
+/// `alloc_system` or `alloc_jemalloc`.
+///
+/// # Unsafety
+///
+/// The methods have the same contracts as the free functions of this module:
+/// sizes are never zero, alignments are powers of two, and `ptr`, `size` and
+/// `align` passed to `dealloc` and the reallocation methods describe a block
+/// previously returned by this allocator. Implementations must not unwind,
+/// and must not allocate through the global allocator themselves.
+#[stable(feature = "global_allocator", since = "1.10.0")]
+pub unsafe trait GlobalAlloc {
+    /// Returns a pointer to `size` bytes of memory aligned to `align`, or a
+    /// null pointer on failure.
+    #[stable(feature = "global_allocator", since = "1.10.0")]
+    unsafe fn alloc(&self, size: usize, align: usize) -> *mut u8;
+
+    /// Deallocates the memory referenced by `ptr`.
+    #[stable(feature = "global_allocator", since = "1.10.0")]
+    unsafe fn dealloc(&self, ptr: *mut u8, size: usize, align: usize);
+
+    /// Resizes the allocation referenced by `ptr` to `new_size` bytes,
+    /// returning a null pointer and leaving the allocation intact on failure.
+    ///
+    /// The default implementation allocates a new block, copies the contents
+    /// and frees the old block.
+    #[stable(feature = "global_allocator", since = "1.10.0")]
+    unsafe fn realloc(&self, ptr: *mut u8, size: usize, new_size: usize, align: usize)
+                      -> *mut u8 {
+        let new_ptr = self.alloc(new_size, align);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, cmp::min(size, new_size));
+            self.dealloc(ptr, size, align);
+        }
+        new_ptr
+    }
+
+    /// Attempts to resize the allocation referenced by `ptr` to `new_size`
+    /// bytes without moving it, returning the usable size of the block
+    /// afterwards as `reallocate_inplace` does.
+    ///
+    /// The default implementation never resizes and returns `size`.
+    #[stable(feature = "global_allocator", since = "1.10.0")]
+    unsafe fn realloc_in_place(&self, _ptr: *mut u8, size: usize, _new_size: usize,
+                               _align: usize) -> usize {
+        size
+    }
+
+    /// Returns the usable size of an allocation created with the specified
+    /// `size` and `align`.
+    ///
+    /// The default implementation returns `size`.
+    #[stable(feature = "global_allocator", since = "1.10.0")]
+    fn usable_size(&self, size: usize, _align: usize) -> usize {
+        size
+    }
+}
+
+/// An allocator that collections can be parameterized over in place of the
+/// global heap.
+///
+/// `RawVec`, `Vec` and `BTreeMap` take an allocator type parameter that
+/// defaults to `Heap`, so existing code keeps using the global allocator,
+/// while `Vec<T, arena::ArenaAlloc<u64>>` keeps its buffer in an arena.
+/// Collections own their allocator value and call it through `&mut self`,
+/// so shared allocators such as arenas are handles that can be cloned.
+///
+/// # Unsafety
+///
+/// The methods have the same contracts as the free functions of this module.
+/// Memory returned by `alloc` must stay valid until it is passed back to
+/// `dealloc` or `realloc` on the same allocator (or a copy of it), even if the
+/// allocator value itself is moved.
+///
+/// The destructors of collections free memory through their allocator but are
+/// treated by the drop checker as not touching borrowed data, which is why
+/// collections require their allocator to be `'static`.
+#[unstable(feature = "allocator_api", issue = "0")]
+pub unsafe trait Alloc {
+    /// Returns a pointer to `size` bytes of memory aligned to `align`, or a
+    /// null pointer on failure.
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8;
+
+    /// Deallocates the memory referenced by `ptr`.
+    unsafe fn dealloc(&mut self, ptr: *mut u8, size: usize, align: usize);
+
+    /// Resizes the allocation referenced by `ptr` to `new_size` bytes,
+    /// returning a null pointer and leaving the allocation intact on failure.
+    ///
+    /// The default implementation allocates a new block, copies the contents
+    /// and frees the old block.
+    unsafe fn realloc(&mut self, ptr: *mut u8, size: usize, new_size: usize, align: usize)
+                      -> *mut u8 {
+        let new_ptr = self.alloc(new_size, align);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, cmp::min(size, new_size));
+            self.dealloc(ptr, size, align);
+        }
+        new_ptr
+    }
+
+    /// Attempts to resize the allocation referenced by `ptr` to `new_size`
+    /// bytes without moving it, returning the usable size of the block
+    /// afterwards as `reallocate_inplace` does.
+    ///
+    /// The default implementation never resizes and returns `size`.
+    unsafe fn realloc_in_place(&mut self, _ptr: *mut u8, size: usize, _new_size: usize,
+                               _align: usize) -> usize {
+        size
+    }
+
+    /// Called by collections when an allocation fails and they have no way of
+    /// reporting the error. The default implementation calls `oom`.
+    fn oom(&mut self) -> ! {
+        ::oom()
+    }
+}
+
+/// The global heap, i.e. the allocator behind the free functions of this
+/// module. This is the default allocator of the standard collections.
+#[derive(Copy, Clone, Default, Debug)]
+#[unstable(feature = "allocator_api", issue = "0")]
+pub struct Heap;
+
+#[unstable(feature = "allocator_api", issue = "0")]
+unsafe impl Alloc for Heap {
+    #[inline]
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8 {
+        allocate(size, align)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, ptr: *mut u8, size: usize, align: usize) {
+        deallocate(ptr, size, align)
+    }
+
+    #[inline]
+    unsafe fn realloc(&mut self, ptr: *mut u8, size: usize, new_size: usize, align: usize)
+                      -> *mut u8 {
+        reallocate(ptr, size, new_size, align)
+    }
+
+    #[inline]
+    unsafe fn realloc_in_place(&mut self, ptr: *mut u8, size: usize, new_size: usize,
+                               align: usize) -> usize {
+        reallocate_inplace(ptr, size, new_size, align)
+    }
+}
+
+#[unstable(feature = "allocator_api", issue = "0")]
+unsafe impl<'a, A: Alloc + ?Sized> Alloc for &'a mut A {
+    #[inline]
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8 {
+        (**self).alloc(size, align)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&mut self, ptr: *mut u8, size: usize, align: usize) {
+        (**self).dealloc(ptr, size, align)
+    }
+
+    #[inline]
+    unsafe fn realloc(&mut self, ptr: *mut u8, size: usize, new_size: usize, align: usize)
+                      -> *mut u8 {
+        (**self).realloc(ptr, size, new_size, align)
+    }
+
+    #[inline]
+    unsafe fn realloc_in_place(&mut self, ptr: *mut u8, size: usize, new_size: usize,
+                               align: usize) -> usize {
+        (**self).realloc_in_place(ptr, size, new_size, align)
+    }
+
+    fn oom(&mut self) -> ! {
+        (**self).oom()
+    }
+}
+
 /// An arbitrary non-null address to represent zero-size allocations.
 ///
 /// This preserves the non-null invariant for types like `Box<T>`. The address
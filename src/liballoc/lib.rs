@@ -84,6 +84,7 @@
 #![feature(needs_allocator)]
 #![feature(optin_builtin_traits)]
 #![feature(placement_in_syntax)]
+#![feature(question_mark)]
 #![feature(shared)]
 #![feature(staged_api)]
 #![feature(unboxed_closures)]
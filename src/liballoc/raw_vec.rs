@@ -11,11 +11,42 @@
 use core::ptr::Unique;
 use core::mem;
 use core::slice;
-use heap;
+use heap::{self, Alloc, Heap};
 use super::oom;
 use super::boxed::Box;
 use core::ops::Drop;
 use core::cmp;
+use core::fmt;
+
+/// The error returned by the fallible `try_reserve` family of methods when
+/// a collection could not grow.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+pub enum CollectionAllocErr {
+    /// The computed capacity exceeded the collection's maximum, usually
+    /// `isize::MAX` bytes.
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    CapacityOverflow,
+    /// The allocator returned an error.
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    AllocErr,
+}
+
+#[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+impl fmt::Display for CollectionAllocErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CollectionAllocErr::CapacityOverflow => {
+                f.write_str("memory allocation failed because the computed capacity \
+                             exceeded the collection's maximum")
+            }
+            CollectionAllocErr::AllocErr => {
+                f.write_str("memory allocation failed because the memory allocator \
+                             returned an error")
+            }
+        }
+    }
+}
 
 /// A low-level utility for more ergonomically allocating, reallocating, and deallocating a
 /// a buffer of memory on the heap without having to worry about all the corner cases
@@ -25,9 +56,9 @@ use core::cmp;
 /// * Produces heap::EMPTY on zero-sized types
 /// * Produces heap::EMPTY on zero-length allocations
 /// * Catches all overflows in capacity computations (promotes them to "capacity overflow" panics)
-/// * Guards against 32-bit systems allocating more than isize::MAX bytes
+/// * Guards against allocating more than isize::MAX bytes
 /// * Guards against overflowing your length
-/// * Aborts on OOM
+/// * Aborts on OOM, unless the `try_reserve` family of methods is used
 /// * Avoids freeing heap::EMPTY
 /// * Contains a ptr::Unique and thus endows the user with all related benefits
 ///
@@ -44,10 +75,17 @@ use core::cmp;
 /// `shrink_to_fit`, and `from_box` will actually set RawVec's private capacity
 /// field. This allows zero-sized types to not be special-cased by consumers of
 /// this type.
+///
+/// The buffer is obtained from the allocator `A`, which defaults to the
+/// global heap. Only a `RawVec` using `Heap` can be converted to and from a
+/// `Box<[T]>`, since boxes are always freed to the global heap. The allocator
+/// must be `'static`, as the destructor frees the buffer through it while
+/// being blind to what the parameters of the `RawVec` borrow.
 #[unsafe_no_drop_flag]
-pub struct RawVec<T> {
+pub struct RawVec<T, A: Alloc + 'static = Heap> {
     ptr: Unique<T>,
     cap: usize,
+    a: A,
 }
 
 impl<T> RawVec<T> {
@@ -56,6 +94,66 @@ impl<T> RawVec<T> {
     /// it makes a RawVec with capacity `usize::MAX`. Useful for implementing
     /// delayed allocation.
     pub fn new() -> Self {
+        RawVec::new_in(Heap)
+    }
+
+    /// Creates a RawVec with exactly the capacity and alignment requirements
+    /// for a `[T; cap]`. This is equivalent to calling RawVec::new when `cap` is 0
+    /// or T is zero-sized. Note that if `T` is zero-sized this means you will *not*
+    /// get a RawVec with the requested capacity!
+    ///
+    /// # Panics
+    ///
+    /// * Panics if the requested capacity exceeds `usize::MAX` bytes.
+    /// * Panics if the requested capacity exceeds `isize::MAX` bytes.
+    ///
+    /// # Aborts
+    ///
+    /// Aborts on OOM
+    pub fn with_capacity(cap: usize) -> Self {
+        RawVec::with_capacity_in(cap, Heap)
+    }
+
+    /// Reconstitutes a RawVec from a pointer and capacity.
+    ///
+    /// # Undefined Behavior
+    ///
+    /// The ptr must be allocated, and with the given capacity. The
+    /// capacity cannot exceed `isize::MAX` (only a concern on 32-bit systems).
+    /// If the ptr and capacity come from a RawVec, then this is guaranteed.
+    pub unsafe fn from_raw_parts(ptr: *mut T, cap: usize) -> Self {
+        RawVec::from_raw_parts_in(ptr, cap, Heap)
+    }
+
+    /// Converts a `Box<[T]>` into a `RawVec<T>`.
+    pub fn from_box(mut slice: Box<[T]>) -> Self {
+        unsafe {
+            let result = RawVec::from_raw_parts(slice.as_mut_ptr(), slice.len());
+            mem::forget(slice);
+            result
+        }
+    }
+
+    /// Converts the entire buffer into `Box<[T]>`.
+    ///
+    /// While it is not *strictly* Undefined Behavior to call
+    /// this procedure while some of the RawVec is unintialized,
+    /// it cetainly makes it trivial to trigger it.
+    ///
+    /// Note that this will correctly reconstitute any `cap` changes
+    /// that may have been performed. (see description of type for details)
+    pub unsafe fn into_box(self) -> Box<[T]> {
+        // NOTE: not calling `cap()` here, actually using the real `cap` field!
+        let slice = slice::from_raw_parts_mut(self.ptr(), self.cap);
+        let output: Box<[T]> = Box::from_raw(slice);
+        mem::forget(self);
+        output
+    }
+}
+
+impl<T, A: Alloc + 'static> RawVec<T, A> {
+    /// Like `new`, but the buffer will be obtained from the given allocator.
+    pub fn new_in(a: A) -> Self {
         unsafe {
             // !0 is usize::MAX. This branch should be stripped at compile time.
             let cap = if mem::size_of::<T>() == 0 {
@@ -68,25 +166,14 @@ impl<T> RawVec<T> {
             RawVec {
                 ptr: Unique::new(heap::EMPTY as *mut T),
                 cap: cap,
+                a: a,
             }
         }
     }
 
-    /// Creates a RawVec with exactly the capacity and alignment requirements
-    /// for a `[T; cap]`. This is equivalent to calling RawVec::new when `cap` is 0
-    /// or T is zero-sized. Note that if `T` is zero-sized this means you will *not*
-    /// get a RawVec with the requested capacity!
-    ///
-    /// # Panics
-    ///
-    /// * Panics if the requested capacity exceeds `usize::MAX` bytes.
-    /// * Panics on 32-bit platforms if the requested capacity exceeds
-    ///   `isize::MAX` bytes.
-    ///
-    /// # Aborts
-    ///
-    /// Aborts on OOM
-    pub fn with_capacity(cap: usize) -> Self {
+    /// Like `with_capacity`, but the buffer will be obtained from the given
+    /// allocator.
+    pub fn with_capacity_in(cap: usize, mut a: A) -> Self {
         unsafe {
             let elem_size = mem::size_of::<T>();
 
@@ -98,9 +185,9 @@ impl<T> RawVec<T> {
                 heap::EMPTY as *mut u8
             } else {
                 let align = mem::align_of::<T>();
-                let ptr = heap::allocate(alloc_size, align);
+                let ptr = a.alloc(alloc_size, align);
                 if ptr.is_null() {
-                    oom()
+                    a.oom()
                 }
                 ptr
             };
@@ -108,35 +195,27 @@ impl<T> RawVec<T> {
             RawVec {
                 ptr: Unique::new(ptr as *mut _),
                 cap: cap,
+                a: a,
             }
         }
     }
 
-    /// Reconstitutes a RawVec from a pointer and capacity.
+    /// Reconstitutes a RawVec from a pointer, capacity and the allocator the
+    /// pointer was obtained from.
     ///
     /// # Undefined Behavior
     ///
-    /// The ptr must be allocated, and with the given capacity. The
+    /// The ptr must be allocated by `a`, and with the given capacity. The
     /// capacity cannot exceed `isize::MAX` (only a concern on 32-bit systems).
     /// If the ptr and capacity come from a RawVec, then this is guaranteed.
-    pub unsafe fn from_raw_parts(ptr: *mut T, cap: usize) -> Self {
+    pub unsafe fn from_raw_parts_in(ptr: *mut T, cap: usize, a: A) -> Self {
         RawVec {
             ptr: Unique::new(ptr),
             cap: cap,
+            a: a,
         }
     }
 
-    /// Converts a `Box<[T]>` into a `RawVec<T>`.
-    pub fn from_box(mut slice: Box<[T]>) -> Self {
-        unsafe {
-            let result = RawVec::from_raw_parts(slice.as_mut_ptr(), slice.len());
-            mem::forget(slice);
-            result
-        }
-    }
-}
-
-impl<T> RawVec<T> {
     /// Gets a raw pointer to the start of the allocation. Note that this is
     /// heap::EMPTY if `cap = 0` or T is zero-sized. In the former case, you must
     /// be careful.
@@ -155,6 +234,16 @@ impl<T> RawVec<T> {
         }
     }
 
+    /// Returns a shared reference to the allocator backing this RawVec.
+    pub fn alloc(&self) -> &A {
+        &self.a
+    }
+
+    /// Returns a mutable reference to the allocator backing this RawVec.
+    pub fn alloc_mut(&mut self) -> &mut A {
+        &mut self.a
+    }
+
     /// Doubles the size of the type's backing allocation. This is common enough
     /// to want to do that it's easiest to just have a dedicated method. Slightly
     /// more efficient logic can be provided for this than the general case.
@@ -168,8 +257,7 @@ impl<T> RawVec<T> {
     ///
     /// * Panics if T is zero-sized on the assumption that you managed to exhaust
     ///   all `usize::MAX` slots in your imaginary buffer.
-    /// * Panics on 32-bit platforms if the requested capacity exceeds
-    ///   `isize::MAX` bytes.
+    /// * Panics if the requested capacity exceeds `isize::MAX` bytes.
     ///
     /// # Aborts
     ///
@@ -214,7 +302,7 @@ impl<T> RawVec<T> {
                 } else {
                     4
                 };
-                let ptr = heap::allocate(new_cap * elem_size, align);
+                let ptr = self.a.alloc(new_cap * elem_size, align);
                 (new_cap, ptr)
             } else {
                 // Since we guarantee that we never allocate more than isize::MAX bytes,
@@ -222,16 +310,16 @@ impl<T> RawVec<T> {
                 let new_cap = 2 * self.cap;
                 let new_alloc_size = new_cap * elem_size;
                 alloc_guard(new_alloc_size);
-                let ptr = heap::reallocate(self.ptr() as *mut _,
-                                           self.cap * elem_size,
-                                           new_alloc_size,
-                                           align);
+                let ptr = self.a.realloc(self.ptr() as *mut _,
+                                         self.cap * elem_size,
+                                         new_alloc_size,
+                                         align);
                 (new_cap, ptr)
             };
 
             // If allocate or reallocate fail, we'll get `null` back
             if ptr.is_null() {
-                oom()
+                self.a.oom()
             }
 
             self.ptr = Unique::new(ptr as *mut _);
@@ -249,8 +337,7 @@ impl<T> RawVec<T> {
     ///
     /// * Panics if T is zero-sized on the assumption that you managed to exhaust
     ///   all `usize::MAX` slots in your imaginary buffer.
-    /// * Panics on 32-bit platforms if the requested capacity exceeds
-    ///   `isize::MAX` bytes.
+    /// * Panics if the requested capacity exceeds `isize::MAX` bytes.
     #[inline(never)]
     #[cold]
     pub fn double_in_place(&mut self) -> bool {
@@ -268,10 +355,10 @@ impl<T> RawVec<T> {
             let new_alloc_size = new_cap * elem_size;
 
             alloc_guard(new_alloc_size);
-            let size = heap::reallocate_inplace(self.ptr() as *mut _,
-                                                self.cap * elem_size,
-                                                new_alloc_size,
-                                                align);
+            let size = self.a.realloc_in_place(self.ptr() as *mut _,
+                                               self.cap * elem_size,
+                                               new_alloc_size,
+                                               align);
             if size >= new_alloc_size {
                 // We can't directly divide `size`.
                 self.cap = new_cap;
@@ -294,66 +381,91 @@ impl<T> RawVec<T> {
     /// # Panics
     ///
     /// * Panics if the requested capacity exceeds `usize::MAX` bytes.
-    /// * Panics on 32-bit platforms if the requested capacity exceeds
-    ///   `isize::MAX` bytes.
+    /// * Panics if the requested capacity exceeds `isize::MAX` bytes.
     ///
     /// # Aborts
     ///
     /// Aborts on OOM
     pub fn reserve_exact(&mut self, used_cap: usize, needed_extra_cap: usize) {
+        match self.try_reserve_exact(used_cap, needed_extra_cap) {
+            Ok(()) => {}
+            Err(CollectionAllocErr::CapacityOverflow) => panic!("capacity overflow"),
+            Err(CollectionAllocErr::AllocErr) => self.a.oom(),
+        }
+    }
+
+    /// The same as `reserve_exact`, but returns an error instead of panicking
+    /// on capacity overflow or aborting when the allocator fails.
+    ///
+    /// On error the buffer is left untouched.
+    pub fn try_reserve_exact(&mut self, used_cap: usize, needed_extra_cap: usize)
+                             -> Result<(), CollectionAllocErr> {
         unsafe {
             let elem_size = mem::size_of::<T>();
-            let align = mem::align_of::<T>();
 
             // NOTE: we don't early branch on ZSTs here because we want this
             // to actually catch "asking for more than usize::MAX" in that case.
             // If we make it past the first branch then we are guaranteed to
-            // panic.
+            // fail.
 
             // Don't actually need any more capacity.
             // Wrapping in case they gave a bad `used_cap`.
             if self.cap().wrapping_sub(used_cap) >= needed_extra_cap {
-                return;
+                return Ok(());
             }
 
-            // Nothing we can really do about these checks :(
-            let new_cap = used_cap.checked_add(needed_extra_cap).expect("capacity overflow");
-            let new_alloc_size = new_cap.checked_mul(elem_size).expect("capacity overflow");
-            alloc_guard(new_alloc_size);
+            let new_cap = used_cap.checked_add(needed_extra_cap)
+                                  .ok_or(CollectionAllocErr::CapacityOverflow)?;
+            let new_alloc_size = new_cap.checked_mul(elem_size)
+                                        .ok_or(CollectionAllocErr::CapacityOverflow)?;
+            try_alloc_guard(new_alloc_size)?;
 
-            let ptr = if self.cap == 0 {
-                heap::allocate(new_alloc_size, align)
-            } else {
-                heap::reallocate(self.ptr() as *mut _,
-                                 self.cap * elem_size,
-                                 new_alloc_size,
-                                 align)
-            };
+            self.try_grow_to(new_cap, new_alloc_size)
+        }
+    }
 
-            // If allocate or reallocate fail, we'll get `null` back
-            if ptr.is_null() {
-                oom()
-            }
+    /// Moves the buffer into an allocation of `new_alloc_size` bytes holding
+    /// `new_cap` elements. The caller has already checked the size.
+    unsafe fn try_grow_to(&mut self, new_cap: usize, new_alloc_size: usize)
+                          -> Result<(), CollectionAllocErr> {
+        let elem_size = mem::size_of::<T>();
+        let align = mem::align_of::<T>();
 
-            self.ptr = Unique::new(ptr as *mut _);
-            self.cap = new_cap;
+        let ptr = if self.cap == 0 {
+            self.a.alloc(new_alloc_size, align)
+        } else {
+            self.a.realloc(self.ptr() as *mut _,
+                           self.cap * elem_size,
+                           new_alloc_size,
+                           align)
+        };
+
+        // If allocate or reallocate fail, we'll get `null` back, and the old
+        // allocation is still valid.
+        if ptr.is_null() {
+            return Err(CollectionAllocErr::AllocErr);
         }
+
+        self.ptr = Unique::new(ptr as *mut _);
+        self.cap = new_cap;
+        Ok(())
     }
 
     /// Calculates the buffer's new size given that it'll hold `used_cap +
     /// needed_extra_cap` elements. This logic is used in amortized reserve methods.
     /// Returns `(new_capacity, new_alloc_size)`.
-    fn amortized_new_size(&self, used_cap: usize, needed_extra_cap: usize) -> (usize, usize) {
+    fn amortized_new_size(&self, used_cap: usize, needed_extra_cap: usize)
+                          -> Result<(usize, usize), CollectionAllocErr> {
         let elem_size = mem::size_of::<T>();
-        // Nothing we can really do about these checks :(
         let required_cap = used_cap.checked_add(needed_extra_cap)
-                                   .expect("capacity overflow");
+                                   .ok_or(CollectionAllocErr::CapacityOverflow)?;
         // Cannot overflow, because `cap <= isize::MAX`, and type of `cap` is `usize`.
         let double_cap = self.cap * 2;
         // `double_cap` guarantees exponential growth.
         let new_cap = cmp::max(double_cap, required_cap);
-        let new_alloc_size = new_cap.checked_mul(elem_size).expect("capacity overflow");
-        (new_cap, new_alloc_size)
+        let new_alloc_size = new_cap.checked_mul(elem_size)
+                                    .ok_or(CollectionAllocErr::CapacityOverflow)?;
+        Ok((new_cap, new_alloc_size))
     }
 
     /// Ensures that the buffer contains at least enough space to hold
@@ -371,8 +483,7 @@ impl<T> RawVec<T> {
     /// # Panics
     ///
     /// * Panics if the requested capacity exceeds `usize::MAX` bytes.
-    /// * Panics on 32-bit platforms if the requested capacity exceeds
-    ///   `isize::MAX` bytes.
+    /// * Panics if the requested capacity exceeds `isize::MAX` bytes.
     ///
     /// # Aborts
     ///
@@ -401,41 +512,35 @@ impl<T> RawVec<T> {
     /// }
     /// ```
     pub fn reserve(&mut self, used_cap: usize, needed_extra_cap: usize) {
-        unsafe {
-            let elem_size = mem::size_of::<T>();
-            let align = mem::align_of::<T>();
+        match self.try_reserve(used_cap, needed_extra_cap) {
+            Ok(()) => {}
+            Err(CollectionAllocErr::CapacityOverflow) => panic!("capacity overflow"),
+            Err(CollectionAllocErr::AllocErr) => self.a.oom(),
+        }
+    }
 
+    /// The same as `reserve`, but returns an error instead of panicking on
+    /// capacity overflow or aborting when the allocator fails.
+    ///
+    /// On error the buffer is left untouched.
+    pub fn try_reserve(&mut self, used_cap: usize, needed_extra_cap: usize)
+                       -> Result<(), CollectionAllocErr> {
+        unsafe {
             // NOTE: we don't early branch on ZSTs here because we want this
             // to actually catch "asking for more than usize::MAX" in that case.
             // If we make it past the first branch then we are guaranteed to
-            // panic.
+            // fail.
 
             // Don't actually need any more capacity.
             // Wrapping in case they give a bad `used_cap`
             if self.cap().wrapping_sub(used_cap) >= needed_extra_cap {
-                return;
+                return Ok(());
             }
 
-            let (new_cap, new_alloc_size) = self.amortized_new_size(used_cap, needed_extra_cap);
-            // FIXME: may crash and burn on over-reserve
-            alloc_guard(new_alloc_size);
-
-            let ptr = if self.cap == 0 {
-                heap::allocate(new_alloc_size, align)
-            } else {
-                heap::reallocate(self.ptr() as *mut _,
-                                 self.cap * elem_size,
-                                 new_alloc_size,
-                                 align)
-            };
-
-            // If allocate or reallocate fail, we'll get `null` back
-            if ptr.is_null() {
-                oom()
-            }
+            let (new_cap, new_alloc_size) = self.amortized_new_size(used_cap, needed_extra_cap)?;
+            try_alloc_guard(new_alloc_size)?;
 
-            self.ptr = Unique::new(ptr as *mut _);
-            self.cap = new_cap;
+            self.try_grow_to(new_cap, new_alloc_size)
         }
     }
 
@@ -454,8 +559,7 @@ impl<T> RawVec<T> {
     /// # Panics
     ///
     /// * Panics if the requested capacity exceeds `usize::MAX` bytes.
-    /// * Panics on 32-bit platforms if the requested capacity exceeds
-    ///   `isize::MAX` bytes.
+    /// * Panics if the requested capacity exceeds `isize::MAX` bytes.
     pub fn reserve_in_place(&mut self, used_cap: usize, needed_extra_cap: usize) -> bool {
         unsafe {
             let elem_size = mem::size_of::<T>();
@@ -473,14 +577,16 @@ impl<T> RawVec<T> {
                 return false;
             }
 
-            let (_, new_alloc_size) = self.amortized_new_size(used_cap, needed_extra_cap);
-            // FIXME: may crash and burn on over-reserve
+            let new_alloc_size = match self.amortized_new_size(used_cap, needed_extra_cap) {
+                Ok((_, new_alloc_size)) => new_alloc_size,
+                Err(_) => panic!("capacity overflow"),
+            };
             alloc_guard(new_alloc_size);
 
-            let size = heap::reallocate_inplace(self.ptr() as *mut _,
-                                                self.cap * elem_size,
-                                                new_alloc_size,
-                                                align);
+            let size = self.a.realloc_in_place(self.ptr() as *mut _,
+                                               self.cap * elem_size,
+                                               new_alloc_size,
+                                               align);
             if size >= new_alloc_size {
                 self.cap = new_alloc_size / elem_size;
             }
@@ -512,17 +618,23 @@ impl<T> RawVec<T> {
         assert!(self.cap >= amount, "Tried to shrink to a larger capacity");
 
         if amount == 0 {
-            mem::replace(self, RawVec::new());
+            if self.cap != 0 {
+                unsafe {
+                    self.a.dealloc(self.ptr() as *mut _, self.cap * elem_size, align);
+                    self.ptr = Unique::new(heap::EMPTY as *mut T);
+                }
+                self.cap = 0;
+            }
         } else if self.cap != amount {
             unsafe {
                 // Overflow check is unnecessary as the vector is already at
                 // least this large.
-                let ptr = heap::reallocate(self.ptr() as *mut _,
-                                           self.cap * elem_size,
-                                           amount * elem_size,
-                                           align);
+                let ptr = self.a.realloc(self.ptr() as *mut _,
+                                         self.cap * elem_size,
+                                         amount * elem_size,
+                                         align);
                 if ptr.is_null() {
-                    oom()
+                    self.a.oom()
                 }
                 self.ptr = Unique::new(ptr as *mut _);
             }
@@ -530,22 +642,6 @@ impl<T> RawVec<T> {
         }
     }
 
-    /// Converts the entire buffer into `Box<[T]>`.
-    ///
-    /// While it is not *strictly* Undefined Behavior to call
-    /// this procedure while some of the RawVec is unintialized,
-    /// it cetainly makes it trivial to trigger it.
-    ///
-    /// Note that this will correctly reconstitute any `cap` changes
-    /// that may have been performed. (see description of type for details)
-    pub unsafe fn into_box(self) -> Box<[T]> {
-        // NOTE: not calling `cap()` here, actually using the real `cap` field!
-        let slice = slice::from_raw_parts_mut(self.ptr(), self.cap);
-        let output: Box<[T]> = Box::from_raw(slice);
-        mem::forget(self);
-        output
-    }
-
     /// This is a stupid name in the hopes that someone will find this in the
     /// not too distant future and remove it with the rest of
     /// #[unsafe_no_drop_flag]
@@ -554,7 +650,7 @@ impl<T> RawVec<T> {
     }
 }
 
-impl<T> Drop for RawVec<T> {
+impl<T, A: Alloc + 'static> Drop for RawVec<T, A> {
     #[unsafe_destructor_blind_to_params]
     /// Frees the memory owned by the RawVec *without* trying to Drop its contents.
     fn drop(&mut self) {
@@ -564,7 +660,7 @@ impl<T> Drop for RawVec<T> {
 
             let num_bytes = elem_size * self.cap;
             unsafe {
-                heap::deallocate(*self.ptr as *mut _, num_bytes, align);
+                self.a.dealloc(*self.ptr as *mut _, num_bytes, align);
             }
         }
     }
@@ -576,16 +672,25 @@ impl<T> Drop for RawVec<T> {
 // * We don't ever allocate `> isize::MAX` byte-size objects
 // * We don't overflow `usize::MAX` and actually allocate too little
 //
-// On 64-bit we just need to check for overflow since trying to allocate
-// `> isize::MAX` bytes will surely fail. On 32-bit we need to add an extra
-// guard for this in case we're running on a platform which can use all 4GB in
+// The first is checked on all targets. On 64-bit, trying to allocate
+// `> isize::MAX` bytes would surely fail anyway, but `try_reserve` has to
+// report this as a capacity overflow rather than pass such a size on to the
+// allocator. On 32-bit it also covers platforms which can use all 4GB in
 // user-space. e.g. PAE or x32
 
 #[inline]
 fn alloc_guard(alloc_size: usize) {
-    if mem::size_of::<usize>() < 8 {
-        assert!(alloc_size <= ::core::isize::MAX as usize,
-                "capacity overflow");
+    if try_alloc_guard(alloc_size).is_err() {
+        panic!("capacity overflow");
+    }
+}
+
+#[inline]
+fn try_alloc_guard(alloc_size: usize) -> Result<(), CollectionAllocErr> {
+    if alloc_size > ::core::isize::MAX as usize {
+        Err(CollectionAllocErr::CapacityOverflow)
+    } else {
+        Ok(())
     }
 }
 
@@ -626,4 +731,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn try_reserve_reports_capacity_overflow() {
+        let mut v: RawVec<u32> = RawVec::new();
+        v.reserve(0, 4);
+        assert_eq!(v.try_reserve(4, !0), Err(CollectionAllocErr::CapacityOverflow));
+        assert_eq!(v.try_reserve_exact(4, !0), Err(CollectionAllocErr::CapacityOverflow));
+        assert_eq!(v.try_reserve_exact(0, !0 / 2), Err(CollectionAllocErr::CapacityOverflow));
+        // The failed attempts leave the buffer as it was.
+        assert_eq!(4, v.cap());
+
+        assert_eq!(v.try_reserve_exact(4, 6), Ok(()));
+        assert_eq!(10, v.cap());
+        assert_eq!(v.try_reserve(10, 1), Ok(()));
+        assert!(v.cap() >= 11);
+    }
+
+    #[test]
+    fn try_reserve_over_isize_max() {
+        let mut v: RawVec<u8> = RawVec::new();
+        let too_big = ::core::isize::MAX as usize + 1;
+        assert_eq!(v.try_reserve_exact(0, too_big), Err(CollectionAllocErr::CapacityOverflow));
+        assert_eq!(v.try_reserve(0, too_big), Err(CollectionAllocErr::CapacityOverflow));
+        assert_eq!(0, v.cap());
+    }
+
 }
@@ -30,6 +30,7 @@
 #![cfg_attr(not(stage0), deny(warnings))]
 
 #![feature(alloc)]
+#![feature(allocator_api)]
 #![feature(core_intrinsics)]
 #![feature(heap_api)]
 #![feature(heap_api)]
@@ -47,8 +48,9 @@ use std::intrinsics;
 use std::marker::{PhantomData, Send};
 use std::mem;
 use std::ptr;
+use std::rc::Rc;
 
-use alloc::heap;
+use alloc::heap::{self, Alloc};
 use alloc::raw_vec::RawVec;
 
 /// A faster arena that can hold objects of only one type.
@@ -144,7 +146,7 @@ impl<T> TypedArena<T> {
     #[inline]
     pub fn alloc(&self, object: T) -> &mut T {
         if self.ptr == self.end {
-            self.grow()
+            self.grow(1)
         }
 
         unsafe {
@@ -165,22 +167,47 @@ impl<T> TypedArena<T> {
         }
     }
 
-    /// Grows the arena.
+    /// Allocates uninitialized space for `len` contiguous objects of a
+    /// non-zero-sized `T`.
+    ///
+    /// Chunks other than the last one are assumed to be full when the arena
+    /// is cleared or dropped, so this may only be used if `T` needs no drop.
+    unsafe fn alloc_raw_slice(&self, len: usize) -> *mut T {
+        if self.remaining() < len {
+            self.grow(len)
+        }
+        let start = self.ptr.get();
+        self.ptr.set(start.offset(len as isize));
+        start
+    }
+
+    // Returns the number of objects that fit before the end of the last chunk.
+    #[inline]
+    fn remaining(&self) -> usize {
+        (self.end.get() as usize - self.ptr.get() as usize) / cmp::max(1, mem::size_of::<T>())
+    }
+
+    /// Grows the arena so that at least `n` more objects fit.
     #[inline(never)]
     #[cold]
-    fn grow(&self) {
+    fn grow(&self, n: usize) {
         unsafe {
             let mut chunks = self.chunks.borrow_mut();
             let prev_capacity = chunks.last().unwrap().storage.cap();
-            let new_capacity = prev_capacity.checked_mul(2).unwrap();
+            let mut new_capacity = prev_capacity.checked_mul(2).unwrap();
             if chunks.last_mut().unwrap().storage.double_in_place() {
                 self.end.set(chunks.last().unwrap().end());
-            } else {
-                let chunk = TypedArenaChunk::<T>::new(new_capacity);
-                self.ptr.set(chunk.start());
-                self.end.set(chunk.end());
-                chunks.push(chunk);
+                if self.remaining() >= n {
+                    return;
+                }
+            }
+            while new_capacity < n {
+                new_capacity = new_capacity.checked_mul(2).unwrap();
             }
+            let chunk = TypedArenaChunk::<T>::new(new_capacity);
+            self.ptr.set(chunk.start());
+            self.end.set(chunk.end());
+            chunks.push(chunk);
         }
     }
     /// Clears the arena. Deallocates all but the longest chunk which may be reused.
@@ -245,11 +272,63 @@ impl<T> Drop for TypedArena<T> {
 
 unsafe impl<T: Send> Send for TypedArena<T> {}
 
+/// A handle to a shared `TypedArena`, which can back a collection such as
+/// `Vec<U, ArenaAlloc<T>>`.
+///
+/// Every clone of the handle keeps the arena alive, so memory handed out by
+/// it stays valid for as long as a collection using it does. Allocations are
+/// rounded up to a whole number of `T`s and are only freed when the arena
+/// goes away. Requests for an alignment greater than that of `T`, or from an
+/// arena of zero-sized `T`, fail.
+pub struct ArenaAlloc<T> {
+    arena: Rc<TypedArena<T>>,
+}
+
+impl<T: Copy> ArenaAlloc<T> {
+    /// Creates a new arena and a handle to it.
+    pub fn new() -> ArenaAlloc<T> {
+        ArenaAlloc { arena: Rc::new(TypedArena::new()) }
+    }
+
+    /// Creates a new arena with preallocated space for `capacity` objects,
+    /// and a handle to it.
+    pub fn with_capacity(capacity: usize) -> ArenaAlloc<T> {
+        ArenaAlloc { arena: Rc::new(TypedArena::with_capacity(capacity)) }
+    }
+
+    /// Returns the arena, which objects may be allocated in directly too.
+    pub fn arena(&self) -> &TypedArena<T> {
+        &self.arena
+    }
+}
+
+impl<T> Clone for ArenaAlloc<T> {
+    fn clone(&self) -> ArenaAlloc<T> {
+        ArenaAlloc { arena: self.arena.clone() }
+    }
+}
+
+unsafe impl<T: Copy> Alloc for ArenaAlloc<T> {
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8 {
+        let elem_size = mem::size_of::<T>();
+        if elem_size == 0 || align > mem::align_of::<T>() {
+            return ptr::null_mut();
+        }
+        match size.checked_add(elem_size - 1) {
+            Some(rounded) => self.arena.alloc_raw_slice(rounded / elem_size) as *mut u8,
+            None => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&mut self, _ptr: *mut u8, _size: usize, _align: usize) {}
+}
+
 #[cfg(test)]
 mod tests {
     extern crate test;
     use self::test::Bencher;
-    use super::TypedArena;
+    use super::{ArenaAlloc, TypedArena};
+    use alloc::heap::Alloc;
     use std::cell::Cell;
 
     #[allow(dead_code)]
@@ -360,6 +439,31 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_typed_arena_as_allocator() {
+        let alloc: ArenaAlloc<u64> = ArenaAlloc::with_capacity(4);
+        let mut v = Vec::new_in(alloc.clone());
+        for i in 0..1000u32 {
+            v.push(i);
+        }
+        assert!(v.iter().cloned().eq(0..1000));
+
+        let mut w = Vec::with_capacity_in(3, alloc.clone());
+        w.extend_from_slice(&[1u8, 2, 3]);
+        assert_eq!(w, [1, 2, 3]);
+
+        // The vectors keep the arena alive.
+        drop(alloc);
+        v.push(1000);
+        assert_eq!(v[1000], 1000);
+
+        // `u64` alignment is the most the arena can provide.
+        let mut a = ArenaAlloc::<u64>::new();
+        unsafe {
+            assert!(Alloc::alloc(&mut a, 8, 16).is_null());
+        }
+    }
+
     // Drop tests
 
     struct DropCounter<'a> {
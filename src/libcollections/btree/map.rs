@@ -16,6 +16,7 @@ use core::marker::PhantomData;
 use core::ops::Index;
 use core::{fmt, intrinsics, mem, ptr};
 
+use alloc::heap::{Alloc, Heap};
 use borrow::Borrow;
 use Bound::{self, Included, Excluded, Unbounded};
 
@@ -126,13 +127,22 @@ use self::Entry::*;
 /// let stat = player_stats.entry("attack").or_insert(100);
 /// *stat += random_stat_buff();
 /// ```
+///
+/// # Allocators
+///
+/// The nodes of a `BTreeMap<K, V>` live on the global heap. A `BTreeMap<K, V, A>` instead
+/// obtains and frees them through the allocator `A`, which the map owns; see `new_in`. As with
+/// `Vec`, the allocator must not borrow anything, since the destructor of the map frees the
+/// nodes through it. Cloning a map clones its allocator, and nodes of the clone may be handed
+/// back to the original, so clones of an allocator must be able to free each other's memory.
 #[stable(feature = "rust1", since = "1.0.0")]
-pub struct BTreeMap<K, V> {
+pub struct BTreeMap<K, V, A: Alloc + 'static = Heap> {
     root: node::Root<K, V>,
-    length: usize
+    length: usize,
+    alloc: A,
 }
 
-impl<K, V> Drop for BTreeMap<K, V> {
+impl<K, V, A: Alloc + 'static> Drop for BTreeMap<K, V, A> {
     #[unsafe_destructor_blind_to_params]
     fn drop(&mut self) {
         unsafe {
@@ -141,17 +151,20 @@ impl<K, V> Drop for BTreeMap<K, V> {
     }
 }
 
-impl<K: Clone, V: Clone> Clone for BTreeMap<K, V> {
-    fn clone(&self) -> BTreeMap<K, V> {
-        fn clone_subtree<K: Clone, V: Clone>(
-                node: node::NodeRef<marker::Immut, K, V, marker::LeafOrInternal>)
-                -> BTreeMap<K, V> {
+impl<K: Clone, V: Clone, A: Alloc + Clone + 'static> Clone for BTreeMap<K, V, A> {
+    fn clone(&self) -> BTreeMap<K, V, A> {
+        fn clone_subtree<K: Clone, V: Clone, A: Alloc + Clone + 'static>(
+                node: node::NodeRef<marker::Immut, K, V, marker::LeafOrInternal>,
+                alloc: &A)
+                -> BTreeMap<K, V, A> {
 
             match node.force() {
                 Leaf(leaf) => {
+                    let mut alloc = alloc.clone();
                     let mut out_tree = BTreeMap {
-                        root: node::Root::new_leaf(),
-                        length: 0
+                        root: node::Root::new_leaf(&mut alloc),
+                        length: 0,
+                        alloc: alloc,
                     };
 
                     {
@@ -173,10 +186,10 @@ impl<K: Clone, V: Clone> Clone for BTreeMap<K, V> {
                     out_tree
                 },
                 Internal(internal) => {
-                    let mut out_tree = clone_subtree(internal.first_edge().descend());
+                    let mut out_tree = clone_subtree(internal.first_edge().descend(), alloc);
 
                     {
-                        let mut out_node = out_tree.root.push_level();
+                        let mut out_node = out_tree.root.push_level(&mut out_tree.alloc);
                         let mut in_edge = internal.first_edge();
                         while let Ok(kv) = in_edge.right_kv() {
                             let (k, v) = kv.into_kv();
@@ -184,16 +197,18 @@ impl<K: Clone, V: Clone> Clone for BTreeMap<K, V> {
 
                             let k = (*k).clone();
                             let v = (*v).clone();
-                            let subtree = clone_subtree(in_edge.descend());
+                            let subtree = clone_subtree(in_edge.descend(), alloc);
 
                             // We can't destructure subtree directly
                             // because BTreeMap implements Drop
-                            let (subroot, sublength) = unsafe {
+                            let (subroot, sublength, suballoc) = unsafe {
                                 let root = ptr::read(&subtree.root);
                                 let length = subtree.length;
+                                let alloc = ptr::read(&subtree.alloc);
                                 mem::forget(subtree);
-                                (root, length)
+                                (root, length, alloc)
                             };
+                            drop(suballoc);
 
                             out_node.push(k, v, subroot);
                             out_tree.length += 1 + sublength;
@@ -205,11 +220,11 @@ impl<K: Clone, V: Clone> Clone for BTreeMap<K, V> {
             }
         }
 
-        clone_subtree(self.root.as_ref())
+        clone_subtree(self.root.as_ref(), &self.alloc)
     }
 }
 
-impl<K, Q: ?Sized> super::Recover<Q> for BTreeMap<K, ()>
+impl<K, Q: ?Sized, A: Alloc + 'static> super::Recover<Q> for BTreeMap<K, (), A>
     where K: Borrow<Q> + Ord,
           Q: Ord
 {
@@ -228,6 +243,7 @@ impl<K, Q: ?Sized> super::Recover<Q> for BTreeMap<K, ()>
                 Some(OccupiedEntry {
                     handle: handle,
                     length: &mut self.length,
+                    alloc: &mut self.alloc,
                     _marker: PhantomData,
                 }.remove_kv().0)
             },
@@ -243,6 +259,7 @@ impl<K, Q: ?Sized> super::Recover<Q> for BTreeMap<K, ()>
                     key: key,
                     handle: handle,
                     length: &mut self.length,
+                    alloc: &mut self.alloc,
                     _marker: PhantomData,
                 }.insert(());
                 None
@@ -267,10 +284,11 @@ pub struct IterMut<'a, K: 'a, V: 'a> {
 
 /// An owning iterator over a BTreeMap's entries.
 #[stable(feature = "rust1", since = "1.0.0")]
-pub struct IntoIter<K, V> {
+pub struct IntoIter<K, V, A: Alloc = Heap> {
     front: Handle<NodeRef<marker::Owned, K, V, marker::Leaf>, marker::Edge>,
     back: Handle<NodeRef<marker::Owned, K, V, marker::Leaf>, marker::Edge>,
-    length: usize
+    length: usize,
+    alloc: A,
 }
 
 /// An iterator over a BTreeMap's keys.
@@ -308,26 +326,27 @@ pub struct RangeMut<'a, K: 'a, V: 'a> {
 
 /// A view into a single entry in a map, which may either be vacant or occupied.
 #[stable(feature = "rust1", since = "1.0.0")]
-pub enum Entry<'a, K: 'a, V: 'a> {
+pub enum Entry<'a, K: 'a, V: 'a, A: 'a + Alloc = Heap> {
     /// A vacant Entry
     #[stable(feature = "rust1", since = "1.0.0")]
     Vacant(
-        #[stable(feature = "rust1", since = "1.0.0")] VacantEntry<'a, K, V>
+        #[stable(feature = "rust1", since = "1.0.0")] VacantEntry<'a, K, V, A>
     ),
 
     /// An occupied Entry
     #[stable(feature = "rust1", since = "1.0.0")]
     Occupied(
-        #[stable(feature = "rust1", since = "1.0.0")] OccupiedEntry<'a, K, V>
+        #[stable(feature = "rust1", since = "1.0.0")] OccupiedEntry<'a, K, V, A>
     ),
 }
 
 /// A vacant Entry.
 #[stable(feature = "rust1", since = "1.0.0")]
-pub struct VacantEntry<'a, K: 'a, V: 'a> {
+pub struct VacantEntry<'a, K: 'a, V: 'a, A: 'a + Alloc = Heap> {
     key: K,
     handle: Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge>,
     length: &'a mut usize,
+    alloc: &'a mut A,
 
     // Be invariant in `K` and `V`
     _marker: PhantomData<&'a mut (K, V)>,
@@ -335,7 +354,7 @@ pub struct VacantEntry<'a, K: 'a, V: 'a> {
 
 /// An occupied Entry.
 #[stable(feature = "rust1", since = "1.0.0")]
-pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
+pub struct OccupiedEntry<'a, K: 'a, V: 'a, A: 'a + Alloc = Heap> {
     handle: Handle<NodeRef<
         marker::Mut<'a>,
         K, V,
@@ -343,6 +362,7 @@ pub struct OccupiedEntry<'a, K: 'a, V: 'a> {
     >, marker::KV>,
 
     length: &'a mut usize,
+    alloc: &'a mut A,
 
     // Be invariant in `K` and `V`
     _marker: PhantomData<&'a mut (K, V)>,
@@ -371,12 +391,40 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
     pub fn new() -> BTreeMap<K, V> {
+        BTreeMap::new_in(Heap)
+    }
+}
+
+impl<K: Ord, V, A: Alloc + 'static> BTreeMap<K, V, A> {
+    /// Makes a new empty BTreeMap whose nodes are obtained from the allocator `a`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(allocator_api)]
+    ///
+    /// use std::collections::BTreeMap;
+    /// use std::heap::Heap;
+    ///
+    /// let mut map: BTreeMap<i32, &str, Heap> = BTreeMap::new_in(Heap);
+    /// map.insert(1, "a");
+    /// assert_eq!(map[&1], "a");
+    /// ```
+    #[unstable(feature = "allocator_api", issue = "0")]
+    pub fn new_in(mut a: A) -> BTreeMap<K, V, A> {
         BTreeMap {
-            root: node::Root::new_leaf(),
-            length: 0
+            root: node::Root::new_leaf(&mut a),
+            length: 0,
+            alloc: a,
         }
     }
 
+    /// Returns a reference to the allocator backing this map.
+    #[unstable(feature = "allocator_api", issue = "0")]
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
     /// Clears the map, removing all values.
     ///
     /// # Examples
@@ -394,7 +442,9 @@ impl<K: Ord, V> BTreeMap<K, V> {
     #[stable(feature = "rust1", since = "1.0.0")]
     pub fn clear(&mut self) {
         // FIXME(gereeter) .clear() allocates
-        *self = BTreeMap::new();
+        let root = mem::replace(&mut self.root, node::Root::new_leaf(&mut self.alloc));
+        let length = mem::replace(&mut self.length, 0);
+        drop(IntoIter::new(root, length, &mut self.alloc));
     }
 
     /// Returns a reference to the value corresponding to the key.
@@ -534,6 +584,7 @@ impl<K: Ord, V> BTreeMap<K, V> {
                 Some(OccupiedEntry {
                     handle: handle,
                     length: &mut self.length,
+                    alloc: &mut self.alloc,
                     _marker: PhantomData,
                 }.remove())
             },
@@ -572,7 +623,7 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// ```
     #[unstable(feature = "btree_append", reason = "recently added as part of collections reform 2",
                issue = "19986")]
-    pub fn append(&mut self, other: &mut Self) {
+    pub fn append(&mut self, other: &mut Self) where A: Clone {
         // Do we have to append anything at all?
         if other.len() == 0 {
             return;
@@ -585,8 +636,10 @@ impl<K: Ord, V> BTreeMap<K, V> {
         }
 
         // First, we merge `self` and `other` into a sorted sequence in linear time.
-        let self_iter = mem::replace(self, BTreeMap::new()).into_iter();
-        let other_iter = mem::replace(other, BTreeMap::new()).into_iter();
+        let self_alloc = self.alloc.clone();
+        let other_alloc = other.alloc.clone();
+        let self_iter = mem::replace(self, BTreeMap::new_in(self_alloc)).into_iter();
+        let other_iter = mem::replace(other, BTreeMap::new_in(other_alloc)).into_iter();
         let iter = MergeIter {
             left: self_iter.peekable(),
             right: other_iter.peekable(),
@@ -771,17 +824,19 @@ impl<K: Ord, V> BTreeMap<K, V> {
     /// assert_eq!(count["a"], 3);
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
-    pub fn entry(&mut self, key: K) -> Entry<K, V> {
+    pub fn entry(&mut self, key: K) -> Entry<K, V, A> {
         match search::search_tree(self.root.as_mut(), &key) {
             Found(handle) => Occupied(OccupiedEntry {
                 handle: handle,
                 length: &mut self.length,
+                alloc: &mut self.alloc,
                 _marker: PhantomData,
             }),
             GoDown(handle) => Vacant(VacantEntry {
                 key: key,
                 handle: handle,
                 length: &mut self.length,
+                alloc: &mut self.alloc,
                 _marker: PhantomData,
             })
         }
@@ -813,7 +868,7 @@ impl<K: Ord, V> BTreeMap<K, V> {
                         },
                         Err(node) => {
                             // We are at the top, create a new root node and push there.
-                            open_node = node.into_root_mut().push_level();
+                            open_node = node.into_root_mut().push_level(&mut self.alloc);
                             break;
                         },
                     }
@@ -821,9 +876,9 @@ impl<K: Ord, V> BTreeMap<K, V> {
 
                 // Push key-value pair and new right subtree.
                 let tree_height = open_node.height() - 1;
-                let mut right_tree = node::Root::new_leaf();
+                let mut right_tree = node::Root::new_leaf(&mut self.alloc);
                 for _ in 0..tree_height {
-                    right_tree.push_level();
+                    right_tree.push_level(&mut self.alloc);
                 }
                 open_node.push(key, value, right_tree);
 
@@ -858,7 +913,7 @@ impl<K: Ord, V> BTreeMap<K, V> {
     }
 }
 
-impl<'a, K: 'a, V: 'a> IntoIterator for &'a BTreeMap<K, V> {
+impl<'a, K: 'a, V: 'a, A: Alloc + 'static> IntoIterator for &'a BTreeMap<K, V, A> {
     type Item = (&'a K, &'a V);
     type IntoIter = Iter<'a, K, V>;
 
@@ -908,7 +963,7 @@ impl<'a, K, V> Clone for Iter<'a, K, V> {
     }
 }
 
-impl<'a, K: 'a, V: 'a> IntoIterator for &'a mut BTreeMap<K, V> {
+impl<'a, K: 'a, V: 'a, A: Alloc + 'static> IntoIterator for &'a mut BTreeMap<K, V, A> {
     type Item = (&'a K, &'a mut V);
     type IntoIter = IterMut<'a, K, V>;
 
@@ -949,32 +1004,43 @@ impl<'a, K: 'a, V: 'a> ExactSizeIterator for IterMut<'a, K, V> {
     fn len(&self) -> usize { self.length }
 }
 
-impl<K, V> IntoIterator for BTreeMap<K, V> {
+impl<K, V, A: Alloc + 'static> IntoIterator for BTreeMap<K, V, A> {
     type Item = (K, V);
-    type IntoIter = IntoIter<K, V>;
+    type IntoIter = IntoIter<K, V, A>;
 
-    fn into_iter(self) -> IntoIter<K, V> {
-        let root1 = unsafe { ptr::read(&self.root).into_ref() };
-        let root2 = unsafe { ptr::read(&self.root).into_ref() };
-        let len = self.length;
+    fn into_iter(self) -> IntoIter<K, V, A> {
+        let (root, len, alloc) = unsafe {
+            (ptr::read(&self.root), self.length, ptr::read(&self.alloc))
+        };
         mem::forget(self);
 
+        IntoIter::new(root, len, alloc)
+    }
+}
+
+impl<K, V, A: Alloc> IntoIter<K, V, A> {
+    /// Takes ownership of the tree under `root`, whose nodes were obtained from `alloc`.
+    fn new(root: node::Root<K, V>, length: usize, alloc: A) -> IntoIter<K, V, A> {
+        let root1 = root.into_ref();
+        let root2 = unsafe { ptr::read(&root1) };
+
         IntoIter {
             front: first_leaf_edge(root1),
             back: last_leaf_edge(root2),
-            length: len
+            length: length,
+            alloc: alloc,
         }
     }
 }
 
-impl<K, V> Drop for IntoIter<K, V> {
+impl<K, V, A: Alloc> Drop for IntoIter<K, V, A> {
     fn drop(&mut self) {
         for _ in &mut *self { }
         unsafe {
             let leaf_node = ptr::read(&self.front).into_node();
-            if let Some(first_parent) = leaf_node.deallocate_and_ascend() {
+            if let Some(first_parent) = leaf_node.deallocate_and_ascend(&mut self.alloc) {
                 let mut cur_node = first_parent.into_node();
-                while let Some(parent) = cur_node.deallocate_and_ascend() {
+                while let Some(parent) = cur_node.deallocate_and_ascend(&mut self.alloc) {
                     cur_node = parent.into_node()
                 }
             }
@@ -982,7 +1048,7 @@ impl<K, V> Drop for IntoIter<K, V> {
     }
 }
 
-impl<K, V> Iterator for IntoIter<K, V> {
+impl<K, V, A: Alloc> Iterator for IntoIter<K, V, A> {
     type Item = (K, V);
 
     fn next(&mut self) -> Option<(K, V)> {
@@ -1002,7 +1068,7 @@ impl<K, V> Iterator for IntoIter<K, V> {
                 return Some((k, v));
             },
             Err(last_edge) => unsafe {
-                unwrap_unchecked(last_edge.into_node().deallocate_and_ascend())
+                unwrap_unchecked(last_edge.into_node().deallocate_and_ascend(&mut self.alloc))
             }
         };
 
@@ -1015,7 +1081,9 @@ impl<K, V> Iterator for IntoIter<K, V> {
                     return Some((k, v));
                 },
                 Err(last_edge) => unsafe {
-                    cur_handle = unwrap_unchecked(last_edge.into_node().deallocate_and_ascend());
+                    cur_handle = unwrap_unchecked(
+                        last_edge.into_node().deallocate_and_ascend(&mut self.alloc)
+                    );
                 }
             }
         }
@@ -1026,7 +1094,7 @@ impl<K, V> Iterator for IntoIter<K, V> {
     }
 }
 
-impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+impl<K, V, A: Alloc> DoubleEndedIterator for IntoIter<K, V, A> {
     fn next_back(&mut self) -> Option<(K, V)> {
         if self.length == 0 {
             return None;
@@ -1044,7 +1112,7 @@ impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
                 return Some((k, v));
             },
             Err(last_edge) => unsafe {
-                unwrap_unchecked(last_edge.into_node().deallocate_and_ascend())
+                unwrap_unchecked(last_edge.into_node().deallocate_and_ascend(&mut self.alloc))
             }
         };
 
@@ -1057,14 +1125,16 @@ impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
                     return Some((k, v));
                 },
                 Err(last_edge) => unsafe {
-                    cur_handle = unwrap_unchecked(last_edge.into_node().deallocate_and_ascend());
+                    cur_handle = unwrap_unchecked(
+                        last_edge.into_node().deallocate_and_ascend(&mut self.alloc)
+                    );
                 }
             }
         }
     }
 }
 
-impl<K, V> ExactSizeIterator for IntoIter<K, V> {
+impl<K, V, A: Alloc> ExactSizeIterator for IntoIter<K, V, A> {
     fn len(&self) -> usize { self.length }
 }
 
@@ -1348,7 +1418,7 @@ impl<K: Ord, V> FromIterator<(K, V)> for BTreeMap<K, V> {
     }
 }
 
-impl<K: Ord, V> Extend<(K, V)> for BTreeMap<K, V> {
+impl<K: Ord, V, A: Alloc + 'static> Extend<(K, V)> for BTreeMap<K, V, A> {
     #[inline]
     fn extend<T: IntoIterator<Item=(K, V)>>(&mut self, iter: T) {
         for (k, v) in iter {
@@ -1357,13 +1427,14 @@ impl<K: Ord, V> Extend<(K, V)> for BTreeMap<K, V> {
     }
 }
 
-impl<'a, K: Ord + Copy, V: Copy> Extend<(&'a K, &'a V)> for BTreeMap<K, V> {
+impl<'a, K: Ord + Copy, V: Copy, A: Alloc + 'static> Extend<(&'a K, &'a V)>
+        for BTreeMap<K, V, A> {
     fn extend<I: IntoIterator<Item=(&'a K, &'a V)>>(&mut self, iter: I) {
         self.extend(iter.into_iter().map(|(&key, &value)| (key, value)));
     }
 }
 
-impl<K: Hash, V: Hash> Hash for BTreeMap<K, V> {
+impl<K: Hash, V: Hash, A: Alloc + 'static> Hash for BTreeMap<K, V, A> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         for elt in self {
             elt.hash(state);
@@ -1377,36 +1448,36 @@ impl<K: Ord, V> Default for BTreeMap<K, V> {
     }
 }
 
-impl<K: PartialEq, V: PartialEq> PartialEq for BTreeMap<K, V> {
-    fn eq(&self, other: &BTreeMap<K, V>) -> bool {
+impl<K: PartialEq, V: PartialEq, A: Alloc + 'static> PartialEq for BTreeMap<K, V, A> {
+    fn eq(&self, other: &BTreeMap<K, V, A>) -> bool {
         self.len() == other.len() &&
             self.iter().zip(other).all(|(a, b)| a == b)
     }
 }
 
-impl<K: Eq, V: Eq> Eq for BTreeMap<K, V> {}
+impl<K: Eq, V: Eq, A: Alloc + 'static> Eq for BTreeMap<K, V, A> {}
 
-impl<K: PartialOrd, V: PartialOrd> PartialOrd for BTreeMap<K, V> {
+impl<K: PartialOrd, V: PartialOrd, A: Alloc + 'static> PartialOrd for BTreeMap<K, V, A> {
     #[inline]
-    fn partial_cmp(&self, other: &BTreeMap<K, V>) -> Option<Ordering> {
+    fn partial_cmp(&self, other: &BTreeMap<K, V, A>) -> Option<Ordering> {
         self.iter().partial_cmp(other.iter())
     }
 }
 
-impl<K: Ord, V: Ord> Ord for BTreeMap<K, V> {
+impl<K: Ord, V: Ord, A: Alloc + 'static> Ord for BTreeMap<K, V, A> {
     #[inline]
-    fn cmp(&self, other: &BTreeMap<K, V>) -> Ordering {
+    fn cmp(&self, other: &BTreeMap<K, V, A>) -> Ordering {
         self.iter().cmp(other.iter())
     }
 }
 
-impl<K: Debug, V: Debug> Debug for BTreeMap<K, V> {
+impl<K: Debug, V: Debug, A: Alloc + 'static> Debug for BTreeMap<K, V, A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_map().entries(self.iter()).finish()
     }
 }
 
-impl<'a, K: Ord, Q: ?Sized, V> Index<&'a Q> for BTreeMap<K, V>
+impl<'a, K: Ord, Q: ?Sized, V, A: Alloc + 'static> Index<&'a Q> for BTreeMap<K, V, A>
     where K: Borrow<Q>, Q: Ord
 {
     type Output = V;
@@ -1458,7 +1529,7 @@ unsafe fn unwrap_unchecked<T>(val: Option<T>) -> T {
     })
 }
 
-impl<K, V> BTreeMap<K, V> {
+impl<K, V, A: Alloc + 'static> BTreeMap<K, V, A> {
     /// Gets an iterator over the entries of the map, sorted by key.
     ///
     /// # Examples
@@ -1634,7 +1705,7 @@ impl<K, V> BTreeMap<K, V> {
     }
 }
 
-impl<'a, K: Ord, V> Entry<'a, K, V> {
+impl<'a, K: Ord, V, A: Alloc> Entry<'a, K, V, A> {
     /// Ensures a value is in the entry by inserting the default if empty, and returns
     /// a mutable reference to the value in the entry.
     #[stable(feature = "rust1", since = "1.0.0")]
@@ -1665,7 +1736,7 @@ impl<'a, K: Ord, V> Entry<'a, K, V> {
     }
 }
 
-impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+impl<'a, K: Ord, V, A: Alloc> VacantEntry<'a, K, V, A> {
     /// Gets a reference to the key that would be used when inserting a value
     /// through the VacantEntry.
     #[unstable(feature = "map_entry_keys", issue = "32281")]
@@ -1685,7 +1756,8 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
         let mut ins_v;
         let mut ins_edge;
 
-        let mut cur_parent = match self.handle.insert(self.key, value) {
+        let alloc = self.alloc;
+        let mut cur_parent = match self.handle.insert(self.key, value, &mut *alloc) {
             (Fit(handle), _) => return handle.into_kv_mut().1,
             (Split(left, k, v, right), ptr) => {
                 ins_k = k;
@@ -1698,7 +1770,7 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
 
         loop {
             match cur_parent {
-                Ok(parent) => match parent.insert(ins_k, ins_v, ins_edge) {
+                Ok(parent) => match parent.insert(ins_k, ins_v, ins_edge, &mut *alloc) {
                     Fit(_) => return unsafe { &mut *out_ptr },
                     Split(left, k, v, right) => {
                         ins_k = k;
@@ -1708,7 +1780,7 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
                     }
                 },
                 Err(root) => {
-                    root.push_level().push(ins_k, ins_v, ins_edge);
+                    root.push_level(alloc).push(ins_k, ins_v, ins_edge);
                     return unsafe { &mut *out_ptr };
                 }
             }
@@ -1716,7 +1788,7 @@ impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
     }
 }
 
-impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+impl<'a, K: Ord, V, A: Alloc> OccupiedEntry<'a, K, V, A> {
     /// Gets a reference to the key in the entry.
     #[unstable(feature = "map_entry_keys", issue = "32281")]
     pub fn key(&self) -> &K {
@@ -1785,12 +1857,12 @@ impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
         // Handle underflow
         let mut cur_node = small_leaf.forget_type();
         while cur_node.len() < node::CAPACITY / 2 {
-            match handle_underfull_node(cur_node) {
+            match handle_underfull_node(cur_node, &mut *self.alloc) {
                 AtRoot => break,
                 EmptyParent(_) => unreachable!(),
                 Merged(parent) => if parent.len() == 0 {
                     // We must be at the root
-                    parent.into_root_mut().pop_level();
+                    parent.into_root_mut().pop_level(&mut *self.alloc);
                     break;
                 } else {
                     cur_node = parent.forget_type();
@@ -1810,10 +1882,11 @@ enum UnderflowResult<'a, K, V> {
     Stole(NodeRef<marker::Mut<'a>, K, V, marker::Internal>)
 }
 
-fn handle_underfull_node<'a, K, V>(node: NodeRef<marker::Mut<'a>,
-                                                 K, V,
-                                                 marker::LeafOrInternal>)
-                                                 -> UnderflowResult<'a, K, V> {
+fn handle_underfull_node<'a, K, V, A: Alloc>(node: NodeRef<marker::Mut<'a>,
+                                                            K, V,
+                                                            marker::LeafOrInternal>,
+                                              alloc: &mut A)
+                                              -> UnderflowResult<'a, K, V> {
     let parent = if let Ok(parent) = node.ascend() {
         parent
     } else {
@@ -1831,7 +1904,7 @@ fn handle_underfull_node<'a, K, V>(node: NodeRef<marker::Mut<'a>,
     };
 
     if handle.can_merge() {
-        Merged(handle.merge().into_node())
+        Merged(handle.merge(alloc).into_node())
     } else {
         if is_left {
             handle.steal_left();
@@ -41,15 +41,13 @@
 // - A node of length `n` has `n` keys, `n` values, and (in an internal node) `n + 1` edges.
 //   This implies that even an empty internal node has at least one edge.
 
-use alloc::heap;
+use alloc::heap::Alloc;
 use core::marker::PhantomData;
 use core::mem;
 use core::nonzero::NonZero;
 use core::ptr::{self, Unique};
 use core::slice;
 
-use boxed::Box;
-
 const B: usize = 6;
 pub const CAPACITY: usize = 2 * B - 1;
 
@@ -130,24 +128,20 @@ impl<K, V> InternalNode<K, V> {
 }
 
 /// An owned pointer to a node. This basically is either `Box<LeafNode<K, V>>` or
-/// `Box<InternalNode<K, V>>`. However, it contains no information as to which of the two types
-/// of nodes is acutally behind the box, and, partially due to this lack of information, has no
-/// destructor.
+/// `Box<InternalNode<K, V>>`, allocated by the allocator of the tree rather than the heap.
+/// However, it contains no information as to which of the two types of nodes is acutally
+/// behind the box, and, partially due to this lack of information, has no destructor.
 struct BoxedNode<K, V> {
     ptr: Unique<LeafNode<K, V>>
 }
 
 impl<K, V> BoxedNode<K, V> {
-    fn from_leaf(node: Box<LeafNode<K, V>>) -> Self {
-        unsafe {
-            BoxedNode { ptr: Unique::new(Box::into_raw(node)) }
-        }
+    unsafe fn from_leaf(node: *mut LeafNode<K, V>) -> Self {
+        BoxedNode { ptr: Unique::new(node) }
     }
 
-    fn from_internal(node: Box<InternalNode<K, V>>) -> Self {
-        unsafe {
-            BoxedNode { ptr: Unique::new(Box::into_raw(node) as *mut LeafNode<K, V>) }
-        }
+    unsafe fn from_internal(node: *mut InternalNode<K, V>) -> Self {
+        BoxedNode { ptr: Unique::new(node as *mut LeafNode<K, V>) }
     }
 
     unsafe fn from_ptr(ptr: NonZero<*const LeafNode<K, V>>) -> Self {
@@ -161,6 +155,21 @@ impl<K, V> BoxedNode<K, V> {
     }
 }
 
+/// Moves `node` into memory obtained from `alloc`, calling `oom` on it if there is none.
+unsafe fn allocate_node<T, A: Alloc>(alloc: &mut A, node: T) -> *mut T {
+    let ptr = alloc.alloc(mem::size_of::<T>(), mem::align_of::<T>()) as *mut T;
+    if ptr.is_null() {
+        alloc.oom()
+    }
+    ptr::write(ptr, node);
+    ptr
+}
+
+/// Frees a node obtained through `allocate_node`, without dropping its contents.
+unsafe fn deallocate_node<T, A: Alloc>(alloc: &mut A, node: *mut T) {
+    alloc.dealloc(node as *mut u8, mem::size_of::<T>(), mem::align_of::<T>());
+}
+
 /// An owned tree. Note that despite being owned, this does not have a destructor,
 /// and must be cleaned up manually.
 pub struct Root<K, V> {
@@ -172,9 +181,9 @@ unsafe impl<K: Sync, V: Sync> Sync for Root<K, V> { }
 unsafe impl<K: Send, V: Send> Send for Root<K, V> { }
 
 impl<K, V> Root<K, V> {
-    pub fn new_leaf() -> Self {
+    pub fn new_leaf<A: Alloc>(alloc: &mut A) -> Self {
         Root {
-            node: BoxedNode::from_leaf(Box::new(unsafe { LeafNode::new() })),
+            node: unsafe { BoxedNode::from_leaf(allocate_node(alloc, LeafNode::new())) },
             height: 0
         }
     }
@@ -211,12 +220,14 @@ impl<K, V> Root<K, V> {
 
     /// Adds a new internal node with a single edge, pointing to the previous root, and make that
     /// new node the root. This increases the height by 1 and is the opposite of `pop_level`.
-    pub fn push_level(&mut self)
+    pub fn push_level<A: Alloc>(&mut self, alloc: &mut A)
             -> NodeRef<marker::Mut, K, V, marker::Internal> {
-        let mut new_node = Box::new(unsafe { InternalNode::new() });
-        new_node.edges[0] = unsafe { BoxedNode::from_ptr(self.node.as_ptr()) };
+        unsafe {
+            let new_node = allocate_node(alloc, InternalNode::new());
+            ptr::write(&mut (*new_node).edges[0], BoxedNode::from_ptr(self.node.as_ptr()));
 
-        self.node = BoxedNode::from_internal(new_node);
+            self.node = BoxedNode::from_internal(new_node);
+        }
         self.height += 1;
 
         let mut ret = NodeRef {
@@ -237,10 +248,10 @@ impl<K, V> Root<K, V> {
     /// the tree consists only of a leaf node. As it is intended only to be called when the root
     /// has only one edge, no cleanup is done on any of the other children are elements of the root.
     /// This decreases the height by 1 and is the opposite of `push_level`.
-    pub fn pop_level(&mut self) {
+    pub fn pop_level<A: Alloc>(&mut self, alloc: &mut A) {
         debug_assert!(self.height > 0);
 
-        let top = *self.node.ptr as *mut u8;
+        let top = *self.node.ptr as *mut InternalNode<K, V>;
 
         self.node = unsafe {
             BoxedNode::from_ptr(self.as_mut()
@@ -253,11 +264,7 @@ impl<K, V> Root<K, V> {
         self.as_mut().as_leaf_mut().parent = ptr::null();
 
         unsafe {
-            heap::deallocate(
-                top,
-                mem::size_of::<InternalNode<K, V>>(),
-                mem::align_of::<InternalNode<K, V>>()
-            );
+            deallocate_node(alloc, top);
         }
     }
 }
@@ -419,7 +426,7 @@ impl<K, V> NodeRef<marker::Owned, K, V, marker::Leaf> {
     /// Similar to `ascend`, gets a reference to a node's parent node, but also
     /// deallocate the current node in the process. This is unsafe because the
     /// current node will still be accessible despite being deallocated.
-    pub unsafe fn deallocate_and_ascend(self) -> Option<
+    pub unsafe fn deallocate_and_ascend<A: Alloc>(self, alloc: &mut A) -> Option<
         Handle<
             NodeRef<
                 marker::Owned,
@@ -429,9 +436,9 @@ impl<K, V> NodeRef<marker::Owned, K, V, marker::Leaf> {
             marker::Edge
         >
     > {
-        let ptr = self.as_leaf() as *const LeafNode<K, V> as *const u8 as *mut u8;
+        let ptr = self.as_leaf() as *const LeafNode<K, V> as *mut LeafNode<K, V>;
         let ret = self.ascend().ok();
-        heap::deallocate(ptr, mem::size_of::<LeafNode<K, V>>(), mem::align_of::<LeafNode<K, V>>());
+        deallocate_node(alloc, ptr);
         ret
     }
 }
@@ -440,7 +447,7 @@ impl<K, V> NodeRef<marker::Owned, K, V, marker::Internal> {
     /// Similar to `ascend`, gets a reference to a node's parent node, but also
     /// deallocate the current node in the process. This is unsafe because the
     /// current node will still be accessible despite being deallocated.
-    pub unsafe fn deallocate_and_ascend(self) -> Option<
+    pub unsafe fn deallocate_and_ascend<A: Alloc>(self, alloc: &mut A) -> Option<
         Handle<
             NodeRef<
                 marker::Owned,
@@ -450,13 +457,9 @@ impl<K, V> NodeRef<marker::Owned, K, V, marker::Internal> {
             marker::Edge
         >
     > {
-        let ptr = self.as_internal() as *const InternalNode<K, V> as *const u8 as *mut u8;
+        let ptr = self.as_internal() as *const InternalNode<K, V> as *mut InternalNode<K, V>;
         let ret = self.ascend().ok();
-        heap::deallocate(
-            ptr,
-            mem::size_of::<InternalNode<K, V>>(),
-            mem::align_of::<InternalNode<K, V>>()
-        );
+        deallocate_node(alloc, ptr);
         ret
     }
 }
@@ -884,7 +887,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge
     /// this edge. This method splits the node if there isn't enough room.
     ///
     /// The returned pointer points to the inserted value.
-    pub fn insert(mut self, key: K, val: V)
+    pub fn insert<A: Alloc>(mut self, key: K, val: V, alloc: &mut A)
             -> (InsertResult<'a, K, V, marker::Leaf>, *mut V) {
 
         if self.node.len() < CAPACITY {
@@ -892,7 +895,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::Edge
             (InsertResult::Fit(Handle::new_kv(self.node, self.idx)), ptr)
         } else {
             let middle = Handle::new_kv(self.node, B);
-            let (mut left, k, v, mut right) = middle.split();
+            let (mut left, k, v, mut right) = middle.split(alloc);
             let ptr = if self.idx <= B {
                 unsafe {
                     Handle::new_edge(left.reborrow_mut(), self.idx).insert_fit(key, val)
@@ -959,7 +962,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
     /// Inserts a new key/value pair and an edge that will go to the right of that new pair
     /// between this edge and the key/value pair to the right of this edge. This method splits
     /// the node if there isn't enough room.
-    pub fn insert(mut self, key: K, val: V, edge: Root<K, V>)
+    pub fn insert<A: Alloc>(mut self, key: K, val: V, edge: Root<K, V>, alloc: &mut A)
             -> InsertResult<'a, K, V, marker::Internal> {
 
         // Necessary for correctness, but this is an internal module
@@ -970,7 +973,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
             InsertResult::Fit(Handle::new_kv(self.node, self.idx))
         } else {
             let middle = Handle::new_kv(self.node, B);
-            let (mut left, k, v, mut right) = middle.split();
+            let (mut left, k, v, mut right) = middle.split(alloc);
             if self.idx <= B {
                 unsafe {
                     Handle::new_edge(left.reborrow_mut(), self.idx).insert_fit(key, val, edge);
@@ -1044,10 +1047,10 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, marker::KV>
     /// - The key and value pointed to by this handle and extracted.
     /// - All the key/value pairs to the right of this handle are put into a newly
     ///   allocated node.
-    pub fn split(mut self)
+    pub fn split<A: Alloc>(mut self, alloc: &mut A)
             -> (NodeRef<marker::Mut<'a>, K, V, marker::Leaf>, K, V, Root<K, V>) {
         unsafe {
-            let mut new_node = Box::new(LeafNode::new());
+            let new_node = &mut *allocate_node(alloc, LeafNode::new());
 
             let k = ptr::read(self.node.keys().get_unchecked(self.idx));
             let v = ptr::read(self.node.vals().get_unchecked(self.idx));
@@ -1100,10 +1103,10 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
     /// - The key and value pointed to by this handle and extracted.
     /// - All the edges and key/value pairs to the right of this handle are put into
     ///   a newly allocated node.
-    pub fn split(mut self)
+    pub fn split<A: Alloc>(mut self, alloc: &mut A)
             -> (NodeRef<marker::Mut<'a>, K, V, marker::Internal>, K, V, Root<K, V>) {
         unsafe {
-            let mut new_node = Box::new(InternalNode::new());
+            let new_node = &mut *allocate_node(alloc, InternalNode::new());
 
             let k = ptr::read(self.node.keys().get_unchecked(self.idx));
             let v = ptr::read(self.node.vals().get_unchecked(self.idx));
@@ -1169,7 +1172,7 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
     /// child of the underlying node, returning an edge referencing that new child.
     ///
     /// Assumes that this edge `.can_merge()`.
-    pub fn merge(mut self)
+    pub fn merge<A: Alloc>(mut self, alloc: &mut A)
             -> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::Edge> {
         let self1 = unsafe { ptr::read(&self) };
         let self2 = unsafe { ptr::read(&self) };
@@ -1223,17 +1226,9 @@ impl<'a, K, V> Handle<NodeRef<marker::Mut<'a>, K, V, marker::Internal>, marker::
                     ).correct_parent_link();
                 }
 
-                heap::deallocate(
-                    *right_node.node as *mut u8,
-                    mem::size_of::<InternalNode<K, V>>(),
-                    mem::align_of::<InternalNode<K, V>>()
-                );
+                deallocate_node(alloc, *right_node.node as *mut InternalNode<K, V>);
             } else {
-                heap::deallocate(
-                    *right_node.node as *mut u8,
-                    mem::size_of::<LeafNode<K, V>>(),
-                    mem::align_of::<LeafNode<K, V>>()
-                );
+                deallocate_node(alloc, *right_node.node as *mut LeafNode<K, V>);
             }
 
             Handle::new_edge(self.node, self.idx)
@@ -31,6 +31,7 @@
 #![cfg_attr(not(stage0), deny(warnings))]
 
 #![feature(alloc)]
+#![feature(allocator_api)]
 #![feature(allow_internal_unstable)]
 #![feature(box_patterns)]
 #![feature(box_syntax)]
@@ -51,6 +52,7 @@
 #![feature(staged_api)]
 #![feature(step_by)]
 #![feature(str_char)]
+#![feature(try_reserve)]
 #![feature(unboxed_closures)]
 #![feature(unicode)]
 #![feature(unique)]
@@ -85,6 +87,9 @@ pub use string::String;
 #[doc(no_inline)]
 pub use vec::Vec;
 
+#[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+pub use alloc::raw_vec::CollectionAllocErr;
+
 // Needed for the vec! macro
 pub use alloc::boxed;
 
@@ -70,6 +70,7 @@ use range::RangeArgument;
 use str::{self, FromStr, Utf8Error, Chars};
 use vec::Vec;
 use boxed::Box;
+use CollectionAllocErr;
 
 /// A UTF-8 encoded, growable string.
 ///
@@ -928,6 +929,60 @@ impl String {
         self.vec.reserve_exact(additional)
     }
 
+    /// Tries to reserve capacity for at least `additional` more bytes to be
+    /// inserted in the given `String`.
+    ///
+    /// This is the fallible counterpart of [`reserve()`]: on capacity
+    /// overflow or allocator failure the error is returned and the `String`
+    /// is left unchanged.
+    ///
+    /// [`reserve()`]: #method.reserve
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// #![feature(try_reserve)]
+    ///
+    /// let mut s = String::new();
+    ///
+    /// s.try_reserve(10).unwrap();
+    ///
+    /// assert!(s.capacity() >= 10);
+    /// ```
+    #[inline]
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), CollectionAllocErr> {
+        self.vec.try_reserve(additional)
+    }
+
+    /// Tries to ensure that this `String`'s capacity is `additional` bytes
+    /// larger than its length.
+    ///
+    /// This is the fallible counterpart of [`reserve_exact()`].
+    ///
+    /// [`reserve_exact()`]: #method.reserve_exact
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// #![feature(try_reserve)]
+    ///
+    /// let mut s = String::new();
+    ///
+    /// s.try_reserve_exact(10).unwrap();
+    ///
+    /// assert!(s.capacity() >= 10);
+    /// ```
+    #[inline]
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), CollectionAllocErr> {
+        self.vec.try_reserve_exact(additional)
+    }
+
     /// Shrinks the capacity of this `String` to match its length.
     ///
     /// # Examples
@@ -60,8 +60,8 @@
 #![stable(feature = "rust1", since = "1.0.0")]
 
 use alloc::boxed::Box;
-use alloc::heap::EMPTY;
-use alloc::raw_vec::RawVec;
+use alloc::heap::{Alloc, EMPTY, Heap};
+use alloc::raw_vec::{CollectionAllocErr, RawVec};
 use borrow::ToOwned;
 use borrow::Cow;
 use core::cmp::Ordering;
@@ -267,10 +267,22 @@ use super::range::RangeArgument;
 /// Vec does not currently guarantee the order in which elements are dropped
 /// (the order has changed in the past, and may change again).
 ///
+/// # Allocators
+///
+/// The buffer of a `Vec<T>` lives on the global heap. A `Vec<T, A>` instead
+/// obtains and frees its buffer through the allocator `A`, which the vector
+/// owns; see `new_in` and `with_capacity_in`. Conversions to and from `Box`,
+/// `String` and the other collections are only available for `Vec<T>`.
+///
+/// The allocator must not borrow anything. The destructor of a `Vec` is
+/// assumed not to look at what its parameters borrow, which lets a vector
+/// hold references that die with it, but an allocator is used by that
+/// destructor to free the buffer.
+///
 #[unsafe_no_drop_flag]
 #[stable(feature = "rust1", since = "1.0.0")]
-pub struct Vec<T> {
-    buf: RawVec<T>,
+pub struct Vec<T, A: Alloc + 'static = Heap> {
+    buf: RawVec<T, A>,
     len: usize,
 }
 
@@ -386,6 +398,67 @@ impl<T> Vec<T> {
         }
     }
 
+    /// Converts the vector into Box<[T]>.
+    ///
+    /// Note that this will drop any excess capacity. Calling this and
+    /// converting back to a vector with `into_vec()` is equivalent to calling
+    /// `shrink_to_fit()`.
+    #[stable(feature = "rust1", since = "1.0.0")]
+    pub fn into_boxed_slice(mut self) -> Box<[T]> {
+        unsafe {
+            self.shrink_to_fit();
+            let buf = ptr::read(&self.buf);
+            mem::forget(self);
+            buf.into_box()
+        }
+    }
+}
+
+impl<T, A: Alloc + 'static> Vec<T, A> {
+    /// Constructs a new, empty `Vec<T, A>` that will allocate from `a`.
+    ///
+    /// The vector will not allocate until elements are pushed onto it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(allocator_api)]
+    ///
+    /// use std::heap::Heap;
+    ///
+    /// let mut vec: Vec<i32, Heap> = Vec::new_in(Heap);
+    /// vec.push(1);
+    /// assert_eq!(vec, [1]);
+    /// ```
+    #[inline]
+    #[unstable(feature = "allocator_api", issue = "0")]
+    pub fn new_in(a: A) -> Vec<T, A> {
+        Vec {
+            buf: RawVec::new_in(a),
+            len: 0,
+        }
+    }
+
+    /// Constructs a new, empty `Vec<T, A>` with the specified capacity that
+    /// will allocate from `a`.
+    ///
+    /// See `with_capacity` for the meaning of `capacity`.
+    #[inline]
+    #[unstable(feature = "allocator_api", issue = "0")]
+    pub fn with_capacity_in(capacity: usize, a: A) -> Vec<T, A> {
+        Vec {
+            buf: RawVec::with_capacity_in(capacity, a),
+            len: 0,
+        }
+    }
+
+    /// Returns a reference to the allocator backing this vector.
+    #[inline]
+    #[unstable(feature = "allocator_api", issue = "0")]
+    pub fn allocator(&self) -> &A {
+        self.buf.alloc()
+    }
+
     /// Returns the number of elements the vector can hold without
     /// reallocating.
     ///
@@ -445,6 +518,54 @@ impl<T> Vec<T> {
         self.buf.reserve_exact(self.len, additional);
     }
 
+    /// Tries to reserve capacity for at least `additional` more elements to be
+    /// inserted in the given `Vec<T>`. The collection may reserve more space to
+    /// avoid frequent reallocations.
+    ///
+    /// Unlike `reserve`, this does not panic if the new capacity overflows and
+    /// does not abort if the allocator fails; the error is returned instead
+    /// and the vector is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(try_reserve)]
+    /// use std::collections::CollectionAllocErr;
+    ///
+    /// let mut vec = vec![1];
+    /// vec.try_reserve(10).unwrap();
+    /// assert!(vec.capacity() >= 11);
+    ///
+    /// assert_eq!(vec.try_reserve(usize::max_value()),
+    ///            Err(CollectionAllocErr::CapacityOverflow));
+    /// ```
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), CollectionAllocErr> {
+        self.buf.try_reserve(self.len, additional)
+    }
+
+    /// Tries to reserve the minimum capacity for exactly `additional` more
+    /// elements to be inserted in the given `Vec<T>`. Does nothing if the
+    /// capacity is already sufficient.
+    ///
+    /// This is the fallible counterpart of `reserve_exact`: on capacity
+    /// overflow or allocator failure the error is returned and the vector is
+    /// left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(try_reserve)]
+    ///
+    /// let mut vec = vec![1];
+    /// vec.try_reserve_exact(10).unwrap();
+    /// assert!(vec.capacity() >= 11);
+    /// ```
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), CollectionAllocErr> {
+        self.buf.try_reserve_exact(self.len, additional)
+    }
+
     /// Shrinks the capacity of the vector as much as possible.
     ///
     /// It will drop down as close as possible to the length but the allocator
@@ -464,21 +585,6 @@ impl<T> Vec<T> {
         self.buf.shrink_to_fit(self.len);
     }
 
-    /// Converts the vector into Box<[T]>.
-    ///
-    /// Note that this will drop any excess capacity. Calling this and
-    /// converting back to a vector with `into_vec()` is equivalent to calling
-    /// `shrink_to_fit()`.
-    #[stable(feature = "rust1", since = "1.0.0")]
-    pub fn into_boxed_slice(mut self) -> Box<[T]> {
-        unsafe {
-            self.shrink_to_fit();
-            let buf = ptr::read(&self.buf);
-            mem::forget(self);
-            buf.into_box()
-        }
-    }
-
     /// Shorten a vector to be `len` elements long, dropping excess elements.
     ///
     /// If `len` is greater than the vector's current length, this has no
@@ -792,7 +898,7 @@ impl<T> Vec<T> {
     /// assert_eq!(v, &[]);
     /// ```
     #[stable(feature = "drain", since = "1.6.0")]
-    pub fn drain<R>(&mut self, range: R) -> Drain<T>
+    pub fn drain<R>(&mut self, range: R) -> Drain<T, A>
         where R: RangeArgument<usize>
     {
         // Memory safety
@@ -895,11 +1001,13 @@ impl<T> Vec<T> {
     /// ```
     #[inline]
     #[stable(feature = "split_off", since = "1.4.0")]
-    pub fn split_off(&mut self, at: usize) -> Self {
+    pub fn split_off(&mut self, at: usize) -> Self
+        where A: Clone
+    {
         assert!(at <= self.len(), "`at` out of bounds");
 
         let other_len = self.len - at;
-        let mut other = Vec::with_capacity(other_len);
+        let mut other = Vec::with_capacity_in(other_len, self.buf.alloc().clone());
 
         // Unsafely `set_len` and copy items to `other`.
         unsafe {
@@ -914,7 +1022,7 @@ impl<T> Vec<T> {
     }
 }
 
-impl<T: Clone> Vec<T> {
+impl<T: Clone, A: Alloc + 'static> Vec<T, A> {
     /// Resizes the `Vec` in-place so that `len()` is equal to `new_len`.
     ///
     /// If `new_len` is greater than `len()`, the `Vec` is extended by the
@@ -1001,7 +1109,7 @@ impl<T: Clone> Vec<T> {
     }
 }
 
-impl<T: PartialEq> Vec<T> {
+impl<T: PartialEq, A: Alloc + 'static> Vec<T, A> {
     /// Removes consecutive repeated elements in the vector.
     ///
     /// If the vector is sorted, this removes all duplicates.
@@ -1120,22 +1228,14 @@ pub fn from_elem<T: Clone>(elem: T, n: usize) -> Vec<T> {
 ////////////////////////////////////////////////////////////////////////////////
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: Clone> Clone for Vec<T> {
-    #[cfg(not(test))]
-    fn clone(&self) -> Vec<T> {
-        <[T]>::to_vec(&**self)
-    }
-
-    // HACK(japaric): with cfg(test) the inherent `[T]::to_vec` method, which is
-    // required for this method definition, is not available. Instead use the
-    // `slice::to_vec`  function which is only available with cfg(test)
-    // NB see the slice::hack module in slice.rs for more information
-    #[cfg(test)]
-    fn clone(&self) -> Vec<T> {
-        ::slice::to_vec(&**self)
+impl<T: Clone, A: Alloc + Clone + 'static> Clone for Vec<T, A> {
+    fn clone(&self) -> Vec<T, A> {
+        let mut v = Vec::with_capacity_in(self.len(), self.buf.alloc().clone());
+        v.extend_from_slice(self);
+        v
     }
 
-    fn clone_from(&mut self, other: &Vec<T>) {
+    fn clone_from(&mut self, other: &Vec<T, A>) {
         // drop anything in self that will not be overwritten
         self.truncate(other.len());
         let len = self.len();
@@ -1150,7 +1250,7 @@ impl<T: Clone> Clone for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: Hash> Hash for Vec<T> {
+impl<T: Hash, A: Alloc + 'static> Hash for Vec<T, A> {
     #[inline]
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
         Hash::hash(&**self, state)
@@ -1158,7 +1258,7 @@ impl<T: Hash> Hash for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> Index<usize> for Vec<T> {
+impl<T, A: Alloc + 'static> Index<usize> for Vec<T, A> {
     type Output = T;
 
     #[inline]
@@ -1169,7 +1269,7 @@ impl<T> Index<usize> for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> IndexMut<usize> for Vec<T> {
+impl<T, A: Alloc + 'static> IndexMut<usize> for Vec<T, A> {
     #[inline]
     fn index_mut(&mut self, index: usize) -> &mut T {
         // NB built-in indexing via `&mut [T]`
@@ -1179,7 +1279,7 @@ impl<T> IndexMut<usize> for Vec<T> {
 
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::Index<ops::Range<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::Index<ops::Range<usize>> for Vec<T, A> {
     type Output = [T];
 
     #[inline]
@@ -1188,7 +1288,7 @@ impl<T> ops::Index<ops::Range<usize>> for Vec<T> {
     }
 }
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::Index<ops::RangeTo<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::Index<ops::RangeTo<usize>> for Vec<T, A> {
     type Output = [T];
 
     #[inline]
@@ -1197,7 +1297,7 @@ impl<T> ops::Index<ops::RangeTo<usize>> for Vec<T> {
     }
 }
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::Index<ops::RangeFrom<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::Index<ops::RangeFrom<usize>> for Vec<T, A> {
     type Output = [T];
 
     #[inline]
@@ -1206,7 +1306,7 @@ impl<T> ops::Index<ops::RangeFrom<usize>> for Vec<T> {
     }
 }
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::Index<ops::RangeFull> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::Index<ops::RangeFull> for Vec<T, A> {
     type Output = [T];
 
     #[inline]
@@ -1215,7 +1315,7 @@ impl<T> ops::Index<ops::RangeFull> for Vec<T> {
     }
 }
 #[unstable(feature = "inclusive_range", reason = "recently added, follows RFC", issue = "28237")]
-impl<T> ops::Index<ops::RangeInclusive<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::Index<ops::RangeInclusive<usize>> for Vec<T, A> {
     type Output = [T];
 
     #[inline]
@@ -1224,7 +1324,7 @@ impl<T> ops::Index<ops::RangeInclusive<usize>> for Vec<T> {
     }
 }
 #[unstable(feature = "inclusive_range", reason = "recently added, follows RFC", issue = "28237")]
-impl<T> ops::Index<ops::RangeToInclusive<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::Index<ops::RangeToInclusive<usize>> for Vec<T, A> {
     type Output = [T];
 
     #[inline]
@@ -1234,42 +1334,42 @@ impl<T> ops::Index<ops::RangeToInclusive<usize>> for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::IndexMut<ops::Range<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::IndexMut<ops::Range<usize>> for Vec<T, A> {
     #[inline]
     fn index_mut(&mut self, index: ops::Range<usize>) -> &mut [T] {
         IndexMut::index_mut(&mut **self, index)
     }
 }
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::IndexMut<ops::RangeTo<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::IndexMut<ops::RangeTo<usize>> for Vec<T, A> {
     #[inline]
     fn index_mut(&mut self, index: ops::RangeTo<usize>) -> &mut [T] {
         IndexMut::index_mut(&mut **self, index)
     }
 }
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::IndexMut<ops::RangeFrom<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::IndexMut<ops::RangeFrom<usize>> for Vec<T, A> {
     #[inline]
     fn index_mut(&mut self, index: ops::RangeFrom<usize>) -> &mut [T] {
         IndexMut::index_mut(&mut **self, index)
     }
 }
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::IndexMut<ops::RangeFull> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::IndexMut<ops::RangeFull> for Vec<T, A> {
     #[inline]
     fn index_mut(&mut self, _index: ops::RangeFull) -> &mut [T] {
         self
     }
 }
 #[unstable(feature = "inclusive_range", reason = "recently added, follows RFC", issue = "28237")]
-impl<T> ops::IndexMut<ops::RangeInclusive<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::IndexMut<ops::RangeInclusive<usize>> for Vec<T, A> {
     #[inline]
     fn index_mut(&mut self, index: ops::RangeInclusive<usize>) -> &mut [T] {
         IndexMut::index_mut(&mut **self, index)
     }
 }
 #[unstable(feature = "inclusive_range", reason = "recently added, follows RFC", issue = "28237")]
-impl<T> ops::IndexMut<ops::RangeToInclusive<usize>> for Vec<T> {
+impl<T, A: Alloc + 'static> ops::IndexMut<ops::RangeToInclusive<usize>> for Vec<T, A> {
     #[inline]
     fn index_mut(&mut self, index: ops::RangeToInclusive<usize>) -> &mut [T] {
         IndexMut::index_mut(&mut **self, index)
@@ -1277,7 +1377,7 @@ impl<T> ops::IndexMut<ops::RangeToInclusive<usize>> for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::Deref for Vec<T> {
+impl<T, A: Alloc + 'static> ops::Deref for Vec<T, A> {
     type Target = [T];
 
     fn deref(&self) -> &[T] {
@@ -1290,7 +1390,7 @@ impl<T> ops::Deref for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ops::DerefMut for Vec<T> {
+impl<T, A: Alloc + 'static> ops::DerefMut for Vec<T, A> {
     fn deref_mut(&mut self) -> &mut [T] {
         unsafe {
             let ptr = self.buf.ptr();
@@ -1328,9 +1428,9 @@ impl<T> FromIterator<T> for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> IntoIterator for Vec<T> {
+impl<T, A: Alloc + 'static> IntoIterator for Vec<T, A> {
     type Item = T;
-    type IntoIter = IntoIter<T>;
+    type IntoIter = IntoIter<T, A>;
 
     /// Creates a consuming iterator, that is, one that moves each value out of
     /// the vector (from start to end). The vector cannot be used after calling
@@ -1346,7 +1446,7 @@ impl<T> IntoIterator for Vec<T> {
     /// }
     /// ```
     #[inline]
-    fn into_iter(mut self) -> IntoIter<T> {
+    fn into_iter(mut self) -> IntoIter<T, A> {
         unsafe {
             let ptr = self.as_mut_ptr();
             assume(!ptr.is_null());
@@ -1368,7 +1468,7 @@ impl<T> IntoIterator for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<'a, T> IntoIterator for &'a Vec<T> {
+impl<'a, T, A: Alloc + 'static> IntoIterator for &'a Vec<T, A> {
     type Item = &'a T;
     type IntoIter = slice::Iter<'a, T>;
 
@@ -1378,7 +1478,7 @@ impl<'a, T> IntoIterator for &'a Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<'a, T> IntoIterator for &'a mut Vec<T> {
+impl<'a, T, A: Alloc + 'static> IntoIterator for &'a mut Vec<T, A> {
     type Item = &'a mut T;
     type IntoIter = slice::IterMut<'a, T>;
 
@@ -1388,26 +1488,26 @@ impl<'a, T> IntoIterator for &'a mut Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> Extend<T> for Vec<T> {
+impl<T, A: Alloc + 'static> Extend<T> for Vec<T, A> {
     #[inline]
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
         <Self as SpecExtend<I>>::spec_extend(self, iter);
     }
 }
 
-impl<I: IntoIterator> SpecExtend<I> for Vec<I::Item> {
+impl<I: IntoIterator, A: Alloc + 'static> SpecExtend<I> for Vec<I::Item, A> {
     default fn spec_extend(&mut self, iter: I) {
         self.extend_desugared(iter.into_iter())
     }
 }
 
-impl<T> SpecExtend<Vec<T>> for Vec<T> {
-    fn spec_extend(&mut self, ref mut other: Vec<T>) {
+impl<T, A: Alloc + 'static> SpecExtend<Vec<T, A>> for Vec<T, A> {
+    fn spec_extend(&mut self, ref mut other: Vec<T, A>) {
         self.append(other);
     }
 }
 
-impl<T> Vec<T> {
+impl<T, A: Alloc + 'static> Vec<T, A> {
     fn extend_desugared<I: Iterator<Item = T>>(&mut self, mut iterator: I) {
         // This function should be the moral equivalent of:
         //
@@ -1430,7 +1530,7 @@ impl<T> Vec<T> {
 }
 
 #[stable(feature = "extend_ref", since = "1.2.0")]
-impl<'a, T: 'a + Copy> Extend<&'a T> for Vec<T> {
+impl<'a, T: 'a + Copy, A: Alloc + 'static> Extend<&'a T> for Vec<T, A> {
     fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
         self.extend(iter.into_iter().cloned());
     }
@@ -1451,7 +1551,14 @@ macro_rules! __impl_slice_eq1 {
     }
 }
 
-__impl_slice_eq1! { Vec<A>, Vec<B> }
+#[stable(feature = "rust1", since = "1.0.0")]
+impl<A, B, AA: Alloc + 'static, BA: Alloc + 'static> PartialEq<Vec<B, BA>> for Vec<A, AA> where A: PartialEq<B> {
+    #[inline]
+    fn eq(&self, other: &Vec<B, BA>) -> bool { self[..] == other[..] }
+    #[inline]
+    fn ne(&self, other: &Vec<B, BA>) -> bool { self[..] != other[..] }
+}
+
 __impl_slice_eq1! { Vec<A>, &'b [B] }
 __impl_slice_eq1! { Vec<A>, &'b mut [B] }
 __impl_slice_eq1! { Cow<'a, [A]>, &'b [B], Clone }
@@ -1480,26 +1587,26 @@ array_impls! {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: PartialOrd> PartialOrd for Vec<T> {
+impl<T: PartialOrd, A: Alloc + 'static> PartialOrd for Vec<T, A> {
     #[inline]
-    fn partial_cmp(&self, other: &Vec<T>) -> Option<Ordering> {
+    fn partial_cmp(&self, other: &Vec<T, A>) -> Option<Ordering> {
         PartialOrd::partial_cmp(&**self, &**other)
     }
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: Eq> Eq for Vec<T> {}
+impl<T: Eq, A: Alloc + 'static> Eq for Vec<T, A> {}
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: Ord> Ord for Vec<T> {
+impl<T: Ord, A: Alloc + 'static> Ord for Vec<T, A> {
     #[inline]
-    fn cmp(&self, other: &Vec<T>) -> Ordering {
+    fn cmp(&self, other: &Vec<T, A>) -> Ordering {
         Ord::cmp(&**self, &**other)
     }
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> Drop for Vec<T> {
+impl<T, A: Alloc + 'static> Drop for Vec<T, A> {
     #[unsafe_destructor_blind_to_params]
     fn drop(&mut self) {
         if self.buf.unsafe_no_drop_flag_needs_drop() {
@@ -1520,35 +1627,35 @@ impl<T> Default for Vec<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T: fmt::Debug> fmt::Debug for Vec<T> {
+impl<T: fmt::Debug, A: Alloc + 'static> fmt::Debug for Vec<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         fmt::Debug::fmt(&**self, f)
     }
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> AsRef<Vec<T>> for Vec<T> {
-    fn as_ref(&self) -> &Vec<T> {
+impl<T, A: Alloc + 'static> AsRef<Vec<T, A>> for Vec<T, A> {
+    fn as_ref(&self) -> &Vec<T, A> {
         self
     }
 }
 
 #[stable(feature = "vec_as_mut", since = "1.5.0")]
-impl<T> AsMut<Vec<T>> for Vec<T> {
-    fn as_mut(&mut self) -> &mut Vec<T> {
+impl<T, A: Alloc + 'static> AsMut<Vec<T, A>> for Vec<T, A> {
+    fn as_mut(&mut self) -> &mut Vec<T, A> {
         self
     }
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> AsRef<[T]> for Vec<T> {
+impl<T, A: Alloc + 'static> AsRef<[T]> for Vec<T, A> {
     fn as_ref(&self) -> &[T] {
         self
     }
 }
 
 #[stable(feature = "vec_as_mut", since = "1.5.0")]
-impl<T> AsMut<[T]> for Vec<T> {
+impl<T, A: Alloc + 'static> AsMut<[T]> for Vec<T, A> {
     fn as_mut(&mut self) -> &mut [T] {
         self
     }
@@ -1604,19 +1711,19 @@ impl<'a, T> FromIterator<T> for Cow<'a, [T]> where T: Clone {
 
 /// An iterator that moves out of a vector.
 #[stable(feature = "rust1", since = "1.0.0")]
-pub struct IntoIter<T> {
-    _buf: RawVec<T>,
+pub struct IntoIter<T, A: Alloc + 'static = Heap> {
+    _buf: RawVec<T, A>,
     ptr: *const T,
     end: *const T,
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-unsafe impl<T: Send> Send for IntoIter<T> {}
+unsafe impl<T: Send, A: Alloc + Send + 'static> Send for IntoIter<T, A> {}
 #[stable(feature = "rust1", since = "1.0.0")]
-unsafe impl<T: Sync> Sync for IntoIter<T> {}
+unsafe impl<T: Sync, A: Alloc + Sync + 'static> Sync for IntoIter<T, A> {}
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: Alloc + 'static> Iterator for IntoIter<T, A> {
     type Item = T;
 
     #[inline]
@@ -1663,7 +1770,7 @@ impl<T> Iterator for IntoIter<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> DoubleEndedIterator for IntoIter<T> {
+impl<T, A: Alloc + 'static> DoubleEndedIterator for IntoIter<T, A> {
     #[inline]
     fn next_back(&mut self) -> Option<T> {
         unsafe {
@@ -1687,7 +1794,7 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T, A: Alloc + 'static> ExactSizeIterator for IntoIter<T, A> {}
 
 #[stable(feature = "vec_into_iter_clone", since = "1.8.0")]
 impl<T: Clone> Clone for IntoIter<T> {
@@ -1699,7 +1806,7 @@ impl<T: Clone> Clone for IntoIter<T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<T> Drop for IntoIter<T> {
+impl<T, A: Alloc + 'static> Drop for IntoIter<T, A> {
     #[unsafe_destructor_blind_to_params]
     fn drop(&mut self) {
         // destroy the remaining elements
@@ -1711,23 +1818,23 @@ impl<T> Drop for IntoIter<T> {
 
 /// A draining iterator for `Vec<T>`.
 #[stable(feature = "drain", since = "1.6.0")]
-pub struct Drain<'a, T: 'a> {
+pub struct Drain<'a, T: 'a, A: Alloc + 'static = Heap> {
     /// Index of tail to preserve
     tail_start: usize,
     /// Length of tail
     tail_len: usize,
     /// Current remaining range to remove
     iter: slice::IterMut<'a, T>,
-    vec: *mut Vec<T>,
+    vec: *mut Vec<T, A>,
 }
 
 #[stable(feature = "drain", since = "1.6.0")]
-unsafe impl<'a, T: Sync> Sync for Drain<'a, T> {}
+unsafe impl<'a, T: Sync, A: Alloc + Sync + 'static> Sync for Drain<'a, T, A> {}
 #[stable(feature = "drain", since = "1.6.0")]
-unsafe impl<'a, T: Send> Send for Drain<'a, T> {}
+unsafe impl<'a, T: Send, A: Alloc + Send + 'static> Send for Drain<'a, T, A> {}
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<'a, T> Iterator for Drain<'a, T> {
+impl<'a, T, A: Alloc + 'static> Iterator for Drain<'a, T, A> {
     type Item = T;
 
     #[inline]
@@ -1741,7 +1848,7 @@ impl<'a, T> Iterator for Drain<'a, T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<'a, T> DoubleEndedIterator for Drain<'a, T> {
+impl<'a, T, A: Alloc + 'static> DoubleEndedIterator for Drain<'a, T, A> {
     #[inline]
     fn next_back(&mut self) -> Option<T> {
         self.iter.next_back().map(|elt| unsafe { ptr::read(elt as *const _) })
@@ -1749,7 +1856,7 @@ impl<'a, T> DoubleEndedIterator for Drain<'a, T> {
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<'a, T> Drop for Drain<'a, T> {
+impl<'a, T, A: Alloc + 'static> Drop for Drain<'a, T, A> {
     fn drop(&mut self) {
         // exhaust self first
         while let Some(_) = self.next() {}
@@ -1771,4 +1878,4 @@ impl<'a, T> Drop for Drain<'a, T> {
 
 
 #[stable(feature = "rust1", since = "1.0.0")]
-impl<'a, T> ExactSizeIterator for Drain<'a, T> {}
+impl<'a, T, A: Alloc + 'static> ExactSizeIterator for Drain<'a, T, A> {}
@@ -29,7 +29,7 @@ use core::slice;
 use core::hash::{Hash, Hasher};
 use core::cmp;
 
-use alloc::raw_vec::RawVec;
+use alloc::raw_vec::{CollectionAllocErr, RawVec};
 
 use super::range::RangeArgument;
 use super::vec::Vec;
@@ -539,6 +539,68 @@ impl<T> VecDeque<T> {
         }
     }
 
+    /// Tries to reserve the minimum capacity for exactly `additional` more
+    /// elements to be inserted in the given `VecDeque`.
+    ///
+    /// As with `reserve_exact`, the ring buffer's capacity is always a power
+    /// of two, so this is the same as `try_reserve`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(try_reserve)]
+    /// use std::collections::VecDeque;
+    ///
+    /// let mut buf: VecDeque<i32> = vec![1].into_iter().collect();
+    /// buf.try_reserve_exact(10).unwrap();
+    /// assert!(buf.capacity() >= 11);
+    /// ```
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), CollectionAllocErr> {
+        self.try_reserve(additional)
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to be
+    /// inserted in the given `VecDeque`.
+    ///
+    /// This is the fallible counterpart of `reserve`: on capacity overflow or
+    /// allocator failure the error is returned and the `VecDeque` is left
+    /// unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(try_reserve)]
+    /// use std::collections::{CollectionAllocErr, VecDeque};
+    ///
+    /// let mut buf: VecDeque<i32> = vec![1].into_iter().collect();
+    /// buf.try_reserve(10).unwrap();
+    /// assert!(buf.capacity() >= 11);
+    ///
+    /// assert_eq!(buf.try_reserve(usize::max_value()),
+    ///            Err(CollectionAllocErr::CapacityOverflow));
+    /// ```
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), CollectionAllocErr> {
+        let old_cap = self.cap();
+        let used_cap = self.len() + 1;
+        let new_cap = match used_cap.checked_add(additional)
+                                    .and_then(|needed_cap| needed_cap.checked_next_power_of_two()) {
+            Some(new_cap) => new_cap,
+            None => return Err(CollectionAllocErr::CapacityOverflow),
+        };
+
+        if new_cap > self.capacity() {
+            if let Err(e) = self.buf.try_reserve_exact(used_cap, new_cap - used_cap) {
+                return Err(e);
+            }
+            unsafe {
+                self.handle_cap_increase(old_cap);
+            }
+        }
+        Ok(())
+    }
+
     /// Shrinks the capacity of the `VecDeque` as much as possible.
     ///
     /// It will drop down as close as possible to the length but the allocator may still inform the
@@ -8,9 +8,11 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::collections::Bound::{Excluded, Included, Unbounded, self};
 use std::collections::btree_map::Entry::{Occupied, Vacant};
+use std::heap::{Alloc, Heap};
 use std::rc::Rc;
 
 #[test]
@@ -393,6 +395,53 @@ fn test_clone() {
     }
 }
 
+#[derive(Clone)]
+struct Counting {
+    live: Rc<Cell<usize>>,
+}
+
+unsafe impl Alloc for Counting {
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8 {
+        self.live.set(self.live.get() + 1);
+        Heap.alloc(size, align)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8, size: usize, align: usize) {
+        self.live.set(self.live.get() - 1);
+        Heap.dealloc(ptr, size, align)
+    }
+}
+
+#[test]
+fn test_custom_allocator() {
+    let live = Rc::new(Cell::new(0));
+    {
+        let mut map = BTreeMap::new_in(Counting { live: live.clone() });
+        assert_eq!(live.get(), 1);
+        for i in 0..1000 {
+            map.insert(i, i);
+        }
+        // 1000 keys need more than one node per 11 of them
+        assert!(live.get() > 1000 / 11);
+
+        let mut other = map.clone();
+        for i in 0..500 {
+            assert_eq!(other.remove(&(2 * i)), Some(2 * i));
+        }
+        let mut more = BTreeMap::new_in(Counting { live: live.clone() });
+        more.extend((1000..1100).map(|i| (i, i)));
+        other.append(&mut more);
+        assert_eq!(other.len(), 600);
+        assert!(other.keys().cloned().eq((0..500).map(|i| 2 * i + 1).chain(1000..1100)));
+
+        map.clear();
+        assert!(map.is_empty());
+        let sum = other.into_iter().take(10).fold(0, |a, (k, _)| a + k);
+        assert_eq!(sum, (0..10).fold(0, |a, i| a + 2 * i + 1));
+    }
+    assert_eq!(live.get(), 0);
+}
+
 #[test]
 #[allow(dead_code)]
 fn test_variance() {
@@ -12,6 +12,7 @@
 
 #![feature(binary_heap_extras)]
 #![feature(binary_heap_append)]
+#![feature(allocator_api)]
 #![feature(box_syntax)]
 #![feature(btree_append)]
 #![feature(btree_range)]
@@ -30,6 +31,7 @@
 #![feature(str_char)]
 #![feature(str_escape)]
 #![feature(test)]
+#![feature(try_reserve)]
 #![feature(unboxed_closures)]
 #![feature(unicode)]
 #![feature(vec_deque_contains)]
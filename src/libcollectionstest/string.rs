@@ -9,6 +9,7 @@
 // except according to those terms.
 
 use std::borrow::Cow;
+use std::collections::CollectionAllocErr;
 use std::iter::repeat;
 
 use test::Bencher;
@@ -388,6 +389,20 @@ fn test_into_boxed_str() {
     assert_eq!(&*ys, "hello my name is bob");
 }
 
+#[test]
+fn test_try_reserve() {
+    let mut s = String::from("abc");
+    assert_eq!(s.try_reserve(10), Ok(()));
+    assert!(s.capacity() >= 13);
+    assert_eq!(s.try_reserve_exact(20), Ok(()));
+    assert!(s.capacity() >= 23);
+
+    let cap = s.capacity();
+    assert_eq!(s.try_reserve(usize::max_value()), Err(CollectionAllocErr::CapacityOverflow));
+    assert_eq!(s.capacity(), cap);
+    assert_eq!(s, "abc");
+}
+
 #[bench]
 fn bench_with_capacity(b: &mut Bencher) {
     b.iter(|| {
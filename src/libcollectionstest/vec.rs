@@ -9,8 +9,12 @@
 // except according to those terms.
 
 use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::CollectionAllocErr;
+use std::heap::{Alloc, Heap};
 use std::iter::{FromIterator, repeat};
 use std::mem::size_of;
+use std::rc::Rc;
 
 use test::Bencher;
 
@@ -79,6 +83,72 @@ fn test_reserve() {
     assert!(v.capacity() >= 33)
 }
 
+#[test]
+fn test_try_reserve() {
+    let mut v: Vec<u32> = vec![1, 2, 3];
+
+    assert_eq!(v.try_reserve(10), Ok(()));
+    assert!(v.capacity() >= 13);
+    assert_eq!(v.try_reserve_exact(20), Ok(()));
+    assert!(v.capacity() >= 23);
+
+    let cap = v.capacity();
+    assert_eq!(v.try_reserve(usize::max_value()), Err(CollectionAllocErr::CapacityOverflow));
+    assert_eq!(v.try_reserve_exact(usize::max_value() / 2),
+               Err(CollectionAllocErr::CapacityOverflow));
+    assert_eq!(v.capacity(), cap);
+    assert_eq!(v, [1, 2, 3]);
+}
+
+#[derive(Clone)]
+struct Counting {
+    live: Rc<Cell<usize>>,
+    fail: bool,
+}
+
+unsafe impl Alloc for Counting {
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8 {
+        if self.fail {
+            return ::std::ptr::null_mut();
+        }
+        self.live.set(self.live.get() + size);
+        Heap.alloc(size, align)
+    }
+
+    unsafe fn dealloc(&mut self, ptr: *mut u8, size: usize, align: usize) {
+        self.live.set(self.live.get() - size);
+        Heap.dealloc(ptr, size, align)
+    }
+}
+
+#[test]
+fn test_custom_allocator() {
+    let live = Rc::new(Cell::new(0));
+    {
+        let mut v = Vec::new_in(Counting { live: live.clone(), fail: false });
+        assert_eq!(live.get(), 0);
+        v.extend(0..100u32);
+        assert_eq!(live.get(), v.capacity() * size_of::<u32>());
+
+        let w = v.split_off(50);
+        assert!(w.iter().cloned().eq(50..100));
+        let c = w.clone();
+        assert_eq!(c, w);
+        assert_eq!(live.get(), (v.capacity() + w.capacity() + c.capacity()) * size_of::<u32>());
+
+        v.truncate(0);
+        v.shrink_to_fit();
+        assert_eq!(v.capacity(), 0);
+        let sum = w.into_iter().fold(0, |a, b| a + b);
+        assert_eq!(sum, (50..100).fold(0, |a, b| a + b));
+    }
+    assert_eq!(live.get(), 0);
+
+    let mut v: Vec<u8, _> = Vec::new_in(Counting { live: live.clone(), fail: true });
+    assert_eq!(v.try_reserve(1), Err(CollectionAllocErr::AllocErr));
+    assert_eq!(v.capacity(), 0);
+}
+
 #[test]
 fn test_extend() {
     let mut v = Vec::new();
@@ -8,7 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use std::collections::VecDeque;
+use std::collections::{CollectionAllocErr, VecDeque};
 use std::fmt::Debug;
 
 use test;
@@ -283,6 +283,22 @@ fn test_reserve() {
     assert!(d.capacity() >= 51);
 }
 
+#[test]
+fn test_try_reserve() {
+    let mut d = VecDeque::new();
+    d.push_back(0);
+    d.push_front(1);
+    assert_eq!(d.try_reserve(50), Ok(()));
+    assert!(d.capacity() >= 52);
+    assert_eq!(d.try_reserve_exact(100), Ok(()));
+    assert!(d.capacity() >= 102);
+
+    let cap = d.capacity();
+    assert_eq!(d.try_reserve(usize::max_value()), Err(CollectionAllocErr::CapacityOverflow));
+    assert_eq!(d.capacity(), cap);
+    assert_eq!(d.iter().cloned().collect::<Vec<_>>(), [1, 0]);
+}
+
 #[test]
 fn test_swap() {
     let mut d: VecDeque<_> = (0..5).collect();
@@ -9,6 +9,8 @@
 // except according to those terms.
 
 use hir::def_id::DefId;
+use rustc_data_structures::fnv::FnvHashMap;
+use std::cell::RefCell;
 use std::rc::Rc;
 
 use super::dep_node::DepNode;
@@ -18,13 +20,57 @@ use super::thread::{DepGraphThreadData, DepMessage};
 
 #[derive(Clone)]
 pub struct DepGraph {
-    data: Rc<DepGraphThreadData>
+    data: Rc<DepGraphThreadData>,
+
+    /// Work products saved by the previous session which are still
+    /// available in the incremental directory, keyed by their id.
+    previous_work_products: Rc<RefCell<FnvHashMap<String, WorkProduct>>>,
+
+    /// Why work from the previous session had to be redone, recorded for
+    /// `-Z incremental-explain` only. Nodes are identified by name.
+    dirty_reasons: Rc<RefCell<Vec<(String, DirtyReason<String>)>>>,
+}
+
+/// Why a node, or a codegen unit, of the previous incremental session was
+/// invalidated. `N` identifies nodes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DirtyReason<N> {
+    /// The HIR of the item changed.
+    HirChanged,
+    /// The item of another crate changed, according to the hashes in the
+    /// metadata of that crate.
+    MetaDataChanged,
+    /// An input of the node no longer exists; this is its path.
+    InputRemoved(String),
+    /// An input of the node is dirty itself.
+    DirtyInput(N),
+    /// An input which isn't tracked by the dep-graph changed, like the
+    /// `input_hash` of a work product.
+    UntrackedInputChanged,
+}
+
+/// A work product is an intermediate result of compilation, such as the
+/// object file of a codegen unit, which is saved into the incremental
+/// directory so that a later session can re-use it instead of producing it
+/// again.
+#[derive(Clone, Debug, RustcEncodable, RustcDecodable)]
+pub struct WorkProduct {
+    /// A hash of everything besides the dep-graph which determines the
+    /// contents of the work product. It is only valid to re-use the work
+    /// product if this hash is unchanged.
+    pub input_hash: u64,
+
+    /// The names of the files making up the work product, relative to the
+    /// incremental directory.
+    pub saved_files: Vec<String>,
 }
 
 impl DepGraph {
     pub fn new(enabled: bool) -> DepGraph {
         DepGraph {
-            data: Rc::new(DepGraphThreadData::new(enabled))
+            data: Rc::new(DepGraphThreadData::new(enabled)),
+            previous_work_products: Rc::new(RefCell::new(FnvHashMap())),
+            dirty_reasons: Rc::new(RefCell::new(Vec::new())),
         }
     }
 
@@ -68,4 +114,27 @@ impl DepGraph {
     pub fn write(&self, v: DepNode<DefId>) {
         self.data.enqueue(DepMessage::Write(v));
     }
+
+    /// Records a work product left behind by the previous session. This is
+    /// done while loading the dep-graph, for work products whose saved files
+    /// still exist.
+    pub fn insert_previous_work_product(&self, id: String, work_product: WorkProduct) {
+        self.previous_work_products.borrow_mut().insert(id, work_product);
+    }
+
+    /// Returns the work product with the given id saved by the previous
+    /// session, if any. Whether it can be re-used is up to the caller.
+    pub fn previous_work_product(&self, id: &str) -> Option<WorkProduct> {
+        self.previous_work_products.borrow().get(id).cloned()
+    }
+
+    /// Records why the node named `node` was invalidated.
+    pub fn record_dirty_reason(&self, node: String, reason: DirtyReason<String>) {
+        self.dirty_reasons.borrow_mut().push((node, reason));
+    }
+
+    /// Returns the reasons recorded with `record_dirty_reason`, in order.
+    pub fn dirty_reasons(&self) -> Vec<(String, DirtyReason<String>)> {
+        self.dirty_reasons.borrow().clone()
+    }
 }
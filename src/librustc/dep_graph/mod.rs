@@ -19,6 +19,6 @@ mod visit;
 
 pub use self::dep_tracking_map::{DepTrackingMap, DepTrackingMapConfig};
 pub use self::dep_node::DepNode;
-pub use self::graph::DepGraph;
+pub use self::graph::{DepGraph, DirtyReason, WorkProduct};
 pub use self::query::DepGraphQuery;
 pub use self::visit::visit_all_items_in_krate;
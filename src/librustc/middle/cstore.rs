@@ -63,6 +63,7 @@ pub struct LinkMeta {
 pub struct CrateSource {
     pub dylib: Option<(PathBuf, PathKind)>,
     pub rlib: Option<(PathBuf, PathKind)>,
+    pub rmeta: Option<(PathBuf, PathKind)>,
     pub cnum: ast::CrateNum,
 }
 
@@ -236,10 +237,15 @@ pub trait CrateStore<'tcx> : Any {
     fn plugin_registrar_fn(&self, cnum: ast::CrateNum) -> Option<DefId>;
     fn native_libraries(&self, cnum: ast::CrateNum) -> Vec<(NativeLibraryKind, String)>;
     fn reachable_ids(&self, cnum: ast::CrateNum) -> Vec<DefId>;
+    fn item_hashes(&self, cnum: ast::CrateNum) -> FnvHashMap<DefIndex, u64>;
 
     // resolve
     fn def_key(&self, def: DefId) -> hir_map::DefKey;
     fn relative_def_path(&self, def: DefId) -> hir_map::DefPath;
+    fn retrace_path(&self,
+                    cnum: ast::CrateNum,
+                    path_data: &[hir_map::DisambiguatedDefPathData])
+                    -> Option<DefId>;
     fn variant_kind(&self, def_id: DefId) -> Option<VariantKind>;
     fn struct_ctor_def_id(&self, struct_def_id: DefId) -> Option<DefId>;
     fn tuple_struct_definition_if_ctor(&self, did: DefId) -> Option<DefId>;
@@ -274,10 +280,11 @@ pub trait CrateStore<'tcx> : Any {
     fn encode_metadata(&self,
                        tcx: &TyCtxt<'tcx>,
                        reexports: &def::ExportMap,
-                       item_symbols: &RefCell<NodeMap<String>>,
+                       item_symbols: Option<&RefCell<NodeMap<String>>>,
                        link_meta: &LinkMeta,
                        reachable: &NodeSet,
                        mir_map: &MirMap<'tcx>,
+                       item_hashes: &DefIdMap<u64>,
                        krate: &hir::Crate) -> Vec<u8>;
     fn metadata_encoding_version(&self) -> &[u8];
 }
@@ -426,10 +433,16 @@ impl<'tcx> CrateStore<'tcx> for DummyCrateStore {
     fn native_libraries(&self, cnum: ast::CrateNum) -> Vec<(NativeLibraryKind, String)>
         { bug!("native_libraries") }
     fn reachable_ids(&self, cnum: ast::CrateNum) -> Vec<DefId> { bug!("reachable_ids") }
+    fn item_hashes(&self, cnum: ast::CrateNum) -> FnvHashMap<DefIndex, u64>
+        { bug!("item_hashes") }
 
     // resolve
     fn def_key(&self, def: DefId) -> hir_map::DefKey { bug!("def_key") }
     fn relative_def_path(&self, def: DefId) -> hir_map::DefPath { bug!("relative_def_path") }
+    fn retrace_path(&self,
+                    cnum: ast::CrateNum,
+                    path_data: &[hir_map::DisambiguatedDefPathData])
+                    -> Option<DefId> { bug!("retrace_path") }
     fn variant_kind(&self, def_id: DefId) -> Option<VariantKind> { bug!("variant_kind") }
     fn struct_ctor_def_id(&self, struct_def_id: DefId) -> Option<DefId>
         { bug!("struct_ctor_def_id") }
@@ -472,10 +485,11 @@ impl<'tcx> CrateStore<'tcx> for DummyCrateStore {
     fn encode_metadata(&self,
                        tcx: &TyCtxt<'tcx>,
                        reexports: &def::ExportMap,
-                       item_symbols: &RefCell<NodeMap<String>>,
+                       item_symbols: Option<&RefCell<NodeMap<String>>>,
                        link_meta: &LinkMeta,
                        reachable: &NodeSet,
                        mir_map: &MirMap<'tcx>,
+                       item_hashes: &DefIdMap<u64>,
                        krate: &hir::Crate) -> Vec<u8> { vec![] }
     fn metadata_encoding_version(&self) -> &[u8] { bug!("metadata_encoding_version") }
 }
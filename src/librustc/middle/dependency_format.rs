@@ -90,6 +90,30 @@ pub enum Linkage {
 }
 
 pub fn calculate(sess: &session::Session) {
+    // Crates only found as `.rmeta` files are good for type-checking, but
+    // their metadata has no symbols to translate against.
+    for cnum in sess.cstore.crates() {
+        let src = sess.cstore.used_crate_source(cnum);
+        if src.rlib.is_none() && src.dylib.is_none() {
+            sess.err(&format!("crate `{}` required to be available in rlib or dylib \
+                               format, but it was only found as metadata (`.rmeta`)",
+                              sess.cstore.crate_name(cnum)));
+        }
+    }
+
+    // The shims generated for a `#[global_allocator]` replace the allocator
+    // crate for the whole program, so they only make sense in an executable.
+    if let Some(span) = sess.global_allocator.get() {
+        let only_executables = sess.crate_types.borrow().iter().all(|ty| {
+            *ty == config::CrateTypeExecutable
+        });
+        if !only_executables {
+            sess.span_err(span, "`#[global_allocator]` can only be defined in a \
+                                 binary crate");
+        }
+    }
+    sess.abort_if_errors();
+
     let mut fmts = sess.dependency_formats.borrow_mut();
     for &ty in sess.crate_types.borrow().iter() {
         let linkage = calculate_type(sess, ty);
@@ -308,7 +332,8 @@ fn activate_allocator(sess: &session::Session, list: &mut DependencyList) {
 }
 
 // After the linkage for a crate has been determined we need to verify that
-// there's only going to be one allocator in the output.
+// there's only going to be one allocator in the output, counting a
+// `#[global_allocator]` defined by the local crate.
 fn verify_ok(sess: &session::Session, list: &[Linkage]) {
     if list.len() == 0 {
         return
@@ -322,6 +347,11 @@ fn verify_ok(sess: &session::Session, list: &[Linkage]) {
         if let Linkage::NotLinked = *linkage {
             continue
         }
+        if let Some(span) = sess.global_allocator.get() {
+            sess.span_err(span, &format!("the `#[global_allocator]` in this crate \
+                                          conflicts with the allocator crate `{}`",
+                                         sess.cstore.crate_name(cnum)));
+        }
         if let Some(prev_alloc) = allocator {
             let prev_name = sess.cstore.crate_name(prev_alloc);
             let cur_name = sess.cstore.crate_name(cnum);
@@ -68,6 +68,7 @@ pub enum OutputType {
     Object,
     Exe,
     DepInfo,
+    Metadata,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -86,7 +87,8 @@ impl OutputType {
     fn is_compatible_with_codegen_units_and_single_output_file(&self) -> bool {
         match *self {
             OutputType::Exe |
-            OutputType::DepInfo => true,
+            OutputType::DepInfo |
+            OutputType::Metadata => true,
             OutputType::Bitcode |
             OutputType::Assembly |
             OutputType::LlvmAssembly |
@@ -94,7 +96,7 @@ impl OutputType {
         }
     }
 
-    fn shorthand(&self) -> &'static str {
+    pub fn shorthand(&self) -> &'static str {
         match *self {
             OutputType::Bitcode => "llvm-bc",
             OutputType::Assembly => "asm",
@@ -102,6 +104,7 @@ impl OutputType {
             OutputType::Object => "obj",
             OutputType::Exe => "link",
             OutputType::DepInfo => "dep-info",
+            OutputType::Metadata => "metadata",
         }
     }
 }
@@ -212,6 +215,7 @@ impl OutputFilenames {
             OutputType::LlvmAssembly => base.with_extension("ll"),
             OutputType::Object => base.with_extension("o"),
             OutputType::DepInfo => base.with_extension("d"),
+            OutputType::Metadata => base.with_extension("rmeta"),
             OutputType::Exe => base,
         }
     }
@@ -281,6 +285,18 @@ impl Options {
             self.debugging_opts.dump_dep_graph ||
             self.debugging_opts.query_dep_graph
     }
+
+    /// True if the crate has to be translated to produce the requested
+    /// outputs. Metadata and dep-info are produced right after analysis.
+    pub fn needs_trans(&self) -> bool {
+        self.output_types.keys().any(|output_type| {
+            match *output_type {
+                OutputType::Metadata |
+                OutputType::DepInfo => false,
+                _ => true,
+            }
+        })
+    }
 }
 
 // The type of entry function, so
@@ -317,6 +333,13 @@ impl Passes {
     }
 }
 
+/// The format of the report of `-Z incremental-explain`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ExplainFormat {
+    Text,
+    Json,
+}
+
 /// Declare a macro that will define all CodegenOptions/DebuggingOptions fields and parsers all
 /// at once. The goal of this macro is to define an interface that can be
 /// programmatically used by the option parser in order to initialize the struct
@@ -402,11 +425,13 @@ macro_rules! options {
             Some("a space-separated list of passes, or `all`");
         pub const parse_opt_uint: Option<&'static str> =
             Some("a number");
+        pub const parse_explain_format: Option<&'static str> =
+            Some("`text` or `json`");
     }
 
     #[allow(dead_code)]
     mod $mod_set {
-        use super::{$struct_name, Passes, SomePasses, AllPasses};
+        use super::{$struct_name, Passes, SomePasses, AllPasses, ExplainFormat};
 
         $(
             pub fn $opt(cg: &mut $struct_name, v: Option<&str>) -> bool {
@@ -510,6 +535,14 @@ macro_rules! options {
                 }
             }
         }
+
+        fn parse_explain_format(slot: &mut Option<ExplainFormat>, v: Option<&str>) -> bool {
+            match v {
+                Some("text") => { *slot = Some(ExplainFormat::Text); true }
+                Some("json") => { *slot = Some(ExplainFormat::Json); true }
+                _ => false,
+            }
+        }
     }
 ) }
 
@@ -646,6 +679,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
           "attempt to recover from parse errors (experimental)"),
     incremental: Option<String> = (None, parse_opt_string,
           "enable incremental compilation (experimental)"),
+    incremental_explain: Option<ExplainFormat> = (None, parse_explain_format,
+          "explain why work of the previous incremental session was redone \
+           (`text` or `json`)"),
     dump_dep_graph: bool = (false, parse_bool,
           "dump the dependency graph to $RUST_DEP_GRAPH (default: /tmp/dep_graph.gv)"),
     query_dep_graph: bool = (false, parse_bool,
@@ -906,7 +942,7 @@ pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
                "NAME"),
         opt::multi_s("", "emit", "Comma separated list of types of output for \
                               the compiler to emit",
-                 "[asm|llvm-bc|llvm-ir|obj|link|dep-info]"),
+                 "[asm|llvm-bc|llvm-ir|obj|link|dep-info|metadata]"),
         opt::multi_s("", "print", "Comma separated list of compiler information to \
                                print on stdout",
                  "[crate-name|file-names|sysroot|cfg|target-list]"),
@@ -1076,6 +1112,7 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
                     "obj" => OutputType::Object,
                     "link" => OutputType::Exe,
                     "dep-info" => OutputType::DepInfo,
+                    "metadata" => OutputType::Metadata,
                     part => {
                         early_error(error_format, &format!("unknown emission type: `{}`",
                                                     part))
@@ -86,6 +86,10 @@ pub struct Session {
     /// didn't already find one, and this tracks what was injected.
     pub injected_allocator: Cell<Option<ast::CrateNum>>,
 
+    /// Set by the metadata::creader module to the span of the shims generated
+    /// for a `#[global_allocator]` static in the local crate, if any.
+    pub global_allocator: Cell<Option<Span>>,
+
     /// Names of all bang-style macros and syntax extensions
     /// available in this crate
     pub available_macros: RefCell<HashSet<Name>>,
@@ -502,6 +506,7 @@ pub fn build_session_(sopts: config::Options,
         recursion_limit: Cell::new(64),
         next_node_id: Cell::new(1),
         injected_allocator: Cell::new(None),
+        global_allocator: Cell::new(None),
         available_macros: RefCell::new(HashSet::new()),
         imported_macro_spans: RefCell::new(HashMap::new()),
     };
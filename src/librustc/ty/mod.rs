@@ -18,7 +18,7 @@ pub use self::IntVarValue::*;
 pub use self::LvaluePreference::*;
 pub use self::fold::TypeFoldable;
 
-use dep_graph::{self, DepNode};
+use dep_graph::{self, DepGraph, DepNode};
 use hir::map as ast_map;
 use middle;
 use middle::cstore::{self, LOCAL_CRATE};
@@ -2095,10 +2095,12 @@ impl LvaluePreference {
 /// these share the pattern that if the id is local, it should have been loaded
 /// into the map by the `typeck::collect` phase.  If the def-id is external,
 /// then we have to go consult the crate loading code (and cache the result for
-/// the future).
+/// the future). Loading from the crate store registers a read of the
+/// metadata of `def_id`.
 fn lookup_locally_or_in_crate_store<M, F>(descr: &str,
                                           def_id: DefId,
                                           map: &M,
+                                          dep_graph: &DepGraph,
                                           load_external: F)
                                           -> M::Value where
     M: MemoizationMap<Key=DefId>,
@@ -2108,6 +2110,7 @@ fn lookup_locally_or_in_crate_store<M, F>(descr: &str,
         if def_id.is_local() {
             bug!("No def'n found for {:?} in tcx.{}", def_id, descr);
         }
+        dep_graph.read(DepNode::MetaData(def_id));
         load_external()
     })
 }
@@ -2425,14 +2428,14 @@ impl<'tcx> TyCtxt<'tcx> {
 
     pub fn impl_or_trait_item(&self, id: DefId) -> ImplOrTraitItem<'tcx> {
         lookup_locally_or_in_crate_store(
-            "impl_or_trait_items", id, &self.impl_or_trait_items,
+            "impl_or_trait_items", id, &self.impl_or_trait_items, &self.dep_graph,
             || self.sess.cstore.impl_or_trait_item(self, id)
                    .expect("missing ImplOrTraitItem in metadata"))
     }
 
     pub fn trait_item_def_ids(&self, id: DefId) -> Rc<Vec<ImplOrTraitItemId>> {
         lookup_locally_or_in_crate_store(
-            "trait_item_def_ids", id, &self.trait_item_def_ids,
+            "trait_item_def_ids", id, &self.trait_item_def_ids, &self.dep_graph,
             || Rc::new(self.sess.cstore.trait_item_def_ids(id)))
     }
 
@@ -2440,7 +2443,7 @@ impl<'tcx> TyCtxt<'tcx> {
     /// an inherent impl.
     pub fn impl_trait_ref(&self, id: DefId) -> Option<TraitRef<'tcx>> {
         lookup_locally_or_in_crate_store(
-            "impl_trait_refs", id, &self.impl_trait_refs,
+            "impl_trait_refs", id, &self.impl_trait_refs, &self.dep_graph,
             || self.sess.cstore.impl_trait_ref(self, id))
     }
 
@@ -2498,14 +2501,14 @@ impl<'tcx> TyCtxt<'tcx> {
     // the type cache. Returns the type parameters and type.
     pub fn lookup_item_type(&self, did: DefId) -> TypeScheme<'tcx> {
         lookup_locally_or_in_crate_store(
-            "tcache", did, &self.tcache,
+            "tcache", did, &self.tcache, &self.dep_graph,
             || self.sess.cstore.item_type(self, did))
     }
 
     /// Given the did of a trait, returns its canonical trait ref.
     pub fn lookup_trait_def(&self, did: DefId) -> &'tcx TraitDef<'tcx> {
         lookup_locally_or_in_crate_store(
-            "trait_defs", did, &self.trait_defs,
+            "trait_defs", did, &self.trait_defs, &self.dep_graph,
             || self.alloc_trait_def(self.sess.cstore.trait_def(self, did))
         )
     }
@@ -2515,7 +2518,7 @@ impl<'tcx> TyCtxt<'tcx> {
     /// use lookup_adt_def instead.
     pub fn lookup_adt_def_master(&self, did: DefId) -> AdtDefMaster<'tcx> {
         lookup_locally_or_in_crate_store(
-            "adt_defs", did, &self.adt_defs,
+            "adt_defs", did, &self.adt_defs, &self.dep_graph,
             || self.sess.cstore.adt_def(self, did)
         )
     }
@@ -2530,14 +2533,14 @@ impl<'tcx> TyCtxt<'tcx> {
     /// Given the did of an item, returns its full set of predicates.
     pub fn lookup_predicates(&self, did: DefId) -> GenericPredicates<'tcx> {
         lookup_locally_or_in_crate_store(
-            "predicates", did, &self.predicates,
+            "predicates", did, &self.predicates, &self.dep_graph,
             || self.sess.cstore.item_predicates(self, did))
     }
 
     /// Given the did of a trait, returns its superpredicates.
     pub fn lookup_super_predicates(&self, did: DefId) -> GenericPredicates<'tcx> {
         lookup_locally_or_in_crate_store(
-            "super_predicates", did, &self.super_predicates,
+            "super_predicates", did, &self.super_predicates, &self.dep_graph,
             || self.sess.cstore.item_super_predicates(self, did))
     }
 
@@ -2601,7 +2604,7 @@ impl<'tcx> TyCtxt<'tcx> {
 
     pub fn item_variances(&self, item_id: DefId) -> Rc<ItemVariances> {
         lookup_locally_or_in_crate_store(
-            "item_variance_map", item_id, &self.item_variance_map,
+            "item_variance_map", item_id, &self.item_variance_map, &self.dep_graph,
             || Rc::new(self.sess.cstore.item_variances(item_id)))
     }
 
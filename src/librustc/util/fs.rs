@@ -10,6 +10,8 @@
 
 use std::path::{self, Path, PathBuf};
 use std::ffi::OsString;
+use std::fs;
+use std::io;
 
 // Unfortunately, on windows, it looks like msvcrt.dll is silently translating
 // verbatim paths under the hood to non-verbatim paths! This manifests itself as
@@ -53,3 +55,18 @@ pub fn fix_windows_verbatim_for_gcc(p: &Path) -> PathBuf {
         _ => p.to_path_buf(),
     }
 }
+
+/// Hard-links `p` to `q`, or copies it if linking isn't possible (e.g.
+/// because they are on different filesystems). Any existing file at `q` is
+/// replaced.
+pub fn link_or_copy<P: AsRef<Path>, Q: AsRef<Path>>(p: P, q: Q) -> io::Result<()> {
+    let p = p.as_ref();
+    let q = q.as_ref();
+    if q.exists() {
+        fs::remove_file(q)?;
+    }
+    match fs::hard_link(p, q) {
+        Ok(()) => Ok(()),
+        Err(_) => fs::copy(p, q).map(|_| ()),
+    }
+}
@@ -225,6 +225,16 @@ pub fn compile_input(sess: &Session,
 
             result?;
 
+            if sess.opts.output_types.contains_key(&OutputType::Metadata) {
+                write_metadata_file(tcx, mir_map.as_ref().unwrap(), &analysis, &id, &outputs)?;
+            }
+
+            // Metadata and dep-info are written by now, so there is nothing
+            // left to do if no other output was requested.
+            if !sess.opts.needs_trans() {
+                return Err(0usize);
+            }
+
             if log_enabled!(::log::INFO) {
                 println!("Pre-trans");
                 tcx.print_debug_stats();
@@ -979,6 +989,10 @@ pub fn phase_4_translate_to_llvm<'tcx>(tcx: &TyCtxt<'tcx>,
              "translation",
              move || trans::trans_crate(tcx, &mir_map, analysis));
 
+    time(time_passes,
+         "explain dirty nodes",
+         move || rustc_incremental::explain_dirty(tcx));
+
     time(time_passes,
          "assert dep graph",
          move || rustc_incremental::assert_dep_graph(tcx));
@@ -1037,6 +1051,27 @@ fn escape_dep_filename(filename: &str) -> String {
     filename.replace(" ", "\\ ")
 }
 
+/// Writes the metadata of the crate on its own for `--emit=metadata`, before
+/// translating it, so that dependent crates can be type-checked early.
+fn write_metadata_file<'tcx>(tcx: &TyCtxt<'tcx>,
+                             mir_map: &MirMap<'tcx>,
+                             analysis: &ty::CrateAnalysis,
+                             crate_name: &str,
+                             outputs: &OutputFilenames)
+                             -> CompileResult {
+    let sess = tcx.sess;
+    let metadata = time(sess.time_passes(),
+                        "encoding metadata",
+                        || trans::encode_metadata_only(tcx, mir_map, analysis));
+
+    let path = link::metadata_filename(sess, crate_name, outputs);
+    if let Err(err) = fs::File::create(&path).and_then(|mut file| file.write_all(&metadata)) {
+        sess.err(&format!("failed to write metadata to `{}`: {}", path.display(), err));
+        return Err(sess.err_count());
+    }
+    Ok(())
+}
+
 fn write_out_deps(sess: &Session, outputs: &OutputFilenames, id: &str) {
     let mut out_filenames = Vec::new();
     for output_type in sess.opts.output_types.keys() {
@@ -1048,6 +1083,9 @@ fn write_out_deps(sess: &Session, outputs: &OutputFilenames, id: &str) {
                     out_filenames.push(p);
                 }
             }
+            OutputType::Metadata => {
+                out_filenames.push(link::metadata_filename(sess, id, outputs));
+            }
             _ => {
                 out_filenames.push(file);
             }
@@ -13,9 +13,11 @@
 
 use std::hash::{Hash, SipHasher, Hasher};
 use rustc::hir::def_id::{CRATE_DEF_INDEX, DefId};
+use rustc::hir;
 use rustc::hir::svh::Svh;
 use rustc::ty;
 use rustc::hir::intravisit::{self, Visitor};
+use rustc::util::nodemap::DefIdMap;
 
 use self::svh_visitor::StrictVersionHashVisitor;
 
@@ -25,6 +27,10 @@ pub trait SvhCalculate {
 
     /// Calculate the SVH for a particular item.
     fn calculate_item_hash(&self, def_id: DefId) -> u64;
+
+    /// Calculate the SVHs of all items of the krate, which are stored in
+    /// its metadata.
+    fn calculate_item_hashes(&self) -> DefIdMap<u64>;
 }
 
 impl<'tcx> SvhCalculate for ty::TyCtxt<'tcx> {
@@ -95,6 +101,27 @@ impl<'tcx> SvhCalculate for ty::TyCtxt<'tcx> {
 
         state.finish()
     }
+
+    fn calculate_item_hashes(&self) -> DefIdMap<u64> {
+        let mut visitor = ItemHashesVisitor {
+            tcx: self,
+            hashes: DefIdMap(),
+        };
+        self.map.krate().visit_all_items(&mut visitor);
+        visitor.hashes
+    }
+}
+
+struct ItemHashesVisitor<'a, 'tcx: 'a> {
+    tcx: &'a ty::TyCtxt<'tcx>,
+    hashes: DefIdMap<u64>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for ItemHashesVisitor<'a, 'tcx> {
+    fn visit_item(&mut self, item: &'tcx hir::Item) {
+        let def_id = self.tcx.map.local_def_id(item.id);
+        self.hashes.insert(def_id, self.tcx.calculate_item_hash(def_id));
+    }
 }
 
 // FIXME (#14132): Even this SVH computation still has implementation
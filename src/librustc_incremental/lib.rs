@@ -37,5 +37,10 @@ mod persist;
 
 pub use assert_dep_graph::assert_dep_graph;
 pub use calculate_svh::SvhCalculate;
+pub use persist::codegen_unit_name;
+pub use persist::explain_dirty;
 pub use persist::load_dep_graph;
+pub use persist::node_name;
 pub use persist::save_dep_graph;
+pub use persist::save_trans_partition;
+pub use persist::save_work_products;
@@ -10,7 +10,7 @@
 
 //! The data that we will serialize and deserialize.
 
-use rustc::dep_graph::DepNode;
+use rustc::dep_graph::{DepNode, WorkProduct};
 use rustc_serialize::{Decoder as RustcDecoder, Encoder as RustcEncoder};
 
 use super::directory::DefPathIndex;
@@ -20,6 +20,10 @@ pub struct SerializedDepGraph {
     pub nodes: Vec<DepNode<DefPathIndex>>,
     pub edges: Vec<SerializedEdge>,
     pub hashes: Vec<SerializedHash>,
+
+    /// hashes of the `MetaData` nodes, i.e. of the items of other
+    /// crates that we read, as recorded in the metadata of those crates
+    pub metadata_hashes: Vec<SerializedHash>,
 }
 
 pub type SerializedEdge = (DepNode<DefPathIndex>, DepNode<DefPathIndex>);
@@ -28,6 +32,14 @@ pub type SerializedEdge = (DepNode<DefPathIndex>, DepNode<DefPathIndex>);
 pub struct SerializedHash {
     pub index: DefPathIndex,
 
-    /// the hash itself, computed by `calculate_item_hash`
+    /// the hash itself, computed by `calculate_item_hash` (in this crate
+    /// or, for `MetaData` nodes, in the crate of the item)
     pub hash: u64,
 }
+
+/// A work product along with its id, as saved in the incremental directory.
+#[derive(Debug, RustcEncodable, RustcDecodable)]
+pub struct SerializedWorkProduct {
+    pub id: String,
+    pub work_product: WorkProduct,
+}
@@ -18,8 +18,10 @@ use rustc::hir::map::DefPath;
 use rustc::hir::def_id::DefId;
 use rustc::ty;
 use rustc::util::nodemap::DefIdMap;
+use rustc_data_structures::fnv::FnvHashMap;
 use rustc_serialize::{Decoder as RustcDecoder, Encoder as RustcEncoder};
 use std::fmt::{self, Debug};
+use syntax::ast::CrateNum;
 
 /// Index into the DefIdDirectory
 #[derive(Copy, Clone, Debug, PartialOrd, Ord, Hash, PartialEq, Eq,
@@ -32,17 +34,65 @@ pub struct DefPathIndex {
 pub struct DefIdDirectory {
     // N.B. don't use Removable here because these def-ids are loaded
     // directly without remapping, so loading them should not fail.
-    paths: Vec<DefPath>
+    paths: Vec<DefPath>,
+
+    // The other crates which `paths` are relative to. Crate numbers
+    // are assigned anew in each session, so we identify crates by
+    // name and disambiguator instead.
+    krates: Vec<KrateInfo>,
+}
+
+#[derive(Debug, RustcEncodable, RustcDecodable)]
+pub struct KrateInfo {
+    krate: CrateNum,
+    name: String,
+    disambiguator: String,
 }
 
 impl DefIdDirectory {
     pub fn new() -> DefIdDirectory {
-        DefIdDirectory { paths: vec![] }
+        DefIdDirectory { paths: vec![], krates: vec![] }
+    }
+
+    /// Describes `node` using the paths of the directory, which works even
+    /// if the items it refers to no longer exist.
+    pub fn node_string(&self, node: DepNode<DefPathIndex>) -> String {
+        let node = node.map_def(|&index| {
+            let path = &self.paths[index.index as usize];
+            let krate = self.krates.iter()
+                                   .find(|info| info.krate == path.krate)
+                                   .map(|info| info.name.clone());
+            Some(krate.into_iter()
+                      .chain(path.data.iter().map(|component| component.data.to_string()))
+                      .collect::<Vec<_>>()
+                      .join("::"))
+        }).unwrap();
+        format!("{:?}", node)
     }
 
     pub fn retrace(&self, tcx: &ty::TyCtxt) -> RetracedDefIdDirectory {
+        // Find the current number of each crate, if it is still loaded.
+        let current_krates = tcx.sess.cstore.crates();
+        let krates = self.krates.iter().filter_map(|info| {
+            current_krates.iter()
+                          .find(|&&cnum| {
+                              tcx.sess.cstore.original_crate_name(cnum)[..] == info.name[..] &&
+                              tcx.sess.cstore.crate_disambiguator(cnum)[..] ==
+                                  info.disambiguator[..]
+                          })
+                          .map(|&cnum| (info.krate, cnum))
+        }).collect::<FnvHashMap<_, _>>();
+
         let ids = self.paths.iter()
-                            .map(|path| tcx.map.retrace_path(path))
+                            .map(|path| {
+                                if path.is_local() {
+                                    tcx.map.retrace_path(path)
+                                } else {
+                                    krates.get(&path.krate).and_then(|&cnum| {
+                                        tcx.sess.cstore.retrace_path(cnum, &path.data)
+                                    })
+                                }
+                            })
                             .collect();
         RetracedDefIdDirectory { ids: ids }
     }
@@ -79,19 +129,26 @@ impl<'a,'tcx> DefIdDirectoryBuilder<'a,'tcx> {
     }
 
     pub fn add(&mut self, def_id: DefId) -> Option<DefPathIndex> {
-        if !def_id.is_local() {
-            // FIXME(#32015) clarify story about cross-crate dep tracking
-            return None;
-        }
-
         let tcx = self.tcx;
         let paths = &mut self.directory.paths;
+        let krates = &mut self.directory.krates;
         self.hash.entry(def_id)
                  .or_insert_with(|| {
                      let def_path = tcx.def_path(def_id);
-                     if !def_path.is_local() {
+                     if def_id.is_local() != def_path.is_local() {
+                         // an item inlined from another crate
                          return None;
                      }
+                     if !def_path.is_local() &&
+                        !krates.iter().any(|info| info.krate == def_path.krate) {
+                         krates.push(KrateInfo {
+                             krate: def_path.krate,
+                             name: tcx.sess.cstore.original_crate_name(def_path.krate)
+                                                  .to_string(),
+                             disambiguator: tcx.sess.cstore.crate_disambiguator(def_path.krate)
+                                                           .to_string(),
+                         });
+                     }
                      let index = paths.len() as u32;
                      paths.push(def_path);
                      Some(DefPathIndex { index: index })
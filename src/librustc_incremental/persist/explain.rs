@@ -0,0 +1,127 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Support for `-Z incremental-explain`, which reports why work of the
+//! previous incremental session had to be redone.
+//!
+//! While loading the dep-graph, we record for every node which became dirty
+//! the reason it did: either its HIR changed, one of its inputs was removed,
+//! or one of its inputs became dirty. Trans records the same for the codegen
+//! units it rebuilds. Following the dirty inputs back from a node yields the
+//! chain of changes which invalidated it, e.g.:
+//!
+//! ```text
+//! codegen unit `cgu-3` was invalidated:
+//!     Hir("foo::bar") changed
+//!     which made TypeckItemBody("baz") dirty
+//!     which made TransCrateItem("baz") dirty
+//!     which made codegen unit `cgu-3` dirty
+//! ```
+
+use rustc::dep_graph::{DepNode, DirtyReason};
+use rustc::hir::def_id::DefId;
+use rustc::session::config::ExplainFormat;
+use rustc::ty;
+use rustc_data_structures::fnv::{FnvHashMap, FnvHashSet};
+use rustc_serialize::json;
+
+/// The name of `node` in the output of `-Z incremental-explain`.
+pub fn node_name<'tcx>(tcx: &ty::TyCtxt<'tcx>, node: &DepNode<DefId>) -> String {
+    let node = node.map_def(|&def_id| Some(tcx.item_path_str(def_id))).unwrap();
+    format!("{:?}", node)
+}
+
+/// The name of a codegen unit in the output of `-Z incremental-explain`.
+pub fn codegen_unit_name(name: &str) -> String {
+    format!("codegen unit `{}`", name)
+}
+
+#[derive(PartialEq, RustcEncodable)]
+struct Explanation {
+    /// The invalidated node.
+    node: String,
+    /// What ultimately caused the invalidation, which concerns the first
+    /// node of `chain`.
+    cause: String,
+    /// The nodes through which the cause reached `node`, starting with the
+    /// changed one and ending with `node`.
+    chain: Vec<String>,
+}
+
+/// Prints the reasons recorded in the dep-graph, in the format requested with
+/// `-Z incremental-explain`.
+pub fn explain_dirty<'tcx>(tcx: &ty::TyCtxt<'tcx>) {
+    let format = match tcx.sess.opts.debugging_opts.incremental_explain {
+        Some(format) => format,
+        None => return,
+    };
+
+    let reasons = tcx.dep_graph.dirty_reasons();
+    let mut reason_of = FnvHashMap();
+    for &(ref node, ref reason) in &reasons {
+        reason_of.entry(&node[..]).or_insert(reason);
+    }
+
+    let mut explanations = reasons.iter().map(|&(ref node, _)| {
+        explain(node, &reason_of)
+    }).collect::<Vec<_>>();
+    explanations.sort_by(|a, b| a.node.cmp(&b.node));
+    explanations.dedup();
+
+    if format == ExplainFormat::Json {
+        println!("{}", json::as_pretty_json(&explanations));
+        return;
+    }
+
+    for explanation in &explanations {
+        println!("{} was invalidated:", explanation.node);
+        println!("    {}", explanation.cause);
+        for node in explanation.chain.iter().skip(1) {
+            println!("    which made {} dirty", node);
+        }
+    }
+}
+
+fn explain(node: &str, reason_of: &FnvHashMap<&str, &DirtyReason<String>>) -> Explanation {
+    let mut chain = vec![node.to_string()];
+    let mut visited = FnvHashSet();
+    let mut current = node;
+    let cause;
+    loop {
+        visited.insert(current);
+        cause = match reason_of.get(current) {
+            Some(&&DirtyReason::DirtyInput(ref input)) if !visited.contains(&input[..]) => {
+                chain.push(input.clone());
+                current = input;
+                continue;
+            }
+            Some(&&DirtyReason::DirtyInput(_)) => format!("{} depends on itself", current),
+            Some(&&DirtyReason::HirChanged) => format!("{} changed", current),
+            Some(&&DirtyReason::MetaDataChanged) => {
+                format!("{} changed in its crate", current)
+            }
+            Some(&&DirtyReason::InputRemoved(ref path)) => {
+                format!("{}, an input of {}, was removed", path, current)
+            }
+            Some(&&DirtyReason::UntrackedInputChanged) => {
+                format!("the symbols or options of {} changed", current)
+            }
+            None => format!("{} is new", current),
+        };
+        break;
+    }
+    chain.reverse();
+
+    Explanation {
+        node: node.to_string(),
+        cause: cause,
+        chain: chain,
+    }
+}
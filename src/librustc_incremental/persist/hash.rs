@@ -0,0 +1,56 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The hashes of `MetaData` nodes. Every crate records the hashes of its
+//! items in its metadata (see `SvhCalculate::calculate_item_hashes`); other
+//! definitions, like methods or fields, share the hash of their enclosing
+//! item.
+
+use rustc::hir::def_id::{DefId, DefIndex};
+use rustc::ty;
+use rustc_data_structures::fnv::FnvHashMap;
+use syntax::ast::CrateNum;
+
+pub struct MetaDataHashes<'a, 'tcx: 'a> {
+    tcx: &'a ty::TyCtxt<'tcx>,
+    item_hashes: FnvHashMap<CrateNum, FnvHashMap<DefIndex, u64>>,
+}
+
+impl<'a, 'tcx> MetaDataHashes<'a, 'tcx> {
+    pub fn new(tcx: &'a ty::TyCtxt<'tcx>) -> MetaDataHashes<'a, 'tcx> {
+        MetaDataHashes {
+            tcx: tcx,
+            item_hashes: FnvHashMap(),
+        }
+    }
+
+    /// The hash of the definition `def_id` of another crate, or `None` if
+    /// the metadata of that crate has no hash for it.
+    pub fn hash(&mut self, def_id: DefId) -> Option<u64> {
+        assert!(!def_id.is_local());
+
+        let tcx = self.tcx;
+        let item_hashes = self.item_hashes.entry(def_id.krate).or_insert_with(|| {
+            tcx.sess.cstore.item_hashes(def_id.krate)
+        });
+
+        let mut index = def_id.index;
+        loop {
+            if let Some(&hash) = item_hashes.get(&index) {
+                return Some(hash);
+            }
+            let def_key = tcx.sess.cstore.def_key(DefId { krate: def_id.krate, index: index });
+            match def_key.parent {
+                Some(parent) => index = parent,
+                None => return None,
+            }
+        }
+    }
+}
@@ -13,10 +13,10 @@
 use calculate_svh::SvhCalculate;
 use rbml::Error;
 use rbml::opaque::Decoder;
-use rustc::dep_graph::DepNode;
+use rustc::dep_graph::{DepNode, DirtyReason};
 use rustc::hir::def_id::DefId;
 use rustc::ty;
-use rustc_data_structures::fnv::FnvHashSet;
+use rustc_data_structures::fnv::{FnvHashMap, FnvHashSet};
 use rustc_serialize::Decodable as RustcDecodable;
 use std::io::Read;
 use std::fs::File;
@@ -25,10 +25,16 @@ use std::path::Path;
 use super::data::*;
 use super::directory::*;
 use super::dirty_clean;
+use super::explain;
+use super::hash::MetaDataHashes;
 use super::util::*;
+use super::work_product;
 
 type DirtyNodes = FnvHashSet<DepNode<DefId>>;
 
+/// Why each dirty node became dirty, for `-Z incremental-explain`.
+type DirtyCauses = FnvHashMap<DepNode<DefId>, DirtyReason<DepNode<DefId>>>;
+
 type CleanEdges = Vec<(DepNode<DefId>, DepNode<DefId>)>;
 
 /// If we are in incremental mode, and a previous dep-graph exists,
@@ -44,6 +50,7 @@ pub fn load_dep_graph<'tcx>(tcx: &ty::TyCtxt<'tcx>) {
         // FIXME(#32754) lock file?
         load_dep_graph_if_exists(tcx, &dep_graph);
         dirty_clean::check_dirty_clean_annotations(tcx);
+        work_product::load_work_products(tcx);
     }
 }
 
@@ -91,8 +98,12 @@ pub fn decode_dep_graph<'tcx>(tcx: &ty::TyCtxt<'tcx>, data: &[u8])
     debug!("decode_dep_graph: retraced = {:#?}", retraced);
 
     // Compute the set of Hir nodes whose data has changed.
+    let mut causes = FnvHashMap();
     let mut dirty_nodes =
-        initial_dirty_nodes(tcx, &serialized_dep_graph.hashes, &retraced);
+        initial_dirty_nodes(tcx, &serialized_dep_graph.hashes, &retraced, &mut causes);
+
+    // Add the metadata nodes of items of other crates which have changed.
+    dirty_nodes.extend(dirty_metadata_nodes(tcx, &serialized_dep_graph, &retraced, &mut causes));
 
     debug!("decode_dep_graph: initial dirty_nodes = {:#?}", dirty_nodes);
 
@@ -103,8 +114,25 @@ pub fn decode_dep_graph<'tcx>(tcx: &ty::TyCtxt<'tcx>, data: &[u8])
     // target to `dirty_nodes`. It stops when it reaches a fixed
     // point.
     let clean_edges = compute_clean_edges(&serialized_dep_graph.edges,
+                                          &directory,
                                           &retraced,
-                                          &mut dirty_nodes);
+                                          &mut dirty_nodes,
+                                          &mut causes);
+
+    if tcx.sess.opts.debugging_opts.incremental_explain.is_some() {
+        for (node, cause) in causes {
+            let cause = match cause {
+                DirtyReason::HirChanged => DirtyReason::HirChanged,
+                DirtyReason::MetaDataChanged => DirtyReason::MetaDataChanged,
+                DirtyReason::InputRemoved(path) => DirtyReason::InputRemoved(path),
+                DirtyReason::DirtyInput(input) => {
+                    DirtyReason::DirtyInput(explain::node_name(tcx, &input))
+                }
+                DirtyReason::UntrackedInputChanged => DirtyReason::UntrackedInputChanged,
+            };
+            tcx.dep_graph.record_dirty_reason(explain::node_name(tcx, &node), cause);
+        }
+    }
 
     // Add synthetic `foo->foo` edges for each clean node `foo` that
     // we had before. This is sort of a hack to create clean nodes in
@@ -131,7 +159,8 @@ pub fn decode_dep_graph<'tcx>(tcx: &ty::TyCtxt<'tcx>, data: &[u8])
 
 fn initial_dirty_nodes<'tcx>(tcx: &ty::TyCtxt<'tcx>,
                              hashed_items: &[SerializedHash],
-                             retraced: &RetracedDefIdDirectory)
+                             retraced: &RetracedDefIdDirectory,
+                             causes: &mut DirtyCauses)
                              -> DirtyNodes {
     let mut items_removed = false;
     let mut dirty_nodes = FnvHashSet();
@@ -144,6 +173,7 @@ fn initial_dirty_nodes<'tcx>(tcx: &ty::TyCtxt<'tcx>,
                        def_id, current_hash, hashed_item.hash);
                 if current_hash != hashed_item.hash {
                     dirty_nodes.insert(DepNode::Hir(def_id));
+                    causes.insert(DepNode::Hir(def_id), DirtyReason::HirChanged);
                 }
             }
             None => {
@@ -156,15 +186,60 @@ fn initial_dirty_nodes<'tcx>(tcx: &ty::TyCtxt<'tcx>,
     // the meta-node `Krate` to be dirty, since that means something
     // which (potentially) read the contents of every single item.
     if items_removed || !dirty_nodes.is_empty() {
+        let cause = match dirty_nodes.iter().next() {
+            Some(&node) => DirtyReason::DirtyInput(node),
+            None => DirtyReason::InputRemoved("an item of the crate".to_string()),
+        };
         dirty_nodes.insert(DepNode::Krate);
+        causes.insert(DepNode::Krate, cause);
+    }
+
+    dirty_nodes
+}
+
+fn dirty_metadata_nodes<'tcx>(tcx: &ty::TyCtxt<'tcx>,
+                              serialized_dep_graph: &SerializedDepGraph,
+                              retraced: &RetracedDefIdDirectory,
+                              causes: &mut DirtyCauses)
+                              -> DirtyNodes {
+    let previous_hashes = serialized_dep_graph.metadata_hashes
+                                              .iter()
+                                              .map(|hashed_item| (hashed_item.index,
+                                                                  hashed_item.hash))
+                                              .collect::<FnvHashMap<_, _>>();
+
+    let mut metadata_hasher = MetaDataHashes::new(tcx);
+    let mut dirty_nodes = FnvHashSet();
+    for &node in &serialized_dep_graph.nodes {
+        let index = match node {
+            DepNode::MetaData(index) => index,
+            _ => continue,
+        };
+
+        // If the item no longer exists, the nodes which read it are
+        // marked dirty in `compute_clean_edges`.
+        if let Some(def_id) = retraced.def_id(index) {
+            // A crate compiled without item hashes gives us nothing to
+            // compare, so we have to assume the item changed.
+            let current_hash = metadata_hasher.hash(def_id);
+            let previous_hash = previous_hashes.get(&index).cloned();
+            debug!("dirty_metadata_nodes: hash of {:?} is {:?}, was {:?}",
+                   def_id, current_hash, previous_hash);
+            if current_hash.is_none() || current_hash != previous_hash {
+                dirty_nodes.insert(DepNode::MetaData(def_id));
+                causes.insert(DepNode::MetaData(def_id), DirtyReason::MetaDataChanged);
+            }
+        }
     }
 
     dirty_nodes
 }
 
 fn compute_clean_edges(serialized_edges: &[(SerializedEdge)],
+                       directory: &DefIdDirectory,
                        retraced: &RetracedDefIdDirectory,
-                       dirty_nodes: &mut DirtyNodes)
+                       dirty_nodes: &mut DirtyNodes,
+                       causes: &mut DirtyCauses)
                        -> CleanEdges {
     // Build up an initial list of edges. Include an edge (source,
     // target) if neither node has been removed. If the source has
@@ -177,6 +252,9 @@ fn compute_clean_edges(serialized_edges: &[(SerializedEdge)],
             } else {
                 // source removed, target must be dirty
                 dirty_nodes.insert(target);
+                causes.entry(target).or_insert_with(|| {
+                    DirtyReason::InputRemoved(directory.node_string(serialized_source))
+                });
             }
         } else {
             // target removed, ignore the edge
@@ -200,6 +278,7 @@ fn compute_clean_edges(serialized_edges: &[(SerializedEdge)],
                 debug!("compute_clean_edges: dirty source {:?} -> {:?}",
                        source, target);
                 dirty_nodes.insert(target);
+                causes.entry(target).or_insert(DirtyReason::DirtyInput(source));
             } else if dirty_nodes.contains(&clean_edges[i].1) {
                 let (source, target) = clean_edges.swap_remove(i);
                 debug!("compute_clean_edges: dirty target {:?} -> {:?}",
@@ -15,9 +15,14 @@
 mod data;
 mod directory;
 mod dirty_clean;
+mod explain;
+mod hash;
 mod load;
 mod save;
 mod util;
+mod work_product;
 
+pub use self::explain::{codegen_unit_name, explain_dirty, node_name};
 pub use self::load::load_dep_graph;
 pub use self::save::save_dep_graph;
+pub use self::work_product::{save_trans_partition, save_work_products};
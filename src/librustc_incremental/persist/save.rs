@@ -18,6 +18,7 @@ use std::fs::{self, File};
 
 use super::data::*;
 use super::directory::*;
+use super::hash::MetaDataHashes;
 use super::util::*;
 
 pub fn save_dep_graph<'tcx>(tcx: &ty::TyCtxt<'tcx>) {
@@ -104,10 +105,28 @@ pub fn encode_dep_graph<'tcx>(tcx: &ty::TyCtxt<'tcx>,
              })
              .collect();
 
+    // Record the hashes of the items of other crates that we read, so
+    // that the next session can tell whether they changed.
+    let mut metadata_hasher = MetaDataHashes::new(tcx);
+    let metadata_hashes =
+        query.nodes()
+             .into_iter()
+             .filter_map(|dep_node| match dep_node {
+                 DepNode::MetaData(def_id) => {
+                     assert!(!def_id.is_local());
+                     builder.add(def_id)
+                            .and_then(|index| {
+                                metadata_hasher.hash(def_id).map(|hash| {
+                                    SerializedHash { index: index, hash: hash }
+                                })
+                            })
+                 }
+                 _ => None
+             })
+             .collect();
+
     // Create the serialized dep-graph, dropping nodes that are
-    // from other crates or from inlined items.
-    //
-    // FIXME(#32015) fix handling of other crates
+    // from inlined items.
     let graph = SerializedDepGraph {
         nodes: query.nodes().into_iter()
                             .flat_map(|node| builder.map(node))
@@ -122,6 +141,7 @@ pub fn encode_dep_graph<'tcx>(tcx: &ty::TyCtxt<'tcx>,
                             })
                             .collect(),
         hashes: hashes,
+        metadata_hashes: metadata_hashes,
     };
 
     debug!("graph = {:#?}", graph);
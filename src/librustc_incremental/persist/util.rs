@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use rustc::session::Session;
 use rustc::ty;
 
 use std::fs;
@@ -17,18 +18,29 @@ use std::path::{PathBuf, Path};
 pub fn dep_graph_path<'tcx>(tcx: &ty::TyCtxt<'tcx>) -> Option<PathBuf> {
     // For now, just save/load dep-graph from
     // directory/dep_graph.rbml
-    tcx.sess.opts.incremental.as_ref().and_then(|incr_dir| {
+    in_incr_comp_dir(&tcx.sess, "dep_graph.rbml")
+}
+
+pub fn work_products_path(sess: &Session) -> Option<PathBuf> {
+    in_incr_comp_dir(sess, "work_products.rbml")
+}
+
+/// Returns the path of `file_name` within the incremental directory,
+/// creating the directory if needed, or `None` if we are not compiling
+/// incrementally.
+pub fn in_incr_comp_dir(sess: &Session, file_name: &str) -> Option<PathBuf> {
+    sess.opts.incremental.as_ref().and_then(|incr_dir| {
         match create_dir_racy(&incr_dir) {
             Ok(()) => {}
             Err(err) => {
-                tcx.sess.err(
+                sess.err(
                     &format!("could not create the directory `{}`: {}",
                              incr_dir.display(), err));
                 return None;
             }
         }
 
-        Some(incr_dir.join("dep_graph.rbml"))
+        Some(incr_dir.join(file_name))
     })
 }
 
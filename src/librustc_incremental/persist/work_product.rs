@@ -0,0 +1,140 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Code to save/load the work products (see `rustc::dep_graph::WorkProduct`)
+//! of a session. Unlike the dep-graph, work products are produced after the
+//! `TyCtxt` is gone, by the LLVM passes, so saving them only needs a
+//! `Session`.
+
+use rbml::Error;
+use rbml::opaque::{Decoder, Encoder};
+use rustc::dep_graph::WorkProduct;
+use rustc::session::Session;
+use rustc::ty;
+use rustc::util::fs::link_or_copy;
+use rustc_serialize::{Decodable as RustcDecodable, Encodable as RustcEncodable};
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::path::PathBuf;
+
+use super::data::*;
+use super::util::*;
+
+/// Copies `files`, the outputs produced for the codegen unit `cgu_name`, into
+/// the incremental directory and returns the corresponding work product, or
+/// `None` if some file couldn't be saved (in which case the codegen unit will
+/// simply be rebuilt next time).
+pub fn save_trans_partition(sess: &Session,
+                            cgu_name: &str,
+                            input_hash: u64,
+                            files: &[PathBuf])
+                            -> Option<WorkProduct> {
+    debug!("save_trans_partition({:?},{},{:?})", cgu_name, input_hash, files);
+
+    let mut saved_files = vec![];
+    for path in files {
+        let file_name = match path.extension() {
+            Some(extension) => format!("{}.{}", cgu_name, extension.to_string_lossy()),
+            None => cgu_name.to_string(),
+        };
+        let saved_path = match in_incr_comp_dir(sess, &file_name) {
+            Some(saved_path) => saved_path,
+            None => return None,
+        };
+        match link_or_copy(path, &saved_path) {
+            Ok(()) => saved_files.push(file_name),
+            Err(err) => {
+                sess.warn(&format!("error copying object file `{}` \
+                                    to incremental directory as `{}`: {}",
+                                   path.display(), saved_path.display(), err));
+                return None;
+            }
+        }
+    }
+
+    Some(WorkProduct {
+        input_hash: input_hash,
+        saved_files: saved_files,
+    })
+}
+
+/// Writes out the list of work products of this session, replacing the one
+/// of the previous session.
+pub fn save_work_products(sess: &Session, work_products: Vec<(String, WorkProduct)>) {
+    let path = match work_products_path(sess) {
+        Some(path) => path,
+        None => return,
+    };
+
+    let work_products = work_products.into_iter()
+                                     .map(|(id, work_product)| {
+                                         SerializedWorkProduct {
+                                             id: id,
+                                             work_product: work_product,
+                                         }
+                                     })
+                                     .collect::<Vec<_>>();
+
+    let mut wr = Cursor::new(Vec::new());
+    if let Err(err) = work_products.encode(&mut Encoder::new(&mut wr)) {
+        sess.err(&format!("could not encode work products to `{}`: {}",
+                          path.display(), err));
+        return;
+    }
+
+    let data = wr.into_inner();
+    if let Err(err) = File::create(&path).and_then(|mut file| file.write_all(&data)) {
+        sess.err(&format!("failed to write work products to `{}`: {}",
+                          path.display(), err));
+    }
+}
+
+/// Loads the work products saved by the previous session whose files are
+/// all still present into the dep-graph, where trans can find them.
+pub fn load_work_products<'tcx>(tcx: &ty::TyCtxt<'tcx>) {
+    let path = match work_products_path(&tcx.sess) {
+        Some(path) => path,
+        None => return,
+    };
+    if !path.exists() {
+        return;
+    }
+
+    let mut data = vec![];
+    if let Err(err) = File::open(&path).and_then(|mut file| file.read_to_end(&mut data)) {
+        tcx.sess.err(&format!("could not load work products from `{}`: {}",
+                              path.display(), err));
+        return;
+    }
+
+    let work_products = match decode_work_products(&data) {
+        Ok(work_products) => work_products,
+        Err(err) => {
+            bug!("decoding error in work products from `{}`: {}", path.display(), err);
+        }
+    };
+
+    for swp in work_products {
+        let all_files_exist = swp.work_product.saved_files.iter().all(|file_name| {
+            in_incr_comp_dir(&tcx.sess, file_name).map_or(false, |path| path.exists())
+        });
+        if all_files_exist {
+            debug!("load_work_products: reconciled {:?}", swp);
+            tcx.dep_graph.insert_previous_work_product(swp.id, swp.work_product);
+        } else {
+            debug!("load_work_products: missing files for {:?}", swp);
+        }
+    }
+}
+
+fn decode_work_products(data: &[u8]) -> Result<Vec<SerializedWorkProduct>, Error> {
+    let mut decoder = Decoder::new(data, 0);
+    Vec::<SerializedWorkProduct>::decode(&mut decoder)
+}
@@ -243,6 +243,11 @@ pub const tag_items_data_item_defaultness: usize = 0xa8;
 
 pub const tag_items_data_parent_impl: usize = 0xa9;
 
+pub const tag_item_hashes: usize = 0x114; // top-level only
+pub const tag_item_hash: usize = 0xaa;
+pub const tag_item_hash_index: usize = 0xab;
+pub const tag_item_hash_value: usize = 0xac;
+
 pub const tag_rustc_version: usize = 0x10f;
 pub fn rustc_version() -> String {
     format!(
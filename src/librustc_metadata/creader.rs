@@ -71,9 +71,10 @@ fn dump_crates(cstore: &CStore) {
         info!("  hash: {}", data.hash());
         info!("  reqd: {}", data.explicitly_linked.get());
         opt_source.map(|cs| {
-            let CrateSource { dylib, rlib, cnum: _ } = cs;
+            let CrateSource { dylib, rlib, rmeta, cnum: _ } = cs;
             dylib.map(|dl| info!("  dylib: {}", dl.0.display()));
             rlib.map(|rl|  info!("   rlib: {}", rl.0.display()));
+            rmeta.map(|rl| info!("  rmeta: {}", rl.0.display()));
         });
     })
 }
@@ -205,7 +206,8 @@ impl<'a> CrateReader<'a> {
                 let found = locs.iter().any(|l| {
                     let l = fs::canonicalize(l).ok();
                     source.dylib.as_ref().map(|p| &p.0) == l.as_ref() ||
-                    source.rlib.as_ref().map(|p| &p.0) == l.as_ref()
+                    source.rlib.as_ref().map(|p| &p.0) == l.as_ref() ||
+                    source.rmeta.as_ref().map(|p| &p.0) == l.as_ref()
                 });
                 if found {
                     ret = Some(cnum);
@@ -220,6 +222,7 @@ impl<'a> CrateReader<'a> {
             // path (this is a top-level dependency) as we don't want to
             // implicitly load anything inside the dependency lookup path.
             let prev_kind = source.dylib.as_ref().or(source.rlib.as_ref())
+                                  .or(source.rmeta.as_ref())
                                   .unwrap().1;
             if ret.is_none() && (prev_kind == kind || prev_kind == PathKind::All) {
                 ret = Some(cnum);
@@ -281,6 +284,7 @@ impl<'a> CrateReader<'a> {
 
     fn register_crate(&mut self,
                       root: &Option<CratePaths>,
+                      dependent: Option<&str>,
                       ident: &str,
                       name: &str,
                       span: Span,
@@ -301,6 +305,7 @@ impl<'a> CrateReader<'a> {
                 ident: ident.to_string(),
                 dylib: lib.dylib.clone().map(|p| p.0),
                 rlib:  lib.rlib.clone().map(|p| p.0),
+                rmeta: lib.rmeta.clone().map(|p| p.0),
             })
         } else {
             None
@@ -308,16 +313,18 @@ impl<'a> CrateReader<'a> {
         // Maintain a reference to the top most crate.
         let root = if root.is_some() { root } else { &crate_paths };
 
-        let loader::Library { dylib, rlib, metadata } = lib;
+        let loader::Library { dylib, rlib, rmeta, metadata } = lib;
 
-        let cnum_map = self.resolve_crate_deps(root, metadata.as_slice(), span);
+        let cnum_map = self.resolve_crate_deps(root, name, metadata.as_slice(), span);
         let staged_api = self.is_staged_api(metadata.as_slice());
 
         let cmeta = Rc::new(cstore::crate_metadata {
             name: name.to_string(),
             extern_crate: Cell::new(None),
+            dependent: dependent.map(|d| d.to_string()),
             index: decoder::load_index(metadata.as_slice()),
             xref_index: decoder::load_xrefs(metadata.as_slice()),
+            key_map: RefCell::new(None),
             data: metadata,
             cnum_map: RefCell::new(cnum_map),
             cnum: cnum,
@@ -329,6 +336,7 @@ impl<'a> CrateReader<'a> {
         let source = cstore::CrateSource {
             dylib: dylib,
             rlib: rlib,
+            rmeta: rmeta,
             cnum: cnum,
         };
 
@@ -349,6 +357,7 @@ impl<'a> CrateReader<'a> {
 
     fn resolve_crate(&mut self,
                      root: &Option<CratePaths>,
+                     dependent: Option<&str>,
                      ident: &str,
                      name: &str,
                      hash: Option<&Svh>,
@@ -365,6 +374,7 @@ impl<'a> CrateReader<'a> {
             None => {
                 let mut load_ctxt = loader::Context {
                     sess: self.sess,
+                    cstore: self.cstore,
                     span: span,
                     ident: ident,
                     crate_name: name,
@@ -376,6 +386,7 @@ impl<'a> CrateReader<'a> {
                     rejected_via_hash: vec!(),
                     rejected_via_triple: vec!(),
                     rejected_via_kind: vec!(),
+                    examined: vec!(),
                     should_match_name: true,
                 };
                 let library = load_ctxt.load_library_crate();
@@ -406,7 +417,7 @@ impl<'a> CrateReader<'a> {
                 (cnum, data, self.cstore.used_crate_source(cnum))
             }
             LookupResult::Loaded(library) => {
-                self.register_crate(root, ident, name, span, library,
+                self.register_crate(root, dependent, ident, name, span, library,
                                     explicitly_linked)
             }
         }
@@ -445,6 +456,7 @@ impl<'a> CrateReader<'a> {
     // Go through the crate metadata and load any crates that it references
     fn resolve_crate_deps(&mut self,
                           root: &Option<CratePaths>,
+                          name: &str,
                           cdata: &[u8],
                           span : Span)
                           -> cstore::cnum_map {
@@ -454,6 +466,7 @@ impl<'a> CrateReader<'a> {
         decoder::get_crate_deps(cdata).iter().map(|dep| {
             debug!("resolving dep crate {} hash: `{}`", dep.name, dep.hash);
             let (local_cnum, _, _) = self.resolve_crate(root,
+                                                        Some(name),
                                                         &dep.name,
                                                         &dep.name,
                                                         Some(&dep.hash),
@@ -473,6 +486,7 @@ impl<'a> CrateReader<'a> {
         let name = info.name.clone();
         let mut load_ctxt = loader::Context {
             sess: self.sess,
+            cstore: self.cstore,
             span: span,
             ident: &ident[..],
             crate_name: &name[..],
@@ -484,6 +498,7 @@ impl<'a> CrateReader<'a> {
             rejected_via_hash: vec!(),
             rejected_via_triple: vec!(),
             rejected_via_kind: vec!(),
+            examined: vec!(),
             should_match_name: true,
         };
         let library = match load_ctxt.maybe_load_library_crate() {
@@ -508,7 +523,7 @@ impl<'a> CrateReader<'a> {
                                                           PathKind::Crate).is_none();
         let metadata = if register {
             // Register crate now to avoid double-reading metadata
-            let (_, cmd, _) = self.register_crate(&None, &info.ident,
+            let (_, cmd, _) = self.register_crate(&None, None, &info.ident,
                                                   &info.name, span, library,
                                                   true);
             PMDSource::Registered(cmd)
@@ -649,6 +664,10 @@ impl<'a> CrateReader<'a> {
         });
         if !needs_allocator || found_required_allocator { return }
 
+        // A `#[global_allocator]` in the local crate provides the allocator
+        // symbols itself, so there's nothing to inject.
+        if self.sess.global_allocator.get().is_some() { return }
+
         // At this point we've determined that we need an allocator and no
         // previous allocator has been activated. We look through our outputs of
         // crate types to see what kind of allocator types we may need.
@@ -688,7 +707,7 @@ impl<'a> CrateReader<'a> {
         } else {
             &self.sess.target.target.options.exe_allocation_crate
         };
-        let (cnum, data, _) = self.resolve_crate(&None, name, name, None,
+        let (cnum, data, _) = self.resolve_crate(&None, None, name, name, None,
                                                  codemap::DUMMY_SP,
                                                  PathKind::Crate, false);
 
@@ -794,6 +813,16 @@ impl<'a> LocalCrateReader<'a> {
     }
 
     fn process_item(&mut self, i: &ast::Item) {
+        if attr::contains_name(&i.attrs, "rustc_global_allocator") {
+            if let Some(prev) = self.sess.global_allocator.get() {
+                self.sess.struct_span_err(i.span, "cannot define more than one \
+                                                   `#[global_allocator]`")
+                    .span_note(prev, "the previous global allocator is defined here")
+                    .emit();
+            }
+            self.sess.global_allocator.set(Some(i.span));
+        }
+
         match i.node {
             ast::ItemKind::ExternCrate(_) => {
                 if !should_link(i) {
@@ -803,6 +832,7 @@ impl<'a> LocalCrateReader<'a> {
                 match self.creader.extract_crate_info(i) {
                     Some(info) => {
                         let (cnum, _, _) = self.creader.resolve_crate(&None,
+                                                                      None,
                                                                       &info.ident,
                                                                       &info.name,
                                                                       None,
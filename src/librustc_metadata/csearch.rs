@@ -11,6 +11,7 @@
 use cstore;
 use decoder;
 use encoder;
+use header;
 use loader;
 
 use middle::cstore::{CrateStore, CrateSource, ChildItem, ExternCrate, FoundAst, DefLike};
@@ -365,6 +366,15 @@ impl<'tcx> CrateStore<'tcx> for cstore::CStore {
         decoder::get_reachable_ids(&cdata)
     }
 
+    /// Returns the hashes of the items of the crate `cnum`, as computed
+    /// when it was compiled. Other definitions share the hash of their
+    /// enclosing item.
+    fn item_hashes(&self, cnum: ast::CrateNum) -> FnvHashMap<DefIndex, u64>
+    {
+        let cdata = self.get_crate_data(cnum);
+        decoder::get_item_hashes(&cdata)
+    }
+
     /// Returns the `DefKey` for a given `DefId`. This indicates the
     /// parent `DefId` as well as some idea of what kind of data the
     /// `DefId` refers to.
@@ -378,6 +388,28 @@ impl<'tcx> CrateStore<'tcx> for cstore::CStore {
         decoder::def_path(&cdata, def.index)
     }
 
+    /// Finds the definition of the crate `cnum` with the given path, which
+    /// is relative to the crate root (the inverse of `relative_def_path`).
+    fn retrace_path(&self,
+                    cnum: ast::CrateNum,
+                    path_data: &[hir_map::DisambiguatedDefPathData])
+                    -> Option<DefId>
+    {
+        let cdata = self.get_crate_data(cnum);
+        let mut index = CRATE_DEF_INDEX;
+        for data in path_data {
+            let key = hir_map::DefKey {
+                parent: Some(index),
+                disambiguated_data: data.clone(),
+            };
+            index = match decoder::def_index_for_def_key(&cdata, key) {
+                Some(index) => index,
+                None => return None,
+            };
+        }
+        Some(DefId { krate: cnum, index: index })
+    }
+
     fn variant_kind(&self, def_id: DefId) -> Option<VariantKind> {
         let cdata = self.get_crate_data(def_id.krate);
         decoder::get_variant_kind(&cdata, def_id.index)
@@ -503,10 +535,11 @@ impl<'tcx> CrateStore<'tcx> for cstore::CStore {
     fn encode_metadata(&self,
                        tcx: &TyCtxt<'tcx>,
                        reexports: &def::ExportMap,
-                       item_symbols: &RefCell<NodeMap<String>>,
+                       item_symbols: Option<&RefCell<NodeMap<String>>>,
                        link_meta: &LinkMeta,
                        reachable: &NodeSet,
                        mir_map: &MirMap<'tcx>,
+                       item_hashes: &DefIdMap<u64>,
                        krate: &hir::Crate) -> Vec<u8>
     {
         let ecx = encoder::EncodeContext {
@@ -518,6 +551,7 @@ impl<'tcx> CrateStore<'tcx> for cstore::CStore {
             cstore: self,
             reachable: reachable,
             mir_map: mir_map,
+            item_hashes: item_hashes,
             type_abbrevs: RefCell::new(FnvHashMap()),
         };
         encoder::encode_metadata(ecx, krate)
@@ -526,7 +560,7 @@ impl<'tcx> CrateStore<'tcx> for cstore::CStore {
 
     fn metadata_encoding_version(&self) -> &[u8]
     {
-        encoder::metadata_encoding_version
+        header::METADATA_PREFIX
     }
 
     /// Returns a map from a sufficiently visible external item (i.e. an external item that is
@@ -17,10 +17,12 @@ pub use self::MetadataBlob::*;
 
 use creader;
 use decoder;
+use header::MetadataHeader;
 use index;
 use loader;
 
-use rustc::hir::def_id::DefId;
+use rustc::hir::def_id::{DefId, DefIndex};
+use rustc::hir::map as hir_map;
 use rustc::hir::svh::Svh;
 use rustc::middle::cstore::{ExternCrate};
 use rustc::util::nodemap::{FnvHashMap, NodeMap, NodeSet, DefIdMap};
@@ -68,6 +70,10 @@ pub struct crate_metadata {
     /// (e.g., by the allocator)
     pub extern_crate: Cell<Option<ExternCrate>>,
 
+    /// The name of the crate whose dependency on this one caused it to be
+    /// loaded, or `None` if it was loaded for the local crate.
+    pub dependent: Option<String>,
+
     pub data: MetadataBlob,
     pub cnum_map: RefCell<cnum_map>,
     pub cnum: ast::CrateNum,
@@ -77,6 +83,10 @@ pub struct crate_metadata {
     pub index: index::Index,
     pub xref_index: index::DenseIndex,
 
+    /// Map from the `DefKey` of each item to its index, built on demand
+    /// when retracing paths into this crate.
+    pub key_map: RefCell<Option<FnvHashMap<hir_map::DefKey, DefIndex>>>,
+
     /// Flag if this crate is required by an rlib version of this crate, or in
     /// other words whether it was explicitly linked to. An example of a crate
     /// where this is false is when an allocator crate is injected into the
@@ -284,23 +294,19 @@ impl crate_metadata {
 }
 
 impl MetadataBlob {
-    pub fn as_slice<'a>(&'a self) -> &'a [u8] {
-        let slice = match *self {
+    /// The metadata including its header.
+    pub fn raw_bytes<'a>(&'a self) -> &'a [u8] {
+        match *self {
             MetadataVec(ref vec) => &vec[..],
             MetadataArchive(ref ar) => ar.as_slice(),
-        };
-        if slice.len() < 4 {
-            &[] // corrupt metadata
-        } else {
-            let len = (((slice[0] as u32) << 24) |
-                       ((slice[1] as u32) << 16) |
-                       ((slice[2] as u32) << 8) |
-                       ((slice[3] as u32) << 0)) as usize;
-            if len + 4 <= slice.len() {
-                &slice[4.. len + 4]
-            } else {
-                &[] // corrupt or old metadata
-            }
+        }
+    }
+
+    /// The RBML document of the metadata.
+    pub fn as_slice<'a>(&'a self) -> &'a [u8] {
+        match MetadataHeader::read(self.raw_bytes()) {
+            Ok(header) => header.body().unwrap_or(&[]), // incompatible metadata
+            Err(_) => &[], // corrupt or old metadata
         }
     }
 }
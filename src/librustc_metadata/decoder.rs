@@ -15,9 +15,10 @@
 use self::Family::*;
 
 use astencode::decode_inlined_item;
-use cstore::{self, crate_metadata};
+use cstore::{self, crate_metadata, MetadataBlob};
 use common::*;
 use def_key;
+use header::MetadataHeader;
 use encoder::def_to_u64;
 use index;
 use tls_context;
@@ -25,7 +26,7 @@ use tydecode::TyDecoder;
 
 use rustc::hir::svh::Svh;
 use rustc::hir::map as hir_map;
-use rustc::util::nodemap::FnvHashMap;
+use rustc::util::nodemap::{FnvHashMap, FnvHashSet};
 use rustc::hir;
 
 use middle::cstore::{LOCAL_CRATE, FoundAst, InlinedItem, LinkagePreference};
@@ -1304,11 +1305,118 @@ pub fn get_crate_name(data: &[u8]) -> &str {
     maybe_get_crate_name(data).expect("no crate name in crate")
 }
 
-pub fn list_crate_metadata(bytes: &[u8], out: &mut io::Write) -> io::Result<()> {
+pub fn list_crate_metadata(blob: &MetadataBlob, out: &mut io::Write) -> io::Result<()> {
+    list_metadata_header(blob.raw_bytes(), out)?;
+    let bytes = blob.as_slice();
     let hash = get_crate_hash(bytes);
     let md = rbml::Doc::new(bytes);
+    list_crate_info(bytes, out)?;
     list_crate_attributes(md, &hash, out)?;
-    list_crate_deps(bytes, out)
+    list_crate_deps(bytes, out)?;
+    list_sections(md, out)?;
+    list_items(bytes, out)
+}
+
+fn list_metadata_header(data: &[u8], out: &mut io::Write) -> io::Result<()> {
+    let header = MetadataHeader::read(data).unwrap();
+    write!(out, "=Metadata Header=\n")?;
+    write!(out, "format version {}\n", header.format_version)?;
+    write!(out, "written by {}\n", header.rustc_version)?;
+    write!(out, "\n")
+}
+
+fn list_crate_info(data: &[u8], out: &mut io::Write) -> io::Result<()> {
+    write!(out, "=Crate=\n")?;
+    write!(out, "name {}\n", get_crate_name(data))?;
+    write!(out, "hash {}\n", get_crate_hash(data))?;
+    write!(out, "disambiguator {}\n", get_crate_disambiguator(data))?;
+    write!(out, "triple {}\n", get_crate_triple(data).unwrap_or(String::new()))?;
+    write!(out, "\n")
+}
+
+/// Lists the top-level documents of the metadata with their sizes.
+fn list_sections(md: rbml::Doc, out: &mut io::Write) -> io::Result<()> {
+    write!(out, "=Sections=\n")?;
+    for (tag, doc) in reader::docs(md) {
+        let name = match tag {
+            tag_items => "items",
+            tag_attributes => "attributes",
+            tag_crate_deps => "dependencies",
+            tag_crate_hash => "crate hash",
+            tag_crate_crate_name => "crate name",
+            tag_crate_triple => "target triple",
+            tag_dylib_dependency_formats => "dylib dependency formats",
+            tag_lang_items => "lang items",
+            tag_misc_info => "misc info",
+            tag_impls => "impls",
+            tag_native_libraries => "native libraries",
+            tag_plugin_registrar_fn => "plugin registrar",
+            tag_reachable_ids => "reachable ids",
+            tag_struct_fields => "struct fields",
+            tag_macro_defs => "macro definitions",
+            tag_rustc_version => "rustc version",
+            tag_index => "item index",
+            tag_xref_index => "xref index",
+            tag_xref_data => "xref data",
+            tag_crate_disambiguator => "crate disambiguator",
+            tag_item_hashes => "item hashes",
+            tag_codemap => "codemap",
+            _ => "unknown",
+        };
+        write!(out, "{:#x} {}: {} bytes\n", tag, name, doc.end - doc.start)?;
+    }
+    write!(out, "\n")
+}
+
+/// Lists the items of the crate with their paths, families and symbols.
+/// Symbols of reachable items are marked as exported.
+fn list_items(data: &[u8], out: &mut io::Write) -> io::Result<()> {
+    let items_doc = reader::get_doc(rbml::Doc::new(data), tag_items);
+    let items_data_doc = reader::get_doc(items_doc, tag_items_data);
+    let reachable = reader::tagged_docs(reader::get_doc(rbml::Doc::new(data), tag_reachable_ids),
+                                        tag_reachable_id)
+        .map(|doc| DefIndex::from_u32(reader::doc_as_u32(doc)))
+        .collect::<FnvHashSet<_>>();
+
+    let mut keys = FnvHashMap();
+    let mut items = vec![];
+    for item_doc in reader::tagged_docs(items_data_doc, tag_items_data_item) {
+        let index = DefIndex::new((reader::doc_as_u64(reader::get_doc(item_doc, tag_def_id)) &
+                                   0xFFFF_FFFF) as usize);
+        if let Some(key) = item_def_key(item_doc) {
+            keys.insert(index, key);
+        }
+        items.push((index, item_doc));
+    }
+    items.sort_by_key(|&(index, _)| index);
+
+    write!(out, "=Items=\n")?;
+    for (index, item_doc) in items {
+        let mut path = vec![];
+        let mut current = Some(index);
+        while let Some(key) = current.and_then(|index| keys.get(&index)) {
+            if key.disambiguated_data.data == hir_map::DefPathData::CrateRoot {
+                break;
+            }
+            if key.disambiguated_data.disambiguator == 0 {
+                path.push(key.disambiguated_data.data.to_string());
+            } else {
+                path.push(format!("{}#{}",
+                                  key.disambiguated_data.data.to_string(),
+                                  key.disambiguated_data.disambiguator));
+            }
+            current = key.parent;
+        }
+        path.reverse();
+
+        write!(out, "{} {:?} {}", index.as_u32(), item_family(item_doc), path.join("::"))?;
+        if let Some(symbol) = reader::maybe_get_doc(item_doc, tag_items_data_item_symbol) {
+            let exported = if reachable.contains(&index) { " (exported)" } else { "" };
+            write!(out, " symbol {}{}", symbol.as_str_slice(), exported)?;
+        }
+        write!(out, "\n")?;
+    }
+    write!(out, "\n")
 }
 
 // Translates a def_id from an external crate to a def_id for the current
@@ -1561,6 +1669,19 @@ pub fn get_reachable_ids(cdata: Cmd) -> Vec<DefId> {
     }).collect()
 }
 
+pub fn get_item_hashes(cdata: Cmd) -> FnvHashMap<DefIndex, u64> {
+    let hashes_doc = match reader::maybe_get_doc(rbml::Doc::new(cdata.data()),
+                                                 tag_item_hashes) {
+        Some(doc) => doc,
+        None => return FnvHashMap(),
+    };
+    reader::tagged_docs(hashes_doc, tag_item_hash).map(|doc| {
+        let index = reader::doc_as_u32(reader::get_doc(doc, tag_item_hash_index));
+        let hash = reader::doc_as_u64(reader::get_doc(doc, tag_item_hash_value));
+        (DefIndex::from_u32(index), hash)
+    }).collect()
+}
+
 pub fn is_typedef(cdata: Cmd, id: DefIndex) -> bool {
     let item_doc = cdata.lookup_item(id);
     match item_family(item_doc) {
@@ -1740,15 +1861,8 @@ pub fn closure_ty<'tcx>(cdata: Cmd, closure_id: DefIndex, tcx: &TyCtxt<'tcx>)
 pub fn def_key(cdata: Cmd, id: DefIndex) -> hir_map::DefKey {
     debug!("def_key: id={:?}", id);
     let item_doc = cdata.lookup_item(id);
-    match reader::maybe_get_doc(item_doc, tag_def_key) {
-        Some(def_key_doc) => {
-            let mut decoder = reader::Decoder::new(def_key_doc);
-            let simple_key = def_key::DefKey::decode(&mut decoder).unwrap();
-            let name = reader::maybe_get_doc(item_doc, tag_paths_data_name).map(|name| {
-                token::intern(name.as_str_slice())
-            });
-            def_key::recover_def_key(simple_key, name)
-        }
+    match item_def_key(item_doc) {
+        Some(def_key) => def_key,
         None => {
             bug!("failed to find block with tag {:?} for item with family {:?}",
                    tag_def_key,
@@ -1757,6 +1871,35 @@ pub fn def_key(cdata: Cmd, id: DefIndex) -> hir_map::DefKey {
     }
 }
 
+fn item_def_key(item_doc: rbml::Doc) -> Option<hir_map::DefKey> {
+    reader::maybe_get_doc(item_doc, tag_def_key).map(|def_key_doc| {
+        let mut decoder = reader::Decoder::new(def_key_doc);
+        let simple_key = def_key::DefKey::decode(&mut decoder).unwrap();
+        let name = reader::maybe_get_doc(item_doc, tag_paths_data_name).map(|name| {
+            token::intern(name.as_str_slice())
+        });
+        def_key::recover_def_key(simple_key, name)
+    })
+}
+
+/// Returns the item of the crate with the given `DefKey`, if any. The
+/// first call builds a map of the keys of all items of the crate.
+pub fn def_index_for_def_key(cdata: Cmd, key: hir_map::DefKey) -> Option<DefIndex> {
+    let mut key_map = cdata.key_map.borrow_mut();
+    if key_map.is_none() {
+        let items_doc = reader::get_doc(rbml::Doc::new(cdata.data()), tag_items);
+        let items_data_doc = reader::get_doc(items_doc, tag_items_data);
+        let mut map = FnvHashMap();
+        for item_doc in reader::tagged_docs(items_data_doc, tag_items_data_item) {
+            if let Some(key) = item_def_key(item_doc) {
+                map.insert(key, item_def_id(item_doc, cdata).index);
+            }
+        }
+        *key_map = Some(map);
+    }
+    key_map.as_ref().unwrap().get(&key).cloned()
+}
+
 pub fn def_path(cdata: Cmd, id: DefIndex) -> hir_map::DefPath {
     debug!("def_path(id={:?})", id);
     hir_map::DefPath::make(cdata.cnum, id, |parent| def_key(cdata, parent))
@@ -15,6 +15,7 @@
 
 use astencode::encode_inlined_item;
 use common::*;
+use header;
 use cstore;
 use decoder;
 use def_key;
@@ -34,7 +35,7 @@ use rustc::ty::util::IntTypeExt;
 use rustc::hir::svh::Svh;
 use rustc::mir::mir_map::MirMap;
 use rustc::session::config;
-use rustc::util::nodemap::{FnvHashMap, NodeMap, NodeSet};
+use rustc::util::nodemap::{DefIdMap, FnvHashMap, NodeMap, NodeSet};
 
 use rustc_serialize::Encodable;
 use std::cell::RefCell;
@@ -60,12 +61,15 @@ pub struct EncodeContext<'a, 'tcx: 'a> {
     pub diag: &'a Handler,
     pub tcx: &'a TyCtxt<'tcx>,
     pub reexports: &'a def::ExportMap,
-    pub item_symbols: &'a RefCell<NodeMap<String>>,
+    /// The symbols of the translated items, or `None` when only the metadata
+    /// is emitted, without translating the crate.
+    pub item_symbols: Option<&'a RefCell<NodeMap<String>>>,
     pub link_meta: &'a LinkMeta,
     pub cstore: &'a cstore::CStore,
     pub type_abbrevs: tyencode::abbrev_map<'tcx>,
     pub reachable: &'a NodeSet,
     pub mir_map: &'a MirMap<'tcx>,
+    pub item_hashes: &'a DefIdMap<u64>,
 }
 
 impl<'a, 'tcx> EncodeContext<'a,'tcx> {
@@ -209,7 +213,11 @@ fn encode_region(ecx: &EncodeContext,
 fn encode_symbol(ecx: &EncodeContext,
                  rbml_w: &mut Encoder,
                  id: NodeId) {
-    match ecx.item_symbols.borrow().get(&id) {
+    let item_symbols = match ecx.item_symbols {
+        Some(item_symbols) => item_symbols,
+        None => return,
+    };
+    match item_symbols.borrow().get(&id) {
         Some(x) => {
             debug!("encode_symbol(id={}, str={})", id, *x);
             rbml_w.wr_tagged_str(tag_items_data_item_symbol, x);
@@ -514,7 +522,7 @@ fn encode_info_for_struct_ctor<'a, 'tcx>(ecx: &EncodeContext<'a, 'tcx>,
     encode_name(rbml_w, name);
     encode_parent_item(rbml_w, ecx.tcx.map.local_def_id(struct_id));
 
-    if ecx.item_symbols.borrow().contains_key(&ctor_id) {
+    if ecx.item_symbols.map_or(false, |symbols| symbols.borrow().contains_key(&ctor_id)) {
         encode_symbol(ecx, rbml_w, ctor_id);
     }
 
@@ -1777,6 +1785,24 @@ fn encode_reachable(ecx: &EncodeContext, rbml_w: &mut Encoder) {
     rbml_w.end_tag();
 }
 
+/// Encodes the hashes of the items of the crate, which let incremental
+/// compilation of downstream crates find out which items changed.
+fn encode_item_hashes(ecx: &EncodeContext, rbml_w: &mut Encoder) {
+    let mut item_hashes = ecx.item_hashes.iter()
+                                         .map(|(def_id, &hash)| (def_id.index, hash))
+                                         .collect::<Vec<_>>();
+    item_hashes.sort();
+
+    rbml_w.start_tag(tag_item_hashes);
+    for (index, hash) in item_hashes {
+        rbml_w.start_tag(tag_item_hash);
+        rbml_w.wr_tagged_u32(tag_item_hash_index, index.as_u32());
+        rbml_w.wr_tagged_u64(tag_item_hash_value, hash);
+        rbml_w.end_tag();
+    }
+    rbml_w.end_tag();
+}
+
 fn encode_crate_dep(rbml_w: &mut Encoder,
                     dep: &cstore::crate_metadata) {
     rbml_w.start_tag(tag_crate_dep);
@@ -1829,10 +1855,6 @@ fn encode_dylib_dependency_formats(rbml_w: &mut Encoder, ecx: &EncodeContext) {
     }
 }
 
-// NB: Increment this as you change the metadata encoding version.
-#[allow(non_upper_case_globals)]
-pub const metadata_encoding_version : &'static [u8] = &[b'r', b'u', b's', b't', 0, 0, 0, 2 ];
-
 pub fn encode_metadata(ecx: EncodeContext, krate: &hir::Crate) -> Vec<u8> {
     let mut wr = Cursor::new(Vec::new());
 
@@ -1863,15 +1885,11 @@ pub fn encode_metadata(ecx: EncodeContext, krate: &hir::Crate) -> Vec<u8> {
     //
     // The root cause of why these extra bytes were appearing was never
     // discovered, and in the meantime the solution we're employing is to insert
-    // the length of the metadata to the start of the metadata. Later on this
-    // will allow us to slice the metadata to the precise length that we just
-    // generated regardless of trailing bytes that end up in it.
-    let len = v.len() as u32;
-    v.insert(0, (len >>  0) as u8);
-    v.insert(0, (len >>  8) as u8);
-    v.insert(0, (len >> 16) as u8);
-    v.insert(0, (len >> 24) as u8);
-    return v;
+    // the length of the metadata to the start of the metadata, as part of the
+    // header. Later on this will allow us to slice the metadata to the precise
+    // length that we just generated regardless of trailing bytes that end up
+    // in it.
+    header::write_header(&v)
 }
 
 fn encode_metadata_inner(rbml_w: &mut Encoder,
@@ -1959,6 +1977,7 @@ fn encode_metadata_inner(rbml_w: &mut Encoder,
     i = rbml_w.writer.seek(SeekFrom::Current(0)).unwrap();
     encode_misc_info(&ecx, krate, rbml_w);
     encode_reachable(&ecx, rbml_w);
+    encode_item_hashes(&ecx, rbml_w);
     stats.misc_bytes = rbml_w.writer.seek(SeekFrom::Current(0)).unwrap() - i;
 
     // Encode and index the items.
@@ -0,0 +1,115 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The header in front of the RBML document of crate metadata. It
+//! identifies the format of the document and the compiler which wrote it:
+//!
+//! ```text
+//! "rust" | format version | rustc version length | rustc version | body length | body
+//! ```
+//!
+//! The lengths and the format version are big-endian `u32`s. The layout up
+//! to and including the rustc version must never change, so that any
+//! compiler can tell which compiler wrote a crate, even one whose metadata
+//! it can't read.
+
+use common::rustc_version;
+
+/// The first bytes of all metadata: the magic `rust` and the format
+/// version. They also precede the compressed metadata in dylibs.
+///
+/// NB: Increment the format version as you change the metadata encoding.
+pub const METADATA_PREFIX: &'static [u8] = &[b'r', b'u', b's', b't', 0, 0, 0, 3];
+
+/// The format version of the metadata written and read by this compiler.
+pub fn metadata_format_version() -> u32 {
+    read_u32(&METADATA_PREFIX[4..])
+}
+
+pub struct MetadataHeader<'a> {
+    pub format_version: u32,
+    pub rustc_version: &'a str,
+    rest: &'a [u8],
+}
+
+impl<'a> MetadataHeader<'a> {
+    /// Reads the header at the start of `data`, whatever its format version.
+    pub fn read(data: &'a [u8]) -> Result<MetadataHeader<'a>, String> {
+        if data.len() < METADATA_PREFIX.len() || data[..4] != METADATA_PREFIX[..4] {
+            return Err(format!("no metadata header found, the crate was probably \
+                                compiled by an old version of rustc"));
+        }
+        let format_version = read_u32(&data[4..]);
+        let (rustc_version, rest) = match read_bytes(&data[METADATA_PREFIX.len()..]) {
+            Some(split) => split,
+            None => return Err(format!("truncated metadata header")),
+        };
+        let rustc_version = match ::std::str::from_utf8(rustc_version) {
+            Ok(rustc_version) => rustc_version,
+            Err(_) => return Err(format!("corrupt metadata header")),
+        };
+        Ok(MetadataHeader {
+            format_version: format_version,
+            rustc_version: rustc_version,
+            rest: rest,
+        })
+    }
+
+    /// Whether this compiler can read the metadata.
+    pub fn is_compatible(&self) -> bool {
+        self.format_version == metadata_format_version()
+    }
+
+    /// The RBML document, if this compiler can read the metadata. Any
+    /// trailing bytes are dropped (see `encoder::encode_metadata`).
+    pub fn body(&self) -> Option<&'a [u8]> {
+        if !self.is_compatible() {
+            return None;
+        }
+        read_bytes(self.rest).map(|(body, _)| body)
+    }
+}
+
+/// Puts the header in front of the RBML document `body`.
+pub fn write_header(body: &[u8]) -> Vec<u8> {
+    let rustc_version = rustc_version();
+    let mut data = Vec::with_capacity(METADATA_PREFIX.len() + rustc_version.len() +
+                                      body.len() + 8);
+    data.extend_from_slice(METADATA_PREFIX);
+    write_bytes(&mut data, rustc_version.as_bytes());
+    write_bytes(&mut data, body);
+    data
+}
+
+fn read_u32(data: &[u8]) -> u32 {
+    ((data[0] as u32) << 24) |
+    ((data[1] as u32) << 16) |
+    ((data[2] as u32) << 8) |
+    ((data[3] as u32) << 0)
+}
+
+/// Splits a length-prefixed byte string off the front of `data`.
+fn read_bytes(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    if data.len() < 4 {
+        return None;
+    }
+    let len = read_u32(data) as usize;
+    if len + 4 <= data.len() {
+        Some((&data[4..len + 4], &data[len + 4..]))
+    } else {
+        None
+    }
+}
+
+fn write_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+    let len = bytes.len() as u32;
+    data.extend_from_slice(&[(len >> 24) as u8, (len >> 16) as u8, (len >> 8) as u8, len as u8]);
+    data.extend_from_slice(bytes);
+}
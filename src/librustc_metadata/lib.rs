@@ -48,6 +48,7 @@ pub mod diagnostics;
 
 pub mod astencode;
 pub mod common;
+pub mod header;
 pub mod def_key;
 pub mod tyencode;
 pub mod tydecode;
@@ -61,6 +61,16 @@
 //! Most parts of this loading systems keep the dylib/rlib as just separate
 //! variables.
 //!
+//! ## Metadata-only crates
+//!
+//! With `--emit=metadata`, a crate is also written as a `lib*.rmeta` file,
+//! which holds nothing but its metadata (with the usual header, see
+//! `header.rs`) and which is written before the crate is translated. This is
+//! enough for type-checking dependent crates, so builds can move on to them
+//! early. As the crate wasn't translated, the `.rmeta` file lacks the symbols
+//! needed for translating against it; an rlib or dylib has to be found as
+//! well for that (see `dependency_format`).
+//!
 //! ## Where to look?
 //!
 //! We can't exactly scan your whole hard drive when looking for dependencies,
@@ -212,9 +222,9 @@
 //! no means all of the necessary details. Take a look at the rest of
 //! metadata::loader or metadata::creader for all the juicy details!
 
-use cstore::{MetadataBlob, MetadataVec, MetadataArchive};
+use cstore::{CStore, MetadataBlob, MetadataVec, MetadataArchive};
 use decoder;
-use encoder;
+use header::{MetadataHeader, METADATA_PREFIX, metadata_format_version};
 
 use rustc::hir::svh::Svh;
 use rustc::session::Session;
@@ -244,11 +254,52 @@ use flate;
 
 pub struct CrateMismatch {
     path: PathBuf,
-    got: String,
+}
+
+/// A library examined while looking for a crate, recorded to explain the
+/// outcome if loading fails.
+pub struct Candidate {
+    path: PathBuf,
+    /// The hash of the crate, the compiler which built it and its target, as
+    /// far as they could be read from the metadata.
+    hash: Option<Svh>,
+    rustc_version: Option<String>,
+    triple: Option<String>,
+    /// Why the library was rejected, `None` if it was accepted.
+    rejection: Option<String>,
+}
+
+impl Candidate {
+    fn rejected(path: &Path, rejection: String) -> Candidate {
+        Candidate {
+            path: path.to_path_buf(),
+            hash: None,
+            rustc_version: None,
+            triple: None,
+            rejection: Some(rejection),
+        }
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(ref hash) = self.hash {
+            write!(f, " (hash {}, compiled by {} for {})",
+                   hash,
+                   self.rustc_version.as_ref().map_or("an unknown rustc", |v| &v[..]),
+                   self.triple.as_ref().map_or("an unknown target", |t| &t[..]))?;
+        }
+        match self.rejection {
+            Some(ref rejection) => write!(f, ": rejected, {}", rejection),
+            None => write!(f, ": accepted"),
+        }
+    }
 }
 
 pub struct Context<'a> {
     pub sess: &'a Session,
+    pub cstore: &'a CStore,
     pub span: Span,
     pub ident: &'a str,
     pub crate_name: &'a str,
@@ -261,12 +312,14 @@ pub struct Context<'a> {
     pub rejected_via_hash: Vec<CrateMismatch>,
     pub rejected_via_triple: Vec<CrateMismatch>,
     pub rejected_via_kind: Vec<CrateMismatch>,
+    pub examined: Vec<Candidate>,
     pub should_match_name: bool,
 }
 
 pub struct Library {
     pub dylib: Option<(PathBuf, PathKind)>,
     pub rlib: Option<(PathBuf, PathKind)>,
+    pub rmeta: Option<(PathBuf, PathKind)>,
     pub metadata: MetadataBlob,
 }
 
@@ -279,7 +332,8 @@ pub struct ArchiveMetadata {
 pub struct CratePaths {
     pub ident: String,
     pub dylib: Option<PathBuf>,
-    pub rlib: Option<PathBuf>
+    pub rlib: Option<PathBuf>,
+    pub rmeta: Option<PathBuf>,
 }
 
 pub const METADATA_FILENAME: &'static str = "rust.metadata.bin";
@@ -287,6 +341,7 @@ pub const METADATA_FILENAME: &'static str = "rust.metadata.bin";
 #[derive(Copy, Clone, PartialEq)]
 enum CrateFlavor {
     Rlib,
+    Rmeta,
     Dylib
 }
 
@@ -294,6 +349,7 @@ impl fmt::Display for CrateFlavor {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(match *self {
             CrateFlavor::Rlib => "rlib",
+            CrateFlavor::Rmeta => "rmeta",
             CrateFlavor::Dylib => "dylib"
         })
     }
@@ -301,12 +357,7 @@ impl fmt::Display for CrateFlavor {
 
 impl CratePaths {
     fn paths(&self) -> Vec<PathBuf> {
-        match (&self.dylib, &self.rlib) {
-            (&None,    &None)              => vec!(),
-            (&Some(ref p), &None) |
-            (&None, &Some(ref p))          => vec!(p.clone()),
-            (&Some(ref p1), &Some(ref p2)) => vec!(p1.clone(), p2.clone()),
-        }
+        self.dylib.iter().chain(&self.rlib).chain(&self.rmeta).cloned().collect()
     }
 }
 
@@ -343,20 +394,16 @@ impl<'a> Context<'a> {
                              self.ident, add)
         };
 
-        if !self.rejected_via_triple.is_empty() {
-            let mismatches = self.rejected_via_triple.iter();
-            for (i, &CrateMismatch{ ref path, ref got }) in mismatches.enumerate() {
-                err.note(&format!("crate `{}`, path #{}, triple {}: {}",
-                                  self.ident, i+1, got, path.display()));
-            }
-        }
         if !self.rejected_via_hash.is_empty() {
             err.note("perhaps this crate needs to be recompiled?");
-            let mismatches = self.rejected_via_hash.iter();
-            for (i, &CrateMismatch{ ref path, .. }) in mismatches.enumerate() {
-                err.note(&format!("crate `{}` path #{}: {}",
-                                  self.ident, i+1, path.display()));
-            }
+        }
+        if !self.rejected_via_kind.is_empty() {
+            err.help("please recompile this crate using --crate-type lib");
+        }
+        for (i, candidate) in self.examined.iter().enumerate() {
+            err.note(&format!("crate `{}` path #{}: {}", self.ident, i+1, candidate));
+        }
+        if !self.rejected_via_hash.is_empty() {
             match self.root {
                 &None => {}
                 &Some(ref r) => {
@@ -366,14 +413,7 @@ impl<'a> Context<'a> {
                     }
                 }
             }
-        }
-        if !self.rejected_via_kind.is_empty() {
-            err.help("please recompile this crate using --crate-type lib");
-            let mismatches = self.rejected_via_kind.iter();
-            for (i, &CrateMismatch { ref path, .. }) in mismatches.enumerate() {
-                err.note(&format!("crate `{}` path #{}: {}",
-                                  self.ident, i+1, path.display()));
-            }
+            self.note_loaded_versions(&mut err);
         }
 
         err.emit();
@@ -381,6 +421,48 @@ impl<'a> Context<'a> {
         unreachable!();
     }
 
+    // Points out the versions of the crate which are already loaded, along
+    // with the crates whose dependencies pulled them in, as one of those is
+    // usually what conflicts with the version `self.root` was compiled
+    // against.
+    fn note_loaded_versions(&self, err: &mut DiagnosticBuilder) {
+        let mut loaded = Vec::new();
+        self.cstore.iter_crate_data(|cnum, data| {
+            if data.name == self.crate_name {
+                loaded.push((cnum, data.clone()));
+            }
+        });
+        loaded.sort_by_key(|&(cnum, _)| cnum);
+        for (cnum, data) in loaded {
+            let source = match self.cstore.opt_used_crate_source(cnum) {
+                Some(source) => source,
+                None => continue,
+            };
+            let path = match source.dylib.or(source.rlib).or(source.rmeta) {
+                Some((path, _)) => path,
+                None => continue,
+            };
+            let hash = self.cstore.get_crate_hash(cnum);
+            let dependent = match data.dependent {
+                Some(ref dependent) => format!("as a dependency of `{}`", dependent),
+                None => "by this crate".to_string(),
+            };
+            match self.root {
+                &Some(ref r) => {
+                    err.help(&format!("`{}` was compiled against a different version of \
+                                       `{}` than the one already loaded from {} (hash {}) \
+                                       {}; recompile `{}` against that version",
+                                      r.ident, self.ident, path.display(), hash, dependent,
+                                      r.ident));
+                }
+                &None => {
+                    err.note(&format!("crate `{}` is already loaded from {} (hash {}) {}",
+                                      self.ident, path.display(), hash, dependent));
+                }
+            }
+        }
+    }
+
     fn find_library_crate(&mut self) -> Option<Library> {
         // If an SVH is specified, then this is a transitive dependency that
         // must be loaded via -L plus some filtering.
@@ -421,20 +503,23 @@ impl<'a> Context<'a> {
                 None => return FileDoesntMatch,
                 Some(file) => file,
             };
-            let (hash, rlib) = if file.starts_with(&rlib_prefix[..]) &&
-                                  file.ends_with(".rlib") {
+            let (hash, flavor) = if file.starts_with(&rlib_prefix[..]) &&
+                                    file.ends_with(".rlib") {
                 (&file[(rlib_prefix.len()) .. (file.len() - ".rlib".len())],
-                 true)
+                 CrateFlavor::Rlib)
+            } else if file.starts_with(&rlib_prefix[..]) &&
+                      file.ends_with(".rmeta") {
+                (&file[(rlib_prefix.len()) .. (file.len() - ".rmeta".len())],
+                 CrateFlavor::Rmeta)
             } else if file.starts_with(&dylib_prefix) &&
                       file.ends_with(&dypair.1) {
                 (&file[(dylib_prefix.len()) .. (file.len() - dypair.1.len())],
-                 false)
+                 CrateFlavor::Dylib)
             } else {
                 if file.starts_with(&staticlib_prefix[..]) &&
                    file.ends_with(&staticpair.1) {
                     staticlibs.push(CrateMismatch {
                         path: path.to_path_buf(),
-                        got: "static".to_string()
                     });
                 }
                 return FileDoesntMatch
@@ -442,37 +527,44 @@ impl<'a> Context<'a> {
             info!("lib candidate: {}", path.display());
 
             let hash_str = hash.to_string();
-            let slot = candidates.entry(hash_str)
-                                 .or_insert_with(|| (HashMap::new(), HashMap::new()));
-            let (ref mut rlibs, ref mut dylibs) = *slot;
+            let slot = candidates.entry(hash_str).or_insert_with(|| {
+                (HashMap::new(), HashMap::new(), HashMap::new())
+            });
+            let (ref mut rlibs, ref mut rmetas, ref mut dylibs) = *slot;
             fs::canonicalize(path).map(|p| {
-                if rlib {
-                    rlibs.insert(p, kind);
-                } else {
-                    dylibs.insert(p, kind);
-                }
+                match flavor {
+                    CrateFlavor::Rlib => rlibs.insert(p, kind),
+                    CrateFlavor::Rmeta => rmetas.insert(p, kind),
+                    CrateFlavor::Dylib => dylibs.insert(p, kind),
+                };
                 FileMatches
             }).unwrap_or(FileDoesntMatch)
         });
+        for lib in &staticlibs {
+            self.examined.push(Candidate::rejected(&lib.path,
+                                                   format!("it is a static library")));
+        }
         self.rejected_via_kind.extend(staticlibs);
 
         // We have now collected all known libraries into a set of candidates
         // keyed of the filename hash listed. For each filename, we also have a
-        // list of rlibs/dylibs that apply. Here, we map each of these lists
+        // list of rlibs/rmetas/dylibs that apply. Here, we map each of these lists
         // (per hash), to a Library candidate for returning.
         //
         // A Library candidate is created if the metadata for the set of
         // libraries corresponds to the crate id and hash criteria that this
         // search is being performed for.
         let mut libraries = HashMap::new();
-        for (_hash, (rlibs, dylibs)) in candidates {
+        for (_hash, (rlibs, rmetas, dylibs)) in candidates {
             let mut slot = None;
             let rlib = self.extract_one(rlibs, CrateFlavor::Rlib, &mut slot);
+            let rmeta = self.extract_one(rmetas, CrateFlavor::Rmeta, &mut slot);
             let dylib = self.extract_one(dylibs, CrateFlavor::Dylib, &mut slot);
             if let Some((h, m)) = slot {
                 libraries.insert(h, Library {
                     dylib: dylib,
                     rlib: rlib,
+                    rmeta: rmeta,
                     metadata: m,
                 });
             }
@@ -490,19 +582,12 @@ impl<'a> Context<'a> {
                                                self.crate_name);
                 err.note("candidates:");
                 for (_, lib) in libraries {
-                    match lib.dylib {
-                        Some((ref p, _)) => {
-                            err.note(&format!("path: {}",
-                                              p.display()));
-                        }
-                        None => {}
-                    }
-                    match lib.rlib {
-                        Some((ref p, _)) => {
-                            err.note(&format!("path: {}",
-                                              p.display()));
-                        }
-                        None => {}
+                    let paths = lib.dylib.iter().chain(&lib.rlib).chain(&lib.rmeta);
+                    for &(ref p, _) in paths {
+                        match self.examined.iter().find(|c| c.path == *p) {
+                            Some(candidate) => err.note(&format!("path: {}", candidate)),
+                            None => err.note(&format!("path: {}", p.display())),
+                        };
                     }
                     let data = lib.metadata.as_slice();
                     let name = decoder::get_crate_name(data);
@@ -553,6 +638,7 @@ impl<'a> Context<'a> {
                 }
                 Err(err) => {
                     info!("no metadata found: {}", err);
+                    self.examined.push(Candidate::rejected(&lib, err));
                     continue
                 }
             };
@@ -592,28 +678,55 @@ impl<'a> Context<'a> {
     }
 
     fn crate_matches(&mut self, crate_data: &[u8], libpath: &Path) -> Option<Svh> {
+        let result = self.check_crate_metadata(crate_data, libpath);
+        self.examined.push(Candidate {
+            path: libpath.to_path_buf(),
+            hash: decoder::maybe_get_crate_hash(crate_data),
+            rustc_version: decoder::crate_rustc_version(crate_data),
+            triple: decoder::get_crate_triple(crate_data),
+            rejection: result.as_ref().err().cloned(),
+        });
+        result.ok()
+    }
+
+    // Checks whether `crate_data` is the metadata of the crate we're looking
+    // for, returning its hash if so and why it isn't otherwise.
+    fn check_crate_metadata(&mut self, crate_data: &[u8], libpath: &Path)
+                            -> Result<Svh, String> {
         if self.should_match_name {
             match decoder::maybe_get_crate_name(crate_data) {
                 Some(ref name) if self.crate_name == *name => {}
-                _ => { info!("Rejecting via crate name"); return None }
+                Some(name) => {
+                    info!("Rejecting via crate name");
+                    return Err(format!("its crate name is `{}`", name));
+                }
+                None => {
+                    info!("Rejecting via crate name");
+                    return Err(format!("it has no crate name"));
+                }
             }
         }
         let hash = match decoder::maybe_get_crate_hash(crate_data) {
-            None => { info!("Rejecting via lack of crate hash"); return None; }
+            None => {
+                info!("Rejecting via lack of crate hash");
+                return Err(format!("it has no crate hash"));
+            }
             Some(h) => h,
         };
 
         let triple = match decoder::get_crate_triple(crate_data) {
-            None => { debug!("triple not present"); return None }
+            None => {
+                debug!("triple not present");
+                return Err(format!("it has no target triple"));
+            }
             Some(t) => t,
         };
         if triple != self.triple {
             info!("Rejecting via crate triple: expected {} got {}", self.triple, triple);
             self.rejected_via_triple.push(CrateMismatch {
                 path: libpath.to_path_buf(),
-                got: triple.to_string()
             });
-            return None;
+            return Err(format!("it was compiled for {}, not {}", triple, self.triple));
         }
 
         if let Some(myhash) = self.hash {
@@ -621,13 +734,17 @@ impl<'a> Context<'a> {
                 info!("Rejecting via hash: expected {} got {}", *myhash, hash);
                 self.rejected_via_hash.push(CrateMismatch {
                     path: libpath.to_path_buf(),
-                    got: myhash.as_str().to_string()
                 });
-                return None;
+                let dependent = match self.root {
+                    &Some(ref r) => format!("`{}`", r.ident),
+                    &None => format!("the crate depending on it"),
+                };
+                return Err(format!("{} was compiled against the version with hash {}",
+                                   dependent, myhash));
             }
         }
 
-        Some(hash)
+        Ok(hash)
     }
 
 
@@ -652,6 +769,7 @@ impl<'a> Context<'a> {
         let sess = self.sess;
         let dylibname = self.dylibname();
         let mut rlibs = HashMap::new();
+        let mut rmetas = HashMap::new();
         let mut dylibs = HashMap::new();
         {
             let locs = locs.iter().map(|l| PathBuf::from(l)).filter(|loc| {
@@ -668,7 +786,8 @@ impl<'a> Context<'a> {
                         return false;
                     }
                 };
-                if file.starts_with("lib") && file.ends_with(".rlib") {
+                if file.starts_with("lib") &&
+                   (file.ends_with(".rlib") || file.ends_with(".rmeta")) {
                     return true
                 } else {
                     let (ref prefix, ref suffix) = dylibname;
@@ -679,18 +798,22 @@ impl<'a> Context<'a> {
                 }
                 sess.struct_err(&format!("extern location for {} is of an unknown type: {}",
                                          self.crate_name, loc.display()))
-                    .help(&format!("file name should be lib*.rlib or {}*.{}",
+                    .help(&format!("file name should be lib*.rlib, lib*.rmeta or {}*.{}",
                                    dylibname.0, dylibname.1))
                     .emit();
                 false
             });
 
             // Now that we have an iterator of good candidates, make sure
-            // there's at most one rlib and at most one dylib.
+            // there's at most one rlib, rmeta and dylib.
             for loc in locs {
-                if loc.file_name().unwrap().to_str().unwrap().ends_with(".rlib") {
+                let file = loc.file_name().unwrap().to_str().unwrap();
+                if file.ends_with(".rlib") {
                     rlibs.insert(fs::canonicalize(&loc).unwrap(),
                                  PathKind::ExternFlag);
+                } else if file.ends_with(".rmeta") {
+                    rmetas.insert(fs::canonicalize(&loc).unwrap(),
+                                  PathKind::ExternFlag);
                 } else {
                     dylibs.insert(fs::canonicalize(&loc).unwrap(),
                                   PathKind::ExternFlag);
@@ -698,16 +821,18 @@ impl<'a> Context<'a> {
             }
         };
 
-        // Extract the rlib/dylib pair.
+        // Extract the rlib/rmeta/dylib triple.
         let mut slot = None;
         let rlib = self.extract_one(rlibs, CrateFlavor::Rlib, &mut slot);
+        let rmeta = self.extract_one(rmetas, CrateFlavor::Rmeta, &mut slot);
         let dylib = self.extract_one(dylibs, CrateFlavor::Dylib, &mut slot);
 
-        if rlib.is_none() && dylib.is_none() { return None }
+        if rlib.is_none() && rmeta.is_none() && dylib.is_none() { return None }
         match slot {
             Some((_, metadata)) => Some(Library {
                 dylib: dylib,
                 rlib: rlib,
+                rmeta: rmeta,
                 metadata: metadata,
             }),
             None => None,
@@ -773,7 +898,15 @@ fn get_metadata_section_imp(target: &Target, flavor: CrateFlavor, filename: &Pat
         return match ArchiveMetadata::new(archive).map(|ar| MetadataArchive(ar)) {
             None => Err(format!("failed to read rlib metadata: '{}'",
                                 filename.display())),
-            Some(blob) => Ok(blob)
+            Some(blob) => verify_metadata_header(blob, filename)
+        };
+    }
+    if flavor == CrateFlavor::Rmeta {
+        let mut data = vec![];
+        return match fs::File::open(filename).and_then(|mut f| f.read_to_end(&mut data)) {
+            Ok(_) => verify_metadata_header(MetadataVec(data), filename),
+            Err(err) => Err(format!("failed to read rmeta metadata: '{}': {}",
+                                    filename.display(), err)),
         };
     }
     unsafe {
@@ -802,12 +935,12 @@ fn get_metadata_section_imp(target: &Target, flavor: CrateFlavor, filename: &Pat
                 let cbuf = llvm::LLVMGetSectionContents(si.llsi);
                 let csz = llvm::LLVMGetSectionSize(si.llsi) as usize;
                 let cvbuf: *const u8 = cbuf as *const u8;
-                let vlen = encoder::metadata_encoding_version.len();
+                let vlen = METADATA_PREFIX.len();
                 debug!("checking {} bytes of metadata-version stamp",
                        vlen);
                 let minsz = cmp::min(vlen, csz);
                 let buf0 = slice::from_raw_parts(cvbuf, minsz);
-                let version_ok = buf0 == encoder::metadata_encoding_version;
+                let version_ok = buf0 == METADATA_PREFIX;
                 if !version_ok {
                     return Err((format!("incompatible metadata version found: '{}'",
                                         filename.display())));
@@ -818,7 +951,9 @@ fn get_metadata_section_imp(target: &Target, flavor: CrateFlavor, filename: &Pat
                        csz - vlen);
                 let bytes = slice::from_raw_parts(cvbuf1, csz - vlen);
                 match flate::inflate_bytes(bytes) {
-                    Ok(inflated) => return Ok(MetadataVec(inflated)),
+                    Ok(inflated) => {
+                        return verify_metadata_header(MetadataVec(inflated), filename)
+                    }
                     Err(_) => {}
                 }
             }
@@ -828,6 +963,23 @@ fn get_metadata_section_imp(target: &Target, flavor: CrateFlavor, filename: &Pat
     }
 }
 
+/// Checks that this compiler can read `blob`, whose header tells which
+/// compiler wrote it otherwise.
+fn verify_metadata_header(blob: MetadataBlob, filename: &Path) -> Result<MetadataBlob, String> {
+    match MetadataHeader::read(blob.raw_bytes()) {
+        Ok(ref header) if header.is_compatible() => {}
+        Ok(header) => {
+            return Err(format!("incompatible metadata version found: '{}' has metadata \
+                                format version {}, written by {}, but this compiler \
+                                reads format version {}",
+                               filename.display(), header.format_version,
+                               header.rustc_version, metadata_format_version()));
+        }
+        Err(err) => return Err(format!("{}: '{}'", err, filename.display())),
+    }
+    Ok(blob)
+}
+
 pub fn meta_section_name(target: &Target) -> &'static str {
     if target.options.is_like_osx {
         "__DATA,__note.rustc"
@@ -863,9 +1015,15 @@ pub fn read_meta_section_name(target: &Target) -> &'static str {
 pub fn list_file_metadata(target: &Target, path: &Path,
                           out: &mut io::Write) -> io::Result<()> {
     let filename = path.file_name().unwrap().to_str().unwrap();
-    let flavor = if filename.ends_with(".rlib") { CrateFlavor::Rlib } else { CrateFlavor::Dylib };
+    let flavor = if filename.ends_with(".rlib") {
+        CrateFlavor::Rlib
+    } else if filename.ends_with(".rmeta") {
+        CrateFlavor::Rmeta
+    } else {
+        CrateFlavor::Dylib
+    };
     match get_metadata_section(target, flavor, path) {
-        Ok(bytes) => decoder::list_crate_metadata(bytes.as_slice(), out),
+        Ok(blob) => decoder::list_crate_metadata(&blob, out),
         Err(msg) => {
             write!(out, "{}\n", msg)
         }
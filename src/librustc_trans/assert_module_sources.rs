@@ -0,0 +1,127 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks, for unit tests of incremental compilation, whether the object
+//! file of the codegen unit a module is translated into is re-used from the
+//! previous session or regenerated. Modules are annotated with:
+//!
+//! - `#[rustc_partition_reused(cfg="rpass2")]`: if we are in
+//!   `#[cfg(rpass2)]`, the codegen unit MUST be re-used.
+//! - `#[rustc_partition_translated(cfg="rpass2")]`: same as above, except
+//!   that the codegen unit MUST be translated again.
+//!
+//! Errors are reported if we are in the suitable configuration but the
+//! required condition is not met.
+
+use rustc::hir;
+use rustc::hir::intravisit::Visitor;
+use syntax::ast;
+use syntax::attr::AttrMetaMethods;
+
+use context::SharedCrateContext;
+use ModuleTranslation;
+
+const REUSED: &'static str = "rustc_partition_reused";
+const TRANSLATED: &'static str = "rustc_partition_translated";
+const CFG: &'static str = "cfg";
+
+pub fn assert_module_sources(shared_ccx: &SharedCrateContext, modules: &[ModuleTranslation]) {
+    // if the `rustc_attrs` feature is not enabled, then the
+    // attributes we are interested in cannot be present anyway, so
+    // skip the walk.
+    let tcx = shared_ccx.tcx();
+    if !tcx.sess.features.borrow().rustc_attrs {
+        return;
+    }
+
+    let _ignore = tcx.dep_graph.in_ignore();
+    // The modules whose items ended up in each codegen unit.
+    let units = shared_ccx.iter().zip(modules).map(|(ccx, mtrans)| {
+        let parents = ccx.translated_items().borrow().iter().filter_map(|&def_id| {
+            tcx.map.as_local_node_id(def_id).map(|id| tcx.map.get_module_parent(id))
+        }).collect::<Vec<_>>();
+        (parents, mtrans)
+    }).collect::<Vec<_>>();
+
+    tcx.map.krate().visit_all_items(&mut AssertModuleSource {
+        shared_ccx: shared_ccx,
+        units: &units,
+    });
+}
+
+struct AssertModuleSource<'a, 'b: 'a, 'tcx: 'b> {
+    shared_ccx: &'a SharedCrateContext<'b, 'tcx>,
+    units: &'a [(Vec<ast::NodeId>, &'a ModuleTranslation)],
+}
+
+impl<'a, 'b, 'tcx> AssertModuleSource<'a, 'b, 'tcx> {
+    /// Given a `#[rustc_partition_reused]` or `#[rustc_partition_translated]`
+    /// attribute, scan for a `cfg="foo"` attribute and check whether we have
+    /// a cfg flag called `foo`.
+    fn check_config(&self, attr: &ast::Attribute) -> bool {
+        let tcx = self.shared_ccx.tcx();
+        let config = &tcx.map.krate().config;
+        for item in attr.meta_item_list().unwrap_or(&[]) {
+            if item.check_name(CFG) {
+                let value = match item.value_str() {
+                    Some(value) => value,
+                    None => {
+                        tcx.sess.span_fatal(item.span,
+                                            &format!("associated value expected for `{}`",
+                                                     item.name()));
+                    }
+                };
+                if config.iter().any(|cfg| cfg.check_name(&value[..])) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+impl<'a, 'b, 'tcx, 'v> Visitor<'v> for AssertModuleSource<'a, 'b, 'tcx> {
+    fn visit_item(&mut self, item: &'v hir::Item) {
+        let tcx = self.shared_ccx.tcx();
+        for attr in &item.attrs {
+            let expected_reuse = if attr.check_name(REUSED) {
+                true
+            } else if attr.check_name(TRANSLATED) {
+                false
+            } else {
+                continue
+            };
+            if !self.check_config(attr) {
+                continue
+            }
+
+            let module_name = tcx.item_path_str(tcx.map.local_def_id(item.id));
+            let mtrans = match self.units.iter().find(|&&(ref parents, _)| {
+                parents.contains(&item.id)
+            }) {
+                Some(&(_, mtrans)) => mtrans,
+                None => {
+                    tcx.sess.span_err(attr.span,
+                                      &format!("no items of module `{}` were translated",
+                                               module_name));
+                    continue
+                }
+            };
+            if mtrans.previous.is_some() != expected_reuse {
+                tcx.sess.span_err(attr.span,
+                                  &format!("codegen unit `{}` of module `{}` was {}, \
+                                            but should have been {}",
+                                           mtrans.name, module_name,
+                                           if expected_reuse { "translated" } else { "re-used" },
+                                           if expected_reuse { "re-used" } else { "translated" }));
+            }
+        }
+    }
+}
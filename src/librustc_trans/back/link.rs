@@ -274,6 +274,18 @@ pub fn filename_for_input(sess: &Session,
     }
 }
 
+/// The file written for `--emit=metadata`, which sits next to the rlib so
+/// that it is found by the same `-L` paths.
+pub fn metadata_filename(sess: &Session,
+                         crate_name: &str,
+                         outputs: &OutputFilenames) -> PathBuf {
+    if let Some(&Some(ref path)) = outputs.outputs.get(&OutputType::Metadata) {
+        return path.clone();
+    }
+    let libname = format!("{}{}", crate_name, sess.opts.cg.extra_filename);
+    outputs.out_directory.join(&format!("lib{}.rmeta", libname))
+}
+
 pub fn each_linked_rlib(sess: &Session,
                         f: &mut FnMut(ast::CrateNum, &Path)) {
     let crates = sess.cstore.used_crates(LinkagePreference::RequireStatic).into_iter();
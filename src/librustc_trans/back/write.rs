@@ -16,13 +16,17 @@ use session::config::{self, OutputType};
 use llvm;
 use llvm::{ModuleRef, TargetMachineRef, PassManagerRef, DiagnosticInfoRef, ContextRef};
 use llvm::SMDiagnosticRef;
-use {CrateTranslation, ModuleTranslation};
+use {CrateTranslation, ModuleLlvm};
+use rustc::dep_graph::WorkProduct;
+use rustc::util::fs::link_or_copy;
+use rustc_incremental::{save_trans_partition, save_work_products};
 use util::common::time;
 use util::common::path2cstr;
 use syntax::codemap::MultiSpan;
 use syntax::errors::{self, Handler, Level, RenderSpan};
 use syntax::errors::emitter::CoreEmitter;
 
+use std::cmp;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::fs;
@@ -421,11 +425,11 @@ unsafe extern "C" fn diagnostic_handler(info: DiagnosticInfoRef, user: *mut c_vo
 
 // Unsafe due to LLVM calls.
 unsafe fn optimize_and_codegen(cgcx: &CodegenContext,
-                               mtrans: ModuleTranslation,
+                               mtrans: ModuleLlvm,
                                config: ModuleConfig,
                                name_extra: String,
                                output_names: OutputFilenames) {
-    let ModuleTranslation { llmod, llcx } = mtrans;
+    let ModuleLlvm { llmod, llcx } = mtrans;
     let tm = config.tm;
 
     // llcx doesn't outlive this function, so we can put this on the stack.
@@ -689,7 +693,8 @@ pub fn run_passes(sess: &Session,
                 modules_config.emit_obj = true;
                 metadata_config.emit_obj = true;
             },
-            OutputType::DepInfo => {}
+            OutputType::DepInfo |
+            OutputType::Metadata => {}
         }
     }
 
@@ -712,19 +717,38 @@ pub fn run_passes(sess: &Session,
     }
 
     for (index, mtrans) in trans.modules.iter().enumerate() {
+        if let Some(ref work_product) = mtrans.previous {
+            copy_previous_outputs(sess, work_product, index, crate_output);
+            unsafe {
+                llvm::LLVMDisposeModule(mtrans.llvm.llmod);
+                llvm::LLVMContextDispose(mtrans.llvm.llcx);
+            }
+            continue;
+        }
         let work = build_work_item(sess,
-                                   *mtrans,
+                                   mtrans.llvm,
                                    modules_config.clone(),
                                    crate_output.clone(),
                                    format!("{}", index));
         work_items.push(work);
     }
 
+    // The outputs of the modules we are about to rebuild are overwritten
+    // below, so forget about them until they are saved again. Otherwise an
+    // error in LLVM could leave stale outputs looking valid.
+    if sess.opts.incremental.is_some() {
+        save_work_products(sess, previous_work_products(trans));
+    }
+
     // Process the work items, optionally using worker threads.
     if sess.opts.cg.codegen_units == 1 {
         run_work_singlethreaded(sess, &trans.reachable, work_items);
     } else {
-        run_work_multithreaded(sess, work_items, sess.opts.cg.codegen_units);
+        // The workers share one queue of work items, so there is no point
+        // in running more of them than there are CPUs to run them on.
+        let num_workers = cmp::min(sess.opts.cg.codegen_units,
+                                   thread::available_parallelism().unwrap_or(1));
+        run_work_multithreaded(sess, work_items, num_workers);
     }
 
     // All codegen is finished.
@@ -732,6 +756,10 @@ pub fn run_passes(sess: &Session,
         llvm::LLVMRustDisposeTargetMachine(tm);
     }
 
+    if sess.opts.incremental.is_some() && sess.err_count() == 0 {
+        save_module_outputs(sess, trans, &modules_config, crate_output);
+    }
+
     // Produce final compile outputs.
     let copy_gracefully = |from: &Path, to: &Path| {
         if let Err(e) = fs::copy(from, to) {
@@ -793,7 +821,8 @@ pub fn run_passes(sess: &Session,
                 copy_if_one_unit("0.o", OutputType::Object, true);
             }
             OutputType::Exe |
-            OutputType::DepInfo => {}
+            OutputType::DepInfo |
+            OutputType::Metadata => {}
         }
     }
     let user_wants_bitcode = user_wants_bitcode;
@@ -862,15 +891,72 @@ pub fn run_passes(sess: &Session,
     }
 }
 
+/// Returns the work products re-used from the previous incremental session.
+fn previous_work_products(trans: &CrateTranslation) -> Vec<(String, WorkProduct)> {
+    trans.modules.iter().filter_map(|mtrans| {
+        mtrans.previous.as_ref().map(|work_product| (mtrans.name.clone(), work_product.clone()))
+    }).collect()
+}
+
+/// Copies the outputs of the module with the given index saved by the
+/// previous incremental session to where LLVM would have written them.
+fn copy_previous_outputs(sess: &Session,
+                         work_product: &WorkProduct,
+                         index: usize,
+                         crate_output: &OutputFilenames) {
+    let incr_dir = sess.opts.incremental.as_ref().unwrap();
+    for saved_file in &work_product.saved_files {
+        let extension = saved_file.rsplit('.').next().unwrap();
+        let from = incr_dir.join(saved_file);
+        let to = crate_output.with_extension(&format!("{}.{}", index, extension));
+        debug!("copying pre-existing module output {:?} to {:?}", from, to);
+        if let Err(e) = link_or_copy(&from, &to) {
+            sess.err(&format!("could not copy {:?} to {:?}: {}", from, to, e));
+        }
+    }
+}
+
+/// Saves the outputs of the modules which were rebuilt into the incremental
+/// directory and records them, along with the re-used ones, as the work
+/// products of this session.
+fn save_module_outputs(sess: &Session,
+                       trans: &CrateTranslation,
+                       config: &ModuleConfig,
+                       crate_output: &OutputFilenames) {
+    let mut work_products = previous_work_products(trans);
+    for (index, mtrans) in trans.modules.iter().enumerate() {
+        if mtrans.previous.is_some() {
+            continue;
+        }
+        let extensions = [(config.emit_obj, "o"),
+                          (config.emit_bc, "bc"),
+                          (config.emit_ir, "ll"),
+                          (config.emit_asm, "s")];
+        let files = extensions.iter()
+                              .filter(|&&(emitted, _)| emitted)
+                              .map(|&(_, ext)| {
+                                  crate_output.with_extension(&format!("{}.{}", index, ext))
+                              })
+                              .collect::<Vec<_>>();
+        if let Some(work_product) = save_trans_partition(sess,
+                                                         &mtrans.name,
+                                                         mtrans.input_hash,
+                                                         &files) {
+            work_products.push((mtrans.name.clone(), work_product));
+        }
+    }
+    save_work_products(sess, work_products);
+}
+
 struct WorkItem {
-    mtrans: ModuleTranslation,
+    mtrans: ModuleLlvm,
     config: ModuleConfig,
     output_names: OutputFilenames,
     name_extra: String
 }
 
 fn build_work_item(sess: &Session,
-                   mtrans: ModuleTranslation,
+                   mtrans: ModuleLlvm,
                    config: ModuleConfig,
                    output_names: OutputFilenames,
                    name_extra: String)
@@ -28,7 +28,7 @@
 pub use self::ValueOrigin::*;
 
 use super::CrateTranslation;
-use super::ModuleTranslation;
+use super::{ModuleLlvm, ModuleTranslation};
 
 use back::{link, symbol_names};
 use lint;
@@ -45,7 +45,8 @@ use rustc::ty::subst::{self, Substs};
 use rustc::traits;
 use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
 use rustc::ty::adjustment::CustomCoerceUnsized;
-use rustc::dep_graph::DepNode;
+use rustc::dep_graph::{DepNode, DirtyReason, WorkProduct};
+use rustc_incremental::{self, SvhCalculate};
 use rustc::hir::map as hir_map;
 use rustc::util::common::time;
 use rustc::mir::mir_map::MirMap;
@@ -84,6 +85,7 @@ use mir;
 use monomorphize::{self, Instance};
 use partitioning::{self, PartitioningStrategy, InstantiationMode};
 use symbol_names_test;
+use assert_module_sources;
 use tvec;
 use type_::Type;
 use type_of;
@@ -92,13 +94,14 @@ use value::Value;
 use Disr;
 use util::common::indenter;
 use util::sha2::Sha256;
-use util::nodemap::{NodeMap, NodeSet};
+use util::nodemap::{FnvHashSet, NodeMap, NodeSet};
 
 use arena::TypedArena;
 use libc::c_uint;
 use std::ffi::{CStr, CString};
 use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher, SipHasher};
 use std::str;
 use std::{i8, i16, i32, i64};
 use syntax::codemap::{Span, DUMMY_SP};
@@ -2491,13 +2494,15 @@ pub fn write_metadata<'a, 'tcx>(cx: &SharedCrateContext<'a, 'tcx>,
         return Vec::new();
     }
 
+    let item_hashes = cx.tcx().calculate_item_hashes();
     let cstore = &cx.tcx().sess.cstore;
     let metadata = cstore.encode_metadata(cx.tcx(),
                                           cx.export_map(),
-                                          cx.item_symbols(),
+                                          Some(cx.item_symbols()),
                                           cx.link_meta(),
                                           reachable,
                                           mir_map,
+                                          &item_hashes,
                                           krate);
     let mut compressed = cstore.metadata_encoding_version().to_vec();
     compressed.extend_from_slice(&flate::deflate_bytes(&metadata));
@@ -2521,6 +2526,25 @@ pub fn write_metadata<'a, 'tcx>(cx: &SharedCrateContext<'a, 'tcx>,
     return metadata;
 }
 
+/// Encodes the metadata of the crate without translating it, for
+/// `--emit=metadata`. No symbol names are known at this point, so the
+/// metadata is only good for type-checking the crates depending on this one.
+pub fn encode_metadata_only<'tcx>(tcx: &TyCtxt<'tcx>,
+                                  mir_map: &MirMap<'tcx>,
+                                  analysis: &ty::CrateAnalysis)
+                                  -> Vec<u8> {
+    let link_meta = link::build_link_meta(tcx, analysis.name);
+    let item_hashes = tcx.calculate_item_hashes();
+    tcx.sess.cstore.encode_metadata(tcx,
+                                    &analysis.export_map,
+                                    None,
+                                    &link_meta,
+                                    &NodeSet(),
+                                    mir_map,
+                                    &item_hashes,
+                                    tcx.map.krate())
+}
+
 /// Find any symbols that are defined in one compilation unit, but not declared
 /// in any other compilation unit.  Give these symbols internal linkage.
 fn internalize_symbols(cx: &SharedCrateContext, reachable: &HashSet<&str>) {
@@ -2727,6 +2751,10 @@ pub fn trans_crate<'tcx>(tcx: &TyCtxt<'tcx>,
                                              check_overflow,
                                              check_dropflag);
 
+    // This has to happen before any item is translated, see
+    // `clean_trans_items`.
+    let clean_trans_items = clean_trans_items(tcx);
+
     {
         let ccx = shared_ccx.get_ccx(0);
         collect_translation_items(&ccx);
@@ -2793,10 +2821,6 @@ pub fn trans_crate<'tcx>(tcx: &TyCtxt<'tcx>,
         }
     }
 
-    let modules = shared_ccx.iter()
-        .map(|ccx| ModuleTranslation { llcx: ccx.llcx(), llmod: ccx.llmod() })
-        .collect();
-
     let sess = shared_ccx.sess();
     let mut reachable_symbols = reachable_symbol_ids.iter().map(|id| {
         shared_ccx.item_symbols().borrow()[id].to_string()
@@ -2829,7 +2853,23 @@ pub fn trans_crate<'tcx>(tcx: &TyCtxt<'tcx>,
         create_imps(&shared_ccx);
     }
 
-    let metadata_module = ModuleTranslation {
+    let config_hash = session_config_hash(sess);
+    let modules: Vec<_> = shared_ccx.iter().enumerate().map(|(index, ccx)| {
+        let name = format!("cgu-{}", index);
+        let input_hash = module_input_hash(&ccx, config_hash);
+        let previous = reusable_work_product(&ccx, &name, input_hash, &clean_trans_items);
+        debug!("trans_crate: module {} {}", name,
+               if previous.is_some() { "is re-used" } else { "needs to be rebuilt" });
+        ModuleTranslation {
+            name: name,
+            input_hash: input_hash,
+            llvm: ModuleLlvm { llcx: ccx.llcx(), llmod: ccx.llmod() },
+            previous: previous,
+        }
+    }).collect();
+    assert_module_sources::assert_module_sources(&shared_ccx, &modules);
+
+    let metadata_module = ModuleLlvm {
         llcx: shared_ccx.metadata_llcx(),
         llmod: shared_ccx.metadata_llmod(),
     };
@@ -2845,6 +2885,141 @@ pub fn trans_crate<'tcx>(tcx: &TyCtxt<'tcx>,
     }
 }
 
+/// Returns the local items whose translation in the previous incremental
+/// session is still valid according to the dep-graph, i.e. whose
+/// `TransCrateItem` node was loaded as clean. This has to be called before
+/// translating anything, since that adds the nodes of all items again.
+fn clean_trans_items(tcx: &TyCtxt) -> FnvHashSet<DefId> {
+    if tcx.sess.opts.incremental.is_none() {
+        return FnvHashSet();
+    }
+    tcx.dep_graph.query()
+                 .nodes()
+                 .into_iter()
+                 .filter_map(|node| match node {
+                     DepNode::TransCrateItem(def_id) => Some(def_id),
+                     _ => None,
+                 })
+                 .collect()
+}
+
+/// Hashes the parts of the session which influence the translated modules,
+/// and hence their outputs, without being tracked by the dep-graph: the
+/// options and the kinds of outputs requested. Items of upstream crates are
+/// tracked by their `MetaData` nodes, so an upstream change only invalidates
+/// the modules that use what changed.
+fn session_config_hash(sess: &Session) -> u64 {
+    let mut state = SipHasher::new();
+    let opts = &sess.opts;
+    opts.target_triple.hash(&mut state);
+    (opts.optimize as usize).hash(&mut state);
+    (opts.debuginfo as usize).hash(&mut state);
+    opts.debug_assertions.hash(&mut state);
+    opts.debugging_opts.force_overflow_checks.hash(&mut state);
+    opts.debugging_opts.force_dropflag_checks.hash(&mut state);
+    opts.cg.codegen_units.hash(&mut state);
+    opts.cg.target_cpu.hash(&mut state);
+    opts.cg.target_feature.hash(&mut state);
+    opts.cg.relocation_model.hash(&mut state);
+    opts.cg.code_model.hash(&mut state);
+    opts.cg.passes.hash(&mut state);
+    opts.cg.llvm_args.hash(&mut state);
+    opts.cg.no_prepopulate_passes.hash(&mut state);
+    opts.cg.no_vectorize_loops.hash(&mut state);
+    opts.cg.no_vectorize_slp.hash(&mut state);
+    opts.cg.soft_float.hash(&mut state);
+    opts.cg.prefer_dynamic.hash(&mut state);
+    opts.cg.no_redzone.hash(&mut state);
+    opts.cg.no_stack_check.hash(&mut state);
+    opts.cg.inline_threshold.hash(&mut state);
+
+    let mut output_types = opts.output_types.keys()
+                                            .map(|output_type| output_type.shorthand())
+                                            .collect::<Vec<_>>();
+    output_types.sort();
+    output_types.hash(&mut state);
+    sess.crate_types.borrow().hash(&mut state);
+    state.finish()
+}
+
+/// Computes the `input_hash` of the module of `ccx`, covering everything
+/// which determines its outputs besides the bodies of the items translated
+/// into it, which are tracked by the dep-graph.
+fn module_input_hash(ccx: &CrateContext, config_hash: u64) -> u64 {
+    let mut state = SipHasher::new();
+    config_hash.hash(&mut state);
+
+    // The symbols defined and declared by the module, after
+    // `internalize_symbols`: changes to other modules show up here, e.g.
+    // when they start using a symbol which could previously be internalized.
+    let mut symbols = unsafe {
+        iter_globals(ccx.llmod()).chain(iter_functions(ccx.llmod())).map(|val| {
+            (CStr::from_ptr(llvm::LLVMGetValueName(val)).to_bytes().to_vec(),
+             llvm::LLVMGetLinkage(val),
+             llvm::LLVMIsDeclaration(val))
+        }).collect::<Vec<_>>()
+    };
+    symbols.sort();
+    symbols.hash(&mut state);
+
+    // The item hashes of the dep-graph don't cover spans, but they end up in
+    // debuginfo and panic messages.
+    let tcx = ccx.tcx();
+    let mut spans = tcx.dep_graph.with_ignore(|| {
+        ccx.translated_items().borrow().iter().map(|&def_id| {
+            tcx.sess.codemap().span_to_string(tcx.map.def_id_span(def_id, DUMMY_SP))
+        }).collect::<Vec<_>>()
+    });
+    spans.sort();
+    spans.hash(&mut state);
+
+    state.finish()
+}
+
+/// Returns the work product saved by the previous incremental session for
+/// the module of `ccx` if it can be re-used instead of running LLVM again:
+/// its `input_hash` must be unchanged and every item translated into the
+/// module must be clean.
+fn reusable_work_product(ccx: &CrateContext,
+                         name: &str,
+                         input_hash: u64,
+                         clean_trans_items: &FnvHashSet<DefId>)
+                         -> Option<WorkProduct> {
+    let sess = ccx.sess();
+    // These need the LLVM module itself, or other outputs than the saved ones.
+    if sess.lto() || sess.opts.cg.save_temps || sess.opts.cg.no_integrated_as {
+        return None;
+    }
+
+    let tcx = ccx.tcx();
+    let work_product = match tcx.dep_graph.previous_work_product(name) {
+        Some(work_product) => work_product,
+        None => return None,
+    };
+    let explain = sess.opts.debugging_opts.incremental_explain.is_some();
+    let translated_items = ccx.translated_items().borrow();
+    if let Some(&def_id) = translated_items.iter().find(|&&d| !clean_trans_items.contains(&d)) {
+        debug!("reusable_work_product: {:?} in {} is dirty", def_id, name);
+        if explain {
+            let input = tcx.dep_graph.with_ignore(|| {
+                rustc_incremental::node_name(tcx, &DepNode::TransCrateItem(def_id))
+            });
+            tcx.dep_graph.record_dirty_reason(rustc_incremental::codegen_unit_name(name),
+                                              DirtyReason::DirtyInput(input));
+        }
+        return None;
+    }
+    if work_product.input_hash != input_hash {
+        debug!("reusable_work_product: input hash of {} changed", name);
+        if explain {
+            tcx.dep_graph.record_dirty_reason(rustc_incremental::codegen_unit_name(name),
+                                              DirtyReason::UntrackedInputChanged);
+        }
+        return None;
+    }
+    Some(work_product)
+}
+
 /// We visit all the items in the krate and translate them.  We do
 /// this in two walks. The first walk just finds module items. It then
 /// walks the full contents of those module items and translates all
@@ -2904,6 +3079,7 @@ impl<'a, 'tcx, 'v> Visitor<'v> for TransItemsWithinModVisitor<'a, 'tcx> {
 
                     trans_item(self.ccx, i);
                 });
+                self.ccx.translated_items().borrow_mut().push(def_id);
 
                 intravisit::walk_item(self, i);
             }
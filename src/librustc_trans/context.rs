@@ -145,6 +145,10 @@ pub struct LocalCrateContext<'tcx> {
     /// to constants.)
     statics_to_rauw: RefCell<Vec<(ValueRef, ValueRef)>>,
 
+    /// The local items translated into this codegen unit, which determine
+    /// whether its object file can be re-used in incremental compilation.
+    translated_items: RefCell<Vec<DefId>>,
+
     lltypes: RefCell<FnvHashMap<Ty<'tcx>, Type>>,
     llsizingtypes: RefCell<FnvHashMap<Ty<'tcx>, Type>>,
     adt_reprs: RefCell<FnvHashMap<Ty<'tcx>, Rc<adt::Repr<'tcx>>>>,
@@ -502,6 +506,7 @@ impl<'tcx> LocalCrateContext<'tcx> {
                 impl_method_cache: RefCell::new(FnvHashMap()),
                 closure_bare_wrapper_cache: RefCell::new(FnvHashMap()),
                 statics_to_rauw: RefCell::new(Vec::new()),
+                translated_items: RefCell::new(Vec::new()),
                 lltypes: RefCell::new(FnvHashMap()),
                 llsizingtypes: RefCell::new(FnvHashMap()),
                 adt_reprs: RefCell::new(FnvHashMap()),
@@ -720,6 +725,10 @@ impl<'b, 'tcx> CrateContext<'b, 'tcx> {
         &self.local.statics_to_rauw
     }
 
+    pub fn translated_items<'a>(&'a self) -> &'a RefCell<Vec<DefId>> {
+        &self.local.translated_items
+    }
+
     pub fn lltypes<'a>(&'a self) -> &'a RefCell<FnvHashMap<Ty<'tcx>, Type>> {
         &self.local.lltypes
     }
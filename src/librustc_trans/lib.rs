@@ -23,6 +23,7 @@
       html_root_url = "https://doc.rust-lang.org/nightly/")]
 #![cfg_attr(not(stage0), deny(warnings))]
 
+#![feature(available_parallelism)]
 #![feature(box_patterns)]
 #![feature(box_syntax)]
 #![feature(const_fn)]
@@ -62,7 +63,9 @@ pub use rustc::middle;
 pub use rustc::lint;
 pub use rustc::util;
 
-pub use base::trans_crate;
+use rustc::dep_graph::WorkProduct;
+
+pub use base::{trans_crate, encode_metadata_only};
 pub use disr::Disr;
 
 pub mod back {
@@ -86,6 +89,7 @@ mod macros;
 mod abi;
 mod adt;
 mod asm;
+mod assert_module_sources;
 mod attributes;
 mod base;
 mod basic_block;
@@ -128,18 +132,33 @@ mod type_;
 mod type_of;
 mod value;
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct ModuleTranslation {
+    /// The name of the codegen unit, which identifies its work product in
+    /// incremental compilation.
+    pub name: String,
+    /// A hash of everything besides the translated items which determines
+    /// the contents of the module, see `rustc::dep_graph::WorkProduct`.
+    pub input_hash: u64,
+    pub llvm: ModuleLlvm,
+    /// The outputs of the previous incremental session for this module, if
+    /// they are still valid. They are then copied instead of running LLVM on
+    /// `llvm`, which only has to be disposed of.
+    pub previous: Option<WorkProduct>,
+}
+
+#[derive(Copy, Clone)]
+pub struct ModuleLlvm {
     pub llcx: llvm::ContextRef,
     pub llmod: llvm::ModuleRef,
 }
 
-unsafe impl Send for ModuleTranslation { }
-unsafe impl Sync for ModuleTranslation { }
+unsafe impl Send for ModuleLlvm { }
+unsafe impl Sync for ModuleLlvm { }
 
 pub struct CrateTranslation {
     pub modules: Vec<ModuleTranslation>,
-    pub metadata_module: ModuleTranslation,
+    pub metadata_module: ModuleLlvm,
     pub link: middle::cstore::LinkMeta,
     pub metadata: Vec<u8>,
     pub reachable: Vec<String>,
@@ -0,0 +1,970 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Semver-aware comparison of two versions of a crate's public API.
+//!
+//! The input to this module is a pair of documents produced by the JSON
+//! backend (`rustdoc -w json`), one for the old and one for the new version
+//! of a library. Every public item is keyed by its path relative to the crate
+//! root (impls are keyed by their trait and self type instead, since moving an
+//! impl between modules is not an API change), and the two sets of items are
+//! compared. Each difference is classified according to Cargo's semver rules
+//! (RFC 1105) as requiring a major, minor or patch version bump.
+//!
+//! Comparisons are structural and performed on the JSON values themselves,
+//! ignoring spans and `DefId`s: both are expected to change between two
+//! compilations even if the API doesn't.
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+use serialize::json::{self, Json};
+
+use json::{SCHEMA, FORMAT_VERSION};
+
+/// The version component that has to be bumped for a change.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        })
+    }
+}
+
+/// A single difference between the two versions of the API.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Change {
+    pub path: String,
+    pub description: String,
+    pub bump: Bump,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} `{}`", self.bump, self.description, self.path)
+    }
+}
+
+/// Returns the bump required by a set of changes, which is a patch bump if
+/// nothing in the API changed.
+pub fn required_bump(changes: &[Change]) -> Bump {
+    changes.iter().fold(Bump::Patch, |acc, c| cmp::max(acc, c.bump))
+}
+
+/// Compares the API described by the JSON documents at `old` and `new`,
+/// prints every change and the required version bump, and returns the exit
+/// code for rustdoc.
+pub fn run(old: &Path, new: &Path) -> isize {
+    let load = |path: &Path| {
+        read_document(path).map_err(|e| format!("{}: {}", path.display(), e))
+    };
+    let changes = match load(old).and_then(|old| load(new).and_then(|new| diff(&old, &new))) {
+        Ok(changes) => changes,
+        Err(e) => {
+            println!("api-diff error: {}", e);
+            return 1;
+        }
+    };
+
+    for change in &changes {
+        println!("{}", change);
+    }
+    if !changes.is_empty() {
+        println!("");
+    }
+    println!("required version bump: {}", required_bump(&changes));
+    0
+}
+
+fn read_document(path: &Path) -> Result<Json, String> {
+    let mut contents = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut contents))
+                    .map_err(|e| e.to_string())?;
+    json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// Computes the list of API changes from the document `old` to the document
+/// `new`, sorted by path.
+pub fn diff(old: &Json, new: &Json) -> Result<Vec<Change>, String> {
+    let old_items = collect_document(old)?;
+    let new_items = collect_document(new)?;
+    let mut changes = Vec::new();
+
+    let mut removed: Vec<&str> = Vec::new();
+    for (path, old_item) in &old_items {
+        let is_nested = removed.iter().any(|r| path.starts_with(&format!("{}::", r)));
+        match new_items.get(path) {
+            None if is_nested => {}
+            None => {
+                removed.push(path);
+                changes.push(Change {
+                    path: path.clone(),
+                    description: format!("removed {}", describe_kind(old_item.kind)),
+                    bump: Bump::Major,
+                });
+            }
+            Some(new_item) => compare_items(path, old_item, new_item, &mut changes),
+        }
+    }
+
+    let mut added: Vec<&str> = Vec::new();
+    for (path, new_item) in &new_items {
+        if old_items.contains_key(path) ||
+           added.iter().any(|a| path.starts_with(&format!("{}::", a))) {
+            continue
+        }
+        added.push(path);
+        let parent = new_item.parent.as_ref().and_then(|p| old_items.get(p));
+        changes.push(Change {
+            path: path.clone(),
+            description: format!("added {}", describe_kind(new_item.kind)),
+            bump: addition_bump(new_item, parent),
+        });
+    }
+
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+/// A public item of one version of the API.
+struct Entry<'a> {
+    kind: &'a str,
+    /// The fields of the `ItemEnum` variant.
+    fields: &'a [Json],
+    item: &'a Json,
+    /// Path of the enclosing struct, enum, trait or impl, if any.
+    parent: Option<String>,
+    /// For re-exports, the path of the re-exported item.
+    target: Option<String>,
+}
+
+fn collect_document(doc: &Json) -> Result<BTreeMap<String, Entry>, String> {
+    if doc.find("schema").and_then(|s| s.as_string()) != Some(SCHEMA) {
+        return Err(format!("not a {} document", SCHEMA));
+    }
+    match doc.find("format_version").and_then(|v| v.as_u64()) {
+        Some(v) if v == FORMAT_VERSION as u64 => {}
+        Some(v) => {
+            return Err(format!("unsupported format version {} (expected {})",
+                               v, FORMAT_VERSION));
+        }
+        None => return Err("missing format version".to_string()),
+    }
+
+    let mut items = BTreeMap::new();
+    if let Some(module) = doc.find_path(&["crate", "module"]) {
+        if !module.is_null() {
+            collect_children(module, "", &mut items);
+        }
+    }
+    Ok(items)
+}
+
+/// Adds the public children of `item` (which lives at `path`) to `out`,
+/// recursively.
+fn collect_children<'a>(item: &'a Json, path: &str, out: &mut BTreeMap<String, Entry<'a>>) {
+    let (kind, fields) = match item_kind(item) {
+        Some(k) => k,
+        None => return,
+    };
+    let inner = fields.get(0);
+    let (children, need_pub) = match kind {
+        "ModuleItem" => (inner.and_then(|m| m.find("items")), true),
+        "StructItem" => (inner.and_then(|s| s.find("fields")), true),
+        "EnumItem" => (inner.and_then(|e| e.find("variants")), false),
+        "TraitItem" => (inner.and_then(|t| t.find("items")), false),
+        // Items of inherent impls need to be `pub`, items of trait impls
+        // are as public as the trait.
+        "ImplItem" => {
+            let is_inherent = inner.and_then(|i| i.find("trait_")).map_or(true, |t| t.is_null());
+            (inner.and_then(|i| i.find("items")), is_inherent)
+        }
+        _ => return,
+    };
+    let children = match children.and_then(|c| c.as_array()) {
+        Some(c) => c,
+        None => return,
+    };
+
+    for child in children {
+        let (child_kind, child_fields) = match item_kind(child) {
+            Some(k) => k,
+            None => continue,
+        };
+        let child_path = match child_kind {
+            "StrippedItem" | "ExternCrateItem" => continue,
+            // A `pub use` which wasn't inlined: every name it brings into
+            // scope is part of the API.
+            "ImportItem" => {
+                if !is_public(child) {
+                    continue
+                }
+                for (name, target) in reexports(child_fields.get(0)) {
+                    out.insert(join(path, &name), Entry {
+                        kind: child_kind,
+                        fields: child_fields,
+                        item: child,
+                        parent: None,
+                        target: Some(target),
+                    });
+                }
+                continue
+            }
+            "ImplItem" => impl_path(child_fields.get(0)),
+            _ if need_pub && !is_public(child) => continue,
+            _ => {
+                match child.find("name").and_then(|n| n.as_string()) {
+                    Some(name) => join(path, name),
+                    None => continue,
+                }
+            }
+        };
+        let parent = if kind == "ModuleItem" { None } else { Some(path.to_string()) };
+
+        // Impls with the same self type, trait and bounds (which can only be
+        // inherent impls) are merged: moving a method from one to the other
+        // doesn't change the API.
+        if !out.contains_key(&child_path) {
+            out.insert(child_path.clone(), Entry {
+                kind: child_kind,
+                fields: child_fields,
+                item: child,
+                parent: parent,
+                target: None,
+            });
+        }
+        collect_children(child, &child_path, out);
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", path, name)
+    }
+}
+
+/// Returns the names brought into scope by a `clean::Import`, along with the
+/// path each of them refers to.
+fn reexports(import: Option<&Json>) -> Vec<(String, String)> {
+    let (kind, fields) = match import.and_then(variant) {
+        Some(v) => v,
+        None => return vec![],
+    };
+    let source = |i: usize| {
+        fields.get(i).and_then(|s| s.find("path")).map_or("_".to_string(), describe_path)
+    };
+    match kind {
+        "SimpleImport" => {
+            match fields.get(0).and_then(|n| n.as_string()) {
+                Some(name) => vec![(name.to_string(), source(1))],
+                None => vec![],
+            }
+        }
+        "GlobImport" => {
+            let glob = format!("{}::*", source(0));
+            vec![(glob.clone(), glob)]
+        }
+        "ImportList" => {
+            let source = source(0);
+            let idents = fields.get(1).and_then(|i| i.as_array());
+            idents.map_or(&[][..], |i| &i[..]).iter().filter_map(|ident| {
+                let name = match ident.find("name").and_then(|n| n.as_string()) {
+                    Some(name) => name,
+                    None => return None,
+                };
+                let rename = ident.find("rename").and_then(|r| r.as_string());
+                Some((rename.unwrap_or(name).to_string(), format!("{}::{}", source, name)))
+            }).collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Returns the name and fields of the `ItemEnum` variant of `item`.
+fn item_kind(item: &Json) -> Option<(&str, &[Json])> {
+    item.find("inner").and_then(variant)
+}
+
+/// Decodes an enum the way the JSON encoder writes it: either a bare string
+/// for variants without fields, or `{"variant": ..., "fields": [...]}`.
+fn variant(json: &Json) -> Option<(&str, &[Json])> {
+    match *json {
+        Json::String(ref s) => Some((&s[..], &[][..])),
+        Json::Object(ref o) => {
+            let name = o.get("variant").and_then(|v| v.as_string());
+            let fields = o.get("fields").and_then(|f| f.as_array());
+            match (name, fields) {
+                (Some(name), Some(fields)) => Some((name, &fields[..])),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn is_public(item: &Json) -> bool {
+    item.find("visibility").and_then(|v| v.as_string()) == Some("Public")
+}
+
+/// Renders the key of an impl: its self type, trait and bounds, written out
+/// in full so that different impls never share a key.
+fn impl_path(impl_: Option<&Json>) -> String {
+    let mut key = format!("<{}", impl_.and_then(|i| i.find("for_"))
+                                      .map_or("_".to_string(), describe_type));
+    match impl_.and_then(|i| i.find("trait_")) {
+        Some(t) if !t.is_null() => {
+            let negative = impl_.and_then(|i| i.find("polarity"))
+                                .and_then(|p| p.as_string()) == Some("Negative");
+            key.push_str(if negative { " as !" } else { " as " });
+            key.push_str(&describe_type(t));
+        }
+        _ => {}
+    }
+    let bounds = impl_.and_then(|i| i.find("generics")).map_or(vec![], describe_bounds);
+    if !bounds.is_empty() {
+        key.push_str(" where ");
+        key.push_str(&bounds.join(", "));
+    }
+    key.push('>');
+    key
+}
+
+/// Renders a `clean::Type` the way it is written in the source.
+fn describe_type(ty: &Json) -> String {
+    let (name, fields) = match variant(ty) {
+        Some(v) => v,
+        None => return "_".to_string(),
+    };
+    let field = |i: usize| fields.get(i).map_or("_".to_string(), describe_type);
+    let mutability = |i: usize| {
+        if fields.get(i).and_then(|m| m.as_string()) == Some("Mutable") { "mut " } else { "" }
+    };
+    match name {
+        "ResolvedPath" => {
+            let path = fields.get(0).map_or("_".to_string(), describe_path);
+            match fields.get(1).and_then(|b| b.as_array()) {
+                Some(bounds) if !bounds.is_empty() => {
+                    format!("{} + {}", path, describe_ty_param_bounds(bounds))
+                }
+                _ => path,
+            }
+        }
+        "Generic" => fields.get(0).and_then(|g| g.as_string()).unwrap_or("_").to_string(),
+        "Primitive" => {
+            fields.get(0).and_then(|p| p.as_string()).unwrap_or("_").to_lowercase()
+        }
+        "BareFunction" => {
+            let decl = match fields.get(0) {
+                Some(decl) => decl,
+                None => return "_".to_string(),
+            };
+            let unsafety = decl.find("unsafety").and_then(|u| u.as_string());
+            let abi = match decl.find("abi").and_then(|a| a.as_string()) {
+                Some("Rust") | None => String::new(),
+                Some(abi) => format!("extern {:?} ", abi.to_lowercase()),
+            };
+            format!("{}{}fn{}",
+                    if unsafety == Some("Unsafe") { "unsafe " } else { "" },
+                    abi,
+                    decl.find("decl").map_or("(_)".to_string(), describe_fn_decl))
+        }
+        "Tuple" => format!("({})", describe_types(fields.get(0))),
+        "Vector" => format!("[{}]", field(0)),
+        "FixedVector" => {
+            format!("[{}; {}]", field(0),
+                    fields.get(1).and_then(|n| n.as_string()).unwrap_or("_"))
+        }
+        "Bottom" => "!".to_string(),
+        "Unique" => format!("Box<{}>", field(0)),
+        "RawPointer" => {
+            format!("*{}{}", if mutability(0).is_empty() { "const " } else { "mut " }, field(1))
+        }
+        "BorrowedRef" => {
+            let lifetime = fields.get(0).and_then(describe_lifetime)
+                                        .map_or(String::new(), |l| format!("{} ", l));
+            format!("&{}{}{}", lifetime, mutability(1), field(2))
+        }
+        "QPath" => {
+            format!("<{} as {}>::{}", field(1), field(2),
+                    fields.get(0).and_then(|n| n.as_string()).unwrap_or("_"))
+        }
+        "PolyTraitRef" => {
+            let bounds = fields.get(0).and_then(|b| b.as_array());
+            describe_ty_param_bounds(bounds.map_or(&[][..], |b| &b[..]))
+        }
+        _ => "_".to_string(),
+    }
+}
+
+/// Renders a `clean::Path`, including the parameters of every segment.
+fn describe_path(path: &Json) -> String {
+    let segments = path.find("segments").and_then(|s| s.as_array());
+    let segments = segments.map_or(&[][..], |s| &s[..]).iter().map(|segment| {
+        let name = segment.find("name").and_then(|n| n.as_string()).unwrap_or("_");
+        let (kind, fields) = match segment.find("params").and_then(variant) {
+            Some(params) => params,
+            None => return name.to_string(),
+        };
+        match kind {
+            "AngleBracketed" => {
+                let mut params = vec![];
+                if let Some(lifetimes) = fields.get(0).and_then(|l| l.as_array()) {
+                    params.extend(lifetimes.iter().filter_map(describe_lifetime));
+                }
+                if let Some(types) = fields.get(1).and_then(|t| t.as_array()) {
+                    params.extend(types.iter().map(describe_type));
+                }
+                if let Some(bindings) = fields.get(2).and_then(|b| b.as_array()) {
+                    params.extend(bindings.iter().map(|binding| {
+                        format!("{}={}",
+                                binding.find("name").and_then(|n| n.as_string()).unwrap_or("_"),
+                                binding.find("ty").map_or("_".to_string(), describe_type))
+                    }));
+                }
+                if params.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}<{}>", name, params.join(", "))
+                }
+            }
+            "Parenthesized" => {
+                let output = match fields.get(1) {
+                    Some(output) if !output.is_null() => {
+                        format!(" -> {}", describe_type(output))
+                    }
+                    _ => String::new(),
+                };
+                format!("{}({}){}", name, describe_types(fields.get(0)), output)
+            }
+            _ => name.to_string(),
+        }
+    }).collect::<Vec<_>>().join("::");
+    let global = path.find("global").and_then(|g| g.as_boolean()) == Some(true);
+    if global { format!("::{}", segments) } else { segments }
+}
+
+/// Renders the argument types and the return type of a `clean::FnDecl`.
+fn describe_fn_decl(decl: &Json) -> String {
+    let inputs = decl.find_path(&["inputs", "values"]).and_then(|v| v.as_array());
+    let inputs = inputs.map_or(&[][..], |i| &i[..]).iter().map(|arg| {
+        arg.find("type_").map_or("_".to_string(), describe_type)
+    }).collect::<Vec<_>>();
+    let output = match decl.find("output").and_then(variant) {
+        Some(("Return", fields)) => {
+            format!(" -> {}", fields.get(0).map_or("_".to_string(), describe_type))
+        }
+        Some(("NoReturn", _)) => " -> !".to_string(),
+        _ => String::new(),
+    };
+    let variadic = decl.find("variadic").and_then(|v| v.as_boolean()) == Some(true);
+    format!("({}{}){}", inputs.join(", "), if variadic { ", ..." } else { "" }, output)
+}
+
+fn describe_types(types: Option<&Json>) -> String {
+    let types = types.and_then(|t| t.as_array());
+    types.map_or(&[][..], |t| &t[..]).iter().map(describe_type).collect::<Vec<_>>().join(", ")
+}
+
+/// Renders a `clean::Lifetime`, which is encoded as a tuple struct.
+fn describe_lifetime(lifetime: &Json) -> Option<String> {
+    lifetime.find("_field0").and_then(|l| l.as_string()).map(|l| l.to_string())
+}
+
+fn describe_ty_param_bounds(bounds: &[Json]) -> String {
+    bounds.iter().map(|bound| {
+        match variant(bound) {
+            Some(("RegionBound", fields)) => {
+                fields.get(0).and_then(describe_lifetime).unwrap_or("'_".to_string())
+            }
+            Some(("TraitBound", fields)) => {
+                let poly = fields.get(0);
+                let lifetimes = poly.and_then(|p| p.find("lifetimes"))
+                                    .and_then(|l| l.as_array())
+                                    .map_or(vec![], |l| {
+                                        l.iter().filter_map(describe_lifetime).collect()
+                                    });
+                let maybe = fields.get(1).and_then(|m| m.as_string()) == Some("Maybe");
+                format!("{}{}{}",
+                        if lifetimes.is_empty() {
+                            String::new()
+                        } else {
+                            format!("for<{}> ", lifetimes.join(", "))
+                        },
+                        if maybe { "?" } else { "" },
+                        poly.and_then(|p| p.find("trait_")).map_or("_".to_string(), describe_type))
+            }
+            _ => "_".to_string(),
+        }
+    }).collect::<Vec<_>>().join(" + ")
+}
+
+/// Renders the bounds of a `clean::Generics` as where clauses, including the
+/// bounds declared on the type parameters themselves.
+fn describe_bounds(generics: &Json) -> Vec<String> {
+    let mut bounds = vec![];
+    if let Some(params) = generics.find("type_params").and_then(|p| p.as_array()) {
+        for param in params {
+            let name = param.find("name").and_then(|n| n.as_string()).unwrap_or("_");
+            match param.find("bounds").and_then(|b| b.as_array()) {
+                Some(b) if !b.is_empty() => {
+                    bounds.push(format!("{}: {}", name, describe_ty_param_bounds(b)));
+                }
+                _ => {}
+            }
+        }
+    }
+    if let Some(predicates) = generics.find("where_predicates").and_then(|p| p.as_array()) {
+        for predicate in predicates {
+            let (kind, fields) = match variant(predicate) {
+                Some(v) => v,
+                None => continue,
+            };
+            let field = |i: usize| fields.get(i).map_or("_".to_string(), describe_type);
+            bounds.push(match kind {
+                "BoundPredicate" => {
+                    let b = fields.get(1).and_then(|b| b.as_array());
+                    format!("{}: {}", field(0),
+                            describe_ty_param_bounds(b.map_or(&[][..], |b| &b[..])))
+                }
+                "RegionPredicate" => {
+                    let b = fields.get(1).and_then(|b| b.as_array()).map_or(vec![], |b| {
+                        b.iter().filter_map(describe_lifetime).collect()
+                    });
+                    format!("{}: {}",
+                            fields.get(0).and_then(describe_lifetime).unwrap_or("'_".to_string()),
+                            b.join(" + "))
+                }
+                "EqPredicate" => format!("{} == {}", field(0), field(1)),
+                _ => continue,
+            });
+        }
+    }
+    bounds
+}
+
+fn describe_kind(kind: &str) -> &'static str {
+    match kind {
+        "ModuleItem" => "module",
+        "StructItem" => "struct",
+        "EnumItem" => "enum",
+        "FunctionItem" | "ForeignFunctionItem" => "function",
+        "TypedefItem" => "type alias",
+        "StaticItem" | "ForeignStaticItem" => "static",
+        "ConstantItem" => "constant",
+        "TraitItem" => "trait",
+        "ImplItem" => "impl",
+        "TyMethodItem" => "required method",
+        "MethodItem" => "method",
+        "StructFieldItem" => "field",
+        "VariantItem" => "variant",
+        "MacroItem" => "macro",
+        "AssociatedConstItem" => "associated constant",
+        "AssociatedTypeItem" => "associated type",
+        "DefaultImplItem" => "default impl",
+        "ImportItem" => "re-export",
+        _ => "item",
+    }
+}
+
+/// The bump required by adding `entry`, whose parent in the old version of
+/// the API (if it existed) is `parent`.
+fn addition_bump(entry: &Entry, parent: Option<&Entry>) -> Bump {
+    let parent_kind = parent.map(|p| p.kind);
+    match (entry.kind, parent_kind) {
+        // Implementors of the trait have to provide the new item.
+        ("TyMethodItem", Some("TraitItem")) => Bump::Major,
+        ("AssociatedTypeItem", Some("TraitItem")) |
+        ("AssociatedConstItem", Some("TraitItem")) => {
+            if entry.fields.get(1).map_or(true, |default| default.is_null()) {
+                Bump::Major
+            } else {
+                Bump::Minor
+            }
+        }
+        // Enums are exhaustive, so matches on them stop compiling.
+        ("VariantItem", _) => Bump::Major,
+        // If the struct had only public fields it could be built with a
+        // struct literal or destructured, neither of which compile anymore.
+        ("StructFieldItem", Some("StructItem")) => {
+            let stripped = parent.and_then(|p| p.fields.get(0))
+                                 .and_then(|s| s.find("fields_stripped"))
+                                 .and_then(|s| s.as_boolean());
+            if stripped == Some(true) { Bump::Minor } else { Bump::Major }
+        }
+        _ => Bump::Minor,
+    }
+}
+
+fn compare_items(path: &str, old: &Entry, new: &Entry, changes: &mut Vec<Change>) {
+    let mut change = |description: String, bump: Bump| {
+        changes.push(Change { path: path.to_string(), description: description, bump: bump });
+    };
+
+    if old.kind != new.kind {
+        let bump = match (old.kind, new.kind) {
+            // A required method gained a default implementation.
+            ("TyMethodItem", "MethodItem") => Bump::Minor,
+            _ => Bump::Major,
+        };
+        change(format!("changed {} into {}", describe_kind(old.kind), describe_kind(new.kind)),
+               bump);
+        return
+    }
+
+    if old.kind == "ImportItem" {
+        // An import can bring several names into scope, only compare the
+        // one this entry stands for.
+        if old.target != new.target {
+            change("changed target of re-export".to_string(), Bump::Major);
+        }
+    } else if signature(old.fields) != signature(new.fields) {
+        change(format!("changed signature of {}", describe_kind(old.kind)), Bump::Major);
+    }
+
+    if old.kind == "StructItem" {
+        let stripped = |e: &Entry| {
+            e.fields.get(0).and_then(|s| s.find("fields_stripped")).and_then(|s| s.as_boolean())
+        };
+        match (stripped(old), stripped(new)) {
+            (Some(false), Some(true)) => {
+                change("added private fields to struct".to_string(), Bump::Major);
+            }
+            (Some(true), Some(false)) => {
+                change("removed all private fields of struct".to_string(), Bump::Minor);
+            }
+            _ => {}
+        }
+    }
+
+    let normalized = |e: &Entry, key: &str| e.item.find(key).map(normalize);
+    if normalized(old, "stability") != normalized(new, "stability") {
+        change(format!("changed stability of {}", describe_kind(old.kind)), Bump::Minor);
+    }
+    if normalized(old, "deprecation") != normalized(new, "deprecation") {
+        change(format!("changed deprecation of {}", describe_kind(old.kind)), Bump::Minor);
+    }
+    if normalized(old, "attrs") != normalized(new, "attrs") {
+        change(format!("changed documentation or attributes of {}", describe_kind(old.kind)),
+               Bump::Patch);
+    }
+}
+
+/// The part of an item that makes up its signature: everything except its
+/// children, which are compared on their own, and the names of arguments,
+/// which callers never see.
+fn signature(fields: &[Json]) -> Vec<Json> {
+    fields.iter().map(|field| {
+        let mut field = normalize(field);
+        strip_arguments(&mut field);
+        match field {
+            Json::Object(mut o) => {
+                for key in &["items", "fields", "variants", "fields_stripped",
+                             "variants_stripped", "provided_trait_methods"] {
+                    o.remove(*key);
+                }
+                Json::Object(o)
+            }
+            other => other,
+        }
+    }).collect()
+}
+
+/// Reduces every `clean::Argument` in `json` to its type, dropping its name
+/// and its `NodeId`.
+fn strip_arguments(json: &mut Json) {
+    match *json {
+        Json::Object(ref mut o) => {
+            if let Some(&mut Json::Object(ref mut inputs)) = o.get_mut("inputs") {
+                if let Some(&mut Json::Array(ref mut args)) = inputs.get_mut("values") {
+                    for arg in args {
+                        if let Json::Object(ref mut arg) = *arg {
+                            arg.remove("name");
+                            arg.remove("id");
+                        }
+                    }
+                }
+            }
+            for value in o.values_mut() {
+                strip_arguments(value);
+            }
+        }
+        Json::Array(ref mut a) => {
+            for value in a {
+                strip_arguments(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Drops the parts of a JSON value which change between compilations without
+/// the API changing: spans and `DefId`s.
+fn normalize(json: &Json) -> Json {
+    match *json {
+        Json::Object(ref o) => {
+            if o.len() == 2 && o.contains_key("krate") && o.contains_key("index") {
+                return Json::Null;
+            }
+            Json::Object(o.iter()
+                          .filter(|&(k, _)| !["source", "def_id", "did"].contains(&&k[..]))
+                          .map(|(k, v)| (k.clone(), normalize(v)))
+                          .collect())
+        }
+        Json::Array(ref a) => Json::Array(a.iter().map(normalize).collect()),
+        ref other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, required_bump, Bump};
+    use serialize::json::{self, Json};
+
+    fn public(name: &str, inner: &str) -> String {
+        format!(r#"{{"name":"{}","attrs":[],"visibility":"Public",
+                     "source":{{"filename":"lib.rs","loline":1,"locol":0,"hiline":1,"hicol":0}},
+                     "def_id":{{"krate":0,"index":{}}},"stability":null,"deprecation":null,
+                     "inner":{}}}"#,
+                name, name.len(), inner)
+    }
+
+    fn inherited(name: &str, inner: &str) -> String {
+        public(name, inner).replace(r#""visibility":"Public""#, r#""visibility":null"#)
+    }
+
+    fn document(items: &[String]) -> Json {
+        let module = public("krate", &format!(r#"{{"variant":"ModuleItem","fields":[
+                                                    {{"is_crate":true,"items":[{}]}}]}}"#,
+                                              items.join(",")));
+        let doc = format!(r#"{{"schema":"rustdoc-json","format_version":1,"passes":[],
+                               "crate":{{"name":"krate","src":"lib.rs","externs":[],
+                                         "primitives":[],"module":{}}},
+                               "external_traits":[]}}"#,
+                          module);
+        json::from_str(&doc).unwrap()
+    }
+
+    fn function(name: &str, ret: &str) -> String {
+        public(name, &format!(r#"{{"variant":"FunctionItem","fields":[{{"decl":{{
+                                    "inputs":{{"values":[]}},"output":{},"variadic":false,
+                                    "attrs":[]}},"generics":{{"lifetimes":[],"type_params":[],
+                                    "where_predicates":[]}},"unsafety":"Normal",
+                                    "constness":"NotConst","abi":"Rust"}}]}}"#,
+                              ret))
+    }
+
+    fn struct_(name: &str, fields: &[String], stripped: bool) -> String {
+        public(name, &format!(r#"{{"variant":"StructItem","fields":[{{"struct_type":"Plain",
+                                    "generics":{{"lifetimes":[],"type_params":[],
+                                    "where_predicates":[]}},"fields":[{}],
+                                    "fields_stripped":{}}}]}}"#,
+                              fields.join(","), stripped))
+    }
+
+    fn field(name: &str) -> String {
+        public(name, r#"{"variant":"StructFieldItem","fields":[
+                          {"variant":"Primitive","fields":["U32"]}]}"#)
+    }
+
+    fn trait_(name: &str, items: &[String]) -> String {
+        public(name, &format!(r#"{{"variant":"TraitItem","fields":[{{"unsafety":"Normal",
+                                    "items":[{}],"generics":{{"lifetimes":[],
+                                    "type_params":[],"where_predicates":[]}},
+                                    "bounds":[]}}]}}"#,
+                              items.join(",")))
+    }
+
+    fn method(name: &str, provided: bool) -> String {
+        let kind = if provided { "MethodItem" } else { "TyMethodItem" };
+        inherited(name, &format!(r#"{{"variant":"{}","fields":[{{"unsafety":"Normal",
+                                       "decl":{{"inputs":{{"values":[]}},"output":"DefaultReturn",
+                                       "variadic":false,"attrs":[]}},"generics":{{
+                                       "lifetimes":[],"type_params":[],"where_predicates":[]}},
+                                       "self_":null,"abi":"Rust"}}]}}"#,
+                                 kind))
+    }
+
+    fn path(segments: &[&str]) -> String {
+        let segments = segments.iter().map(|name| {
+            format!(r#"{{"name":"{}","params":{{"variant":"AngleBracketed",
+                         "fields":[[],[],[]]}}}}"#,
+                    name)
+        }).collect::<Vec<_>>();
+        format!(r#"{{"global":false,"segments":[{}]}}"#, segments.join(","))
+    }
+
+    fn import(inner: &str) -> String {
+        public("", &format!(r#"{{"variant":"ImportItem","fields":[{}]}}"#, inner))
+            .replace(r#""name":"""#, r#""name":null"#)
+    }
+
+    fn impl_(trait_: &str, for_: &str, items: &[String]) -> String {
+        inherited("", &format!(r#"{{"variant":"ImplItem","fields":[{{"unsafety":"Normal",
+                                     "generics":{{"lifetimes":[],"type_params":[],
+                                     "where_predicates":[]}},"provided_trait_methods":[],
+                                     "trait_":{{"variant":"ResolvedPath","fields":[{},null,
+                                     {{"krate":0,"index":1}},false]}},"for_":{},
+                                     "items":[{}],"derived":false,"polarity":null}}]}}"#,
+                               path(&[trait_]), for_, items.join(",")))
+            .replace(r#""name":"""#, r#""name":null"#)
+    }
+
+    fn changes(old: &[String], new: &[String]) -> Vec<(String, Bump)> {
+        diff(&document(old), &document(new)).unwrap()
+                                             .into_iter()
+                                             .map(|c| (c.path, c.bump))
+                                             .collect()
+    }
+
+    #[test]
+    fn no_changes_is_patch() {
+        let items = [function("foo", r#""DefaultReturn""#)];
+        let changes = diff(&document(&items), &document(&items)).unwrap();
+        assert!(changes.is_empty());
+        assert_eq!(required_bump(&changes), Bump::Patch);
+    }
+
+    #[test]
+    fn removals_and_additions() {
+        let foo = function("foo", r#""DefaultReturn""#);
+        let bar = function("bar", r#""DefaultReturn""#);
+        assert_eq!(changes(&[foo.clone()], &[foo.clone(), bar.clone()]),
+                   vec![("bar".to_string(), Bump::Minor)]);
+        assert_eq!(changes(&[foo.clone(), bar.clone()], &[foo.clone()]),
+                   vec![("bar".to_string(), Bump::Major)]);
+        // Private items are not part of the API.
+        let private = inherited("baz", r#"{"variant":"FunctionItem","fields":[]}"#);
+        assert_eq!(changes(&[foo.clone()], &[foo.clone(), private]), vec![]);
+    }
+
+    #[test]
+    fn changed_signature_is_major() {
+        let old = function("foo", r#""DefaultReturn""#);
+        let new = function("foo", r#"{"variant":"Return","fields":[
+                                         {"variant":"Primitive","fields":["U32"]}]}"#);
+        assert_eq!(changes(&[old], &[new]), vec![("foo".to_string(), Bump::Major)]);
+    }
+
+    #[test]
+    fn spans_and_def_ids_are_ignored() {
+        let old = function("foo", r#""DefaultReturn""#);
+        let new = old.replace(r#""loline":1"#, r#""loline":10"#)
+                     .replace(r#""krate":0"#, r#""krate":3"#);
+        assert_eq!(changes(&[old], &[new]), vec![]);
+    }
+
+    #[test]
+    fn trait_methods() {
+        let old = trait_("Tr", &[method("a", false)]);
+        let required = trait_("Tr", &[method("a", false), method("b", false)]);
+        let provided = trait_("Tr", &[method("a", false), method("b", true)]);
+        let defaulted = trait_("Tr", &[method("a", true)]);
+        assert_eq!(changes(&[old.clone()], &[required]),
+                   vec![("Tr::b".to_string(), Bump::Major)]);
+        assert_eq!(changes(&[old.clone()], &[provided]),
+                   vec![("Tr::b".to_string(), Bump::Minor)]);
+        assert_eq!(changes(&[old], &[defaulted]), vec![("Tr::a".to_string(), Bump::Minor)]);
+    }
+
+    #[test]
+    fn struct_fields() {
+        let old = struct_("S", &[field("a")], false);
+        let new = struct_("S", &[field("a"), field("b")], false);
+        assert_eq!(changes(&[old], &[new]), vec![("S::b".to_string(), Bump::Major)]);
+
+        let old = struct_("S", &[field("a")], true);
+        let new = struct_("S", &[field("a"), field("b")], true);
+        assert_eq!(changes(&[old], &[new]), vec![("S::b".to_string(), Bump::Minor)]);
+
+        // Removing the struct doesn't also report every field.
+        let old = struct_("S", &[field("a")], false);
+        assert_eq!(changes(&[old], &[]), vec![("S".to_string(), Bump::Major)]);
+    }
+
+    #[test]
+    fn argument_names_are_ignored() {
+        let foo = function("foo", r#""DefaultReturn""#);
+        let arg = |name: &str, id: u32, ty: &str| {
+            foo.replace(r#""inputs":{"values":[]}"#,
+                        &format!(r#""inputs":{{"values":[{{"name":"{}","id":{},
+                                    "type_":{{"variant":"Primitive","fields":["{}"]}}}}]}}"#,
+                                 name, id, ty))
+        };
+        assert_eq!(changes(&[arg("a", 4, "U32")], &[arg("b", 7, "U32")]), vec![]);
+        assert_eq!(changes(&[arg("a", 4, "U32")], &[arg("a", 4, "U64")]),
+                   vec![("foo".to_string(), Bump::Major)]);
+    }
+
+    #[test]
+    fn reexports() {
+        let simple = |name: &str, source: &[&str]| {
+            import(&format!(r#"{{"variant":"SimpleImport","fields":["{}",
+                                 {{"path":{},"did":null}}]}}"#,
+                            name, path(source)))
+        };
+        let list = |names: &[&str]| {
+            let idents = names.iter().map(|name| {
+                format!(r#"{{"name":"{}","rename":null,"source":null}}"#, name)
+            }).collect::<Vec<_>>();
+            import(&format!(r#"{{"variant":"ImportList","fields":[
+                                 {{"path":{},"did":null}},[{}]]}}"#,
+                            path(&["inner"]), idents.join(",")))
+        };
+        let thing = simple("Thing", &["inner", "Thing"]);
+        assert_eq!(changes(&[thing.clone()], &[]), vec![("Thing".to_string(), Bump::Major)]);
+        assert_eq!(changes(&[], &[thing.clone()]), vec![("Thing".to_string(), Bump::Minor)]);
+        assert_eq!(changes(&[thing], &[simple("Thing", &["other", "Thing"])]),
+                   vec![("Thing".to_string(), Bump::Major)]);
+        assert_eq!(changes(&[list(&["A", "B"])], &[list(&["A"])]),
+                   vec![("B".to_string(), Bump::Major)]);
+        // Private imports are not part of the API.
+        let private = simple("Thing", &["inner", "Thing"])
+            .replace(r#""visibility":"Public""#, r#""visibility":null"#);
+        assert_eq!(changes(&[private], &[]), vec![]);
+    }
+
+    #[test]
+    fn impl_keys() {
+        let pointer = |mutability: &str| {
+            format!(r#"{{"variant":"RawPointer","fields":["{}",
+                         {{"variant":"Primitive","fields":["U8"]}}]}}"#,
+                    mutability)
+        };
+        let const_ = impl_("Tr", &pointer("Immutable"), &[method("tr", true)]);
+        let mut_ = impl_("Tr", &pointer("Mutable"), &[method("tr", true)]);
+        assert_eq!(changes(&[const_.clone(), mut_.clone()], &[mut_.clone(), const_.clone()]),
+                   vec![]);
+        assert_eq!(changes(&[const_.clone(), mut_.clone()], &[const_]),
+                   vec![("<*mut u8 as Tr>".to_string(), Bump::Major)]);
+    }
+
+    #[test]
+    fn rejects_other_documents() {
+        let doc = json::from_str(r#"{"schema":"rustdoc-json","format_version":9999}"#).unwrap();
+        assert!(diff(&doc, &doc).is_err());
+        let doc = json::from_str(r#"{"format_version":1}"#).unwrap();
+        assert!(diff(&doc, &doc).is_err());
+    }
+}
@@ -450,6 +450,7 @@ fn build_module(cx: &DocContext, tcx: &TyCtxt,
     return clean::Module {
         items: items,
         is_crate: false,
+        imports: Default::default(),
     };
 
     fn fill_in(cx: &DocContext, tcx: &TyCtxt, did: DefId,
@@ -386,6 +386,75 @@ pub enum ItemEnum {
 pub struct Module {
     pub items: Vec<Item>,
     pub is_crate: bool,
+    pub imports: ModuleImports,
+}
+
+/// The names which the `use` declarations of a module bring into scope, so
+/// that intra-doc links can be resolved like paths in the module. Unlike the
+/// `ImportItem`s of the module, these aren't stripped when private.
+#[derive(Clone, RustcEncodable, RustcDecodable, Debug, Default)]
+pub struct ModuleImports {
+    /// Names imported one by one, and what they refer to.
+    pub names: Vec<(String, DefId)>,
+    /// Names imported by glob imports of modules of other crates.
+    pub glob_names: Vec<(String, DefId)>,
+    /// Modules of this crate imported by glob imports.
+    pub globs: Vec<DefId>,
+    /// Names imported by the prelude import, which only the crate root has.
+    pub prelude: Vec<(String, DefId)>,
+}
+
+fn clean_imports(cx: &DocContext, module_imports: &[doctree::Import]) -> ModuleImports {
+    let mut imports = ModuleImports::default();
+    for import in module_imports {
+        match import.node {
+            hir::ViewPathSimple(name, _) => {
+                if let Some(did) = resolve_def(cx, import.id) {
+                    imports.names.push((name.clean(cx), did));
+                }
+            }
+            hir::ViewPathList(ref p, ref list) => {
+                for path in list {
+                    let name = match path.node {
+                        hir::PathListIdent { name, .. } => name,
+                        hir::PathListMod { .. } => p.segments.last().unwrap().name,
+                    };
+                    let name = path.node.rename().unwrap_or(name);
+                    if let Some(did) = resolve_def(cx, path.node.id()) {
+                        imports.names.push((name.clean(cx), did));
+                    }
+                }
+            }
+            hir::ViewPathGlob(..) => {
+                let did = match resolve_def(cx, import.id) {
+                    Some(did) => did,
+                    None => continue,
+                };
+                if did.is_local() {
+                    imports.globs.push(did);
+                    continue
+                }
+                let tcx = match cx.tcx_opt() {
+                    Some(tcx) => tcx,
+                    None => continue,
+                };
+                let names = if attr::contains_name(&import.attrs, "prelude_import") {
+                    &mut imports.prelude
+                } else {
+                    &mut imports.glob_names
+                };
+                for child in tcx.sess.cstore.item_children(did) {
+                    match child.def {
+                        cstore::DlDef(def) if child.vis == ty::Visibility::Public => {
+                            names.push((child.name.clean(cx), register_def(cx, def)));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    imports
 }
 
 impl Clean<Item> for doctree::Module {
@@ -437,7 +506,8 @@ impl Clean<Item> for doctree::Module {
             def_id: cx.map.local_def_id(self.id),
             inner: ModuleItem(Module {
                is_crate: self.is_crate,
-               items: items
+               items: items,
+               imports: clean_imports(cx, &self.imports),
             })
         }
     }
@@ -98,7 +98,8 @@ pub trait DocFolder : Sized {
     fn fold_mod(&mut self, m: Module) -> Module {
         Module {
             is_crate: m.is_crate,
-            items: m.items.into_iter().filter_map(|i| self.fold_item(i)).collect()
+            items: m.items.into_iter().filter_map(|i| self.fold_item(i)).collect(),
+            imports: m.imports,
         }
     }
 
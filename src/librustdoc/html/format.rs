@@ -335,6 +335,153 @@ pub fn href(did: DefId) -> Option<(String, ItemType, Vec<String>)> {
     Some((url, shortty, fqp.to_vec()))
 }
 
+/// Splits an intra-doc link target such as `Vec::push`, `::std::vec::Vec`,
+/// `foo()` or `vec!` into its path segments and the kind of item the suffix
+/// asks for. Returns `None` if `target` doesn't look like a Rust path, which
+/// is the case for all ordinary URLs.
+pub fn parse_doc_link_path(target: &str) -> Option<(Vec<String>, Option<ItemType>)> {
+    let (path, kind) = if target.ends_with("()") {
+        (&target[..target.len() - 2], Some(ItemType::Function))
+    } else if target.ends_with("!") {
+        (&target[..target.len() - 1], Some(ItemType::Macro))
+    } else {
+        (target, None)
+    };
+    let path = if path.starts_with("::") { &path[2..] } else { path };
+
+    fn is_ident(s: &str) -> bool {
+        let mut chars = s.chars();
+        match chars.next() {
+            Some(c) if c.is_alphabetic() || c == '_' => {}
+            _ => return false,
+        }
+        chars.all(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    let segments = path.split("::").map(|s| s.to_string()).collect::<Vec<_>>();
+    if segments.iter().all(|s| is_ident(s)) {
+        Some((segments, kind))
+    } else {
+        None
+    }
+}
+
+/// Returns the fully qualified name and type of the item `did`, if it has a
+/// page of its own.
+fn doc_link_path(cache: &render::Cache, did: DefId) -> Option<&(Vec<String>, ItemType)> {
+    cache.paths.get(&did).or_else(|| cache.link_paths.get(&did))
+}
+
+/// Looks up the item known under the fully qualified name `fqp`, preferring
+/// one of the given kind.
+fn lookup_doc_link(cache: &render::Cache, fqp: &[String], kind: Option<ItemType>)
+                   -> Option<DefId> {
+    let dids = match cache.path_index.get(fqp) {
+        Some(dids) => dids,
+        None => return None,
+    };
+    let kind_of = |did: &DefId| doc_link_path(cache, *did).map(|&(_, ty)| ty);
+    match kind {
+        Some(kind) => dids.iter().find(|&&did| kind_of(&did) == Some(kind)).cloned(),
+        // Macros live in a namespace of their own and are asked for with `!`.
+        None => {
+            dids.iter().find(|&&did| kind_of(&did) != Some(ItemType::Macro))
+                       .or(dids.first())
+                       .cloned()
+        }
+    }
+}
+
+/// Resolves the target of an intra-doc link written as a Rust path to a URL
+/// relative to the page currently being rendered.
+///
+/// Paths are resolved like they would be in the module of the documented
+/// item: its own items come first, then names imported into it one by one,
+/// then names of glob imports, then absolute paths starting with a crate
+/// name, and finally names from the prelude. Exported macros are also looked
+/// for at the crate root. The last segment may also name an associated item,
+/// field or variant of the type or trait before it, in which case the link
+/// points to its anchor on that page.
+pub fn resolve_doc_link(target: &str) -> Option<String> {
+    let (mut segments, kind) = match parse_doc_link_path(target) {
+        Some(p) => p,
+        None => return None,
+    };
+    // Macros are documented under their name including the `!`
+    if kind == Some(ItemType::Macro) {
+        segments.last_mut().unwrap().push('!');
+    }
+    let cache = cache();
+    let loc = CURRENT_LOCATION_KEY.with(|l| l.borrow().clone());
+
+    fn joined(prefix: &[String], rest: &[String]) -> Vec<String> {
+        prefix.iter().chain(rest).cloned().collect()
+    }
+    // The item an imported name refers to, followed by the rest of the path.
+    let through = |&(ref name, did): &(String, DefId)| {
+        if *name != segments[0] {
+            return None
+        }
+        doc_link_path(&cache, did).map(|&(ref fqp, _)| joined(fqp, &segments[1..]))
+    };
+
+    let mut candidates = vec![joined(&loc, &segments)];
+    if let Some(imports) = cache.imports.get(&loc) {
+        candidates.extend(imports.names.iter().filter_map(&through));
+        candidates.extend(imports.glob_names.iter().filter_map(&through));
+        candidates.extend(imports.globs.iter().filter_map(|&did| {
+            doc_link_path(&cache, did).map(|&(ref fqp, _)| joined(fqp, &segments))
+        }));
+    }
+    candidates.push(segments.clone());
+    if let Some(imports) = loc.first().and_then(|_| cache.imports.get(&loc[..1])) {
+        candidates.extend(imports.prelude.iter().filter_map(&through));
+    }
+    if kind == Some(ItemType::Macro) && loc.len() > 1 {
+        candidates.push(joined(&loc[..1], &segments));
+    }
+
+    for mut fqp in candidates {
+        if let Some(did) = lookup_doc_link(&cache, &fqp, kind) {
+            if let Some(url) = doc_link_href(&cache, did) {
+                return Some(url);
+            }
+        }
+
+        if fqp.len() < 2 || kind == Some(ItemType::Macro) {
+            continue
+        }
+        let member = fqp.pop().unwrap();
+        let parent = match lookup_doc_link(&cache, &fqp, None) {
+            Some(did) => did,
+            None => continue,
+        };
+        let ty = cache.members.get(&parent).and_then(|members| {
+            members.iter().find(|&&(ref name, _)| *name == member).map(|&(_, ty)| ty)
+        });
+        if let (Some(ty), Some(url)) = (ty, doc_link_href(&cache, parent)) {
+            return Some(format!("{}#{}.{}", url, ty, member));
+        }
+    }
+    None
+}
+
+/// Like `href`, but also for the items only intra-doc links point to.
+fn doc_link_href(cache: &render::Cache, did: DefId) -> Option<String> {
+    match cache.link_paths.get(&did) {
+        Some(&(ref fqp, shortty)) => {
+            let loc = CURRENT_LOCATION_KEY.with(|l| l.borrow().len());
+            let mut url = repeat("../").take(loc).collect::<String>();
+            for component in &fqp[..fqp.len() - 1] {
+                url.push_str(component);
+                url.push_str("/");
+            }
+            Some(format!("{}{}.{}.html", url, shortty, fqp.last().unwrap()))
+        }
+        None => href(did).map(|(url, _, _)| url),
+    }
+}
+
 /// Used when rendering a `ResolvedPath` structure. This invokes the `path`
 /// rendering function with the necessary arguments for linking to a local path.
 fn resolved_path(w: &mut fmt::Formatter, did: DefId, path: &clean::Path,
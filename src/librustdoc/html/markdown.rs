@@ -30,6 +30,7 @@ use libc;
 use rustc::session::config::get_unstable_features_setting;
 use std::ascii::AsciiExt;
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::default::Default;
 use std::ffi::CString;
 use std::fmt;
@@ -37,7 +38,8 @@ use std::slice;
 use std::str;
 use syntax::feature_gate::UnstableFeatures;
 
-use html::render::derive_id;
+use html::format::{parse_doc_link_path, resolve_doc_link};
+use html::render::{cache, derive_id, CURRENT_LOCATION_KEY};
 use html::toc::TocBuilder;
 use html::highlight;
 use html::escape::Escape;
@@ -146,7 +148,12 @@ struct html_toc_data {
 struct MyOpaque {
     dfltblk: extern "C" fn(*mut hoedown_buffer, *const hoedown_buffer,
                            *const hoedown_buffer, *const hoedown_renderer_data),
+    dfltlink: linkfn,
     toc_builder: Option<TocBuilder>,
+    /// Whether link targets written as Rust paths should be resolved.
+    resolve_links: bool,
+    /// Link targets of the definitions added by `add_doc_link_definitions`.
+    implicit_links: HashSet<String>,
 }
 
 #[repr(C)]
@@ -219,6 +226,81 @@ thread_local!(pub static PLAYGROUND_KRATE: RefCell<Option<Option<String>>> = {
     RefCell::new(None)
 });
 
+// Unresolved intra-doc links which have already been warned about, along with
+// the module they were found in. Summaries of items are rendered more than
+// once, so this avoids repeating the same warning.
+thread_local!(static UNRESOLVED_LINKS: RefCell<HashSet<(Vec<String>, String)>> = {
+    RefCell::new(HashSet::new())
+});
+
+/// Finds the shortcut reference links in `s` whose label is a Rust path, like
+/// ``[`Vec::push`]`` or `[Vec::push]`, and appends a link definition for each
+/// of them pointing at that path. Hoedown then renders them as links whose
+/// targets are resolved by the `link` callback in `render`.
+///
+/// Returns the new markdown source and the set of targets that were added.
+fn add_doc_link_definitions(s: &str) -> (String, HashSet<String>) {
+    let mut defined = HashSet::new();
+    let mut labels = Vec::new();
+    let mut in_fence = false;
+
+    for line in s.lines() {
+        let trimmed = line.trim_left();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue
+        }
+        if in_fence {
+            continue
+        }
+        // Explicit definitions take precedence
+        if trimmed.starts_with('[') {
+            if let Some(end) = trimmed.find("]:") {
+                defined.insert(trimmed[1..end].to_lowercase());
+                continue
+            }
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find('[') {
+            let after = &rest[start + 1..];
+            let end = match after.find(|c: char| c == '[' || c == ']') {
+                Some(end) if after[end..].starts_with("]") => end,
+                Some(end) => {
+                    rest = &after[end..];
+                    continue
+                }
+                None => break,
+            };
+            let label = &after[..end];
+            rest = &after[end + 1..];
+            if rest.starts_with("(") || rest.starts_with("[") || rest.starts_with(":") {
+                continue
+            }
+            let path = if label.len() > 2 && label.starts_with("`") && label.ends_with("`") {
+                &label[1..label.len() - 1]
+            } else if label.contains("::") {
+                label
+            } else {
+                continue
+            };
+            if parse_doc_link_path(path).is_some() {
+                labels.push((label.to_string(), path.to_string()));
+            }
+        }
+    }
+
+    let mut out = s.to_string();
+    let mut targets = HashSet::new();
+    for (label, path) in labels {
+        if defined.insert(label.to_lowercase()) {
+            out.push_str(&format!("\n\n[{}]: {}", label, path));
+            targets.insert(path);
+        }
+    }
+    (out, targets)
+}
+
 pub fn render(w: &mut fmt::Formatter, s: &str, print_toc: bool) -> fmt::Result {
     extern fn block(ob: *mut hoedown_buffer, orig_text: *const hoedown_buffer,
                     lang: *const hoedown_buffer, data: *const hoedown_renderer_data) {
@@ -329,6 +411,56 @@ pub fn render(w: &mut fmt::Formatter, s: &str, print_toc: bool) -> fmt::Result {
         unsafe { hoedown_buffer_puts(ob, text.as_ptr()) }
     }
 
+    extern fn link(ob: *mut hoedown_buffer,
+                   content: *const hoedown_buffer,
+                   link: *const hoedown_buffer,
+                   title: *const hoedown_buffer,
+                   data: *const hoedown_renderer_data) -> libc::c_int {
+        unsafe {
+            let opaque = (*data).opaque as *mut hoedown_html_renderer_state;
+            let my_opaque: &MyOpaque = &*((*opaque).opaque as *const MyOpaque);
+            if link.is_null() || !my_opaque.resolve_links {
+                return (my_opaque.dfltlink)(ob, content, link, title, data);
+            }
+            let target = str::from_utf8((*link).as_bytes()).unwrap();
+            if parse_doc_link_path(target).is_none() {
+                return (my_opaque.dfltlink)(ob, content, link, title, data);
+            }
+
+            match resolve_doc_link(target) {
+                Some(url) => {
+                    let url = CString::new(url).unwrap();
+                    let buf = hoedown_buffer_new(DEF_OUNIT);
+                    hoedown_buffer_puts(buf, url.as_ptr());
+                    let ret = (my_opaque.dfltlink)(ob, content, buf, title, data);
+                    hoedown_buffer_free(buf);
+                    ret
+                }
+                // A single identifier could just as well be a relative URL,
+                // leave those alone unless we added the link ourselves.
+                None if !target.contains("::") && !my_opaque.implicit_links.contains(target) => {
+                    (my_opaque.dfltlink)(ob, content, link, title, data)
+                }
+                None => {
+                    let loc = CURRENT_LOCATION_KEY.with(|l| l.borrow().clone());
+                    let first = UNRESOLVED_LINKS.with(|links| {
+                        links.borrow_mut().insert((loc.clone(), target.to_string()))
+                    });
+                    if first {
+                        println!("warning: unresolved link to `{}` in the documentation \
+                                  of `{}`", target, loc.join("::"));
+                    }
+                    // Render just the text of the link
+                    if !content.is_null() {
+                        hoedown_buffer_put(ob, (*content).data as *const libc::c_char,
+                                           (*content).size);
+                    }
+                    1
+                }
+            }
+        }
+    }
+
     extern fn codespan(
         ob: *mut hoedown_buffer,
         text: *const hoedown_buffer,
@@ -349,17 +481,31 @@ pub fn render(w: &mut fmt::Formatter, s: &str, print_toc: bool) -> fmt::Result {
         1
     }
 
+    // Links are only resolved when rendering the documentation of a crate,
+    // not when rendering standalone markdown files.
+    let resolve_links = !cache().paths.is_empty();
+    let (s, implicit_links) = if resolve_links {
+        add_doc_link_definitions(s)
+    } else {
+        (s.to_string(), HashSet::new())
+    };
+    let s = &s[..];
+
     unsafe {
         let ob = hoedown_buffer_new(DEF_OUNIT);
         let renderer = hoedown_html_renderer_new(0, 0);
         let mut opaque = MyOpaque {
             dfltblk: (*renderer).blockcode.unwrap(),
-            toc_builder: if print_toc {Some(TocBuilder::new())} else {None}
+            dfltlink: (*renderer).link.unwrap(),
+            toc_builder: if print_toc {Some(TocBuilder::new())} else {None},
+            resolve_links: resolve_links,
+            implicit_links: implicit_links,
         };
         (*((*renderer).opaque as *mut hoedown_html_renderer_state)).opaque
                 = &mut opaque as *mut _ as *mut libc::c_void;
         (*renderer).blockcode = Some(block);
         (*renderer).header = Some(header);
+        (*renderer).link = Some(link);
         (*renderer).codespan = Some(codespan);
 
         let document = hoedown_document_new(renderer, HOEDOWN_EXTENSIONS, 16);
@@ -570,7 +716,7 @@ pub fn plain_summary_line(md: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::{LangString, Markdown};
-    use super::plain_summary_line;
+    use super::{add_doc_link_definitions, plain_summary_line};
     use html::render::reset_ids;
 
     #[test]
@@ -671,4 +817,29 @@ mod tests {
         t("# top header", "top header");
         t("## header", "header");
     }
+
+    #[test]
+    fn test_add_doc_link_definitions() {
+        fn t(input: &str, expect: &str, targets: &[&str]) {
+            let (output, found) = add_doc_link_definitions(input);
+            assert_eq!(output, expect);
+            let mut found = found.into_iter().collect::<Vec<_>>();
+            found.sort();
+            assert_eq!(found, targets);
+        }
+
+        t("see [`Vec::push`]", "see [`Vec::push`]\n\n[`Vec::push`]: Vec::push", &["Vec::push"]);
+        t("see [Vec::push] or [`vec!`]",
+          "see [Vec::push] or [`vec!`]\n\n[Vec::push]: Vec::push\n\n[`vec!`]: vec!",
+          &["Vec::push", "vec!"]);
+        // Not paths, or not shortcut references
+        t("see [foo] and [`a b`]", "see [foo] and [`a b`]", &[]);
+        t("see [`Vec`](../vec/struct.Vec.html)", "see [`Vec`](../vec/struct.Vec.html)", &[]);
+        t("see [`Vec`][vec]", "see [`Vec`][vec]", &[]);
+        // Explicit definitions win
+        t("see [`Vec`]\n\n[`Vec`]: http://example.com",
+          "see [`Vec`]\n\n[`Vec`]: http://example.com", &[]);
+        // Code blocks are left alone
+        t("```\nx[`i`]\n```", "```\nx[`i`]\n```", &[]);
+    }
 }
@@ -232,6 +232,26 @@ pub struct Cache {
     /// generating explicit hyperlinks to other crates.
     pub external_paths: HashMap<DefId, Vec<String>>,
 
+    /// The paths of the macros, constants and statics of this crate. Nothing
+    /// but intra-doc links points to their pages, so unlike the items in
+    /// `paths` they don't need to be known when rendering types or building
+    /// the search index.
+    pub link_paths: HashMap<DefId, (Vec<String>, ItemType)>,
+
+    /// The reverse of `paths` and `link_paths` (minus enum variants, which are
+    /// linked to their enum): maps each fully qualified name to the items known
+    /// under it, e.g. both the `vec` module and the `vec!` macro. This is used
+    /// to resolve intra-doc links written as Rust paths.
+    pub path_index: HashMap<Vec<String>, Vec<DefId>>,
+
+    /// What the `use` declarations of each module, by path, bring into scope.
+    /// Intra-doc links are resolved through these like paths in the module.
+    pub imports: HashMap<Vec<String>, clean::ModuleImports>,
+
+    /// Names and types of the associated items, fields and variants of each
+    /// type or trait, so that intra-doc links can point to `Type::member`.
+    pub members: HashMap<DefId, Vec<(String, ItemType)>>,
+
     /// This map contains information about all known traits of this crate.
     /// Implementations of a crate should inherit the documentation of the
     /// parent trait if no extra documentation is specified, and default methods
@@ -520,6 +540,10 @@ pub fn run(mut krate: clean::Crate,
         impls: HashMap::new(),
         external_paths: paths.iter().map(|(&k, v)| (k, v.0.clone())).collect(),
         paths: paths,
+        link_paths: HashMap::new(),
+        path_index: HashMap::new(),
+        imports: HashMap::new(),
+        members: HashMap::new(),
         implementors: HashMap::new(),
         stack: Vec::new(),
         parent_stack: Vec::new(),
@@ -560,6 +584,9 @@ pub fn run(mut krate: clean::Crate,
     }
 
     cache.stack.push(krate.name.clone());
+    for (&did, &(ref fqp, _)) in &cache.paths {
+        cache.path_index.entry(fqp.clone()).or_insert(vec![]).push(did);
+    }
     krate = cache.fold_crate(krate);
 
     // Build our search index
@@ -1079,6 +1106,10 @@ impl DocFolder for Cache {
                 _ => ((None, Some(&*self.stack)), false)
             };
 
+            if let (Some(parent), _) = parent {
+                self.members.entry(parent).or_insert(vec![]).push((s.to_string(), shortty(&item)));
+            }
+
             match parent {
                 (parent, Some(path)) if is_method || (!self.stripped_mod) => {
                     // Needed to determine `self` type.
@@ -1143,8 +1174,16 @@ impl DocFolder for Cache {
                 {
                     self.paths.insert(item.def_id,
                                       (self.stack.clone(), shortty(&item)));
+                    self.path_index.entry(self.stack.clone()).or_insert(vec![])
+                                   .push(item.def_id);
                 }
             }
+            clean::MacroItem(..) | clean::ConstantItem(..) |
+            clean::StaticItem(..) if !self.stripped_mod => {
+                self.link_paths.insert(item.def_id, (self.stack.clone(), shortty(&item)));
+                self.path_index.entry(self.stack.clone()).or_insert(vec![])
+                               .push(item.def_id);
+            }
             // link variants to their parent enum because pages aren't emitted
             // for each variant
             clean::VariantItem(..) if !self.stripped_mod => {
@@ -1156,11 +1195,17 @@ impl DocFolder for Cache {
             clean::PrimitiveItem(..) if item.visibility.is_some() => {
                 self.paths.insert(item.def_id, (self.stack.clone(),
                                                 shortty(&item)));
+                self.path_index.entry(self.stack.clone()).or_insert(vec![])
+                               .push(item.def_id);
             }
 
             _ => {}
         }
 
+        if let clean::ModuleItem(ref m) = item.inner {
+            self.imports.insert(self.stack.clone(), m.imports.clone());
+        }
+
         // Maintain the parent stack
         let orig_parent_is_trait_impl = self.parent_is_trait_impl;
         let parent_pushed = match item.inner {
@@ -0,0 +1,96 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rustdoc's JSON backend
+//!
+//! This module serializes a `clean::Crate` into a single JSON document
+//! describing the crate's API: every item with its generics, where clauses,
+//! impls, documentation attributes, visibility and stability. It is meant to
+//! be consumed by tools (API diffing, documentation search, ...) which would
+//! otherwise have to scrape the HTML output.
+//!
+//! The document is wrapped in an envelope recording the schema name and
+//! `FORMAT_VERSION`, which must be bumped whenever the shape of the `clean`
+//! types changes in a way that is visible in the output, so that consumers
+//! can reject documents they don't understand.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use rustc::hir::def_id::DefId;
+use serialize::json;
+use serialize::{Encodable, Encoder};
+
+use clean;
+
+/// The name of the schema written into every document.
+pub const SCHEMA: &'static str = "rustdoc-json";
+
+/// The version of the JSON schema produced by this backend.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Top-level envelope of the JSON output.
+struct Document<'a> {
+    krate: &'a clean::Crate,
+    passes: &'a [String],
+}
+
+#[derive(RustcEncodable)]
+struct ExternalTrait<'a> {
+    def_id: DefId,
+    trait_: &'a clean::Trait,
+}
+
+impl<'a> Encodable for Document<'a> {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        let krate = self.krate;
+
+        // Sort the external traits so that the output is deterministic.
+        let mut external_traits = krate.external_traits
+                                       .iter()
+                                       .map(|(&def_id, trait_)| {
+                                           ExternalTrait { def_id: def_id, trait_: trait_ }
+                                       })
+                                       .collect::<Vec<_>>();
+        external_traits.sort_by(|a, b| a.def_id.cmp(&b.def_id));
+
+        s.emit_struct("Document", 5, |s| {
+            s.emit_struct_field("schema", 0, |s| SCHEMA.encode(s))?;
+            s.emit_struct_field("format_version", 1, |s| FORMAT_VERSION.encode(s))?;
+            s.emit_struct_field("passes", 2, |s| self.passes.encode(s))?;
+            s.emit_struct_field("crate", 3, |s| {
+                s.emit_struct("Crate", 5, |s| {
+                    s.emit_struct_field("name", 0, |s| krate.name.encode(s))?;
+                    s.emit_struct_field("src", 1, |s| krate.src.encode(s))?;
+                    s.emit_struct_field("module", 2, |s| krate.module.encode(s))?;
+                    s.emit_struct_field("externs", 3, |s| krate.externs.encode(s))?;
+                    s.emit_struct_field("primitives", 4, |s| krate.primitives.encode(s))
+                })
+            })?;
+            s.emit_struct_field("external_traits", 4, |s| external_traits.encode(s))
+        })
+    }
+}
+
+/// Renders `krate` as JSON into `<dst>/<crate name>.json`.
+pub fn run(krate: clean::Crate, passes: Vec<String>, dst: PathBuf) -> io::Result<()> {
+    fs::create_dir_all(&dst)?;
+    let path = dst.join(&format!("{}.json", krate.name));
+    write(&path, &krate, &passes)
+}
+
+fn write(path: &Path, krate: &clean::Crate, passes: &[String]) -> io::Result<()> {
+    let doc = Document { krate: krate, passes: passes };
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(&mut w, "{}", json::as_json(&doc))?;
+    w.flush()
+}
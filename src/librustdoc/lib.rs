@@ -20,6 +20,7 @@
 
 #![feature(box_patterns)]
 #![feature(box_syntax)]
+#![feature(file_lock)]
 #![feature(libc)]
 #![feature(rustc_private)]
 #![feature(set_stdio)]
@@ -51,7 +52,7 @@ extern crate serialize as rustc_serialize; // used by deriving
 use std::collections::HashMap;
 use std::default::Default;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::sync::mpsc::channel;
 
@@ -62,6 +63,7 @@ use rustc::session::config::{ErrorOutputType, RustcOptGroup, nightly_options};
 #[macro_use]
 pub mod externalfiles;
 
+pub mod apidiff;
 pub mod clean;
 pub mod core;
 pub mod doctree;
@@ -76,6 +78,7 @@ pub mod html {
     pub mod render;
     pub mod toc;
 }
+pub mod json;
 pub mod markdown;
 pub mod passes;
 pub mod plugins;
@@ -138,7 +141,7 @@ pub fn opts() -> Vec<RustcOptGroup> {
         stable(optopt("r", "input-format", "the input type of the specified file",
                       "[rust]")),
         stable(optopt("w", "output-format", "the output type to write",
-                      "[html]")),
+                      "[html|json]")),
         stable(optopt("o", "output", "where to place the output", "PATH")),
         stable(optopt("", "crate-name", "specify the name of this crate", "NAME")),
         stable(optmulti("L", "library-path", "directory to add to crate search path",
@@ -179,6 +182,12 @@ pub fn opts() -> Vec<RustcOptGroup> {
         unstable(optopt("e", "extend-css",
                         "to redefine some css rules with a given file to generate doc with your \
                          own theme", "PATH")),
+        unstable(optflag("", "no-batch-doctests",
+                         "compile every doctest into its own executable instead of \
+                          compiling those that allow it together")),
+        unstable(optflag("", "api-diff",
+                         "compare two JSON API descriptions (as produced by `-w json`) given \
+                          as OLD NEW and report the semver impact of every change")),
         unstable(optmulti("Z", "",
                           "internal and debugging options (only on nightly build)", "FLAG")),
     )
@@ -227,6 +236,14 @@ pub fn main_args(args: &[String]) -> isize {
         return 0;
     }
 
+    if matches.opt_present("api-diff") {
+        if matches.free.len() != 2 {
+            println!("--api-diff expects two input files: the old and the new API description");
+            return 1;
+        }
+        return apidiff::run(Path::new(&matches.free[0]), Path::new(&matches.free[1]));
+    }
+
     if matches.free.is_empty() {
         println!("expected an input file to act on");
         return 1;
@@ -255,6 +272,7 @@ pub fn main_args(args: &[String]) -> isize {
                                           .collect();
 
     let should_test = matches.opt_present("test");
+    let batch_tests = !matches.opt_present("no-batch-doctests");
     let markdown_input = input.ends_with(".md") || input.ends_with(".markdown");
 
     let output = matches.opt_str("o").map(|s| PathBuf::from(&s));
@@ -279,10 +297,10 @@ pub fn main_args(args: &[String]) -> isize {
 
     match (should_test, markdown_input) {
         (true, true) => {
-            return markdown::test(input, cfgs, libs, externs, test_args)
+            return markdown::test(input, cfgs, libs, externs, test_args, batch_tests)
         }
         (true, false) => {
-            return test::run(input, cfgs, libs, externs, test_args, crate_name)
+            return test::run(input, cfgs, libs, externs, test_args, crate_name, batch_tests)
         }
         (false, true) => return markdown::render(input,
                                                  output.unwrap_or(PathBuf::from("doc")),
@@ -308,6 +326,10 @@ pub fn main_args(args: &[String]) -> isize {
                               renderinfo)
                 .expect("failed to generate documentation")
         }
+        Some("json") => {
+            json::run(krate, passes, output.unwrap_or(PathBuf::from("doc")))
+                .expect("failed to generate JSON documentation")
+        }
         Some(s) => {
             println!("unknown output format: {}", s);
             return 1;
@@ -14,7 +14,7 @@ use std::env;
 use std::ffi::OsString;
 use std::io::prelude::*;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::panic::{self, AssertUnwindSafe};
 use std::process::Command;
 use std::rc::Rc;
@@ -33,6 +33,7 @@ use rustc_back::dynamic_lib::DynamicLibrary;
 use rustc_back::tempdir::TempDir;
 use rustc_driver::{driver, Compilation};
 use rustc_metadata::cstore::CStore;
+use syntax::ast;
 use syntax::codemap::CodeMap;
 use syntax::errors;
 use syntax::errors::emitter::ColorConfig;
@@ -57,7 +58,8 @@ pub fn run(input: &str,
            libs: SearchPaths,
            externs: core::Externs,
            mut test_args: Vec<String>,
-           crate_name: Option<String>)
+           crate_name: Option<String>,
+           batch_tests: bool)
            -> isize {
     let input_path = PathBuf::from(input);
     let input = config::Input::File(input_path.clone());
@@ -131,6 +133,7 @@ pub fn run(input: &str,
                                        libs,
                                        externs,
                                        false,
+                                       batch_tests,
                                        opts);
     collector.fold_crate(krate);
 
@@ -174,17 +177,21 @@ fn scrape_test_config(krate: &::rustc::hir::Crate) -> TestOptions {
     return opts;
 }
 
-fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
-           externs: core::Externs,
-           should_panic: bool, no_run: bool, as_test_harness: bool,
-           compile_fail: bool, opts: &TestOptions) {
-    // the test harness wants its own `main` & top level functions, so
-    // never wrap the test in `fn main() { ... }`
-    let test = maketest(test, Some(cratename), as_test_harness, opts);
-    let input = config::Input::Str {
-        name: driver::anon_src(),
-        input: test.to_owned(),
-    };
+// A `Write` implementation collecting everything written to it into a shared
+// buffer.
+struct Sink(Arc<Mutex<Vec<u8>>>);
+impl Write for Sink {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        Write::write(&mut *self.0.lock().unwrap(), data)
+    }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+/// Builds the compiler session used to compile a doctest into an executable,
+/// with all diagnostics going to `output`.
+fn build_test_session(cfgs: &[String], libs: SearchPaths, externs: core::Externs,
+                      as_test_harness: bool, output: Arc<Mutex<Vec<u8>>>)
+                      -> (session::Session, Rc<CStore>, ast::CrateConfig) {
     let mut outputs = HashMap::new();
     outputs.insert(OutputType::Exe, None);
 
@@ -204,6 +211,50 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
         ..config::basic_options().clone()
     };
 
+    let codemap = Rc::new(CodeMap::new());
+    let emitter = errors::emitter::EmitterWriter::new(box Sink(output),
+                                                      None,
+                                                      codemap.clone());
+    let diagnostic_handler = errors::Handler::with_emitter(true, false, box emitter);
+
+    let cstore = Rc::new(CStore::new(token::get_ident_interner()));
+    let sess = session::build_session_(sessopts,
+                                       None,
+                                       diagnostic_handler,
+                                       codemap,
+                                       cstore.clone());
+    rustc_lint::register_builtins(&mut sess.lint_store.borrow_mut(), Some(&sess));
+
+    let mut cfg = config::build_configuration(&sess);
+    cfg.extend(config::parse_cfgspecs(cfgs.to_vec()));
+    (sess, cstore, cfg)
+}
+
+/// Returns the value of the dynamic library search path variable for running
+/// a doctest executable.
+fn test_dylib_path(libdir: &Path) -> OsString {
+    // We're careful to prepend the *target* dylib search path to the child's
+    // environment to ensure that the target loads the right libraries at
+    // runtime. It would be a sad day if the *host* libraries were loaded as a
+    // mistake.
+    let path = env::var_os(DynamicLibrary::envvar()).unwrap_or(OsString::new());
+    let mut path = env::split_paths(&path).collect::<Vec<_>>();
+    path.insert(0, libdir.to_path_buf());
+    env::join_paths(path).unwrap()
+}
+
+fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
+           externs: core::Externs,
+           should_panic: bool, no_run: bool, as_test_harness: bool,
+           compile_fail: bool, opts: &TestOptions) {
+    // the test harness wants its own `main` & top level functions, so
+    // never wrap the test in `fn main() { ... }`
+    let test = maketest(test, Some(cratename), as_test_harness, opts);
+    let input = config::Input::Str {
+        name: driver::anon_src(),
+        input: test.to_owned(),
+    };
+
     // Shuffle around a few input and output handles here. We're going to pass
     // an explicit handle into rustc to collect output messages, but we also
     // want to catch the error message that rustc prints when it fails.
@@ -214,13 +265,6 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     //
     // The basic idea is to not use a default Handler for rustc, and then also
     // not print things by default to the actual stderr.
-    struct Sink(Arc<Mutex<Vec<u8>>>);
-    impl Write for Sink {
-        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
-            Write::write(&mut *self.0.lock().unwrap(), data)
-        }
-        fn flush(&mut self) -> io::Result<()> { Ok(()) }
-    }
     struct Bomb(Arc<Mutex<Vec<u8>>>, Box<Write+Send>);
     impl Drop for Bomb {
         fn drop(&mut self) {
@@ -228,29 +272,15 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
         }
     }
     let data = Arc::new(Mutex::new(Vec::new()));
-    let codemap = Rc::new(CodeMap::new());
-    let emitter = errors::emitter::EmitterWriter::new(box Sink(data.clone()),
-                                                      None,
-                                                      codemap.clone());
     let old = io::set_panic(box Sink(data.clone()));
-    let _bomb = Bomb(data, old.unwrap_or(box io::stdout()));
+    let _bomb = Bomb(data.clone(), old.unwrap_or(box io::stdout()));
 
     // Compile the code
-    let diagnostic_handler = errors::Handler::with_emitter(true, false, box emitter);
-
-    let cstore = Rc::new(CStore::new(token::get_ident_interner()));
-    let sess = session::build_session_(sessopts,
-                                       None,
-                                       diagnostic_handler,
-                                       codemap,
-                                       cstore.clone());
-    rustc_lint::register_builtins(&mut sess.lint_store.borrow_mut(), Some(&sess));
+    let (sess, cstore, cfg) = build_test_session(&cfgs, libs, externs, as_test_harness, data);
 
     let outdir = Mutex::new(TempDir::new("rustdoctest").ok().expect("rustdoc needs a tempdir"));
     let libdir = sess.target_filesearch(PathKind::All).get_lib_path();
     let mut control = driver::CompileController::basic();
-    let mut cfg = config::build_configuration(&sess);
-    cfg.extend(config::parse_cfgspecs(cfgs.clone()));
     let out = Some(outdir.lock().unwrap().path().to_path_buf());
 
     if no_run {
@@ -280,20 +310,8 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     if no_run { return }
 
     // Run the code!
-    //
-    // We're careful to prepend the *target* dylib search path to the child's
-    // environment to ensure that the target loads the right libraries at
-    // runtime. It would be a sad day if the *host* libraries were loaded as a
-    // mistake.
     let mut cmd = Command::new(&outdir.lock().unwrap().path().join("rust_out"));
-    let var = DynamicLibrary::envvar();
-    let newpath = {
-        let path = env::var_os(var).unwrap_or(OsString::new());
-        let mut path = env::split_paths(&path).collect::<Vec<_>>();
-        path.insert(0, libdir.clone());
-        env::join_paths(path).unwrap()
-    };
-    cmd.env(var, &newpath);
+    cmd.env(DynamicLibrary::envvar(), &test_dylib_path(&libdir));
 
     match cmd.output() {
         Err(e) => panic!("couldn't run the test: {}{}", e,
@@ -312,6 +330,197 @@ fn runtest(test: &str, cratename: &str, cfgs: Vec<String>, libs: SearchPaths,
     }
 }
 
+/// The name under which the source of a batch of doctests is compiled.
+const BATCH_SRC_NAME: &'static str = "<doctests>";
+
+/// Doctests which are compiled together into a single test crate instead of
+/// into one executable each, which saves compiling and linking them one by
+/// one. Each doctest becomes a module with a `#[test]` function, and the
+/// resulting executable is run once per doctest with `--exact` so that
+/// results are still reported per doctest.
+///
+/// The batch is compiled by whichever of its tests runs first. If it fails
+/// to compile, all of its tests fall back to being compiled on their own,
+/// which also reports the compilation errors against the right doctest.
+struct Batch {
+    cratename: String,
+    cfgs: Vec<String>,
+    libs: SearchPaths,
+    externs: core::Externs,
+    opts: TestOptions,
+    /// The source of each test along with whether it should panic.
+    tests: Vec<(String, bool)>,
+    /// The outcome of compiling the batch, once that has been attempted.
+    compiled: Option<Option<CompiledBatch>>,
+}
+
+struct CompiledBatch {
+    /// Holds the test executable, `rust_out`.
+    outdir: TempDir,
+    libdir: PathBuf,
+    /// The line of the batch source on which each test starts.
+    first_lines: Vec<usize>,
+}
+
+/// Whether a doctest can be compiled as part of a batch: it must not need a
+/// crate of its own (for crate attributes, `extern crate` or its own `main`),
+/// nor be expected to fail compilation.
+fn is_batchable(test: &str, ignore: bool, as_test_harness: bool, compile_fail: bool) -> bool {
+    !ignore && !as_test_harness && !compile_fail &&
+        !test.contains("#![") && !test.contains("extern crate") &&
+        !test.contains("fn main") && !test.contains("macro_rules!")
+}
+
+/// Generates the source of the test crate for `batch`, returning it along
+/// with the line on which each test starts.
+fn make_batch_source(batch: &Batch) -> (String, Vec<usize>) {
+    let mut src = String::new();
+    for attr in &batch.opts.attrs {
+        src.push_str(&format!("#![{}]\n", attr));
+    }
+    let inject_crate = !batch.opts.no_crate_inject && batch.cratename != "std" &&
+                       batch.tests.iter().any(|&(ref test, _)| test.contains(&batch.cratename[..]));
+    if inject_crate {
+        src.push_str(&format!("extern crate {};\n", batch.cratename));
+    }
+
+    let mut line = src.lines().count();
+    let mut first_lines = Vec::new();
+    for (i, &(ref test, should_panic)) in batch.tests.iter().enumerate() {
+        // Paths in expressions are relative to the enclosing module, so the
+        // crates a doctest expects at the root have to be brought into scope.
+        let mut header = format!("mod doctest_{} {{\n", i);
+        header.push_str("#[allow(unused_imports)] use std;\n");
+        if inject_crate {
+            header.push_str(&format!("#[allow(unused_imports)] use {};\n", batch.cratename));
+        }
+        header.push_str("#[test]\n");
+        if should_panic {
+            header.push_str("#[should_panic]\n");
+        }
+        header.push_str("fn run() {\n");
+        line += header.lines().count();
+        first_lines.push(line + 1);
+
+        src.push_str(&header);
+        src.push_str(test);
+        if !test.ends_with("\n") {
+            src.push_str("\n");
+        }
+        src.push_str("}\n}\n");
+        line += test.lines().count() + 2;
+    }
+    (src, first_lines)
+}
+
+fn compile_batch(batch: &Batch) -> Option<CompiledBatch> {
+    let (src, first_lines) = make_batch_source(batch);
+    info!("doctest batch: {}", src);
+    let input = config::Input::Str {
+        name: BATCH_SRC_NAME.to_string(),
+        input: src,
+    };
+    let outdir = match TempDir::new("rustdoctest") {
+        Ok(outdir) => outdir,
+        Err(_) => return None,
+    };
+
+    // Nobody is interested in the diagnostics of the batch, see `Batch`.
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let old = io::set_panic(box Sink(output.clone()));
+    let (sess, cstore, cfg) = build_test_session(&batch.cfgs,
+                                                 batch.libs.clone(),
+                                                 batch.externs.clone(),
+                                                 true,
+                                                 output.clone());
+    let libdir = sess.target_filesearch(PathKind::All).get_lib_path();
+    let out = Some(outdir.path().to_path_buf());
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        driver::compile_input(&sess, &cstore, cfg, &input, &out,
+                              &None, None, &driver::CompileController::basic())
+    }));
+    io::set_panic(old.unwrap_or(box io::stderr()));
+
+    match res {
+        Ok(Ok(())) => {
+            Some(CompiledBatch {
+                outdir: outdir,
+                libdir: libdir,
+                first_lines: first_lines,
+            })
+        }
+        _ => {
+            debug!("doctest batch failed to compile:\n{}",
+                   String::from_utf8_lossy(&output.lock().unwrap()));
+            None
+        }
+    }
+}
+
+/// Runs the test with the given index in `batch`, compiling the batch first
+/// if no other test did yet. Returns `false` if the batch couldn't be
+/// compiled, in which case the test has to be run on its own.
+fn run_batched_test(batch: &Mutex<Batch>, index: usize, no_run: bool) -> bool {
+    let (exe, libdir, first_line) = {
+        let mut batch = batch.lock().unwrap();
+        if batch.compiled.is_none() {
+            let compiled = compile_batch(&batch);
+            batch.compiled = Some(compiled);
+        }
+        match batch.compiled {
+            Some(Some(ref c)) => {
+                (c.outdir.path().join("rust_out"), c.libdir.clone(), c.first_lines[index])
+            }
+            _ => return false,
+        }
+    };
+    if no_run { return true }
+
+    let mut cmd = Command::new(&exe);
+    cmd.arg("--exact")
+       .arg(&format!("doctest_{}::run", index))
+       .env("RUST_TEST_THREADS", "1")
+       .env(DynamicLibrary::envvar(), &test_dylib_path(&libdir));
+
+    match cmd.output() {
+        Err(e) => panic!("couldn't run the test: {}{}", e,
+                        if e.kind() == io::ErrorKind::PermissionDenied {
+                            " - maybe your tempdir is mounted with noexec?"
+                        } else { "" }),
+        Ok(out) => {
+            if !out.status.success() {
+                let output = format!("{}\n{}",
+                                     String::from_utf8_lossy(&out.stdout),
+                                     String::from_utf8_lossy(&out.stderr));
+                panic!("test executable failed:\n{}", map_batch_lines(&output, first_line));
+            }
+        }
+    }
+    true
+}
+
+/// Rewrites the locations in the batch source mentioned in `output` (as in
+/// panic messages) to be relative to the doctest starting at `first_line`.
+fn map_batch_lines(output: &str, first_line: usize) -> String {
+    let prefix = format!("{}:", BATCH_SRC_NAME);
+    let mut result = String::new();
+    let mut rest = output;
+    while let Some(pos) = rest.find(&prefix[..]) {
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos + prefix.len()..];
+        let digits = rest.chars().take_while(|c| c.is_digit(10)).count();
+        match rest[..digits].parse::<usize>() {
+            Ok(line) if line >= first_line => {
+                result.push_str(&format!("{}:{}", driver::anon_src(), line - first_line + 1));
+                rest = &rest[digits..];
+            }
+            _ => result.push_str(&prefix),
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
 pub fn maketest(s: &str, cratename: Option<&str>, dont_insert_main: bool,
                 opts: &TestOptions) -> String {
     let (crate_attrs, everything_else) = partition_source(s);
@@ -385,11 +594,25 @@ pub struct Collector {
     current_header: Option<String>,
     cratename: String,
     opts: TestOptions,
+    batch: Option<Arc<Mutex<Batch>>>,
 }
 
 impl Collector {
     pub fn new(cratename: String, cfgs: Vec<String>, libs: SearchPaths, externs: core::Externs,
-               use_headers: bool, opts: TestOptions) -> Collector {
+               use_headers: bool, batch_tests: bool, opts: TestOptions) -> Collector {
+        let batch = if batch_tests {
+            Some(Arc::new(Mutex::new(Batch {
+                cratename: cratename.clone(),
+                cfgs: cfgs.clone(),
+                libs: libs.clone(),
+                externs: externs.clone(),
+                opts: opts.clone(),
+                tests: Vec::new(),
+                compiled: None,
+            })))
+        } else {
+            None
+        };
         Collector {
             tests: Vec::new(),
             names: Vec::new(),
@@ -401,6 +624,7 @@ impl Collector {
             current_header: None,
             cratename: cratename,
             opts: opts,
+            batch: batch,
         }
     }
 
@@ -419,6 +643,15 @@ impl Collector {
         let externs = self.externs.clone();
         let cratename = self.cratename.to_string();
         let opts = self.opts.clone();
+        let batch = match self.batch {
+            Some(ref batch) if is_batchable(&test, should_ignore, as_test_harness,
+                                            compile_fail) => {
+                let mut b = batch.lock().unwrap();
+                b.tests.push((test.clone(), should_panic));
+                Some((batch.clone(), b.tests.len() - 1))
+            }
+            _ => None,
+        };
         debug!("Creating test {}: {}", name, test);
         self.tests.push(testing::TestDescAndFn {
             desc: testing::TestDesc {
@@ -428,6 +661,11 @@ impl Collector {
                 should_panic: testing::ShouldPanic::No,
             },
             testfn: testing::DynTestFn(box move|| {
+                if let Some((batch, index)) = batch {
+                    if run_batched_test(&batch, index, no_run) {
+                        return
+                    }
+                }
                 runtest(&test,
                         &cratename,
                         cfgs,
@@ -515,3 +753,52 @@ impl DocFolder for Collector {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use rustc::session::search_paths::SearchPaths;
+    use super::{Batch, TestOptions, is_batchable, make_batch_source, map_batch_lines};
+
+    #[test]
+    fn test_is_batchable() {
+        assert!(is_batchable("let x = 1;", false, false, false));
+        assert!(!is_batchable("let x = 1;", true, false, false));
+        assert!(!is_batchable("let x = 1;", false, true, false));
+        assert!(!is_batchable("let x = 1;", false, false, true));
+        assert!(!is_batchable("#![feature(box_syntax)]\nlet x = box 1;", false, false, false));
+        assert!(!is_batchable("extern crate foo;", false, false, false));
+        assert!(!is_batchable("fn main() {}", false, false, false));
+        assert!(!is_batchable("macro_rules! m { () => () }", false, false, false));
+    }
+
+    #[test]
+    fn test_make_batch_source() {
+        let batch = Batch {
+            cratename: "foo".to_string(),
+            cfgs: Vec::new(),
+            libs: SearchPaths::new(),
+            externs: HashMap::new(),
+            opts: TestOptions::default(),
+            tests: vec![("let x = foo::bar();\nassert_eq!(x, 1);".to_string(), false),
+                        ("panic!();\n".to_string(), true)],
+            compiled: None,
+        };
+        let (src, first_lines) = make_batch_source(&batch);
+        let lines = src.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "extern crate foo;");
+        assert_eq!(lines[first_lines[0] - 1], "let x = foo::bar();");
+        assert_eq!(lines[first_lines[1] - 1], "panic!();");
+        assert_eq!(lines[first_lines[1] - 2], "fn run() {");
+        assert_eq!(lines[first_lines[1] - 3], "#[should_panic]");
+    }
+
+    #[test]
+    fn test_map_batch_lines() {
+        assert_eq!(map_batch_lines("panicked at 'boom', <doctests>:12", 10),
+                   "panicked at 'boom', <anon>:3");
+        assert_eq!(map_batch_lines("<doctests>:3 and <doctests>:x", 10),
+                   "<doctests>:3 and <doctests>:x");
+        assert_eq!(map_batch_lines("no locations", 1), "no locations");
+    }
+}
@@ -0,0 +1,480 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A hash map which can be shared between threads.
+//!
+//! The map is split into shards, each of which is an ordinary `HashMap`
+//! behind its own `RwLock`. A key always lives in the shard selected by the
+//! top bits of its hash, so operations on keys of different shards don't
+//! contend, and lookups only ever take a shard's lock for reading.
+
+use borrow::Borrow;
+use fmt::{self, Debug};
+use hash::{Hash, BuildHasher};
+use ops::{Deref, DerefMut};
+use sync::{LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use vec;
+
+use super::map::{self, HashMap, RandomState};
+use super::table;
+
+const DEFAULT_SHARDS: usize = 16;
+
+/// A hash map which can be shared between threads, with fine-grained
+/// locking.
+///
+/// The entries are spread over a number of shards, each guarded by its own
+/// reader-writer lock, so that threads working on different keys rarely
+/// wait for each other. All operations take `&self`; references to values
+/// are handed out as guards, which keep the shard of the value locked while
+/// they are alive.
+///
+/// A panic while a shard is locked doesn't poison the map: the `HashMap` of
+/// a shard is always left in a consistent state.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(concurrent_hash_map)]
+///
+/// use std::collections::ConcurrentHashMap;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let map = Arc::new(ConcurrentHashMap::new());
+/// let threads = (0..4).map(|i| {
+///     let map = map.clone();
+///     thread::spawn(move || {
+///         map.insert(i, i * 10);
+///     })
+/// }).collect::<Vec<_>>();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+///
+/// assert_eq!(map.len(), 4);
+/// assert_eq!(*map.get(&2).unwrap(), 20);
+/// ```
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+pub struct ConcurrentHashMap<K, V, S = RandomState> {
+    hash_builder: S,
+    // The number of low bits of a hash to drop to select its shard, see
+    // `shard_index`.
+    shift: u32,
+    shards: Box<[RwLock<HashMap<K, V, S>>]>,
+}
+
+impl<K: Hash + Eq, V> ConcurrentHashMap<K, V, RandomState> {
+    /// Creates an empty map with the default number of shards.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn new() -> ConcurrentHashMap<K, V, RandomState> {
+        ConcurrentHashMap::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Creates an empty map with at least `shards` shards. More shards mean
+    /// less contention but more memory, and slower iteration.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn with_shards(shards: usize) -> ConcurrentHashMap<K, V, RandomState> {
+        ConcurrentHashMap::with_shards_and_hasher(shards, Default::default())
+    }
+}
+
+impl<K, V, S> ConcurrentHashMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Clone
+{
+    /// Creates an empty map which will use the given hash builder to hash
+    /// keys, with the default number of shards.
+    ///
+    /// The same caveats as for `HashMap::with_hasher` apply.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn with_hasher(hash_builder: S) -> ConcurrentHashMap<K, V, S> {
+        ConcurrentHashMap::with_shards_and_hasher(DEFAULT_SHARDS, hash_builder)
+    }
+
+    /// Creates an empty map with at least `shards` shards, which will use
+    /// the given hash builder to hash keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` is 0.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn with_shards_and_hasher(shards: usize, hash_builder: S)
+                                  -> ConcurrentHashMap<K, V, S> {
+        assert!(shards > 0, "a ConcurrentHashMap needs at least one shard");
+        let shards = shards.checked_next_power_of_two().expect("too many shards");
+        let shards = (0..shards).map(|_| {
+            RwLock::new(HashMap::with_hasher(hash_builder.clone()))
+        }).collect::<Vec<_>>();
+        ConcurrentHashMap {
+            hash_builder: hash_builder,
+            shift: 64 - shards.len().trailing_zeros(),
+            shards: shards.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the shard `key` belongs to.
+    fn shard<Q: ?Sized>(&self, key: &Q) -> &RwLock<HashMap<K, V, S>>
+        where Q: Hash
+    {
+        &self.shards[self.shard_index(key)]
+    }
+
+    fn shard_index<Q: ?Sized>(&self, key: &Q) -> usize
+        where Q: Hash
+    {
+        if self.shards.len() == 1 {
+            return 0;
+        }
+        // The most significant bit of a `SafeHash` is always set and the
+        // low bits select the bucket within a shard's table, so the shard
+        // is selected by the bits right below the most significant one.
+        let hash = table::make_hash(&self.hash_builder, key).inspect();
+        ((hash << 1) >> self.shift) as usize
+    }
+
+    /// Returns the number of elements in the map.
+    ///
+    /// As other threads may modify the map concurrently, the result is only
+    /// a snapshot: the shards are counted one after the other.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn len(&self) -> usize {
+        self.shards.iter().fold(0, |len, shard| len + read(shard).len())
+    }
+
+    /// Returns true if the map contains no elements. See `len`.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| read(shard).is_empty())
+    }
+
+    /// Removes all elements from the map.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            write(shard).clear();
+        }
+    }
+
+    /// Inserts a key-value pair into the map, returning the value which was
+    /// previously present for the key, if any.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn insert(&self, k: K, v: V) -> Option<V> {
+        write(self.shard(&k)).insert(k, v)
+    }
+
+    /// Removes a key from the map, returning its value if it was present.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn remove<Q: ?Sized>(&self, k: &Q) -> Option<V>
+        where K: Borrow<Q>, Q: Hash + Eq
+    {
+        write(self.shard(k)).remove(k)
+    }
+
+    /// Returns true if the map contains a value for the specified key.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn contains_key<Q: ?Sized>(&self, k: &Q) -> bool
+        where K: Borrow<Q>, Q: Hash + Eq
+    {
+        read(self.shard(k)).contains_key(k)
+    }
+
+    /// Returns a reference to the value corresponding to the key.
+    ///
+    /// The shard of the key stays locked for reading until the returned
+    /// guard is dropped, so other threads can still read it, but not modify
+    /// it. Trying to modify it from the same thread deadlocks.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn get<Q: ?Sized>(&self, k: &Q) -> Option<ReadGuard<K, V, S>>
+        where K: Borrow<Q>, Q: Hash + Eq
+    {
+        let guard = read(self.shard(k));
+        let value = match guard.get(k) {
+            Some(value) => value as *const V,
+            None => return None,
+        };
+        Some(ReadGuard { _guard: guard, value: value })
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key.
+    ///
+    /// The shard of the key stays locked for writing until the returned
+    /// guard is dropped. Accessing it from the same thread in the meantime
+    /// deadlocks.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn get_mut<Q: ?Sized>(&self, k: &Q) -> Option<WriteGuard<K, V, S>>
+        where K: Borrow<Q>, Q: Hash + Eq
+    {
+        let mut guard = write(self.shard(k));
+        let value = match guard.get_mut(k) {
+            Some(value) => value as *mut V,
+            None => return None,
+        };
+        Some(WriteGuard { _guard: guard, value: value })
+    }
+
+    /// Calls `f` with the entry of the given key, for in-place manipulation,
+    /// and returns its result. The shard of the key is locked for writing
+    /// during the call, so the entry can't change under `f`'s feet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(concurrent_hash_map)]
+    ///
+    /// use std::collections::ConcurrentHashMap;
+    ///
+    /// let counts = ConcurrentHashMap::new();
+    /// for word in "a b a c a".split(' ') {
+    ///     counts.entry(word, |entry| *entry.or_insert(0) += 1);
+    /// }
+    /// assert_eq!(*counts.get("a").unwrap(), 3);
+    /// ```
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn entry<F, R>(&self, key: K, f: F) -> R
+        where F: FnOnce(map::Entry<K, V>) -> R
+    {
+        let mut shard = write(self.shard(&key));
+        f(shard.entry(key))
+    }
+
+    /// An iterator over clones of the entries of the map, in arbitrary
+    /// order.
+    ///
+    /// The iterator never blocks other threads for long: it copies out the
+    /// entries of one shard at a time. It yields every entry which is in the
+    /// map for the whole iteration exactly once; entries inserted or removed
+    /// concurrently may or may not be seen.
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub fn iter(&self) -> Iter<K, V, S>
+        where K: Clone, V: Clone
+    {
+        Iter {
+            map: self,
+            next_shard: 0,
+            entries: Vec::new().into_iter(),
+        }
+    }
+}
+
+// The shards only hold plain `HashMap`s, which are never left half-updated
+// by a panic, so poisoning is ignored.
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<T> {
+    ignore_poison(lock.read())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<T> {
+    ignore_poison(lock.write())
+}
+
+fn ignore_poison<G>(result: LockResult<G>) -> G {
+    result.unwrap_or_else(|err| err.into_inner())
+}
+
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+impl<K, V, S> Default for ConcurrentHashMap<K, V, S>
+    where K: Eq + Hash, S: BuildHasher + Clone + Default
+{
+    fn default() -> ConcurrentHashMap<K, V, S> {
+        ConcurrentHashMap::with_hasher(Default::default())
+    }
+}
+
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+impl<K, V, S> Debug for ConcurrentHashMap<K, V, S>
+    where K: Eq + Hash + Debug, V: Debug, S: BuildHasher
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut map = f.debug_map();
+        for shard in self.shards.iter() {
+            let shard = read(shard);
+            map.entries(shard.iter());
+        }
+        map.finish()
+    }
+}
+
+/// A reference to a value of a `ConcurrentHashMap`, which keeps its shard
+/// locked for reading.
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+pub struct ReadGuard<'a, K: 'a, V: 'a, S: 'a> {
+    _guard: RwLockReadGuard<'a, HashMap<K, V, S>>,
+    // Points into the map behind `_guard`, which can't change while it is
+    // locked.
+    value: *const V,
+}
+
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+impl<'a, K, V, S> Deref for ReadGuard<'a, K, V, S> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        unsafe { &*self.value }
+    }
+}
+
+/// A mutable reference to a value of a `ConcurrentHashMap`, which keeps its
+/// shard locked for writing.
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+pub struct WriteGuard<'a, K: 'a, V: 'a, S: 'a> {
+    _guard: RwLockWriteGuard<'a, HashMap<K, V, S>>,
+    // Points into the map behind `_guard`, which only this guard can access.
+    value: *mut V,
+}
+
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+impl<'a, K, V, S> Deref for WriteGuard<'a, K, V, S> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        unsafe { &*self.value }
+    }
+}
+
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+impl<'a, K, V, S> DerefMut for WriteGuard<'a, K, V, S> {
+    fn deref_mut(&mut self) -> &mut V {
+        unsafe { &mut *self.value }
+    }
+}
+
+/// An iterator over clones of the entries of a `ConcurrentHashMap`, see
+/// `ConcurrentHashMap::iter`.
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+pub struct Iter<'a, K: 'a, V: 'a, S: 'a> {
+    map: &'a ConcurrentHashMap<K, V, S>,
+    next_shard: usize,
+    entries: vec::IntoIter<(K, V)>,
+}
+
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+impl<'a, K, V, S> Iterator for Iter<'a, K, V, S>
+    where K: Eq + Hash + Clone, V: Clone, S: BuildHasher
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(entry);
+            }
+            if self.next_shard == self.map.shards.len() {
+                return None;
+            }
+            let entries = {
+                let shard = read(&self.map.shards[self.next_shard]);
+                shard.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>()
+            };
+            self.entries = entries.into_iter();
+            self.next_shard += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use super::ConcurrentHashMap;
+    use sync::Arc;
+    use thread;
+
+    #[test]
+    fn smoke() {
+        let map = ConcurrentHashMap::new();
+        assert!(map.is_empty());
+        assert_eq!(map.insert(1, 2), None);
+        assert_eq!(map.insert(1, 3), Some(2));
+        assert_eq!(*map.get(&1).unwrap(), 3);
+        assert!(map.get(&2).is_none());
+        *map.get_mut(&1).unwrap() += 1;
+        assert_eq!(*map.get(&1).unwrap(), 4);
+        assert!(map.contains_key(&1));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.remove(&1), Some(4));
+        assert!(!map.contains_key(&1));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn single_shard() {
+        let map = ConcurrentHashMap::with_shards(1);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.len(), 100);
+        assert_eq!(*map.get(&42).unwrap(), 42);
+    }
+
+    #[test]
+    fn entry() {
+        let map = ConcurrentHashMap::new();
+        for &word in &["a", "b", "a"] {
+            map.entry(word, |entry| *entry.or_insert(0) += 1);
+        }
+        assert_eq!(*map.get("a").unwrap(), 2);
+        assert_eq!(*map.get("b").unwrap(), 1);
+    }
+
+    #[test]
+    fn iter() {
+        let map = ConcurrentHashMap::with_shards(4);
+        for i in 0..100 {
+            map.insert(i, i * 2);
+        }
+        let mut entries = map.iter().collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(entries, (0..100).map(|i| (i, i * 2)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn iter_while_modified() {
+        let map = ConcurrentHashMap::with_shards(4);
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        let mut seen = 0;
+        for (k, _) in map.iter() {
+            map.remove(&k);
+            map.insert(k + 1000, k);
+            seen += 1;
+        }
+        // Every original entry is seen, the new ones may or may not be.
+        assert!(seen >= 100);
+        assert_eq!(map.len(), 100);
+        assert!(map.iter().all(|(k, _)| k >= 1000));
+    }
+
+    #[test]
+    fn threads() {
+        let map = Arc::new(ConcurrentHashMap::new());
+        let threads = (0..8).map(|t| {
+            let map = map.clone();
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    map.insert(t * 1000 + i, t);
+                    map.entry(-1, |entry| *entry.or_insert(0) += 1);
+                }
+            })
+        }).collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(map.len(), 8001);
+        assert_eq!(*map.get(&-1).unwrap(), 8000);
+        assert_eq!(*map.get(&7999).unwrap(), 7);
+    }
+
+    #[test]
+    fn debug() {
+        let map = ConcurrentHashMap::new();
+        map.insert(1, 2);
+        assert_eq!(format!("{:?}", map), "{1: 2}");
+    }
+}
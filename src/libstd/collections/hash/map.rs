@@ -11,6 +11,7 @@
 use self::Entry::*;
 use self::VacantEntryState::*;
 
+use alloc::raw_vec::CollectionAllocErr;
 use borrow::Borrow;
 use cmp::max;
 use fmt::{self, Debug};
@@ -58,6 +59,12 @@ impl DefaultResizePolicy {
         usable_size * 11 / 10
     }
 
+    /// Like `min_capacity`, but returns `None` if the result would overflow.
+    #[inline]
+    fn checked_min_capacity(&self, usable_size: usize) -> Option<usize> {
+        usable_size.checked_mul(11).map(|cap| cap / 10)
+    }
+
     /// An inverse of `min_capacity`, approximately.
     #[inline]
     fn usable_capacity(&self, cap: usize) -> usize {
@@ -662,32 +669,68 @@ impl<K, V, S> HashMap<K, V, S>
     /// ```
     #[stable(feature = "rust1", since = "1.0.0")]
     pub fn reserve(&mut self, additional: usize) {
-        let new_size = self.len().checked_add(additional).expect("capacity overflow");
-        let min_cap = self.resize_policy.min_capacity(new_size);
+        match self.try_reserve(additional) {
+            Ok(()) => {}
+            Err(CollectionAllocErr::CapacityOverflow) => panic!("capacity overflow"),
+            Err(CollectionAllocErr::AllocErr) => ::alloc::oom(),
+        }
+    }
 
-        // An invalid value shouldn't make us run out of space. This includes
-        // an overflow check.
-        assert!(new_size <= min_cap);
+    /// Tries to reserve capacity for at least `additional` more elements to be
+    /// inserted in the `HashMap`. The collection may reserve more space to
+    /// avoid frequent reallocations.
+    ///
+    /// Unlike `reserve`, this does not panic if the new allocation size
+    /// overflows and does not abort if the allocator fails; the error is
+    /// returned instead and the map is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(try_reserve)]
+    /// use std::collections::{CollectionAllocErr, HashMap};
+    ///
+    /// let mut map: HashMap<&str, isize> = HashMap::new();
+    /// map.try_reserve(10).unwrap();
+    /// assert!(map.capacity() >= 10);
+    ///
+    /// assert_eq!(map.try_reserve(usize::max_value()),
+    ///            Err(CollectionAllocErr::CapacityOverflow));
+    /// ```
+    #[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), CollectionAllocErr> {
+        let new_size = self.len().checked_add(additional)
+                                 .ok_or(CollectionAllocErr::CapacityOverflow)?;
+        let min_cap = self.resize_policy.checked_min_capacity(new_size)
+                                        .ok_or(CollectionAllocErr::CapacityOverflow)?;
+
+        // An invalid value shouldn't make us run out of space.
+        debug_assert!(new_size <= min_cap);
 
         if self.table.capacity() < min_cap {
-            let new_capacity = max(min_cap.next_power_of_two(), INITIAL_CAPACITY);
-            self.resize(new_capacity);
+            let new_capacity = min_cap.checked_next_power_of_two()
+                                      .ok_or(CollectionAllocErr::CapacityOverflow)?;
+            self.try_resize(max(new_capacity, INITIAL_CAPACITY))?;
         }
+        Ok(())
     }
 
     /// Resizes the internal vectors to a new capacity. It's your responsibility to:
     ///   1) Make sure the new capacity is enough for all the elements, accounting
     ///      for the load factor.
     ///   2) Ensure new_capacity is a power of two or zero.
-    fn resize(&mut self, new_capacity: usize) {
+    ///
+    /// If the new table cannot be allocated the map is left untouched.
+    fn try_resize(&mut self, new_capacity: usize) -> Result<(), CollectionAllocErr> {
         assert!(self.table.size() <= new_capacity);
         assert!(new_capacity.is_power_of_two() || new_capacity == 0);
 
-        let mut old_table = replace(&mut self.table, RawTable::new(new_capacity));
+        let new_table = RawTable::try_new(new_capacity)?;
+        let mut old_table = replace(&mut self.table, new_table);
         let old_size = old_table.size();
 
         if old_table.capacity() == 0 || old_table.size() == 0 {
-            return;
+            return Ok(());
         }
 
         // Grow the table.
@@ -753,6 +796,7 @@ impl<K, V, S> HashMap<K, V, S>
         }
 
         assert_eq!(self.table.size(), old_size);
+        Ok(())
     }
 
     /// Shrinks the capacity of the map as much as possible. It will drop
@@ -1742,6 +1786,7 @@ mod test_map {
 
     use super::HashMap;
     use super::Entry::{Occupied, Vacant};
+    use alloc::raw_vec::CollectionAllocErr;
     use cell::RefCell;
     use rand::{thread_rng, Rng};
 
@@ -2268,6 +2313,23 @@ mod test_map {
         assert_eq!(m.table.capacity(), initial_cap);
     }
 
+    #[test]
+    fn test_try_reserve() {
+        let mut m = HashMap::new();
+        m.insert(0, 0);
+
+        assert_eq!(m.try_reserve(100), Ok(()));
+        let cap = m.capacity();
+        assert!(cap >= 101);
+
+        assert_eq!(m.try_reserve(usize::max_value()), Err(CollectionAllocErr::CapacityOverflow));
+        assert_eq!(m.try_reserve(usize::max_value() / 8),
+                   Err(CollectionAllocErr::CapacityOverflow));
+        assert_eq!(m.capacity(), cap);
+        assert_eq!(m.len(), 1);
+        assert_eq!(m[&0], 0);
+    }
+
     #[test]
     fn test_reserve_shrink_to_fit() {
         let mut m = HashMap::new();
@@ -14,6 +14,7 @@ mod bench;
 mod table;
 pub mod map;
 pub mod set;
+pub mod concurrent;
 
 trait Recover<Q: ?Sized> {
     type Key;
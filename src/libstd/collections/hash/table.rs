@@ -9,6 +9,7 @@
 // except according to those terms.
 
 use alloc::heap::{allocate, deallocate, EMPTY};
+use alloc::raw_vec::CollectionAllocErr;
 
 use cmp;
 use hash::{Hash, Hasher, BuildHasher};
@@ -596,13 +597,24 @@ impl<K, V> RawTable<K, V> {
     /// Does not initialize the buckets. The caller should ensure they,
     /// at the very least, set every hash to EMPTY_BUCKET.
     unsafe fn new_uninitialized(capacity: usize) -> RawTable<K, V> {
+        match RawTable::try_new_uninitialized(capacity) {
+            Ok(table) => table,
+            Err(CollectionAllocErr::CapacityOverflow) => panic!("capacity overflow"),
+            Err(CollectionAllocErr::AllocErr) => ::alloc::oom(),
+        }
+    }
+
+    /// Like `new_uninitialized`, but reports capacity overflow and allocation
+    /// failure to the caller instead of panicking or aborting.
+    unsafe fn try_new_uninitialized(capacity: usize)
+                                    -> Result<RawTable<K, V>, CollectionAllocErr> {
         if capacity == 0 {
-            return RawTable {
+            return Ok(RawTable {
                 size: 0,
                 capacity: 0,
                 hashes: Unique::new(EMPTY as *mut u64),
                 marker: marker::PhantomData,
-            };
+            });
         }
 
         // No need for `checked_mul` before a more restrictive check performed
@@ -625,26 +637,31 @@ impl<K, V> RawTable<K, V> {
                 keys_size,   align_of::< K >(),
                 vals_size,   align_of::< V >());
 
-        assert!(!oflo, "capacity overflow");
+        if oflo {
+            return Err(CollectionAllocErr::CapacityOverflow);
+        }
 
         // One check for overflow that covers calculation and rounding of size.
         let size_of_bucket = size_of::<u64>().checked_add(size_of::<K>()).unwrap()
                                              .checked_add(size_of::<V>()).unwrap();
-        assert!(size >= capacity.checked_mul(size_of_bucket)
-                                .expect("capacity overflow"),
-                "capacity overflow");
+        match capacity.checked_mul(size_of_bucket) {
+            Some(min_size) if size >= min_size => {}
+            _ => return Err(CollectionAllocErr::CapacityOverflow),
+        }
 
         let buffer = allocate(size, malloc_alignment);
-        if buffer.is_null() { ::alloc::oom() }
+        if buffer.is_null() {
+            return Err(CollectionAllocErr::AllocErr);
+        }
 
         let hashes = buffer.offset(hash_offset as isize) as *mut u64;
 
-        RawTable {
+        Ok(RawTable {
             capacity: capacity,
             size:     0,
             hashes:   Unique::new(hashes),
             marker:   marker::PhantomData,
-        }
+        })
     }
 
     fn first_bucket_raw(&self) -> RawBucket<K, V> {
@@ -677,6 +694,16 @@ impl<K, V> RawTable<K, V> {
         }
     }
 
+    /// Creates a new raw table from a given capacity, returning an error
+    /// instead of panicking on overflow or aborting if the allocation fails.
+    pub fn try_new(capacity: usize) -> Result<RawTable<K, V>, CollectionAllocErr> {
+        unsafe {
+            let ret = RawTable::try_new_uninitialized(capacity)?;
+            ptr::write_bytes(*ret.hashes, 0, capacity);
+            Ok(ret)
+        }
+    }
+
     /// The hashtable's capacity, similar to a vector's.
     pub fn capacity(&self) -> usize {
         self.capacity
@@ -424,6 +424,11 @@ pub use core_collections::{linked_list, vec_deque};
 pub use self::hash_map::HashMap;
 #[stable(feature = "rust1", since = "1.0.0")]
 pub use self::hash_set::HashSet;
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+pub use self::concurrent_hash_map::ConcurrentHashMap;
+
+#[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+pub use core_collections::CollectionAllocErr;
 
 mod hash;
 
@@ -440,3 +445,10 @@ pub mod hash_set {
     #[stable(feature = "rust1", since = "1.0.0")]
     pub use super::hash::set::*;
 }
+
+#[unstable(feature = "concurrent_hash_map", issue = "0")]
+pub mod concurrent_hash_map {
+    //! A hashmap which can be shared between threads
+    #[unstable(feature = "concurrent_hash_map", issue = "0")]
+    pub use super::hash::concurrent::*;
+}
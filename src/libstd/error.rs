@@ -50,6 +50,7 @@
 use any::TypeId;
 use boxed::Box;
 use char;
+use collections::CollectionAllocErr;
 use fmt::{self, Debug, Display};
 use marker::{Send, Sync, Reflect};
 use mem::transmute;
@@ -187,6 +188,16 @@ impl Error for string::ParseError {
     }
 }
 
+#[unstable(feature = "try_reserve", reason = "new API", issue = "0")]
+impl Error for CollectionAllocErr {
+    fn description(&self) -> &str {
+        match *self {
+            CollectionAllocErr::CapacityOverflow => "capacity overflow",
+            CollectionAllocErr::AllocErr => "memory allocation failed",
+        }
+    }
+}
+
 #[stable(feature = "decode_utf16", since = "1.9.0")]
 impl Error for char::DecodeUtf16Error {
     fn description(&self) -> &str {
@@ -21,6 +21,7 @@ use fmt;
 use ffi::OsString;
 use io::{self, SeekFrom, Seek, Read, Write};
 use path::{Path, PathBuf};
+use rand::{self, Rng};
 use sys::fs as fs_imp;
 use sys_common::{AsInnerMut, FromInner, AsInner, IntoInner};
 use vec::Vec;
@@ -151,6 +152,67 @@ pub struct DirBuilder {
     recursive: bool,
 }
 
+/// Options which can be used to configure how a directory tree is walked.
+///
+/// This builder is used to create a `WalkDir` iterator, as `walk_dir` does
+/// with the default options: symbolic links aren't followed, and there is no
+/// limit on depth.
+#[unstable(feature = "fs_walk_dir", issue = "0")]
+#[derive(Clone, Debug)]
+pub struct WalkOptions {
+    follow_links: bool,
+    max_depth: usize,
+}
+
+/// Iterator over the entries of a directory and, recursively, of all of its
+/// subdirectories.
+///
+/// This iterator is returned from the `walk_dir` function of this module
+/// and the `walk` method of `WalkOptions`. It walks the tree depth first,
+/// yielding a directory before its contents, and yields `io::Result<DirEntry>`
+/// items like `ReadDir`. An error reading a directory is yielded right after
+/// the directory, and the walk then carries on with its siblings.
+#[unstable(feature = "fs_walk_dir", issue = "0")]
+pub struct WalkDir {
+    // The directories being read, with their canonical paths if symbolic
+    // links are followed, which is what finding loops takes.
+    stack: Vec<(ReadDir, Option<PathBuf>)>,
+    follow_links: bool,
+    max_depth: usize,
+    depth: usize,
+    // Whether the entry yielded last is a directory being walked, which is
+    // on top of `stack` unless entering it failed with the error in
+    // `pending`.
+    descended: bool,
+    pending: Option<io::Error>,
+}
+
+/// Options which can be used to configure what `copy` carries over from a
+/// file besides its contents.
+///
+/// Generally speaking, when using `CopyOptions`, you'll first call `new()`,
+/// then chain calls to methods to set each option, then call `copy()`,
+/// passing the paths of the files to copy. With the default options, this
+/// is the same as the `copy` function of this module.
+///
+/// # Examples
+///
+/// Copying a file along with its timestamps and extended attributes:
+///
+/// ```no_run
+/// #![feature(fs_copy_options)]
+///
+/// use std::fs::CopyOptions;
+///
+/// # fn foo() -> std::io::Result<()> {
+/// try!(CopyOptions::new().timestamps(true).xattrs(true).copy("foo.rlib", "bar.rlib"));
+/// # Ok(())
+/// # }
+/// ```
+#[unstable(feature = "fs_copy_options", issue = "0")]
+#[derive(Clone, Debug)]
+pub struct CopyOptions(fs_imp::CopyOptions);
+
 impl File {
     /// Attempts to open a file in read-only mode.
     ///
@@ -308,6 +370,85 @@ impl File {
             inner: self.inner.duplicate()?
         })
     }
+
+    /// Acquires an exclusive lock on the file, blocking until no other
+    /// process holds a lock on it.
+    ///
+    /// A file has at most one exclusive lock, or any number of shared locks,
+    /// at a time. The lock belongs to this handle and handles cloned from
+    /// it, and is released by `unlock` or when they have all been closed.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with `LOCK_EX`, and to the `LockFileEx` function on Windows with
+    /// `LOCKFILE_EXCLUSIVE_LOCK`. Locks are advisory on Unix: they only keep
+    /// out other processes which lock the file too. On Windows they are
+    /// mandatory, so other processes can't read or write a locked file.
+    ///
+    /// Solaris has no `flock`, so `fcntl` locks of the whole file are used
+    /// there instead. These belong to the process rather than to the handle:
+    /// they never conflict with other locks of the same process, closing any
+    /// handle to the file releases them, and exclusive locks need the file
+    /// to be opened for writing (shared ones for reading).
+    /// Note that, this [may change in the future][changes].
+    /// [changes]: ../io/index.html#platform-specific-behavior
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    ///
+    /// use std::fs::File;
+    ///
+    /// # fn foo() -> std::io::Result<()> {
+    /// let f = try!(File::create("foo.lock"));
+    /// try!(f.lock_exclusive());
+    /// // ... nobody else holds a lock on foo.lock here ...
+    /// try!(f.unlock());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "0")]
+    pub fn lock_exclusive(&self) -> io::Result<()> {
+        self.inner.lock(true)
+    }
+
+    /// Acquires a shared lock on the file, blocking until no other process
+    /// holds an exclusive lock on it.
+    ///
+    /// See `lock_exclusive` for details.
+    #[unstable(feature = "file_lock", issue = "0")]
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.inner.lock(false)
+    }
+
+    /// Attempts to acquire an exclusive lock on the file without blocking.
+    ///
+    /// Returns `Ok(false)` if another process holds a lock on the file.
+    ///
+    /// See `lock_exclusive` for details.
+    #[unstable(feature = "file_lock", issue = "0")]
+    pub fn try_lock_exclusive(&self) -> io::Result<bool> {
+        self.inner.try_lock(true)
+    }
+
+    /// Attempts to acquire a shared lock on the file without blocking.
+    ///
+    /// Returns `Ok(false)` if another process holds an exclusive lock on the
+    /// file.
+    ///
+    /// See `lock_exclusive` for details.
+    #[unstable(feature = "file_lock", issue = "0")]
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.inner.try_lock(false)
+    }
+
+    /// Releases the lock this handle holds on the file.
+    #[unstable(feature = "file_lock", issue = "0")]
+    pub fn unlock(&self) -> io::Result<()> {
+        self.inner.unlock()
+    }
 }
 
 impl AsInner<fs_imp::File> for File {
@@ -1017,11 +1158,16 @@ pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()>
 ///
 /// On success, the total number of bytes copied is returned.
 ///
+/// `CopyOptions` can be used to carry over more than the permission bits.
+///
 /// # Platform-specific behavior
 ///
 /// This function currently corresponds to the `open` function in Unix
 /// with `O_RDONLY` for `from` and `O_WRONLY`, `O_CREAT`, and `O_TRUNC` for `to`.
-/// `O_CLOEXEC` is set for returned file descriptors.
+/// `O_CLOEXEC` is set for returned file descriptors. On Linux, the data is
+/// copied with `copy_file_range` or `sendfile` when the kernel supports them.
+/// If `from` has holes, they are left in `to` too, and the length of the
+/// file is returned.
 /// On Windows, this function currently corresponds to `CopyFileEx`.
 /// Note that, this [may change in the future][changes].
 /// [changes]: ../io/index.html#platform-specific-behavior
@@ -1047,7 +1193,139 @@ pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()>
 /// ```
 #[stable(feature = "rust1", since = "1.0.0")]
 pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<u64> {
-    fs_imp::copy(from.as_ref(), to.as_ref())
+    CopyOptions::new().copy(from, to)
+}
+
+impl CopyOptions {
+    /// Creates a blank new set of options ready for configuration.
+    ///
+    /// The permissions of the file are copied by default, and its
+    /// timestamps and extended attributes aren't.
+    #[unstable(feature = "fs_copy_options", issue = "0")]
+    pub fn new() -> CopyOptions {
+        CopyOptions(fs_imp::CopyOptions::new())
+    }
+
+    /// Sets the option for copying the permission bits of the file.
+    #[unstable(feature = "fs_copy_options", issue = "0")]
+    pub fn permissions(&mut self, permissions: bool) -> &mut CopyOptions {
+        self.0.permissions(permissions); self
+    }
+
+    /// Sets the option for copying the last access and modification times
+    /// of the file.
+    #[unstable(feature = "fs_copy_options", issue = "0")]
+    pub fn timestamps(&mut self, timestamps: bool) -> &mut CopyOptions {
+        self.0.timestamps(timestamps); self
+    }
+
+    /// Sets the option for copying the extended attributes of the file.
+    ///
+    /// Attributes the current process isn't allowed to set, such as those
+    /// outside of the `user` namespace on Linux for unprivileged processes,
+    /// are skipped.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This option is currently supported on Linux and OS X; elsewhere on
+    /// Unix, `copy` returns an error if it is set.
+    #[unstable(feature = "fs_copy_options", issue = "0")]
+    pub fn xattrs(&mut self, xattrs: bool) -> &mut CopyOptions {
+        self.0.xattrs(xattrs); self
+    }
+
+    /// Copies the contents of the file at `from` to `to`, along with what
+    /// these options say, as the `copy` function of this module does.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// On Windows, `CopyFileEx` always copies the attributes, the
+    /// modification time and the alternate data streams of the file, and
+    /// these options have no effect.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the same situations as `copy`,
+    /// and if any of what is to be copied besides the contents can't be.
+    #[unstable(feature = "fs_copy_options", issue = "0")]
+    pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> io::Result<u64> {
+        fs_imp::copy(from.as_ref(), to.as_ref(), &self.0)
+    }
+}
+
+/// Replaces the contents of a file atomically.
+///
+/// The contents are written to a new file in the same directory, flushed to
+/// disk and then renamed over `path`. Whatever happens, a reader opening
+/// `path` sees either its old contents or all of the new ones, never a mix
+/// or a partially written file. If `path` exists, its permissions are kept.
+///
+/// # Errors
+///
+/// This function will return an error in the following situations, but is
+/// not limited to just these cases:
+///
+/// * The directory of `path` does not exist.
+/// * The current process lacks permissions to create files in it.
+///
+/// If an error occurs, `path` is left as it was and the new file removed.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(fs_write_atomic)]
+///
+/// use std::fs;
+///
+/// # fn foo() -> std::io::Result<()> {
+/// try!(fs::write_atomic("config.toml", b"answer = 42\n"));
+/// # Ok(())
+/// # }
+/// ```
+#[unstable(feature = "fs_write_atomic", issue = "0")]
+pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C)
+                                                    -> io::Result<()> {
+    let path = path.as_ref();
+    let file_name = match path.file_name() {
+        Some(name) => name,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "path has no file name")),
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    // Find a name nobody else is using; `create_new` makes sure of it.
+    let tmp;
+    let mut file;
+    loop {
+        let mut name = OsString::from(".");
+        name.push(file_name);
+        name.push(format!(".{:x}.tmp", rand::thread_rng().gen::<u32>()));
+        let candidate = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&candidate) {
+            Ok(f) => {
+                tmp = candidate;
+                file = f;
+                break
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let result = file.write_all(contents.as_ref()).and_then(|()| {
+        if let Ok(metadata) = metadata(path) {
+            set_permissions(&tmp, metadata.permissions())?;
+        }
+        file.sync_all()
+    });
+    drop(file);
+    match result.and_then(|()| rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = remove_file(&tmp);
+            Err(e)
+        }
+    }
 }
 
 /// Creates a new hard link on the filesystem.
@@ -1356,6 +1634,206 @@ pub fn read_dir<P: AsRef<Path>>(path: P) -> io::Result<ReadDir> {
     fs_imp::readdir(path.as_ref()).map(ReadDir)
 }
 
+/// Returns an iterator over the entries within a directory and, recursively,
+/// all of its subdirectories.
+///
+/// This is `WalkOptions::new().walk(path)`: symbolic links are yielded but
+/// not followed. See `WalkDir` for the order of the entries.
+///
+/// # Errors
+///
+/// This function will return an error in the same situations as `read_dir`.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(fs_walk_dir)]
+///
+/// use std::fs;
+///
+/// # fn foo() -> std::io::Result<()> {
+/// for entry in try!(fs::walk_dir(".")) {
+///     let entry = try!(entry);
+///     if try!(entry.file_type()).is_file() {
+///         println!("{}", entry.path().display());
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[unstable(feature = "fs_walk_dir", issue = "0")]
+pub fn walk_dir<P: AsRef<Path>>(path: P) -> io::Result<WalkDir> {
+    WalkOptions::new().walk(path)
+}
+
+impl WalkOptions {
+    /// Creates a blank new set of options, which don't follow symbolic links
+    /// and don't limit the depth of the walk.
+    #[unstable(feature = "fs_walk_dir", issue = "0")]
+    pub fn new() -> WalkOptions {
+        WalkOptions {
+            follow_links: false,
+            max_depth: usize::max_value(),
+        }
+    }
+
+    /// Sets the option for following symbolic links to directories.
+    ///
+    /// When followed, the contents of the directory a link points to are
+    /// walked as if it were a directory itself. A link pointing to one of
+    /// the directories it is in would make the walk endless, so instead an
+    /// error is yielded after the link.
+    #[unstable(feature = "fs_walk_dir", issue = "0")]
+    pub fn follow_links(&mut self, follow_links: bool) -> &mut WalkOptions {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// Sets the maximum depth of the entries yielded, the entries of the
+    /// directory being walked having a depth of 1.
+    ///
+    /// A depth of 1 makes the walk equivalent to `read_dir`.
+    #[unstable(feature = "fs_walk_dir", issue = "0")]
+    pub fn max_depth(&mut self, max_depth: usize) -> &mut WalkOptions {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Starts walking the directory at `path` with these options.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the same situations as
+    /// `read_dir`.
+    #[unstable(feature = "fs_walk_dir", issue = "0")]
+    pub fn walk<P: AsRef<Path>>(&self, path: P) -> io::Result<WalkDir> {
+        let path = path.as_ref();
+        let canonical = if self.follow_links {
+            Some(canonicalize(path)?)
+        } else {
+            None
+        };
+        let mut stack = Vec::new();
+        if self.max_depth > 0 {
+            stack.push((read_dir(path)?, canonical));
+        }
+        Ok(WalkDir {
+            stack: stack,
+            follow_links: self.follow_links,
+            max_depth: self.max_depth,
+            depth: 0,
+            descended: false,
+            pending: None,
+        })
+    }
+}
+
+impl WalkDir {
+    /// Returns the depth of the entry yielded last, the entries of the
+    /// directory being walked having a depth of 1.
+    #[unstable(feature = "fs_walk_dir", issue = "0")]
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Skips the contents of the entry yielded last, if it is a directory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(fs_walk_dir)]
+    ///
+    /// use std::fs;
+    ///
+    /// # fn foo() -> std::io::Result<()> {
+    /// // Lists everything but what's in hidden directories.
+    /// let mut walk = try!(fs::walk_dir("."));
+    /// while let Some(entry) = walk.next() {
+    ///     let entry = try!(entry);
+    ///     if entry.file_name().to_string_lossy().starts_with(".") {
+    ///         walk.skip_dir();
+    ///     } else {
+    ///         println!("{}", entry.path().display());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[unstable(feature = "fs_walk_dir", issue = "0")]
+    pub fn skip_dir(&mut self) {
+        if self.descended {
+            // If entering the directory failed, the error is moot now.
+            if self.pending.take().is_none() {
+                self.stack.pop();
+            }
+            self.descended = false;
+        }
+    }
+
+    fn descend(&mut self, entry: &DirEntry) -> io::Result<()> {
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_symlink() {
+            // A dangling link isn't an error, just not a directory.
+            if !self.follow_links ||
+               !metadata(&path).map(|m| m.is_dir()).unwrap_or(false) {
+                return Ok(())
+            }
+        } else if !file_type.is_dir() {
+            return Ok(())
+        }
+
+        self.descended = true;
+        let canonical = if self.follow_links {
+            let canonical = canonicalize(&path)?;
+            if self.stack.iter().any(|&(_, ref dir)| dir.as_ref() == Some(&canonical)) {
+                return Err(io::Error::new(io::ErrorKind::Other,
+                                          format!("file system loop: `{}` points to \
+                                                   one of its ancestors",
+                                                  path.display())))
+            }
+            Some(canonical)
+        } else {
+            None
+        };
+        self.stack.push((read_dir(&path)?, canonical));
+        Ok(())
+    }
+}
+
+#[unstable(feature = "fs_walk_dir", issue = "0")]
+impl Iterator for WalkDir {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<io::Result<DirEntry>> {
+        self.descended = false;
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e))
+        }
+        loop {
+            let next = match self.stack.last_mut() {
+                Some(top) => top.0.next(),
+                None => return None,
+            };
+            let entry = match next {
+                Some(Ok(entry)) => entry,
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.stack.pop();
+                    continue
+                }
+            };
+            self.depth = self.stack.len();
+            if self.depth < self.max_depth {
+                if let Err(e) = self.descend(&entry) {
+                    self.pending = Some(e);
+                }
+            }
+            return Some(Ok(entry))
+        }
+    }
+}
+
 /// Changes the permissions found on a file or a directory.
 ///
 /// # Platform-specific behavior
@@ -2464,4 +2942,238 @@ mod tests {
             check!(b.created());
         }
     }
+
+    // The paths of the entries of a walk, relative to `root` and sorted.
+    fn walked(root: &Path, walk: fs::WalkDir) -> Vec<String> {
+        let mut v = walk.map(|e| {
+            let path = check!(e).path();
+            path.strip_prefix(root).unwrap().to_str().unwrap().replace("\\", "/")
+        }).collect::<Vec<_>>();
+        v.sort();
+        v
+    }
+
+    #[test]
+    fn walk_dir() {
+        let tmpdir = tmpdir();
+        let root = tmpdir.join("root");
+        check!(fs::create_dir_all(&root.join("a/b")));
+        check!(fs::create_dir_all(&root.join("c")));
+        check!(File::create(&root.join("a/b/f")));
+        check!(File::create(&root.join("g")));
+
+        assert_eq!(walked(&root, check!(fs::walk_dir(&root))),
+                   ["a", "a/b", "a/b/f", "c", "g"]);
+        assert_eq!(walked(&root, check!(fs::WalkOptions::new().max_depth(1).walk(&root))),
+                   ["a", "c", "g"]);
+        assert!(check!(fs::WalkOptions::new().max_depth(0).walk(&root)).next().is_none());
+        error!(fs::walk_dir(&root.join("missing")), "");
+    }
+
+    #[test]
+    fn walk_dir_order_and_depth() {
+        let tmpdir = tmpdir();
+        check!(fs::create_dir_all(&tmpdir.join("a/b")));
+        check!(File::create(&tmpdir.join("a/b/f")));
+
+        let mut walk = check!(fs::walk_dir(tmpdir.path()));
+        let mut seen = Vec::new();
+        while let Some(entry) = walk.next() {
+            seen.push((check!(entry).file_name().into_string().unwrap(), walk.depth()));
+        }
+        assert_eq!(seen, [("a".to_string(), 1), ("b".to_string(), 2), ("f".to_string(), 3)]);
+    }
+
+    #[test]
+    fn walk_dir_skip_dir() {
+        let tmpdir = tmpdir();
+        check!(fs::create_dir_all(&tmpdir.join("skip/inner")));
+        check!(fs::create_dir_all(&tmpdir.join("keep/inner")));
+
+        let mut walk = check!(fs::walk_dir(tmpdir.path()));
+        let mut seen = Vec::new();
+        while let Some(entry) = walk.next() {
+            let entry = check!(entry);
+            if entry.file_name() == *"skip" {
+                walk.skip_dir();
+            }
+            seen.push(entry.path());
+        }
+        seen.sort();
+        assert_eq!(seen, [tmpdir.join("keep"), tmpdir.join("keep/inner"), tmpdir.join("skip")]);
+    }
+
+    #[test]
+    fn walk_dir_links() {
+        let tmpdir = tmpdir();
+        if !got_symlink_permission(&tmpdir) { return };
+        check!(fs::create_dir_all(&tmpdir.join("root/d")));
+        // Walks that follow links start from the canonical root.
+        let root = check!(fs::canonicalize(&tmpdir.join("root")));
+        check!(fs::create_dir_all(&tmpdir.join("target")));
+        check!(File::create(&tmpdir.join("target/t")));
+        check!(symlink_dir(&tmpdir.join("target"), &root.join("link")));
+
+        assert_eq!(walked(&root, check!(fs::walk_dir(&root))), ["d", "link"]);
+        assert_eq!(walked(&root, check!(fs::WalkOptions::new().follow_links(true).walk(&root))),
+                   ["d", "link", "link/t"]);
+
+        // A link back up the tree is reported rather than followed forever.
+        check!(symlink_dir(&root, &root.join("d/up")));
+        let results = check!(fs::WalkOptions::new().follow_links(true).walk(&root))
+                          .collect::<Vec<_>>();
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+        assert_eq!(results.len(), 5);
+    }
+
+    // Locks of the same process never conflict on Solaris.
+    #[test]
+    #[cfg(not(target_os = "solaris"))]
+    fn file_lock() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("lock");
+        let a = check!(File::create(&path));
+        let b = check!(File::open(&path));
+
+        check!(a.lock_exclusive());
+        assert!(!check!(b.try_lock_shared()));
+        assert!(!check!(b.try_lock_exclusive()));
+        check!(a.unlock());
+
+        check!(a.lock_shared());
+        assert!(check!(b.try_lock_shared()));
+        check!(b.unlock());
+        assert!(!check!(b.try_lock_exclusive()));
+        drop(a);
+        assert!(check!(b.try_lock_exclusive()));
+    }
+
+    #[test]
+    fn copy_large_file() {
+        let tmpdir = tmpdir();
+        let input = tmpdir.join("in");
+        let out = tmpdir.join("out");
+
+        let contents = (0..3 << 20).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        check!(check!(File::create(&input)).write_all(&contents));
+        assert_eq!(check!(fs::copy(&input, &out)), contents.len() as u64);
+        let mut v = Vec::new();
+        check!(check!(File::open(&out)).read_to_end(&mut v));
+        assert!(v == contents);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn copy_procfs_file() {
+        // Claims to be empty, but isn't.
+        let tmpdir = tmpdir();
+        let out = tmpdir.join("out");
+        let mut contents = String::new();
+        check!(check!(File::open("/proc/version")).read_to_string(&mut contents));
+        assert!(!contents.is_empty());
+        assert_eq!(check!(fs::copy("/proc/version", &out)), contents.len() as u64);
+        let mut copied = String::new();
+        check!(check!(File::open(&out)).read_to_string(&mut copied));
+        assert_eq!(copied, contents);
+    }
+
+    #[test]
+    fn copy_sparse_file() {
+        let tmpdir = tmpdir();
+        let input = tmpdir.join("in");
+        let out = tmpdir.join("out");
+
+        // Data between two holes.
+        let len = 16 << 20;
+        let mut f = check!(File::create(&input));
+        check!(f.set_len(len));
+        check!(f.seek(SeekFrom::Start(8 << 20)));
+        check!(f.write_all(b"data"));
+        drop(f);
+
+        assert_eq!(check!(fs::copy(&input, &out)), len);
+        let mut v = Vec::new();
+        check!(check!(File::open(&out)).read_to_end(&mut v));
+        assert_eq!(v.len() as u64, len);
+        assert_eq!(&v[8 << 20..(8 << 20) + 4], b"data");
+        assert!(v[..8 << 20].iter().chain(&v[(8 << 20) + 4..]).all(|&b| b == 0));
+        check_holes(&input, &out, len);
+
+        // Only file systems with holes keep them.
+        #[cfg(unix)]
+        fn check_holes(input: &Path, out: &Path, len: u64) {
+            use os::unix::fs::MetadataExt;
+            if check!(fs::metadata(input)).blocks() * 512 < len {
+                assert!(check!(fs::metadata(out)).blocks() * 512 < len);
+            }
+        }
+        #[cfg(windows)]
+        fn check_holes(_input: &Path, _out: &Path, _len: u64) {}
+    }
+
+    #[test]
+    fn copy_options_timestamps() {
+        use time::{SystemTime, UNIX_EPOCH};
+
+        // Where `futimens` is missing, `futimes` only sets microseconds.
+        fn micros(time: SystemTime) -> (u64, u32) {
+            let since = time.duration_since(UNIX_EPOCH).unwrap();
+            (since.as_secs(), since.subsec_nanos() / 1000)
+        }
+
+        let tmpdir = tmpdir();
+        let input = tmpdir.join("in.txt");
+        check!(check!(File::create(&input)).write_all(b"hello"));
+        let mtime = check!(check!(fs::metadata(&input)).modified());
+
+        let out = tmpdir.join("timestamps.txt");
+        assert_eq!(check!(fs::CopyOptions::new().timestamps(true).copy(&input, &out)), 5);
+        assert_eq!(micros(check!(check!(fs::metadata(&out)).modified())), micros(mtime));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn copy_options_permissions() {
+        use os::unix::fs::PermissionsExt;
+
+        let tmpdir = tmpdir();
+        let input = tmpdir.join("in");
+        let out = tmpdir.join("out");
+        check!(File::create(&input));
+        check!(fs::set_permissions(&input, fs::Permissions::from_mode(0o700)));
+
+        check!(fs::CopyOptions::new().permissions(false).copy(&input, &out));
+        assert_eq!(check!(fs::metadata(&out)).permissions().mode() & 0o100, 0);
+        check!(fs::CopyOptions::new().copy(&input, &out));
+        assert_eq!(check!(fs::metadata(&out)).permissions().mode() & 0o777, 0o700);
+    }
+
+    #[test]
+    fn write_atomic() {
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("file");
+
+        check!(fs::write_atomic(&path, b"first"));
+        check!(fs::write_atomic(&path, "second"));
+        let mut s = String::new();
+        check!(check!(File::open(&path)).read_to_string(&mut s));
+        assert_eq!(s, "second");
+
+        // Nothing is left behind besides the file.
+        assert_eq!(check!(fs::read_dir(tmpdir.path())).count(), 1);
+        error!(fs::write_atomic(&tmpdir.join("missing/file"), b""), "");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn write_atomic_keeps_permissions() {
+        use os::unix::fs::PermissionsExt;
+
+        let tmpdir = tmpdir();
+        let path = tmpdir.join("file");
+        check!(File::create(&path));
+        check!(fs::set_permissions(&path, fs::Permissions::from_mode(0o600)));
+        check!(fs::write_atomic(&path, b"contents"));
+        assert_eq!(check!(fs::metadata(&path)).permissions().mode() & 0o777, 0o600);
+    }
 }
@@ -0,0 +1,123 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Memory allocation APIs.
+//!
+//! A binary crate can replace the allocator used by `Box`, `Vec` and the
+//! rest of the standard library by declaring a `static` whose type
+//! implements [`GlobalAlloc`] and tagging it with `#[global_allocator]`.
+//! [`System`] is the allocator provided by the operating system and is a
+//! convenient base for allocators that only want to observe allocations.
+//!
+//! Collections such as `Vec` can also be given an allocator of their own
+//! through the unstable [`Alloc`] trait; [`Heap`] is the default.
+//!
+//! [`GlobalAlloc`]: trait.GlobalAlloc.html
+//! [`System`]: struct.System.html
+//! [`Alloc`]: trait.Alloc.html
+//! [`Heap`]: struct.Heap.html
+//!
+//! # Examples
+//!
+//! Counting every allocation made by the program:
+//!
+//! ```rust,ignore
+//! use std::heap::{GlobalAlloc, System};
+//! use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+//!
+//! struct Counting;
+//!
+//! static ALLOCATIONS: AtomicUsize = ATOMIC_USIZE_INIT;
+//!
+//! unsafe impl GlobalAlloc for Counting {
+//!     unsafe fn alloc(&self, size: usize, align: usize) -> *mut u8 {
+//!         ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+//!         System.alloc(size, align)
+//!     }
+//!
+//!     unsafe fn dealloc(&self, ptr: *mut u8, size: usize, align: usize) {
+//!         System.dealloc(ptr, size, align)
+//!     }
+//! }
+//!
+//! #[global_allocator]
+//! static GLOBAL: Counting = Counting;
+//!
+//! fn main() {
+//!     let before = ALLOCATIONS.load(Ordering::SeqCst);
+//!     let _b = Box::new(5);
+//!     assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), before + 1);
+//! }
+//! ```
+
+#![stable(feature = "global_allocator", since = "1.10.0")]
+
+use sys::alloc as imp;
+
+#[stable(feature = "global_allocator", since = "1.10.0")]
+pub use alloc::heap::GlobalAlloc;
+#[unstable(feature = "allocator_api", issue = "0")]
+pub use alloc::heap::{Alloc, Heap};
+
+/// The default memory allocator provided by the operating system.
+///
+/// This is `malloc` and friends on Unix and the process heap on Windows. It
+/// does not depend on the allocator the program itself was linked with, so
+/// a `#[global_allocator]` may forward to it.
+#[derive(Copy, Clone, Default, Debug)]
+#[stable(feature = "global_allocator", since = "1.10.0")]
+pub struct System;
+
+#[stable(feature = "global_allocator", since = "1.10.0")]
+unsafe impl GlobalAlloc for System {
+    unsafe fn alloc(&self, size: usize, align: usize) -> *mut u8 {
+        imp::allocate(size, align)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, size: usize, align: usize) {
+        imp::deallocate(ptr, size, align)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, size: usize, new_size: usize, align: usize)
+                      -> *mut u8 {
+        imp::reallocate(ptr, size, new_size, align)
+    }
+
+    unsafe fn realloc_in_place(&self, ptr: *mut u8, size: usize, new_size: usize,
+                               align: usize) -> usize {
+        imp::reallocate_inplace(ptr, size, new_size, align)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GlobalAlloc, System};
+
+    #[test]
+    fn system_alloc_roundtrip() {
+        unsafe {
+            for &align in &[1, 8, 64, 4096] {
+                let ptr = System.alloc(100, align);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % align, 0);
+                *ptr = 7;
+                *ptr.offset(99) = 9;
+
+                let ptr = System.realloc(ptr, 100, 1000, align);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % align, 0);
+                assert_eq!(*ptr, 7);
+                assert_eq!(*ptr.offset(99), 9);
+
+                System.dealloc(ptr, 1000, align);
+            }
+        }
+    }
+}
@@ -17,7 +17,7 @@ use marker::Reflect;
 use cmp;
 use error;
 use fmt;
-use io::{self, DEFAULT_BUF_SIZE, Error, ErrorKind, SeekFrom};
+use io::{self, DEFAULT_BUF_SIZE, Error, ErrorKind, IoSlice, SeekFrom};
 use memchr;
 
 /// The `BufReader` struct adds buffering to any reader.
@@ -473,6 +473,21 @@ impl<W: Write> Write for BufWriter<W> {
             Write::write(&mut self.buf, &buf[..amt])
         }
     }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        let total_len = bufs.iter().fold(0, |a: usize, b| a.saturating_add(b.len()));
+        if total_len > self.buf.capacity() - self.buf.len() {
+            self.flush_buf()?;
+        }
+        if total_len >= self.buf.capacity() {
+            self.panicked = true;
+            let r = self.inner.as_mut().unwrap().write_vectored(bufs);
+            self.panicked = false;
+            r
+        } else {
+            Write::write_vectored(&mut self.buf, bufs)
+        }
+    }
     fn flush(&mut self) -> io::Result<()> {
         self.flush_buf().and_then(|()| self.get_mut().flush())
     }
@@ -790,7 +805,7 @@ impl<W: Write> fmt::Debug for LineWriter<W> where W: fmt::Debug {
 mod tests {
     use prelude::v1::*;
     use io::prelude::*;
-    use io::{self, BufReader, BufWriter, LineWriter, SeekFrom};
+    use io::{self, BufReader, BufWriter, IoSlice, LineWriter, SeekFrom};
     use sync::atomic::{AtomicUsize, Ordering};
     use thread;
     use test;
@@ -908,6 +923,39 @@ mod tests {
         assert_eq!(reader.get_ref().pos, expected);
     }
 
+    #[test]
+    fn test_buffered_writer_vectored() {
+        struct Recorder {
+            data: Vec<u8>,
+            calls: usize,
+        }
+
+        impl Write for Recorder {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.calls += 1;
+                self.data.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+                self.calls += 1;
+                self.data.write_vectored(bufs)
+            }
+            fn flush(&mut self) -> io::Result<()> { Ok(()) }
+        }
+
+        let mut writer = BufWriter::with_capacity(4, Recorder { data: Vec::new(), calls: 0 });
+        assert_eq!(writer.write_vectored(&[IoSlice::new(&[0]), IoSlice::new(&[1, 2])]).unwrap(),
+                   3);
+        assert_eq!(writer.get_ref().calls, 0);
+
+        // Too big for the buffer: flush what is buffered, then hand the
+        // buffers to the inner writer in one call.
+        assert_eq!(writer.write_vectored(&[IoSlice::new(&[3, 4]), IoSlice::new(&[5, 6])]).unwrap(),
+                   4);
+        assert_eq!(writer.get_ref().calls, 2);
+        assert_eq!(writer.get_ref().data, [0, 1, 2, 3, 4, 5, 6]);
+    }
+
     #[test]
     fn test_buffered_writer() {
         let inner = Vec::new();
@@ -12,7 +12,7 @@ use prelude::v1::*;
 use io::prelude::*;
 
 use cmp;
-use io::{self, SeekFrom, Error, ErrorKind};
+use io::{self, SeekFrom, Error, ErrorKind, IoSlice, IoSliceMut};
 
 /// A `Cursor` wraps another type and provides it with a
 /// [`Seek`](trait.Seek.html) implementation.
@@ -217,6 +217,18 @@ impl<T> Read for Cursor<T> where T: AsRef<[u8]> {
         self.pos += n as u64;
         Ok(n)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        let mut nread = 0;
+        for buf in bufs {
+            let n = self.read(buf)?;
+            nread += n;
+            if n < buf.len() {
+                break;
+            }
+        }
+        Ok(nread)
+    }
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
@@ -228,6 +240,19 @@ impl<T> BufRead for Cursor<T> where T: AsRef<[u8]> {
     fn consume(&mut self, amt: usize) { self.pos += amt as u64; }
 }
 
+// Writes each buffer in turn, stopping at the first short write.
+fn write_vectored<W: Write>(w: &mut W, bufs: &[IoSlice]) -> io::Result<usize> {
+    let mut nwritten = 0;
+    for buf in bufs {
+        let n = w.write(buf)?;
+        nwritten += n;
+        if n < buf.len() {
+            break;
+        }
+    }
+    Ok(nwritten)
+}
+
 #[stable(feature = "rust1", since = "1.0.0")]
 impl<'a> Write for Cursor<&'a mut [u8]> {
     fn write(&mut self, data: &[u8]) -> io::Result<usize> {
@@ -236,6 +261,9 @@ impl<'a> Write for Cursor<&'a mut [u8]> {
         self.pos += amt as u64;
         Ok(amt)
     }
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        write_vectored(self, bufs)
+    }
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 }
 
@@ -264,6 +292,9 @@ impl Write for Cursor<Vec<u8>> {
         self.set_position(pos + buf.len() as u64);
         Ok(buf.len())
     }
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        write_vectored(self, bufs)
+    }
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 }
 
@@ -275,13 +306,16 @@ impl Write for Cursor<Box<[u8]>> {
         self.pos += amt as u64;
         Ok(amt)
     }
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        write_vectored(self, bufs)
+    }
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 }
 
 #[cfg(test)]
 mod tests {
     use io::prelude::*;
-    use io::{Cursor, SeekFrom};
+    use io::{Cursor, IoSlice, IoSliceMut, SeekFrom};
     use vec::Vec;
 
     #[test]
@@ -294,6 +328,31 @@ mod tests {
         assert_eq!(writer, b);
     }
 
+    #[test]
+    fn test_vectored() {
+        let mut writer = Cursor::new(vec![0u8; 3]);
+        writer.set_position(1);
+        let n = writer.write_vectored(&[IoSlice::new(&[1, 2]), IoSlice::new(&[]),
+                                        IoSlice::new(&[3])]).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(writer.get_ref(), &[0, 1, 2, 3]);
+
+        let mut buf = [0; 3];
+        let mut writer = Cursor::new(&mut buf[..]);
+        let n = writer.write_vectored(&[IoSlice::new(&[1, 2]), IoSlice::new(&[3, 4])]).unwrap();
+        assert_eq!(n, 3);
+        assert_eq!(writer.position(), 3);
+
+        let mut reader = Cursor::new(vec![1, 2, 3, 4, 5]);
+        let (mut a, mut b) = ([0; 2], [0; 4]);
+        let n = reader.read_vectored(&mut [IoSliceMut::new(&mut a),
+                                           IoSliceMut::new(&mut b)]).unwrap();
+        assert_eq!(n, 5);
+        assert_eq!(a, [1, 2]);
+        assert_eq!(b, [3, 4, 5, 0]);
+        assert_eq!(reader.read_vectored(&mut [IoSliceMut::new(&mut a)]).unwrap(), 0);
+    }
+
     #[test]
     fn test_mem_writer() {
         let mut writer = Cursor::new(Vec::new());
@@ -254,13 +254,15 @@ use rustc_unicode::str as core_str;
 use error as std_error;
 use fmt;
 use iter::{Iterator};
-use marker::Sized;
-use ops::{Drop, FnOnce};
+use marker::{Send, Sized, Sync};
+use ops::{Deref, DerefMut, Drop, FnOnce};
 use option::Option::{self, Some, None};
 use result::Result::{Ok, Err};
 use result;
 use string::String;
 use str;
+use sys;
+use sys_common::io as sys_io;
 use vec::Vec;
 use memchr;
 
@@ -477,6 +479,40 @@ pub trait Read {
     #[stable(feature = "rust1", since = "1.0.0")]
     fn read(&mut self, buf: &mut [u8]) -> Result<usize>;
 
+    /// Like `read`, except that it reads into a sequence of buffers, filling
+    /// each one before moving on to the next.
+    ///
+    /// Data is copied to fill each buffer in order, with the final buffer
+    /// written to possibly being only partially filled. This method must
+    /// behave as a single call to `read` with the buffers concatenated.
+    ///
+    /// The default implementation calls `read` with the first non-empty
+    /// buffer. Readers backed by a file descriptor use `readv` on Unix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(iovec)]
+    ///
+    /// use std::io::{self, Cursor, IoSliceMut, Read};
+    ///
+    /// # fn foo() -> io::Result<()> {
+    /// let mut header = [0; 2];
+    /// let mut body = [0; 4];
+    /// let mut r = Cursor::new(&b"hiyall"[..]);
+    /// let n = try!(r.read_vectored(&mut [IoSliceMut::new(&mut header),
+    ///                                    IoSliceMut::new(&mut body)]));
+    /// assert_eq!(n, 6);
+    /// assert_eq!(&header, b"hi");
+    /// assert_eq!(&body, b"yall");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[unstable(feature = "iovec", issue = "0")]
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        sys_io::default_read_vectored(|b| self.read(b), bufs)
+    }
+
     /// Read all bytes until EOF in this source, placing them into `buf`.
     ///
     /// All bytes read from this source will be appended to the specified buffer
@@ -885,6 +921,36 @@ pub trait Write {
     #[stable(feature = "rust1", since = "1.0.0")]
     fn write(&mut self, buf: &[u8]) -> Result<usize>;
 
+    /// Like `write`, except that it writes from a sequence of buffers.
+    ///
+    /// Data is copied from each buffer in order, with the final buffer read
+    /// from possibly being only partially consumed. This method must behave
+    /// as a call to `write` with the buffers concatenated would, so a
+    /// framed message can be sent with a single system call and no copy.
+    ///
+    /// The default implementation calls `write` with the first non-empty
+    /// buffer. Writers backed by a file descriptor use `writev` on Unix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(iovec)]
+    ///
+    /// use std::io::{self, IoSlice, Write};
+    ///
+    /// # fn foo() -> io::Result<()> {
+    /// let mut out = Vec::new();
+    /// let n = try!(out.write_vectored(&[IoSlice::new(b"head"), IoSlice::new(b"body")]));
+    /// assert_eq!(n, 8);
+    /// assert_eq!(out, b"headbody");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[unstable(feature = "iovec", issue = "0")]
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        sys_io::default_write_vectored(|b| self.write(b), bufs)
+    }
+
     /// Flush this output stream, ensuring that all intermediately buffered
     /// contents reach their destination.
     ///
@@ -1047,6 +1113,92 @@ pub trait Write {
     fn by_ref(&mut self) -> &mut Self where Self: Sized { self }
 }
 
+/// A buffer type used with `Write::write_vectored`.
+///
+/// It is semantically a wrapper around an `&[u8]`, but is guaranteed to be
+/// ABI compatible with the `iovec` type on Unix platforms, so a slice of
+/// them can be passed to the operating system without copying.
+#[unstable(feature = "iovec", issue = "0")]
+pub struct IoSlice<'a>(sys::io::IoSlice<'a>);
+
+#[unstable(feature = "iovec", issue = "0")]
+unsafe impl<'a> Send for IoSlice<'a> {}
+#[unstable(feature = "iovec", issue = "0")]
+unsafe impl<'a> Sync for IoSlice<'a> {}
+
+#[unstable(feature = "iovec", issue = "0")]
+impl<'a> IoSlice<'a> {
+    /// Creates a new `IoSlice` wrapping a byte slice.
+    #[inline]
+    pub fn new(buf: &'a [u8]) -> IoSlice<'a> {
+        IoSlice(sys::io::IoSlice::new(buf))
+    }
+}
+
+#[unstable(feature = "iovec", issue = "0")]
+impl<'a> Deref for IoSlice<'a> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+#[unstable(feature = "iovec", issue = "0")]
+impl<'a> fmt::Debug for IoSlice<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.0.as_slice(), f)
+    }
+}
+
+/// A buffer type used with `Read::read_vectored`.
+///
+/// It is semantically a wrapper around an `&mut [u8]`, but is guaranteed to
+/// be ABI compatible with the `iovec` type on Unix platforms, so a slice of
+/// them can be passed to the operating system without copying.
+#[unstable(feature = "iovec", issue = "0")]
+pub struct IoSliceMut<'a>(sys::io::IoSliceMut<'a>);
+
+#[unstable(feature = "iovec", issue = "0")]
+unsafe impl<'a> Send for IoSliceMut<'a> {}
+#[unstable(feature = "iovec", issue = "0")]
+unsafe impl<'a> Sync for IoSliceMut<'a> {}
+
+#[unstable(feature = "iovec", issue = "0")]
+impl<'a> IoSliceMut<'a> {
+    /// Creates a new `IoSliceMut` wrapping a byte slice.
+    #[inline]
+    pub fn new(buf: &'a mut [u8]) -> IoSliceMut<'a> {
+        IoSliceMut(sys::io::IoSliceMut::new(buf))
+    }
+}
+
+#[unstable(feature = "iovec", issue = "0")]
+impl<'a> Deref for IoSliceMut<'a> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+#[unstable(feature = "iovec", issue = "0")]
+impl<'a> DerefMut for IoSliceMut<'a> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.0.as_mut_slice()
+    }
+}
+
+#[unstable(feature = "iovec", issue = "0")]
+impl<'a> fmt::Debug for IoSliceMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.0.as_slice(), f)
+    }
+}
+
 /// The `Seek` trait provides a cursor which can be moved within a stream of
 /// bytes.
 ///
@@ -1439,6 +1591,16 @@ impl<T: Read, U: Read> Read for Chain<T, U> {
         }
         self.second.read(buf)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> Result<usize> {
+        if !self.done_first {
+            match self.first.read_vectored(bufs)? {
+                0 => { self.done_first = true; }
+                n => return Ok(n),
+            }
+        }
+        self.second.read_vectored(bufs)
+    }
 }
 
 #[stable(feature = "chain_bufread", since = "1.9.0")]
@@ -211,6 +211,7 @@
        test(attr(allow(dead_code, deprecated, unused_variables, unused_mut))))]
 
 #![feature(alloc)]
+#![feature(allocator_api)]
 #![feature(allow_internal_unstable)]
 #![feature(asm)]
 #![feature(associated_consts)]
@@ -262,6 +263,7 @@
 #![feature(str_utf16)]
 #![feature(test, rustc_private)]
 #![feature(thread_local)]
+#![feature(try_reserve)]
 #![feature(unboxed_closures)]
 #![feature(unicode)]
 #![feature(unique)]
@@ -422,6 +424,7 @@ pub mod collections;
 pub mod env;
 pub mod ffi;
 pub mod fs;
+pub mod heap;
 pub mod io;
 pub mod net;
 pub mod os;
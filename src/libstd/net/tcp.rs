@@ -12,7 +12,7 @@ use prelude::v1::*;
 use io::prelude::*;
 
 use fmt;
-use io;
+use io::{self, IoSlice, IoSliceMut};
 use net::{ToSocketAddrs, SocketAddr, Shutdown};
 use sys_common::net as net_imp;
 use sys_common::{AsInner, FromInner, IntoInner};
@@ -246,6 +246,9 @@ impl TcpStream {
 #[stable(feature = "rust1", since = "1.0.0")]
 impl Read for TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
     fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
         self.0.read_to_end(buf)
     }
@@ -253,11 +256,17 @@ impl Read for TcpStream {
 #[stable(feature = "rust1", since = "1.0.0")]
 impl Write for TcpStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.write(buf) }
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 }
 #[stable(feature = "rust1", since = "1.0.0")]
 impl<'a> Read for &'a TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
     fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
         self.0.read_to_end(buf)
     }
@@ -265,6 +274,9 @@ impl<'a> Read for &'a TcpStream {
 #[stable(feature = "rust1", since = "1.0.0")]
 impl<'a> Write for &'a TcpStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.write(buf) }
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 }
 
@@ -435,7 +447,7 @@ impl fmt::Debug for TcpListener {
 mod tests {
     use prelude::v1::*;
 
-    use io::ErrorKind;
+    use io::{ErrorKind, IoSlice, IoSliceMut};
     use io::prelude::*;
     use net::*;
     use net::test::{next_test_ip4, next_test_ip6};
@@ -556,6 +568,33 @@ mod tests {
         })
     }
 
+    #[test]
+    fn vectored() {
+        each_ip(&mut |addr| {
+            let acceptor = t!(TcpListener::bind(&addr));
+
+            let t = thread::spawn(move|| {
+                let mut stream = t!(TcpStream::connect(&addr));
+                let bufs = [IoSlice::new(&[1, 2]), IoSlice::new(&[]), IoSlice::new(&[3])];
+                let n = t!(stream.write_vectored(&bufs));
+                assert!(n == 3 || cfg!(windows));
+            });
+
+            let mut stream = t!(acceptor.accept()).0;
+            t.join().unwrap();
+            let mut a = [0; 1];
+            let mut b = [0; 2];
+            let n = t!(stream.read_vectored(&mut [IoSliceMut::new(&mut a),
+                                                  IoSliceMut::new(&mut b)]));
+            // Windows falls back to the first non-empty buffer on both ends.
+            assert!(n == 3 || cfg!(windows));
+            assert_eq!(a, [1]);
+            if n == 3 {
+                assert_eq!(b, [2, 3]);
+            }
+        })
+    }
+
     #[test]
     fn write_close() {
         each_ip(&mut |addr| {
@@ -10,10 +10,18 @@
 
 #![stable(feature = "metadata_ext", since = "1.1.0")]
 
-use libc;
+use prelude::v1::*;
 
-use fs::Metadata;
+use libc::{self, c_int};
+
+use collections::{HashMap, VecDeque};
+use fs::{self, Metadata};
+use io;
+use os::unix::io::{AsRawFd, RawFd};
+use path::{Path, PathBuf};
+use sys::inotify::{self, Inotify};
 use sys_common::AsInner;
+use time::{Duration, Instant};
 
 #[allow(deprecated)]
 use os::linux::raw;
@@ -126,3 +134,641 @@ impl MetadataExt for Metadata {
         self.as_inner().as_inner().st_blocks as u64
     }
 }
+
+/// A watcher of changes to files and directories, backed by inotify.
+///
+/// Paths are added to the watcher with `watch`, and the changes to them, or
+/// to the entries of the directories among them, are then reported as
+/// `Event`s by `next_event` and the iterator returned by `events`. The paths
+/// in the events start with the path given to `watch`.
+///
+/// Events the kernel reports for the same file before they are returned
+/// are coalesced: a file being written to repeatedly is only reported as
+/// modified once, a file created and written to is only reported as
+/// created, and one created and removed isn't reported at all. With
+/// `set_coalesce_delay`, the watcher also waits for a while after a change
+/// for what follows before reporting it, which is what a build tool reacting
+/// to edits usually wants.
+///
+/// To receive events over a channel instead, move the watcher to a thread of
+/// its own which sends what it returns.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(fs_watch)]
+///
+/// use std::os::linux::fs::Watcher;
+/// use std::time::Duration;
+///
+/// # fn foo() -> std::io::Result<()> {
+/// let mut watcher = try!(Watcher::new());
+/// watcher.set_coalesce_delay(Some(Duration::from_millis(100)));
+/// try!(watcher.watch("src", true));
+/// for event in watcher.events() {
+///     println!("{:?}", try!(event));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[unstable(feature = "fs_watch", issue = "0")]
+pub struct Watcher {
+    inotify: Inotify,
+    watches: HashMap<c_int, Watch>,
+    delay: Option<Duration>,
+    // Events read from the kernel but not returned yet, which new ones are
+    // coalesced with.
+    queue: VecDeque<Event>,
+}
+
+// A watched directory or file.
+struct Watch {
+    path: PathBuf,
+    // Whether the path was given to `watch`, rather than found below one.
+    root: bool,
+    recursive: bool,
+}
+
+/// A change to a watched file or directory.
+#[unstable(feature = "fs_watch", issue = "0")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A file or directory was created, or moved in from outside of what is
+    /// watched.
+    Create(PathBuf),
+    /// The contents of a file were modified.
+    Modify(PathBuf),
+    /// The metadata of a file or directory, such as its permissions or
+    /// timestamps, changed.
+    Metadata(PathBuf),
+    /// A file or directory was removed, or moved out of what is watched.
+    Remove(PathBuf),
+    /// A file or directory was renamed from the first path to the second.
+    Rename(PathBuf, PathBuf),
+    /// The kernel dropped events because too many were waiting to be read,
+    /// so anything may have changed.
+    Overflow,
+}
+
+/// An iterator over the events of a `Watcher`.
+///
+/// This struct is created by the `events` method on `Watcher`. It waits for
+/// events forever, and so never returns `None`.
+#[unstable(feature = "fs_watch", issue = "0")]
+pub struct Events<'a> {
+    watcher: &'a mut Watcher,
+}
+
+// The changes reported for every watch.
+const EVENTS: u32 = inotify::IN_CREATE | inotify::IN_MODIFY | inotify::IN_ATTRIB |
+                    inotify::IN_DELETE | inotify::IN_MOVED_FROM | inotify::IN_MOVED_TO |
+                    inotify::IN_DELETE_SELF | inotify::IN_MOVE_SELF;
+
+impl Watcher {
+    /// Creates a watcher, which watches nothing yet.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the process or the user has too many inotify instances open.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn new() -> io::Result<Watcher> {
+        Ok(Watcher {
+            inotify: Inotify::new()?,
+            watches: HashMap::new(),
+            delay: None,
+            queue: VecDeque::new(),
+        })
+    }
+
+    /// Starts watching the file or directory at `path`.
+    ///
+    /// For a directory, changes to its entries are reported, and if
+    /// `recursive` is set, changes to the entries of all of the directories
+    /// below it, including those created later, too. The removal or renaming
+    /// of `path` itself is reported as it being removed, and stops the
+    /// watch.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `path` doesn't exist, or if there are more directories to
+    /// watch than the inotify limit for the user allows, which can be raised
+    /// through `/proc/sys/fs/inotify/max_user_watches`.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P, recursive: bool) -> io::Result<()> {
+        let path = path.as_ref();
+        self.add(path, true, recursive)?;
+        if recursive && fs::metadata(path)?.is_dir() {
+            self.add_below(path, false)?;
+        }
+        Ok(())
+    }
+
+    /// Stops watching `path`, which must have been given to `watch`.
+    ///
+    /// Events for it that were already read from the kernel may still be
+    /// returned.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn unwatch<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let recursive = match self.watches.values().find(|w| w.root && w.path == path) {
+            Some(watch) => watch.recursive,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "path is not being watched")),
+        };
+        let wds = self.watches.iter().filter(|&(_, w)| {
+            if w.path == path {
+                w.root
+            } else {
+                recursive && !w.root && w.path.starts_with(path)
+            }
+        }).map(|(&wd, _)| wd).collect::<Vec<_>>();
+        self.remove(&wds)
+    }
+
+    /// Sets how long to wait after a change for more changes to coalesce
+    /// with it before reporting it.
+    ///
+    /// With `None`, the default, a change is reported as soon as it is read
+    /// from the kernel, along with those read at the same time. A delay too
+    /// long to be represented as an `Instant` is treated like `None`.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn set_coalesce_delay(&mut self, delay: Option<Duration>) {
+        self.delay = delay;
+    }
+
+    /// Returns the coalescing delay of this watcher.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn coalesce_delay(&self) -> Option<Duration> {
+        self.delay
+    }
+
+    /// Waits for the next event.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn next_event(&mut self) -> io::Result<Event> {
+        loop {
+            if let Some(event) = self.next_event_before(None)? {
+                return Ok(event)
+            }
+        }
+    }
+
+    /// Waits for the next event for at most `timeout`, returning `None` if
+    /// there was none by then.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn next_event_timeout(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        // A timeout too long to be represented is as good as none.
+        self.next_event_before(Instant::now().checked_add(timeout))
+    }
+
+    /// Returns an iterator over the events of this watcher, which waits for
+    /// each of them.
+    #[unstable(feature = "fs_watch", issue = "0")]
+    pub fn events(&mut self) -> Events {
+        Events { watcher: self }
+    }
+
+    fn next_event_before(&mut self, deadline: Option<Instant>) -> io::Result<Option<Event>> {
+        while self.queue.is_empty() {
+            let timeout = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Ok(None)
+                    }
+                    Some(deadline.duration_since(now))
+                }
+                None => None,
+            };
+            let events = self.inotify.read(timeout)?;
+            self.process(events)?;
+
+            // Gather what follows closely, so as to coalesce it. A delay too
+            // long to be represented would never end, so it means no delay.
+            if self.queue.is_empty() {
+                continue
+            }
+            let end = match self.delay.and_then(|delay| Instant::now().checked_add(delay)) {
+                Some(end) => end,
+                None => continue,
+            };
+            loop {
+                let now = Instant::now();
+                if now >= end {
+                    break
+                }
+                let events = self.inotify.read(Some(end.duration_since(now)))?;
+                self.process(events)?;
+            }
+        }
+        Ok(self.queue.pop_front())
+    }
+
+    // Turns the events from the kernel into `Event`s.
+    fn process(&mut self, events: Vec<inotify::Event>) -> io::Result<()> {
+        // The `IN_MOVED_TO` events paired with an `IN_MOVED_FROM` before.
+        let mut paired = vec![false; events.len()];
+        for (i, event) in events.iter().enumerate() {
+            if event.mask & inotify::IN_Q_OVERFLOW != 0 {
+                self.push(Event::Overflow);
+                continue
+            }
+            if event.mask & inotify::IN_IGNORED != 0 {
+                self.watches.remove(&event.wd);
+                continue
+            }
+            let (path, root, recursive) = match self.watches.get(&event.wd) {
+                Some(watch) => {
+                    let path = match event.name {
+                        Some(ref name) => watch.path.join(name),
+                        None => watch.path.clone(),
+                    };
+                    (path, watch.root, watch.recursive)
+                }
+                // Watched no longer.
+                None => continue,
+            };
+            let is_dir = event.mask & inotify::IN_ISDIR != 0;
+
+            if event.name.is_none() {
+                // Changes to a directory found below a watched one are
+                // reported by the watch of its parent too, so it's only the
+                // roots that this is news about.
+                if !root {
+                    continue
+                }
+                if event.mask & (inotify::IN_DELETE_SELF | inotify::IN_MOVE_SELF) != 0 {
+                    // The paths of a root moved elsewhere are meaningless.
+                    self.unwatch(&path)?;
+                    self.push(Event::Remove(path));
+                    continue
+                }
+            }
+
+            if event.mask & inotify::IN_CREATE != 0 {
+                self.push(Event::Create(path.clone()));
+                if is_dir && recursive {
+                    self.add_new_dir(&path)?;
+                }
+            } else if event.mask & inotify::IN_MODIFY != 0 {
+                self.push(Event::Modify(path));
+            } else if event.mask & inotify::IN_ATTRIB != 0 {
+                self.push(Event::Metadata(path));
+            } else if event.mask & inotify::IN_DELETE != 0 {
+                self.push(Event::Remove(path));
+            } else if event.mask & inotify::IN_MOVED_FROM != 0 {
+                // Renames within what is watched come as an `IN_MOVED_TO`
+                // with the same cookie right after.
+                let to = events.iter().enumerate().skip(i + 1).find(|&(_, e)| {
+                    e.mask & inotify::IN_MOVED_TO != 0 && e.cookie == event.cookie
+                }).and_then(|(j, e)| {
+                    let watch = match self.watches.get(&e.wd) {
+                        Some(watch) => watch,
+                        None => return None,
+                    };
+                    e.name.as_ref().map(|name| (j, watch.path.join(name)))
+                });
+                match to {
+                    Some((j, to)) => {
+                        paired[j] = true;
+                        if is_dir {
+                            self.rename_below(&path, &to);
+                        }
+                        self.push(Event::Rename(path, to));
+                    }
+                    None => {
+                        if is_dir {
+                            self.remove_below(&path)?;
+                        }
+                        self.push(Event::Remove(path));
+                    }
+                }
+            } else if event.mask & inotify::IN_MOVED_TO != 0 && !paired[i] {
+                self.push(Event::Create(path.clone()));
+                if is_dir && recursive {
+                    self.add_new_dir(&path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Queues `event`, coalescing it with the events for the same path that
+    // are queued already.
+    fn push(&mut self, event: Event) {
+        let coalesced = match event {
+            Event::Create(ref path) => self.queued(|e| *e == Event::Create(path.clone())),
+            Event::Modify(ref path) | Event::Metadata(ref path) => {
+                let create = Event::Create(path.clone());
+                self.queued(|e| *e == create || *e == event)
+            }
+            Event::Remove(ref path) => {
+                // Whatever happened to the file since it was created is moot
+                // now, and so is the creation if there was one.
+                let created = self.queued(|e| *e == Event::Create(path.clone()));
+                let mut i = self.coalescable();
+                while i < self.queue.len() {
+                    let moot = match self.queue[i] {
+                        Event::Create(ref p) | Event::Modify(ref p) |
+                        Event::Metadata(ref p) => p == path,
+                        _ => false,
+                    };
+                    if moot {
+                        self.queue.remove(i);
+                    } else {
+                        i += 1;
+                    }
+                }
+                created
+            }
+            Event::Rename(..) | Event::Overflow => false,
+        };
+        if !coalesced {
+            self.queue.push_back(event);
+        }
+    }
+
+    // Whether an event matching `f` is queued since the last removal or
+    // renaming.
+    fn queued<F: Fn(&Event) -> bool>(&self, f: F) -> bool {
+        self.queue.iter().skip(self.coalescable()).any(|e| f(e))
+    }
+
+    // The index of the first queued event after the last removal, renaming
+    // or overflow, which coalescing must not reach past.
+    fn coalescable(&self) -> usize {
+        self.queue.iter().rposition(|e| match *e {
+            Event::Remove(..) | Event::Rename(..) | Event::Overflow => true,
+            _ => false,
+        }).map_or(0, |i| i + 1)
+    }
+
+    fn add(&mut self, path: &Path, root: bool, recursive: bool) -> io::Result<()> {
+        let mask = if root {
+            EVENTS
+        } else {
+            EVENTS | inotify::IN_ONLYDIR | inotify::IN_DONT_FOLLOW
+        };
+        let wd = self.inotify.add_watch(path, mask)?;
+        // Watching the same directory twice gives the same descriptor.
+        let watch = self.watches.entry(wd).or_insert(Watch {
+            path: PathBuf::new(),
+            root: false,
+            recursive: false,
+        });
+        watch.path = path.to_path_buf();
+        watch.root = watch.root || root;
+        watch.recursive = watch.recursive || recursive;
+        Ok(())
+    }
+
+    // Watches a directory created below a recursively watched one, and what
+    // is already in it, which may have been created before it could be
+    // watched.
+    fn add_new_dir(&mut self, path: &Path) -> io::Result<()> {
+        match self.add(path, false, true) {
+            Ok(()) => self.add_below(path, true),
+            // It's gone already.
+            Err(ref e) if gone(e) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Watches the directories below `path`, reporting what it finds as
+    // created if `announce` is set.
+    fn add_below(&mut self, path: &Path, announce: bool) -> io::Result<()> {
+        let mut walk = match fs::walk_dir(path) {
+            Ok(walk) => walk,
+            Err(ref e) if gone(e) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        while let Some(entry) = walk.next() {
+            // Anything may be removed while we look.
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(ref e) if gone(e) => continue,
+                Err(e) => return Err(e),
+            };
+            let path = entry.path();
+            if announce {
+                self.push(Event::Create(path.clone()));
+            }
+            match entry.file_type() {
+                Ok(ref t) if t.is_dir() => {}
+                _ => continue,
+            }
+            match self.add(&path, false, true) {
+                Ok(()) => {}
+                Err(ref e) if gone(e) => walk.skip_dir(),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    // Updates the paths of the watches below a directory that was renamed.
+    fn rename_below(&mut self, from: &Path, to: &Path) {
+        for watch in self.watches.values_mut() {
+            let path = match watch.path.strip_prefix(from) {
+                Ok(rest) if rest.as_os_str().is_empty() => to.to_path_buf(),
+                Ok(rest) => to.join(rest),
+                Err(_) => continue,
+            };
+            watch.path = path;
+        }
+    }
+
+    // Stops watching the directories below one that was moved out of what
+    // is watched, but not the watches of their own.
+    fn remove_below(&mut self, path: &Path) -> io::Result<()> {
+        let wds = self.watches.iter().filter(|&(_, w)| !w.root && w.path.starts_with(path))
+                                     .map(|(&wd, _)| wd)
+                                     .collect::<Vec<_>>();
+        self.remove(&wds)
+    }
+
+    fn remove(&mut self, wds: &[c_int]) -> io::Result<()> {
+        for wd in wds {
+            self.watches.remove(wd);
+            match self.inotify.rm_watch(*wd) {
+                Ok(()) => {}
+                // The kernel dropped it already, as the directory is gone.
+                Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+// Whether an error means that the file it is about doesn't exist anymore.
+fn gone(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::NotFound || e.raw_os_error() == Some(libc::ENOTDIR)
+}
+
+#[unstable(feature = "fs_watch", issue = "0")]
+impl AsRawFd for Watcher {
+    /// Returns the inotify file descriptor, which is readable when there
+    /// are events to read.
+    ///
+    /// Events that were read already may be waiting in the watcher even if
+    /// it isn't, so a watcher should be drained with `next_event_timeout`
+    /// and a zero timeout after the descriptor is polled.
+    fn as_raw_fd(&self) -> RawFd {
+        self.inotify.as_raw_fd()
+    }
+}
+
+#[unstable(feature = "fs_watch", issue = "0")]
+impl<'a> Iterator for Events<'a> {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<io::Result<Event>> {
+        Some(self.watcher.next_event())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use fs::{self, File};
+    use io::Write;
+    use path::PathBuf;
+    use sys_common::io::test::tmpdir;
+    use time::Duration;
+    use super::*;
+
+    macro_rules! check { ($e:expr) => (
+        match $e {
+            Ok(t) => t,
+            Err(e) => panic!("{} failed with: {}", stringify!($e), e),
+        }
+    ) }
+
+    // Reads events until there are none for a while.
+    fn drain(watcher: &mut Watcher) -> Vec<Event> {
+        let mut events = Vec::new();
+        while let Some(event) = check!(watcher.next_event_timeout(Duration::from_millis(500))) {
+            events.push(event);
+        }
+        events
+    }
+
+    #[test]
+    fn watch_dir() {
+        let tmpdir = tmpdir();
+        let mut watcher = check!(Watcher::new());
+        check!(watcher.watch(tmpdir.path(), false));
+
+        let path = tmpdir.join("f");
+        check!(File::create(&path));
+        assert_eq!(drain(&mut watcher), [Event::Create(path.clone())]);
+        check!(check!(File::create(&path)).write_all(b"data"));
+        assert_eq!(drain(&mut watcher), [Event::Modify(path.clone())]);
+        let to = tmpdir.join("g");
+        check!(fs::rename(&path, &to));
+        assert_eq!(drain(&mut watcher), [Event::Rename(path.clone(), to.clone())]);
+        check!(fs::remove_file(&to));
+        assert_eq!(drain(&mut watcher), [Event::Remove(to)]);
+    }
+
+    #[test]
+    fn watch_recursive() {
+        let tmpdir = tmpdir();
+        check!(fs::create_dir(&tmpdir.join("a")));
+        let mut watcher = check!(Watcher::new());
+        check!(watcher.watch(tmpdir.path(), true));
+
+        // Below a directory that was there already.
+        check!(File::create(&tmpdir.join("a/f")));
+        assert_eq!(drain(&mut watcher), [Event::Create(tmpdir.join("a/f"))]);
+
+        // Below a new one.
+        check!(fs::create_dir_all(&tmpdir.join("b/c")));
+        check!(File::create(&tmpdir.join("b/c/f")));
+        // What was created before the watch below it was added may be
+        // reported twice, by the kernel and by the watcher looking.
+        let mut created = drain(&mut watcher).into_iter().map(|e| match e {
+            Event::Create(path) => path,
+            e => panic!("unexpected event: {:?}", e),
+        }).collect::<Vec<_>>();
+        created.sort();
+        created.dedup();
+        assert_eq!(created, [tmpdir.join("b"), tmpdir.join("b/c"), tmpdir.join("b/c/f")]);
+
+        // Below a renamed one.
+        check!(fs::rename(&tmpdir.join("b"), &tmpdir.join("d")));
+        assert_eq!(drain(&mut watcher), [Event::Rename(tmpdir.join("b"), tmpdir.join("d"))]);
+        check!(fs::remove_file(&tmpdir.join("d/c/f")));
+        assert_eq!(drain(&mut watcher), [Event::Remove(tmpdir.join("d/c/f"))]);
+    }
+
+    #[test]
+    fn coalesce() {
+        let tmpdir = tmpdir();
+        let mut watcher = check!(Watcher::new());
+        watcher.set_coalesce_delay(Some(Duration::from_millis(200)));
+        check!(watcher.watch(tmpdir.path(), false));
+
+        let path = tmpdir.join("f");
+        let mut f = check!(File::create(&path));
+        for _ in 0..10 {
+            check!(f.write_all(b"data"));
+        }
+        assert_eq!(drain(&mut watcher), [Event::Create(path.clone())]);
+
+        check!(File::create(&tmpdir.join("tmp")));
+        check!(fs::remove_file(&tmpdir.join("tmp")));
+        assert!(drain(&mut watcher).is_empty());
+    }
+
+    #[test]
+    fn coalesce_stops_at_removal() {
+        let mut watcher = check!(Watcher::new());
+        let (a, b) = (PathBuf::from("a"), PathBuf::from("b"));
+        watcher.push(Event::Modify(a.clone()));
+        watcher.push(Event::Remove(b.clone()));
+        watcher.push(Event::Create(a.clone()));
+        watcher.push(Event::Remove(a.clone()));
+        // The modification came before `b` was removed, so it stays.
+        assert_eq!(watcher.queue.iter().cloned().collect::<Vec<_>>(),
+                   [Event::Modify(a), Event::Remove(b)]);
+    }
+
+    #[test]
+    fn huge_timeout() {
+        let tmpdir = tmpdir();
+        let mut watcher = check!(Watcher::new());
+        watcher.set_coalesce_delay(Some(Duration::from_millis(200)));
+        check!(watcher.watch(tmpdir.path(), false));
+
+        let path = tmpdir.join("f");
+        check!(File::create(&path));
+        let timeout = Duration::new(u64::max_value(), 0);
+        assert_eq!(check!(watcher.next_event_timeout(timeout)), Some(Event::Create(path)));
+    }
+
+    #[test]
+    fn huge_coalesce_delay() {
+        let tmpdir = tmpdir();
+        let mut watcher = check!(Watcher::new());
+        watcher.set_coalesce_delay(Some(Duration::from_secs(u64::max_value())));
+        check!(watcher.watch(tmpdir.path(), false));
+
+        let path = tmpdir.join("f");
+        check!(File::create(&path));
+        assert_eq!(check!(watcher.next_event()), Event::Create(path));
+    }
+
+    #[test]
+    fn watch_root_removed() {
+        let tmpdir = tmpdir();
+        let dir = tmpdir.join("dir");
+        check!(fs::create_dir(&dir));
+        let mut watcher = check!(Watcher::new());
+        check!(watcher.watch(&dir, true));
+        check!(fs::remove_dir(&dir));
+        assert_eq!(drain(&mut watcher), [Event::Remove(dir.clone())]);
+        assert!(watcher.unwatch(&dir).is_err());
+    }
+}
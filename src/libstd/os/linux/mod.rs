@@ -14,3 +14,4 @@
 
 pub mod raw;
 pub mod fs;
+pub mod thread;
@@ -0,0 +1,117 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Linux-specific extensions to primitives in the `std::thread` module.
+//!
+//! CPUs are identified by their number, starting at 0, as in
+//! `/proc/cpuinfo`. Only the first 1024 CPUs can be named.
+
+#![unstable(feature = "thread_affinity", issue = "0")]
+
+use prelude::v1::*;
+
+use io;
+use libc;
+use sys::thread as imp;
+use sys_common::AsInner;
+use thread::JoinHandle;
+
+/// Linux-specific extensions to threads, which apply to the thread of a
+/// `std::thread::JoinHandle`.
+pub trait ThreadExt {
+    /// Restricts the thread to running on the given CPUs.
+    ///
+    /// # Errors
+    ///
+    /// Fails if none of the CPUs are available to the process, or if a CPU
+    /// number is out of range.
+    fn set_affinity(&self, cpus: &[usize]) -> io::Result<()>;
+
+    /// Returns the CPUs the thread may run on.
+    fn affinity(&self) -> io::Result<Vec<usize>>;
+}
+
+impl<T> ThreadExt for JoinHandle<T> {
+    fn set_affinity(&self, cpus: &[usize]) -> io::Result<()> {
+        imp::set_affinity(self.as_inner().id(), cpus)
+    }
+
+    fn affinity(&self) -> io::Result<Vec<usize>> {
+        imp::affinity(self.as_inner().id())
+    }
+}
+
+/// Restricts the calling thread to running on the given CPUs.
+///
+/// Threads spawned afterwards by the calling thread inherit the restriction.
+///
+/// # Errors
+///
+/// Fails if none of the CPUs are available to the process, or if a CPU
+/// number is out of range.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(thread_affinity)]
+///
+/// use std::os::linux::thread;
+///
+/// // Pin the current thread to the first CPU it may run on.
+/// let cpus = thread::affinity().unwrap();
+/// thread::set_affinity(&cpus[..1]).unwrap();
+/// ```
+pub fn set_affinity(cpus: &[usize]) -> io::Result<()> {
+    imp::set_affinity(unsafe { libc::pthread_self() }, cpus)
+}
+
+/// Returns the CPUs the calling thread may run on.
+pub fn affinity() -> io::Result<Vec<usize>> {
+    imp::affinity(unsafe { libc::pthread_self() })
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use sync::mpsc::channel;
+    use thread;
+    use super::*;
+
+    #[test]
+    fn test_current_affinity() {
+        let cpus = affinity().unwrap();
+        assert!(!cpus.is_empty());
+        // Change it on a thread of its own so other tests aren't affected.
+        thread::spawn(move || {
+            set_affinity(&cpus[..1]).unwrap();
+            assert_eq!(affinity().unwrap(), &cpus[..1]);
+            set_affinity(&cpus).unwrap();
+            assert_eq!(affinity().unwrap(), cpus);
+        }).join().unwrap();
+    }
+
+    #[test]
+    fn test_join_handle_affinity() {
+        let cpus = affinity().unwrap();
+        let (tx, rx) = channel::<()>();
+        let handle = thread::spawn(move || rx.recv().unwrap());
+        handle.set_affinity(&cpus[..1]).unwrap();
+        assert_eq!(handle.affinity().unwrap(), &cpus[..1]);
+        tx.send(()).unwrap();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let err = set_affinity(&[100_000]).unwrap_err();
+        assert_eq!(err.kind(), ::io::ErrorKind::InvalidInput);
+    }
+}
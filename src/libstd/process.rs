@@ -23,6 +23,7 @@ use str;
 use sys::pipe::{read2, AnonPipe};
 use sys::process as imp;
 use sys_common::{AsInner, AsInnerMut, FromInner, IntoInner};
+use time::Duration;
 
 /// Representation of a running or exited child process.
 ///
@@ -76,6 +77,10 @@ impl AsInner<imp::Process> for Child {
     fn as_inner(&self) -> &imp::Process { &self.handle }
 }
 
+impl AsInnerMut<imp::Process> for Child {
+    fn as_inner_mut(&mut self) -> &mut imp::Process { &mut self.handle }
+}
+
 impl FromInner<(imp::Process, imp::StdioPipes)> for Child {
     fn from_inner((handle, io): (imp::Process, imp::StdioPipes)) -> Child {
         Child {
@@ -686,6 +691,70 @@ impl Child {
         self.handle.wait().map(ExitStatus)
     }
 
+    /// Attempts to collect the exit status of the child if it has already
+    /// exited.
+    ///
+    /// This function will not block the calling thread and will only
+    /// check whether the child process has exited or not. If the child has
+    /// exited then `Ok(Some(status))` is returned, and later calls to `wait`
+    /// or `try_wait` will return the same status. If the child is still
+    /// running then `Ok(None)` is returned.
+    ///
+    /// Unlike `wait`, this function does not close the child's stdin.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_try_wait)]
+    ///
+    /// use std::process::Command;
+    ///
+    /// let mut child = Command::new("ls").spawn().unwrap();
+    ///
+    /// match child.try_wait() {
+    ///     Ok(Some(status)) => println!("exited with: {}", status),
+    ///     Ok(None) => println!("child is still running"),
+    ///     Err(e) => println!("error attempting to wait: {}", e),
+    /// }
+    /// ```
+    #[unstable(feature = "process_try_wait", issue = "0")]
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        Ok(self.handle.try_wait()?.map(ExitStatus))
+    }
+
+    /// Waits for the child to exit for at most `dur`, returning its exit
+    /// status if it exited in time.
+    ///
+    /// `Ok(None)` is returned if the timeout elapsed before the child exited,
+    /// in which case the child is left running. As with `try_wait`, the
+    /// child's stdin is not closed; drop it first if the child may be
+    /// waiting on input.
+    ///
+    /// On Unix this polls the child's status with an increasing sleep
+    /// between attempts, so the call may return slightly after the child
+    /// exits.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_try_wait)]
+    ///
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    ///
+    /// let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+    ///
+    /// if child.wait_timeout(Duration::from_secs(1)).unwrap().is_none() {
+    ///     child.kill().unwrap();
+    ///     child.wait().unwrap();
+    /// }
+    /// ```
+    #[unstable(feature = "process_try_wait", issue = "0")]
+    pub fn wait_timeout(&mut self, dur: Duration)
+                        -> io::Result<Option<ExitStatus>> {
+        Ok(self.handle.wait_timeout(dur)?.map(ExitStatus))
+    }
+
     /// Simultaneously waits for the child to exit and collect all remaining
     /// output on the stdout/stderr handles, returning an `Output`
     /// instance.
@@ -772,6 +841,7 @@ mod tests {
     use io::ErrorKind;
     use str;
     use super::{Command, Output, Stdio};
+    use time::Duration;
 
     // FIXME(#10380) these tests should not all be ignored on android.
 
@@ -821,6 +891,95 @@ mod tests {
         }
     }
 
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn send_signal_reported_right() {
+        use os::unix::process::{ChildExt, ExitStatusExt};
+        use libc;
+
+        let mut p = Command::new("/bin/sh")
+                            .arg("-c").arg("read a")
+                            .stdin(Stdio::piped())
+                            .spawn().unwrap();
+        p.send_signal(libc::SIGTERM).unwrap();
+        assert_eq!(p.wait().unwrap().signal(), Some(libc::SIGTERM));
+        assert_eq!(p.send_signal(libc::SIGTERM).unwrap_err().kind(),
+                   ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn process_group_works() {
+        use os::unix::process::CommandExt;
+
+        let output = Command::new("/bin/sh")
+                             .arg("-c").arg("ps -o pgid= -p $$; echo $$")
+                             .process_group(0)
+                             .output().unwrap();
+        assert!(output.status.success());
+        let output = str::from_utf8(&output.stdout).unwrap();
+        let mut ids = output.split_whitespace();
+        assert_eq!(ids.next(), ids.next());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn inherit_fd_works() {
+        use fs::File;
+        use os::unix::prelude::*;
+        use libc;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let mut reader = unsafe { File::from_raw_fd(fds[0]) };
+        let writer = unsafe { File::from_raw_fd(fds[1]) };
+
+        let status = Command::new("/bin/sh")
+                             .arg("-c").arg("echo hello >&5")
+                             .inherit_fd(writer.as_raw_fd(), 5)
+                             .status().unwrap();
+        assert!(status.success());
+        drop(writer);
+
+        let mut s = String::new();
+        reader.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "hello\n");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn rlimit_works() {
+        use os::unix::prelude::*;
+        use libc;
+
+        let output = Command::new("/bin/sh")
+                             .arg("-c").arg("ulimit -n")
+                             .rlimit(libc::RLIMIT_NOFILE as i32, 100, 100)
+                             .output().unwrap();
+        assert!(output.status.success());
+        assert_eq!(str::from_utf8(&output.stdout).unwrap().trim(), "100");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn pre_exec_works() {
+        use io;
+        use os::unix::prelude::*;
+        use libc;
+
+        let status = unsafe {
+            Command::new("/bin/sh").arg("-c").arg("true")
+                    .pre_exec(|| Err(io::Error::from_raw_os_error(libc::EPERM)))
+                    .status()
+        };
+        assert_eq!(status.unwrap_err().raw_os_error(), Some(libc::EPERM));
+    }
+
     pub fn run_output(mut cmd: Command) -> String {
         let p = cmd.spawn();
         assert!(p.is_ok());
@@ -950,6 +1109,39 @@ mod tests {
         assert!(prog.wait().unwrap().code() == Some(1));
     }
 
+    #[test]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn test_try_wait() {
+        let mut prog = Command::new("false").spawn().unwrap();
+        let mut status = prog.try_wait().unwrap();
+        while status.is_none() {
+            ::thread::sleep(Duration::from_millis(10));
+            status = prog.try_wait().unwrap();
+        }
+        assert!(status.unwrap().code() == Some(1));
+        assert!(prog.try_wait().unwrap().unwrap().code() == Some(1));
+        assert!(prog.wait().unwrap().code() == Some(1));
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn test_wait_timeout() {
+        let mut prog = Command::new("sleep").arg("10").spawn().unwrap();
+        assert!(prog.try_wait().unwrap().is_none());
+        assert!(prog.wait_timeout(Duration::from_millis(50)).unwrap().is_none());
+        prog.kill().unwrap();
+        let status = prog.wait_timeout(Duration::from_secs(10)).unwrap();
+        assert!(!status.unwrap().success());
+    }
+
+    #[test]
+    #[cfg_attr(target_os = "android", ignore)]
+    fn test_wait_timeout_huge() {
+        let mut prog = Command::new("true").spawn().unwrap();
+        let status = prog.wait_timeout(Duration::new(u64::max_value(), 0)).unwrap();
+        assert!(status.unwrap().success());
+    }
+
     #[test]
     #[cfg_attr(target_os = "android", ignore)]
     fn test_wait_with_output_once() {
@@ -0,0 +1,460 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A bounded multi-producer, multi-consumer broadcast channel.
+//!
+//! Every message sent on a broadcast channel is delivered to every receiver
+//! which exists at the time it is sent. Receivers are created by cloning an
+//! existing receiver, and a clone starts out at the same position in the
+//! stream as the receiver it was cloned from.
+//!
+//! The channel holds at most `bound` messages which some receiver has yet to
+//! see. Once it is full, senders block until the slowest receiver catches up,
+//! so a receiver which stops receiving will eventually stall all senders;
+//! drop receivers which are no longer interested.
+//!
+//! Each receiver gets its own copy of a message, which is why `T` must be
+//! `Clone`. The last receiver to see a message gets the original.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::sync::mpsc::broadcast;
+//! use std::thread;
+//!
+//! let (tx, rx1) = broadcast::channel(16);
+//! let rx2 = rx1.clone();
+//!
+//! let t = thread::spawn(move || {
+//!     assert_eq!(rx2.recv(), Ok(1));
+//!     assert_eq!(rx2.recv(), Ok(2));
+//! });
+//!
+//! tx.send(1).unwrap();
+//! tx.send(2).unwrap();
+//! assert_eq!(rx1.recv(), Ok(1));
+//! assert_eq!(rx1.recv(), Ok(2));
+//! t.join().unwrap();
+//! ```
+
+#![stable(feature = "mpsc_broadcast", since = "1.10.0")]
+
+use prelude::v1::*;
+
+use cell::Cell;
+use collections::VecDeque;
+use fmt;
+use sync::{Arc, Condvar, Mutex, MutexGuard};
+use sync::mpsc::{SendError, TrySendError, RecvError, TryRecvError,
+                 RecvTimeoutError};
+use time::{Duration, Instant};
+
+/// The sending half of a broadcast channel. It can be cloned to send from
+/// several threads.
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+/// The receiving half of a broadcast channel. It can be cloned to add another
+/// receiver, which will see all messages this receiver has yet to see.
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+    // sequence number of the next message this receiver will see
+    pos: Cell<u64>,
+}
+
+struct Inner<T> {
+    state: Mutex<State<T>>,
+    // signalled when a message is sent, or the last sender goes away
+    readable: Condvar,
+    // signalled when the oldest message is retired, or the last receiver
+    // goes away
+    writable: Condvar,
+}
+
+struct State<T> {
+    // unretired messages, each with the number of receivers yet to see it
+    buf: VecDeque<(T, usize)>,
+    // sequence number of `buf[0]`
+    head: u64,
+    bound: usize,
+    senders: usize,
+    receivers: usize,
+}
+
+/// Creates a new broadcast channel which holds up to `bound` messages that
+/// have not yet been seen by every receiver, returning the sender and the
+/// first receiver.
+///
+/// # Panics
+///
+/// Panics if `bound` is 0.
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+pub fn channel<T: Clone>(bound: usize) -> (Sender<T>, Receiver<T>) {
+    assert!(bound > 0, "broadcast channels need room for at least one message");
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            buf: VecDeque::with_capacity(bound),
+            head: 0,
+            bound: bound,
+            senders: 1,
+            receivers: 1,
+        }),
+        readable: Condvar::new(),
+        writable: Condvar::new(),
+    });
+    (Sender { inner: inner.clone() }, Receiver { inner: inner, pos: Cell::new(0) })
+}
+
+impl<T> Inner<T> {
+    fn lock(&self) -> MutexGuard<State<T>> {
+        // No user code runs with the lock held except `T::clone` and drops,
+        // and a panic in those leaves the state consistent.
+        match self.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl<T> State<T> {
+    // Drops messages which every receiver has seen, returning whether any
+    // room was made.
+    fn retire(&mut self) -> bool {
+        let mut retired = false;
+        loop {
+            match self.buf.front() {
+                Some(&(_, 0)) => {}
+                _ => return retired,
+            }
+            self.buf.pop_front();
+            self.head += 1;
+            retired = true;
+        }
+    }
+}
+
+impl<T: Clone> Sender<T> {
+    /// Sends a value to every receiver, blocking while the channel is full.
+    ///
+    /// An error is returned, handing back the value, if there are no
+    /// receivers left.
+    #[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        let mut state = self.inner.lock();
+        while state.receivers > 0 && state.buf.len() == state.bound {
+            state = match self.inner.writable.wait(state) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        self.push(state, t).map_err(SendError)
+    }
+
+    /// Attempts to send a value to every receiver without blocking.
+    #[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+    pub fn try_send(&self, t: T) -> Result<(), TrySendError<T>> {
+        let state = self.inner.lock();
+        if state.receivers > 0 && state.buf.len() == state.bound {
+            return Err(TrySendError::Full(t))
+        }
+        self.push(state, t).map_err(TrySendError::Disconnected)
+    }
+
+    fn push(&self, mut state: MutexGuard<State<T>>, t: T) -> Result<(), T> {
+        if state.receivers == 0 {
+            return Err(t)
+        }
+        let receivers = state.receivers;
+        state.buf.push_back((t, receivers));
+        drop(state);
+        self.inner.readable.notify_all();
+        Ok(())
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Attempts to return the next message without blocking.
+    #[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let state = self.inner.lock();
+        match self.take(state) {
+            Ok(t) => Ok(t),
+            Err(true) => Err(TryRecvError::Disconnected),
+            Err(false) => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Blocks until the next message arrives, returning an error once there
+    /// are no messages left and all senders are gone.
+    #[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut state = self.inner.lock();
+        while state.senders > 0 && !self.has_message(&state) {
+            state = match self.inner.readable.wait(state) {
+                Ok(guard) => guard,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        self.take(state).map_err(|_| RecvError)
+    }
+
+    /// Like `recv`, but gives up once `timeout` has passed.
+    #[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+    pub fn recv_timeout(&self, timeout: Duration)
+                        -> Result<T, RecvTimeoutError> {
+        // A timeout too large to be represented as a deadline never expires.
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => return self.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let mut state = self.inner.lock();
+        while state.senders > 0 && !self.has_message(&state) {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(RecvTimeoutError::Timeout)
+            }
+            state = match self.inner.readable.wait_timeout(state, deadline - now) {
+                Ok((guard, _)) => guard,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+        match self.take(state) {
+            Ok(t) => Ok(t),
+            Err(_) => Err(RecvTimeoutError::Disconnected),
+        }
+    }
+
+    fn has_message(&self, state: &State<T>) -> bool {
+        self.pos.get() < state.head + state.buf.len() as u64
+    }
+
+    // Takes the next message, or returns whether the channel is disconnected.
+    fn take(&self, mut state: MutexGuard<State<T>>) -> Result<T, bool> {
+        if !self.has_message(&state) {
+            return Err(state.senders == 0)
+        }
+        let idx = (self.pos.get() - state.head) as usize;
+        self.pos.set(self.pos.get() + 1);
+        let t = {
+            let entry = &mut state.buf[idx];
+            entry.1 -= 1;
+            if entry.1 > 0 { Some(entry.0.clone()) } else { None }
+        };
+        let t = match t {
+            Some(t) => t,
+            // We were the last to see the oldest message, so take it.
+            None => {
+                assert_eq!(idx, 0);
+                let (t, _) = state.buf.pop_front().unwrap();
+                state.head += 1;
+                state.retire();
+                drop(state);
+                self.inner.writable.notify_all();
+                return Ok(t)
+            }
+        };
+        Ok(t)
+    }
+}
+
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        self.inner.lock().senders += 1;
+        Sender { inner: self.inner.clone() }
+    }
+}
+
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.inner.lock();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            self.inner.readable.notify_all();
+        }
+    }
+}
+
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        let mut state = self.inner.lock();
+        state.receivers += 1;
+        let skip = (self.pos.get() - state.head) as usize;
+        for entry in state.buf.iter_mut().skip(skip) {
+            entry.1 += 1;
+        }
+        Receiver { inner: self.inner.clone(), pos: Cell::new(self.pos.get()) }
+    }
+}
+
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.inner.lock();
+        state.receivers -= 1;
+        let skip = (self.pos.get() - state.head) as usize;
+        for entry in state.buf.iter_mut().skip(skip) {
+            entry.1 -= 1;
+        }
+        let retired = state.retire();
+        let last = state.receivers == 0;
+        drop(state);
+        if retired || last {
+            self.inner.writable.notify_all();
+        }
+    }
+}
+
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Sender {{ .. }}")
+    }
+}
+
+#[stable(feature = "mpsc_broadcast", since = "1.10.0")]
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Receiver {{ .. }}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use super::channel;
+    use sync::mpsc::{TryRecvError, TrySendError, RecvTimeoutError};
+    use thread;
+    use time::Duration;
+
+    #[test]
+    fn smoke() {
+        let (tx, rx1) = channel(4);
+        let rx2 = rx1.clone();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx1.recv(), Ok(1));
+        assert_eq!(rx2.recv(), Ok(1));
+        assert_eq!(rx2.recv(), Ok(2));
+        assert_eq!(rx1.recv(), Ok(2));
+        assert_eq!(rx1.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn clone_starts_at_same_position() {
+        let (tx, rx1) = channel(4);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(rx1.recv(), Ok(1));
+        let rx2 = rx1.clone();
+        assert_eq!(rx2.recv(), Ok(2));
+        assert_eq!(rx1.recv(), Ok(2));
+    }
+
+    #[test]
+    fn bounded() {
+        let (tx, rx1) = channel(2);
+        let rx2 = rx1.clone();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+
+        // Only the slowest receiver holds messages back.
+        assert_eq!(rx1.recv(), Ok(1));
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx2.recv(), Ok(1));
+        tx.try_send(3).unwrap();
+
+        // Dropping a lagging receiver frees up room as well.
+        drop(rx1);
+        assert_eq!(rx2.recv(), Ok(2));
+        assert_eq!(rx2.recv(), Ok(3));
+    }
+
+    #[test]
+    fn send_blocks_until_received() {
+        let (tx, rx) = channel(1);
+        tx.send(1).unwrap();
+        let t = thread::spawn(move || {
+            tx.send(2).unwrap();
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.recv(), Ok(2));
+        t.join().unwrap();
+    }
+
+    #[test]
+    fn disconnect() {
+        let (tx, rx) = channel(4);
+        tx.send(1).unwrap();
+        drop(tx);
+        assert_eq!(rx.recv(), Ok(1));
+        assert!(rx.recv().is_err());
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+        let (tx, rx) = channel(4);
+        drop(rx);
+        assert!(tx.send(1).is_err());
+    }
+
+    #[test]
+    fn recv_timeout() {
+        let (tx, rx) = channel::<i32>(4);
+        assert_eq!(rx.recv_timeout(Duration::from_millis(10)),
+                   Err(RecvTimeoutError::Timeout));
+        drop(tx);
+        assert_eq!(rx.recv_timeout(Duration::from_millis(10)),
+                   Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn recv_huge_timeout() {
+        let (tx, rx) = channel(4);
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            tx.send(1).unwrap();
+        });
+        assert_eq!(rx.recv_timeout(Duration::new(u64::max_value(), 0)), Ok(1));
+        t.join().unwrap();
+        assert_eq!(rx.recv_timeout(Duration::new(u64::max_value(), 0)),
+                   Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn stress() {
+        const AMT: i32 = 1000;
+        let (tx, rx) = channel(8);
+        let threads = (0..4).map(|_| {
+            let rx = rx.clone();
+            thread::spawn(move || {
+                for i in 0..AMT {
+                    assert_eq!(rx.recv(), Ok(i));
+                }
+                assert!(rx.recv().is_err());
+            })
+        }).collect::<Vec<_>>();
+        drop(rx);
+        for i in 0..AMT {
+            tx.send(i).unwrap();
+        }
+        drop(tx);
+        for t in threads {
+            t.join().unwrap();
+        }
+    }
+}
@@ -36,6 +36,10 @@
 //!    "rendezvous" channel where each sender atomically hands off a message to
 //!    a receiver.
 //!
+//! Waiting on several channels at once is done with a `Selector`, and a
+//! bounded channel delivering every message to several receivers is found in
+//! the `broadcast` module.
+//!
 //! ## Disconnection
 //!
 //! The send and receive operations on channels will all return a `Result`
@@ -271,9 +275,12 @@ use fmt;
 use mem;
 use cell::UnsafeCell;
 use marker::Reflect;
+use time::{Duration, Instant};
 
 #[unstable(feature = "mpsc_select", issue = "27800")]
 pub use self::select::{Select, Handle};
+#[stable(feature = "mpsc_selector", since = "1.10.0")]
+pub use self::selector::Selector;
 use self::select::StartResult;
 use self::select::StartResult::*;
 use self::blocking::SignalToken;
@@ -281,12 +288,15 @@ use self::blocking::SignalToken;
 mod blocking;
 mod oneshot;
 mod select;
+mod selector;
 mod shared;
 mod stream;
 mod sync;
 mod mpsc_queue;
 mod spsc_queue;
 
+pub mod broadcast;
+
 /// The receiving-half of Rust's channel type. This half can only be owned by
 /// one thread
 #[stable(feature = "rust1", since = "1.0.0")]
@@ -379,6 +389,22 @@ pub enum TryRecvError {
     Disconnected,
 }
 
+/// This enumeration is the list of possible errors that made `recv_timeout`
+/// or `recv_deadline` unable to return data when called.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+pub enum RecvTimeoutError {
+    /// This channel is currently empty, but the sender(s) have not yet
+    /// disconnected, so data may yet become available.
+    #[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+    Timeout,
+
+    /// This channel's sending half has become disconnected, and there will
+    /// never be any more data received on this channel
+    #[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+    Disconnected,
+}
+
 /// This enumeration is the list of the possible error outcomes for the
 /// `SyncSender::try_send` method.
 #[stable(feature = "rust1", since = "1.0.0")]
@@ -838,7 +864,7 @@ impl<T> Receiver<T> {
         loop {
             let new_port = match *unsafe { self.inner() } {
                 Flavor::Oneshot(ref p) => {
-                    match unsafe { (*p.get()).recv() } {
+                    match unsafe { (*p.get()).recv(None) } {
                         Ok(t) => return Ok(t),
                         Err(oneshot::Empty) => return unreachable!(),
                         Err(oneshot::Disconnected) => return Err(RecvError),
@@ -846,7 +872,7 @@ impl<T> Receiver<T> {
                     }
                 }
                 Flavor::Stream(ref p) => {
-                    match unsafe { (*p.get()).recv() } {
+                    match unsafe { (*p.get()).recv(None) } {
                         Ok(t) => return Ok(t),
                         Err(stream::Empty) => return unreachable!(),
                         Err(stream::Disconnected) => return Err(RecvError),
@@ -854,14 +880,18 @@ impl<T> Receiver<T> {
                     }
                 }
                 Flavor::Shared(ref p) => {
-                    match unsafe { (*p.get()).recv() } {
+                    match unsafe { (*p.get()).recv(None) } {
                         Ok(t) => return Ok(t),
                         Err(shared::Empty) => return unreachable!(),
                         Err(shared::Disconnected) => return Err(RecvError),
                     }
                 }
-                Flavor::Sync(ref p) => return unsafe {
-                    (*p.get()).recv().map_err(|()| RecvError)
+                Flavor::Sync(ref p) => {
+                    match unsafe { (*p.get()).recv(None) } {
+                        Ok(t) => return Ok(t),
+                        Err(sync::Empty) => return unreachable!(),
+                        Err(sync::Disconnected) => return Err(RecvError),
+                    }
                 }
             };
             unsafe {
@@ -870,6 +900,122 @@ impl<T> Receiver<T> {
         }
     }
 
+    /// Attempts to wait for a value on this receiver, returning an error if the
+    /// corresponding channel has hung up, or if it waits more than `timeout`.
+    ///
+    /// This function will always block the current thread if there is no data
+    /// available and it's possible for more data to be sent. Once a message is
+    /// sent to the corresponding `Sender`, then this receiver will wake up and
+    /// return that message.
+    ///
+    /// If the corresponding `Sender` has disconnected, or it disconnects while
+    /// this call is blocking, this call will wake up and return
+    /// `Err(RecvTimeoutError::Disconnected)`. If no message arrives within
+    /// `timeout`, `Err(RecvTimeoutError::Timeout)` is returned instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(mpsc_recv_timeout)]
+    ///
+    /// use std::sync::mpsc::{self, RecvTimeoutError};
+    /// use std::time::Duration;
+    ///
+    /// let (send, recv) = mpsc::channel::<()>();
+    ///
+    /// let timeout = Duration::from_millis(100);
+    /// assert_eq!(Err(RecvTimeoutError::Timeout), recv.recv_timeout(timeout));
+    /// ```
+    #[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        // Do an optimistic try_recv to avoid the performance impact of
+        // Instant::now() in the full-channel case.
+        match self.try_recv() {
+            Ok(result) => Ok(result),
+            Err(TryRecvError::Disconnected) => Err(RecvTimeoutError::Disconnected),
+            Err(TryRecvError::Empty) => {
+                // A timeout too large to be represented as a deadline never
+                // expires.
+                match Instant::now().checked_add(timeout) {
+                    Some(deadline) => self.recv_deadline(deadline),
+                    None => self.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                }
+            }
+        }
+    }
+
+    /// Attempts to wait for a value on this receiver, returning an error if the
+    /// corresponding channel has hung up, or if `deadline` is reached.
+    ///
+    /// This behaves like `recv_timeout`, but takes the point in time at which
+    /// to give up rather than a duration, which is convenient when several
+    /// operations share one deadline.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(mpsc_recv_timeout)]
+    ///
+    /// use std::sync::mpsc::{self, RecvTimeoutError};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let (send, recv) = mpsc::channel::<()>();
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(100);
+    /// assert_eq!(Err(RecvTimeoutError::Timeout), recv.recv_deadline(deadline));
+    /// ```
+    #[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+    pub fn recv_deadline(&self, deadline: Instant) -> Result<T, RecvTimeoutError> {
+        use self::RecvTimeoutError::*;
+
+        loop {
+            let port_or_empty = match *unsafe { self.inner() } {
+                Flavor::Oneshot(ref p) => {
+                    match unsafe { (*p.get()).recv(Some(deadline)) } {
+                        Ok(t) => return Ok(t),
+                        Err(oneshot::Disconnected) => return Err(Disconnected),
+                        Err(oneshot::Upgraded(rx)) => Some(rx),
+                        Err(oneshot::Empty) => None,
+                    }
+                }
+                Flavor::Stream(ref p) => {
+                    match unsafe { (*p.get()).recv(Some(deadline)) } {
+                        Ok(t) => return Ok(t),
+                        Err(stream::Disconnected) => return Err(Disconnected),
+                        Err(stream::Upgraded(rx)) => Some(rx),
+                        Err(stream::Empty) => None,
+                    }
+                }
+                Flavor::Shared(ref p) => {
+                    match unsafe { (*p.get()).recv(Some(deadline)) } {
+                        Ok(t) => return Ok(t),
+                        Err(shared::Disconnected) => return Err(Disconnected),
+                        Err(shared::Empty) => None,
+                    }
+                }
+                Flavor::Sync(ref p) => {
+                    match unsafe { (*p.get()).recv(Some(deadline)) } {
+                        Ok(t) => return Ok(t),
+                        Err(sync::Disconnected) => return Err(Disconnected),
+                        Err(sync::Empty) => None,
+                    }
+                }
+            };
+
+            if let Some(new_port) = port_or_empty {
+                unsafe {
+                    mem::swap(self.inner_mut(), new_port.inner_mut());
+                }
+            }
+
+            // If we're already passed the deadline, and we're here without
+            // data, return a timeout, else try again.
+            if Instant::now() >= deadline {
+                return Err(Timeout);
+            }
+        }
+    }
+
     /// Returns an iterator that will block waiting for messages, but never
     /// `panic!`. It will return `None` when the channel has hung up.
     #[stable(feature = "rust1", since = "1.0.0")]
@@ -1134,6 +1280,38 @@ impl error::Error for TryRecvError {
     }
 }
 
+#[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+impl fmt::Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecvTimeoutError::Timeout => {
+                "timed out waiting on channel".fmt(f)
+            }
+            RecvTimeoutError::Disconnected => {
+                "channel is empty and sending half is closed".fmt(f)
+            }
+        }
+    }
+}
+
+#[unstable(feature = "mpsc_recv_timeout", issue = "0")]
+impl error::Error for RecvTimeoutError {
+    fn description(&self) -> &str {
+        match *self {
+            RecvTimeoutError::Timeout => {
+                "timed out waiting on channel"
+            }
+            RecvTimeoutError::Disconnected => {
+                "channel is empty and sending half is closed"
+            }
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use prelude::v1::*;
@@ -1141,6 +1319,7 @@ mod tests {
     use env;
     use super::*;
     use thread;
+    use time::{Duration, Instant};
 
     pub fn stress_factor() -> usize {
         match env::var("RUST_TEST_STRESS") {
@@ -1156,6 +1335,93 @@ mod tests {
         assert_eq!(rx.recv().unwrap(), 1);
     }
 
+    #[test]
+    fn recv_timeout() {
+        let (tx, rx) = channel::<i32>();
+        let timeout = Duration::from_millis(1);
+        assert_eq!(rx.recv_timeout(timeout), Err(RecvTimeoutError::Timeout));
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv_timeout(timeout), Ok(1));
+        drop(tx);
+        assert_eq!(rx.recv_timeout(timeout), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn stream_recv_timeout() {
+        let (tx, rx) = channel::<i32>();
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        let timeout = Duration::from_millis(1);
+        assert_eq!(rx.recv_timeout(timeout), Ok(1));
+        assert_eq!(rx.recv_timeout(timeout), Ok(2));
+        assert_eq!(rx.recv_timeout(timeout), Err(RecvTimeoutError::Timeout));
+        tx.send(3).unwrap();
+        assert_eq!(rx.recv(), Ok(3));
+    }
+
+    #[test]
+    fn shared_recv_timeout() {
+        let (tx, rx) = channel();
+        let total = 5;
+        for _ in 0..total {
+            let tx = tx.clone();
+            thread::spawn(move|| {
+                tx.send(()).unwrap();
+            });
+        }
+
+        for _ in 0..total { rx.recv().unwrap(); }
+
+        let timeout = Duration::from_millis(1);
+        assert_eq!(rx.recv_timeout(timeout), Err(RecvTimeoutError::Timeout));
+        tx.send(()).unwrap();
+        assert_eq!(rx.recv_timeout(timeout), Ok(()));
+    }
+
+    #[test]
+    fn recv_timeout_wakes_up() {
+        let (tx, rx) = channel::<i32>();
+        let _t = thread::spawn(move|| {
+            thread::sleep(Duration::from_millis(10));
+            tx.send(1).unwrap();
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(1));
+    }
+
+    #[test]
+    fn recv_timeout_upgrade() {
+        let (tx, rx) = channel::<i32>();
+        let timeout = Duration::from_millis(1);
+        let _tx_clone = tx.clone();
+
+        let start = Instant::now();
+        assert_eq!(rx.recv_timeout(timeout), Err(RecvTimeoutError::Timeout));
+        assert!(Instant::now() >= start + timeout);
+    }
+
+    #[test]
+    fn recv_huge_timeout() {
+        let (tx, rx) = channel();
+        let t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            tx.send(1).unwrap();
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(u64::max_value())), Ok(1));
+        t.join().unwrap();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(u64::max_value())),
+                   Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn recv_deadline() {
+        let (tx, rx) = channel::<i32>();
+        let deadline = Instant::now() + Duration::from_millis(1);
+        assert_eq!(rx.recv_deadline(deadline), Err(RecvTimeoutError::Timeout));
+        assert!(Instant::now() >= deadline);
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv_deadline(deadline), Ok(1));
+    }
+
     #[test]
     fn drop_full() {
         let (tx, _rx) = channel::<Box<isize>>();
@@ -1689,6 +1955,7 @@ mod sync_tests {
     use env;
     use thread;
     use super::*;
+    use time::Duration;
 
     pub fn stress_factor() -> usize {
         match env::var("RUST_TEST_STRESS") {
@@ -1704,6 +1971,27 @@ mod sync_tests {
         assert_eq!(rx.recv().unwrap(), 1);
     }
 
+    #[test]
+    fn recv_timeout() {
+        let (tx, rx) = sync_channel::<i32>(1);
+        let timeout = Duration::from_millis(1);
+        assert_eq!(rx.recv_timeout(timeout), Err(RecvTimeoutError::Timeout));
+        tx.send(1).unwrap();
+        assert_eq!(rx.recv_timeout(timeout), Ok(1));
+        drop(tx);
+        assert_eq!(rx.recv_timeout(timeout), Err(RecvTimeoutError::Disconnected));
+    }
+
+    #[test]
+    fn recv_timeout_rendezvous() {
+        let (tx, rx) = sync_channel::<i32>(0);
+        let _t = thread::spawn(move|| {
+            thread::sleep(Duration::from_millis(10));
+            tx.send(1).unwrap();
+        });
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok(1));
+    }
+
     #[test]
     fn drop_full() {
         let (tx, _rx) = sync_channel::<Box<isize>>(1);
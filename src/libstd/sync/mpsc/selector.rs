@@ -0,0 +1,436 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Waiting on several channel operations at once without a macro.
+//!
+//! Unlike `Select`, whose handles are linked into the set in place and so
+//! have to be pinned on the stack, a `Selector` only borrows the channels it
+//! watches and can be built up in a loop. It also supports waiting for room
+//! in a `sync_channel`, so a thread can wait on input and output together.
+//!
+//! Nothing is received or sent by the selector itself. It reports which
+//! operation is ready, and the caller then completes it with `try_recv` or
+//! `try_send`.
+
+use prelude::v1::*;
+
+use fmt;
+use time::{Duration, Instant};
+
+use sync::mpsc::{Receiver, SyncSender};
+use sync::mpsc::blocking::{self, SignalToken};
+use sync::mpsc::select::Packet;
+use sync::mpsc::select::StartResult::{self, Installed, Abort};
+use sync::mpsc::sync;
+
+/// A set of channel operations to wait on.
+///
+/// Operations are registered with `recv` and `send`, each of which returns the
+/// index identifying that operation. `ready` then blocks until one of them
+/// can go ahead and returns its index:
+///
+/// * A receive is ready when `try_recv` would not return `Empty`: either a
+///   message is available or all senders are gone. As receivers can't be
+///   shared, this stays true until the caller receives.
+/// * A send is ready when `try_send` would not return `Full`: either there's
+///   room in the buffer (for a zero-sized buffer, a receiver is waiting) or
+///   the receiver is gone. Other senders on the same channel may take the
+///   room first, in which case `try_send` reports `Full` and the caller
+///   should wait again.
+///
+/// A selector can be waited on any number of times.
+///
+/// # Examples
+///
+/// Waiting on whichever of two channels gets a message first:
+///
+/// ```
+/// use std::sync::mpsc::{channel, Selector};
+/// use std::thread;
+///
+/// let (_tx1, rx1) = channel::<i32>();
+/// let (tx2, rx2) = channel();
+///
+/// thread::spawn(move || {
+///     tx2.send("hello").unwrap();
+/// });
+///
+/// let mut sel = Selector::new();
+/// let first = sel.recv(&rx1);
+/// let second = sel.recv(&rx2);
+///
+/// let ready = sel.ready();
+/// assert!(ready != first);
+/// assert_eq!(ready, second);
+/// assert_eq!(rx2.try_recv(), Ok("hello"));
+/// ```
+///
+/// Forwarding messages into a bounded channel while still reacting to a
+/// shutdown request:
+///
+/// ```no_run
+/// use std::sync::mpsc::{channel, sync_channel, Selector, TrySendError};
+///
+/// let (_shutdown_tx, shutdown) = channel::<()>();
+/// let (out, _out_rx) = sync_channel(16);
+///
+/// let mut sel = Selector::new();
+/// let stop = sel.recv(&shutdown);
+/// let room = sel.send(&out);
+///
+/// let mut next = 0;
+/// loop {
+///     let ready = sel.ready();
+///     if ready == stop {
+///         break;
+///     }
+///     assert_eq!(ready, room);
+///     match out.try_send(next) {
+///         Ok(()) => next += 1,
+///         Err(TrySendError::Full(_)) => {}
+///         Err(TrySendError::Disconnected(_)) => break,
+///     }
+/// }
+/// ```
+#[stable(feature = "mpsc_selector", since = "1.10.0")]
+pub struct Selector<'a> {
+    ops: Vec<Operation<'a>>,
+}
+
+enum Operation<'a> {
+    Recv(&'a (Packet + 'a)),
+    // The node queues the selecting thread up with the blocked senders; it
+    // only stays linked in while `wait` runs, during which `ops` can't move.
+    Send(&'a (SendPacket + 'a), sync::Node),
+}
+
+// Type-erased sender side of a `sync_channel`, the counterpart of `Packet`.
+trait SendPacket {
+    fn can_send(&self) -> bool;
+    fn start_selection(&self, node: &mut sync::Node,
+                       token: SignalToken) -> StartResult;
+    fn abort_selection(&self, node: &mut sync::Node) -> bool;
+}
+
+impl<T> SendPacket for SyncSender<T> {
+    fn can_send(&self) -> bool {
+        unsafe { (*self.inner.get()).can_send() }
+    }
+
+    fn start_selection(&self, node: &mut sync::Node,
+                       token: SignalToken) -> StartResult {
+        unsafe { (*self.inner.get()).start_send_selection(node, token) }
+    }
+
+    fn abort_selection(&self, node: &mut sync::Node) -> bool {
+        unsafe { (*self.inner.get()).abort_send_selection(node) }
+    }
+}
+
+impl<'a> Operation<'a> {
+    fn is_ready(&self) -> bool {
+        match *self {
+            Operation::Recv(rx) => rx.can_recv(),
+            Operation::Send(tx, _) => tx.can_send(),
+        }
+    }
+
+    fn start_selection(&mut self, token: SignalToken) -> StartResult {
+        match *self {
+            Operation::Recv(rx) => rx.start_selection(token),
+            Operation::Send(tx, ref mut node) => tx.start_selection(node, token),
+        }
+    }
+
+    fn abort_selection(&mut self) -> bool {
+        match *self {
+            Operation::Recv(rx) => rx.abort_selection(),
+            Operation::Send(tx, ref mut node) => tx.abort_selection(node),
+        }
+    }
+}
+
+impl<'a> Selector<'a> {
+    /// Creates a new selector which doesn't wait on any operations yet.
+    #[stable(feature = "mpsc_selector", since = "1.10.0")]
+    pub fn new() -> Selector<'a> {
+        Selector { ops: Vec::new() }
+    }
+
+    /// Adds a receive operation on `rx`, returning its index.
+    #[stable(feature = "mpsc_selector", since = "1.10.0")]
+    pub fn recv<T>(&mut self, rx: &'a Receiver<T>) -> usize {
+        self.ops.push(Operation::Recv(rx));
+        self.ops.len() - 1
+    }
+
+    /// Adds a send operation on `tx`, returning its index.
+    #[stable(feature = "mpsc_selector", since = "1.10.0")]
+    pub fn send<T>(&mut self, tx: &'a SyncSender<T>) -> usize {
+        self.ops.push(Operation::Send(tx, sync::Node::new()));
+        self.ops.len() - 1
+    }
+
+    /// Returns the index of an operation which is ready, without blocking.
+    ///
+    /// Operations are checked in the order they were added.
+    #[stable(feature = "mpsc_selector", since = "1.10.0")]
+    pub fn try_ready(&mut self) -> Option<usize> {
+        self.ops.iter().position(|op| op.is_ready())
+    }
+
+    /// Blocks until one of the operations is ready, returning its index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no operations were added, as this would block forever.
+    #[stable(feature = "mpsc_selector", since = "1.10.0")]
+    pub fn ready(&mut self) -> usize {
+        assert!(!self.ops.is_empty(), "waiting on an empty selector");
+        self.wait(None).unwrap()
+    }
+
+    /// Blocks until one of the operations is ready, or until `timeout` has
+    /// passed. Returns the index of the ready operation, or `None` on timeout.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no operations were added and `timeout` is too large to ever
+    /// expire, as this would block forever.
+    #[stable(feature = "mpsc_selector", since = "1.10.0")]
+    pub fn ready_timeout(&mut self, timeout: Duration) -> Option<usize> {
+        match self.try_ready() {
+            Some(i) => Some(i),
+            // A timeout too large to be represented as a deadline never
+            // expires.
+            None => match Instant::now().checked_add(timeout) {
+                Some(deadline) => self.ready_deadline(deadline),
+                None => Some(self.ready()),
+            },
+        }
+    }
+
+    /// Blocks until one of the operations is ready, or until `deadline` is
+    /// reached. Returns the index of the ready operation, or `None` on
+    /// timeout.
+    #[stable(feature = "mpsc_selector", since = "1.10.0")]
+    pub fn ready_deadline(&mut self, deadline: Instant) -> Option<usize> {
+        self.wait(Some(deadline))
+    }
+
+    fn wait(&mut self, deadline: Option<Instant>) -> Option<usize> {
+        loop {
+            // Preflight check, as in `Select::wait`.
+            if let Some(i) = self.try_ready() {
+                return Some(i)
+            }
+            if let Some(deadline) = deadline {
+                if Instant::now() >= deadline {
+                    return None
+                }
+            }
+
+            // Install one signal token on every operation, stopping early if
+            // one of them turns out to be ready in the meantime.
+            let (wait_token, signal_token) = blocking::tokens();
+            let mut started = 0;
+            let mut aborted = false;
+            for op in self.ops.iter_mut() {
+                match op.start_selection(signal_token.clone()) {
+                    Installed => started += 1,
+                    Abort => { aborted = true; break }
+                }
+            }
+
+            if !aborted {
+                match deadline {
+                    Some(deadline) => { wait_token.wait_max_until(deadline); }
+                    None => wait_token.wait(),
+                }
+            }
+
+            // Take the token back out of everything it was installed on. Each
+            // of these has to run, even once a ready operation is found.
+            let mut ready = None;
+            for (i, op) in self.ops[..started].iter_mut().enumerate() {
+                if op.abort_selection() && ready.is_none() {
+                    ready = Some(i);
+                }
+            }
+
+            if ready.is_some() {
+                return ready
+            }
+            if aborted {
+                return Some(started)
+            }
+            // Otherwise we timed out, or were woken for room which another
+            // sender has taken since. Go around again.
+        }
+    }
+}
+
+#[stable(feature = "mpsc_selector", since = "1.10.0")]
+impl<'a> fmt::Debug for Selector<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Selector").field("operations", &self.ops.len()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use sync::mpsc::*;
+    use thread;
+    use time::Duration;
+
+    #[test]
+    fn smoke() {
+        let (tx1, rx1) = channel::<i32>();
+        let (tx2, rx2) = channel::<i32>();
+        let mut sel = Selector::new();
+        let a = sel.recv(&rx1);
+        let b = sel.recv(&rx2);
+        assert_eq!(sel.try_ready(), None);
+
+        tx2.send(2).unwrap();
+        assert_eq!(sel.ready(), b);
+        assert_eq!(rx2.try_recv(), Ok(2));
+
+        tx1.send(1).unwrap();
+        assert_eq!(sel.ready(), a);
+        assert_eq!(rx1.try_recv(), Ok(1));
+    }
+
+    #[test]
+    fn blocks_until_sent() {
+        let (tx1, rx1) = channel::<i32>();
+        let (tx2, rx2) = sync_channel::<i32>(0);
+        let _t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            tx2.send(2).unwrap();
+            drop(tx1);
+        });
+        let mut sel = Selector::new();
+        sel.recv(&rx1);
+        let b = sel.recv(&rx2);
+        assert_eq!(sel.ready(), b);
+        assert_eq!(rx2.recv(), Ok(2));
+    }
+
+    #[test]
+    fn disconnect_is_ready() {
+        let (tx, rx) = channel::<i32>();
+        let mut sel = Selector::new();
+        let a = sel.recv(&rx);
+        drop(tx);
+        assert_eq!(sel.ready(), a);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn timeout() {
+        let (_tx, rx) = channel::<i32>();
+        let (tx2, _rx2) = sync_channel::<i32>(1);
+        tx2.send(1).unwrap();
+        let mut sel = Selector::new();
+        sel.recv(&rx);
+        sel.send(&tx2);
+        assert_eq!(sel.ready_timeout(Duration::from_millis(10)), None);
+    }
+
+    #[test]
+    fn ready_huge_timeout() {
+        let (tx, rx) = channel::<i32>();
+        let _t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            tx.send(1).unwrap();
+        });
+        let mut sel = Selector::new();
+        let a = sel.recv(&rx);
+        assert_eq!(sel.ready_timeout(Duration::from_secs(u64::max_value())), Some(a));
+        assert_eq!(rx.try_recv(), Ok(1));
+    }
+
+    #[test]
+    fn send_waits_for_room() {
+        let (tx, rx) = sync_channel::<i32>(1);
+        tx.send(1).unwrap();
+        let mut sel = Selector::new();
+        let a = sel.send(&tx);
+        assert_eq!(sel.try_ready(), None);
+
+        let _t = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            assert_eq!(rx.recv(), Ok(1));
+            assert_eq!(rx.recv(), Ok(2));
+        });
+        assert_eq!(sel.ready(), a);
+        tx.try_send(2).unwrap();
+    }
+
+    #[test]
+    fn send_rendezvous() {
+        let (tx, rx) = sync_channel::<i32>(0);
+        let mut sel = Selector::new();
+        let a = sel.send(&tx);
+        assert_eq!(sel.try_ready(), None);
+
+        let t = thread::spawn(move || rx.recv().unwrap());
+        loop {
+            assert_eq!(sel.ready(), a);
+            match tx.try_send(3) {
+                Ok(()) => break,
+                Err(TrySendError::Full(_)) => {}
+                Err(TrySendError::Disconnected(_)) => panic!(),
+            }
+        }
+        assert_eq!(t.join().unwrap(), 3);
+    }
+
+    #[test]
+    fn send_to_disconnected_is_ready() {
+        let (tx, rx) = sync_channel::<i32>(0);
+        drop(rx);
+        let mut sel = Selector::new();
+        let a = sel.send(&tx);
+        assert_eq!(sel.ready(), a);
+    }
+
+    #[test]
+    fn stress() {
+        const AMT: i32 = 10000;
+        let (tx1, rx1) = channel::<i32>();
+        let (tx2, rx2) = sync_channel::<i32>(4);
+        let t = thread::spawn(move || {
+            for i in 0..AMT {
+                if i % 2 == 0 {
+                    tx1.send(i).unwrap();
+                } else {
+                    tx2.send(i).unwrap();
+                }
+            }
+        });
+        let mut sel = Selector::new();
+        let a = sel.recv(&rx1);
+        let b = sel.recv(&rx2);
+        for _ in 0..AMT {
+            let i = sel.ready();
+            if i == a {
+                rx1.try_recv().unwrap();
+            } else {
+                assert_eq!(i, b);
+                rx2.try_recv().unwrap();
+            }
+        }
+        t.join().unwrap();
+    }
+}
@@ -44,6 +44,7 @@ use sync::atomic::{Ordering, AtomicUsize};
 use sync::mpsc::blocking::{self, WaitToken, SignalToken};
 use sync::mpsc::select::StartResult::{self, Installed, Abort};
 use sync::{Mutex, MutexGuard};
+use time::Instant;
 
 pub struct Packet<T> {
     /// Only field outside of the mutex. Just done for kicks, but mainly because
@@ -89,7 +90,7 @@ struct Queue {
     tail: *mut Node,
 }
 
-struct Node {
+pub struct Node {
     token: Option<SignalToken>,
     next: *mut Node,
 }
@@ -126,6 +127,71 @@ fn wait<'a, 'b, T>(lock: &'a Mutex<State<T>>,
     lock.lock().unwrap() // relock
 }
 
+/// Same as `wait`, but for a receiver which gives up once `deadline` passes.
+/// `success` is set to whether it was woken up before that.
+fn wait_timeout_receiver<'a, 'b, T>(lock: &'a Mutex<State<T>>,
+                                    deadline: Instant,
+                                    mut guard: MutexGuard<'b, State<T>>,
+                                    success: &mut bool)
+                                    -> MutexGuard<'a, State<T>>
+{
+    let (wait_token, signal_token) = blocking::tokens();
+    match mem::replace(&mut guard.blocker, BlockedReceiver(signal_token)) {
+        NoneBlocked => {}
+        _ => unreachable!(),
+    }
+    drop(guard);                                     // unlock
+    *success = wait_token.wait_max_until(deadline);  // block
+    let mut new_guard = lock.lock().unwrap();        // relock
+    if !*success {
+        abort_selection(&mut new_guard);
+    }
+    new_guard
+}
+
+/// On a rendezvous channel a sender can only make progress once a receiver is
+/// waiting, which a sender blocked in `Selector` needs to hear about. Called
+/// with the lock held just before a receiver blocks; the woken sender can't
+/// look at the channel until the receiver has been installed and the lock is
+/// released.
+fn wakeup_selecting_sender<'a, T>(guard: &mut MutexGuard<'a, State<T>>) {
+    if guard.cap == 0 {
+        if let Some(token) = guard.queue.dequeue() {
+            token.signal();
+        }
+    }
+}
+
+/// Whether a `try_send` would currently get through: there's room in the
+/// buffer (for a rendezvous channel, a receiver is waiting on the empty
+/// buffer) or the receiver is gone.
+fn can_send<T>(state: &State<T>) -> bool {
+    if state.disconnected {
+        return true
+    }
+    if state.cap == 0 {
+        match state.blocker {
+            BlockedReceiver(..) => state.buf.size() == 0,
+            _ => false,
+        }
+    } else {
+        state.buf.size() < state.buf.cap()
+    }
+}
+
+/// Takes back the receiver's signal token, if it's still installed. Returns
+/// true if no receiver was blocked.
+fn abort_selection<'a, T>(guard: &mut MutexGuard<'a, State<T>>) -> bool {
+    match mem::replace(&mut guard.blocker, NoneBlocked) {
+        NoneBlocked => true,
+        BlockedSender(token) => {
+            guard.blocker = BlockedSender(token);
+            true
+        }
+        BlockedReceiver(token) => { drop(token); false }
+    }
+}
+
 /// Wakes up a thread, dropping the lock at the correct time
 fn wakeup<T>(token: SignalToken, guard: MutexGuard<State<T>>) {
     // We need to be careful to wake up the waiting thread *outside* of the mutex
@@ -238,20 +304,29 @@ impl<T> Packet<T> {
     //
     // When reading this, remember that there can only ever be one receiver at
     // time.
-    pub fn recv(&self) -> Result<T, ()> {
+    pub fn recv(&self, deadline: Option<Instant>) -> Result<T, Failure> {
         let mut guard = self.lock.lock().unwrap();
 
         // Wait for the buffer to have something in it. No need for a while loop
         // because we're the only receiver.
         let mut waited = false;
         if !guard.disconnected && guard.buf.size() == 0 {
-            guard = wait(&self.lock, guard, BlockedReceiver);
-            waited = true;
+            wakeup_selecting_sender(&mut guard);
+            if let Some(deadline) = deadline {
+                guard = wait_timeout_receiver(&self.lock, deadline, guard,
+                                              &mut waited);
+            } else {
+                guard = wait(&self.lock, guard, BlockedReceiver);
+                waited = true;
+            }
         }
-        if guard.disconnected && guard.buf.size() == 0 { return Err(()) }
+        if guard.disconnected && guard.buf.size() == 0 { return Err(Disconnected) }
 
-        // Pick up the data, wake up our neighbors, and carry on
-        assert!(guard.buf.size() > 0);
+        // Pick up the data, wake up our neighbors, and carry on. Data may have
+        // arrived between timing out and relocking, so only a timeout can
+        // leave the buffer empty here.
+        assert!(guard.buf.size() > 0 || (deadline.is_some() && !waited));
+        if guard.buf.size() == 0 { return Err(Empty) }
         let ret = guard.buf.dequeue();
         self.wakeup_senders(waited, guard);
         Ok(ret)
@@ -377,6 +452,7 @@ impl<T> Packet<T> {
         if guard.disconnected || guard.buf.size() > 0 {
             Abort
         } else {
+            wakeup_selecting_sender(&mut guard);
             match mem::replace(&mut guard.blocker, BlockedReceiver(token)) {
                 NoneBlocked => {}
                 BlockedSender(..) => unreachable!(),
@@ -392,15 +468,54 @@ impl<T> Packet<T> {
     // The return value indicates whether there's data on this port.
     pub fn abort_selection(&self) -> bool {
         let mut guard = self.lock.lock().unwrap();
-        match mem::replace(&mut guard.blocker, NoneBlocked) {
-            NoneBlocked => true,
-            BlockedSender(token) => {
-                guard.blocker = BlockedSender(token);
-                true
-            }
-            BlockedReceiver(token) => { drop(token); false }
+        abort_selection(&mut guard)
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    // sender select implementation
+    ////////////////////////////////////////////////////////////////////////////
+
+    pub fn can_send(&self) -> bool {
+        let guard = self.lock.lock().unwrap();
+        can_send(&guard)
+    }
+
+    // Queues up a selecting sender alongside senders blocked for a slot, unless
+    // it could send right away. The node must stay put until it's handed back
+    // to `abort_send_selection`.
+    pub fn start_send_selection(&self, node: &mut Node,
+                                token: SignalToken) -> StartResult {
+        let mut guard = self.lock.lock().unwrap();
+        if can_send(&guard) {
+            Abort
+        } else {
+            guard.queue.push(node, token);
+            Installed
         }
     }
+
+    // Takes a selecting sender back out of the queue, returning whether it
+    // could send now.
+    pub fn abort_send_selection(&self, node: &mut Node) -> bool {
+        let mut guard = self.lock.lock().unwrap();
+        let woken = !guard.queue.remove(node);
+        node.token = None;
+        let ready = can_send(&guard);
+
+        // We may have been handed a free slot which the selector won't end up
+        // using, in which case the next sender in line gets a chance at it. A
+        // spurious wakeup just sends that one back to the queue.
+        let next = if woken && ready { guard.queue.dequeue() } else { None };
+        mem::drop(guard);
+        next.map(|t| t.signal());
+        ready
+    }
+}
+
+impl Node {
+    pub fn new() -> Node {
+        Node { token: None, next: ptr::null_mut() }
+    }
 }
 
 impl<T> Drop for Packet<T> {
@@ -444,7 +559,12 @@ impl<T> Buffer<T> {
 impl Queue {
     fn enqueue(&mut self, node: &mut Node) -> WaitToken {
         let (wait_token, signal_token) = blocking::tokens();
-        node.token = Some(signal_token);
+        self.push(node, signal_token);
+        wait_token
+    }
+
+    fn push(&mut self, node: &mut Node, token: SignalToken) {
+        node.token = Some(token);
         node.next = ptr::null_mut();
 
         if self.tail.is_null() {
@@ -456,8 +576,33 @@ impl Queue {
                 self.tail = node as *mut Node;
             }
         }
+    }
 
-        wait_token
+    // Unlinks `node` if it's still queued, returning whether it was.
+    fn remove(&mut self, node: &mut Node) -> bool {
+        let node = node as *mut Node;
+        let mut prev: *mut Node = ptr::null_mut();
+        let mut cur = self.head;
+        unsafe {
+            while !cur.is_null() {
+                if cur == node {
+                    let next = (*cur).next;
+                    if prev.is_null() {
+                        self.head = next;
+                    } else {
+                        (*prev).next = next;
+                    }
+                    if self.tail == cur {
+                        self.tail = prev;
+                    }
+                    (*cur).next = ptr::null_mut();
+                    return true
+                }
+                prev = cur;
+                cur = (*cur).next;
+            }
+        }
+        false
     }
 
     fn dequeue(&mut self) -> Option<SignalToken> {
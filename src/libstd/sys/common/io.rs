@@ -13,6 +13,28 @@ use io::ErrorKind;
 use io::Read;
 use slice::from_raw_parts_mut;
 
+// Default implementation of `Read::read_vectored`: reads into the first
+// non-empty buffer.
+pub fn default_read_vectored<F>(read: F, bufs: &mut [io::IoSliceMut]) -> io::Result<usize>
+    where F: FnOnce(&mut [u8]) -> io::Result<usize>
+{
+    match bufs.iter_mut().find(|b| !b.is_empty()) {
+        Some(buf) => read(&mut **buf),
+        None => read(&mut [][..]),
+    }
+}
+
+// Default implementation of `Write::write_vectored`: writes the first
+// non-empty buffer.
+pub fn default_write_vectored<F>(write: F, bufs: &[io::IoSlice]) -> io::Result<usize>
+    where F: FnOnce(&[u8]) -> io::Result<usize>
+{
+    match bufs.iter().find(|b| !b.is_empty()) {
+        Some(buf) => write(&**buf),
+        None => write(&[][..]),
+    }
+}
+
 // Provides read_to_end functionality over an uninitialized buffer.
 // This function is unsafe because it calls the underlying
 // read function with a slice into uninitialized memory. The default
@@ -0,0 +1,66 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The system allocator behind `std::heap::System`, mirroring the
+//! implementation in `alloc_system`.
+
+use cmp;
+use libc;
+use ptr;
+
+#[cfg(all(any(target_arch = "x86",
+              target_arch = "arm",
+              target_arch = "mips",
+              target_arch = "powerpc",
+              target_arch = "powerpc64",
+              target_arch = "asmjs")))]
+const MIN_ALIGN: usize = 8;
+#[cfg(all(any(target_arch = "x86_64",
+              target_arch = "aarch64")))]
+const MIN_ALIGN: usize = 16;
+
+pub unsafe fn allocate(size: usize, align: usize) -> *mut u8 {
+    if align <= MIN_ALIGN {
+        libc::malloc(size as libc::size_t) as *mut u8
+    } else {
+        let mut out = ptr::null_mut();
+        let ret = libc::posix_memalign(&mut out, align as libc::size_t, size as libc::size_t);
+        if ret != 0 {
+            ptr::null_mut()
+        } else {
+            out as *mut u8
+        }
+    }
+}
+
+pub unsafe fn reallocate(ptr: *mut u8, old_size: usize, size: usize, align: usize) -> *mut u8 {
+    if align <= MIN_ALIGN {
+        libc::realloc(ptr as *mut libc::c_void, size as libc::size_t) as *mut u8
+    } else {
+        let new_ptr = allocate(size, align);
+        if !new_ptr.is_null() {
+            ptr::copy(ptr, new_ptr, cmp::min(size, old_size));
+            deallocate(ptr, old_size, align);
+        }
+        new_ptr
+    }
+}
+
+pub unsafe fn reallocate_inplace(_ptr: *mut u8,
+                                 old_size: usize,
+                                 _size: usize,
+                                 _align: usize)
+                                 -> usize {
+    old_size
+}
+
+pub unsafe fn deallocate(ptr: *mut u8, _old_size: usize, _align: usize) {
+    libc::free(ptr as *mut libc::c_void)
+}
@@ -73,6 +73,8 @@ pub trait CommandExt {
     /// When this closure is run, aspects such as the stdio file descriptors and
     /// working directory have successfully been changed, so output to these
     /// locations may not appear where intended.
+    ///
+    /// See `pre_exec` for the operations that are safe to perform here.
     #[unstable(feature = "process_exec", issue = "31398")]
     fn before_exec<F>(&mut self, f: F) -> &mut process::Command
         where F: FnMut() -> io::Result<()> + Send + Sync + 'static;
@@ -98,6 +100,139 @@ pub trait CommandExt {
     /// cross-platform `spawn` instead.
     #[stable(feature = "process_exec2", since = "1.9.0")]
     fn exec(&mut self) -> io::Error;
+
+    /// Sets the process group of the child process, as with `setpgid(2)`.
+    ///
+    /// Passing `0` places the child in a new process group whose id is the
+    /// child's own pid; any other value must name an existing process group in
+    /// the same session. Signals sent to the group, such as the `SIGINT` a
+    /// terminal delivers on Ctrl-C, then reach the child and all of its
+    /// descendants that stay in that group.
+    ///
+    /// Failure in the `setpgid` call will cause the spawn to fail. This
+    /// cannot be combined with `setsid`, as a session leader may not change
+    /// its process group.
+    #[unstable(feature = "process_group", issue = "0")]
+    fn process_group(&mut self, pgroup: i32) -> &mut process::Command;
+
+    /// Runs the child as the leader of a new session, as with `setsid(2)`.
+    ///
+    /// The child also becomes the leader of a new process group and is
+    /// detached from the parent's controlling terminal. The parent process
+    /// remains the child reaper of the new process.
+    #[unstable(feature = "process_group", issue = "0")]
+    fn setsid(&mut self, on: bool) -> &mut process::Command;
+
+    /// Schedules a closure to be run in the child between `fork` and `exec`.
+    ///
+    /// This behaves like `before_exec`, and closures registered through
+    /// either method run in the order they were registered, after all of the
+    /// other configuration on this `Command` has been applied.
+    ///
+    /// # Safety
+    ///
+    /// The closure runs in a copy of the parent's address space in which only
+    /// the forking thread exists. Any lock held by another thread at the time
+    /// of the `fork`, including the one inside `malloc`, stays locked forever
+    /// in the child. The closure must therefore restrict itself to
+    /// async-signal-safe operations (see `signal-safety(7)`): raw system
+    /// calls such as `setrlimit`, `prctl`, `chroot` or `unshare` are fine,
+    /// while allocating, taking a `Mutex`, printing through `std::io` or
+    /// panicking are not.
+    ///
+    /// Data captured by the closure should be prepared before spawning, for
+    /// example by building any `CString`s ahead of time.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_exec)]
+    ///
+    /// use std::io;
+    /// use std::os::unix::process::CommandExt;
+    /// use std::process::Command;
+    ///
+    /// let mut cmd = Command::new("ls");
+    /// unsafe {
+    ///     cmd.pre_exec(|| {
+    ///         // Only async-signal-safe work may be done here.
+    ///         Ok(())
+    ///     });
+    /// }
+    /// cmd.spawn().unwrap();
+    /// ```
+    #[unstable(feature = "process_exec", issue = "31398")]
+    unsafe fn pre_exec<F>(&mut self, f: F) -> &mut process::Command
+        where F: FnMut() -> io::Result<()> + Send + Sync + 'static;
+
+    /// Sets the supplementary group ids of the child process, as with
+    /// `setgroups(2)`.
+    ///
+    /// The groups are installed before the `gid` and `uid` are changed, as
+    /// this normally requires privileges the child is about to give up.
+    /// Failure in the `setgroups` call will cause the spawn to fail. If this
+    /// is not called and a `uid` is set, the child's supplementary groups are
+    /// cleared where permitted.
+    #[unstable(feature = "process_credentials", issue = "0")]
+    fn groups(&mut self, groups: &[u32]) -> &mut process::Command;
+
+    /// Sets a resource limit of the child process, as with `setrlimit(2)`.
+    ///
+    /// `resource` is one of the `RLIMIT_*` constants of the platform. Limits
+    /// are applied before the `uid` is changed, so a privileged parent may
+    /// raise a hard limit for an unprivileged child. Failure in the
+    /// `setrlimit` call will cause the spawn to fail.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_credentials)]
+    ///
+    /// use std::os::unix::process::CommandExt;
+    /// use std::process::Command;
+    ///
+    /// // RLIMIT_NOFILE on Linux
+    /// const RLIMIT_NOFILE: i32 = 7;
+    ///
+    /// Command::new("worker").rlimit(RLIMIT_NOFILE, 64, 64).spawn().unwrap();
+    /// ```
+    #[unstable(feature = "process_credentials", issue = "0")]
+    fn rlimit(&mut self, resource: i32, soft: u64, hard: u64)
+              -> &mut process::Command;
+
+    /// Makes the descriptor `src` of this process available as `dst` in the
+    /// child process.
+    ///
+    /// Descriptors opened by the standard library are close-on-exec, so
+    /// apart from stdio a child inherits nothing unless asked to. This
+    /// duplicates `src` onto `dst` in the child without the close-on-exec
+    /// flag; `src` itself is not inherited unless it is mapped as well.
+    /// Mappings are applied after stdio has been set up, so a `dst` of 0, 1
+    /// or 2 takes precedence over the `stdin`, `stdout` and `stderr`
+    /// settings. Sources may be mapped in any order, including onto each
+    /// other's numbers.
+    ///
+    /// `src` must remain open until the child is spawned. Failure to
+    /// duplicate it will cause the spawn to fail.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_inherit_fd)]
+    ///
+    /// use std::fs::File;
+    /// use std::os::unix::io::AsRawFd;
+    /// use std::os::unix::process::CommandExt;
+    /// use std::process::Command;
+    ///
+    /// let config = File::open("worker.conf").unwrap();
+    /// Command::new("worker")
+    ///         .arg("--config-fd=3")
+    ///         .inherit_fd(config.as_raw_fd(), 3)
+    ///         .spawn().unwrap();
+    /// ```
+    #[unstable(feature = "process_inherit_fd", issue = "0")]
+    fn inherit_fd(&mut self, src: RawFd, dst: RawFd) -> &mut process::Command;
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
@@ -127,6 +262,75 @@ impl CommandExt for process::Command {
     fn exec(&mut self) -> io::Error {
         self.as_inner_mut().exec(sys::process::Stdio::Inherit)
     }
+
+    fn process_group(&mut self, pgroup: i32) -> &mut process::Command {
+        self.as_inner_mut().process_group(pgroup);
+        self
+    }
+
+    fn setsid(&mut self, on: bool) -> &mut process::Command {
+        self.as_inner_mut().session_leader(on);
+        self
+    }
+
+    unsafe fn pre_exec<F>(&mut self, f: F) -> &mut process::Command
+        where F: FnMut() -> io::Result<()> + Send + Sync + 'static
+    {
+        self.as_inner_mut().before_exec(Box::new(f));
+        self
+    }
+
+    fn groups(&mut self, groups: &[u32]) -> &mut process::Command {
+        self.as_inner_mut().groups(groups);
+        self
+    }
+
+    fn rlimit(&mut self, resource: i32, soft: u64, hard: u64)
+              -> &mut process::Command {
+        self.as_inner_mut().rlimit(resource, soft, hard);
+        self
+    }
+
+    fn inherit_fd(&mut self, src: RawFd, dst: RawFd) -> &mut process::Command {
+        self.as_inner_mut().fd(src, dst);
+        self
+    }
+}
+
+/// Unix-specific extensions to `std::process::Child`
+#[unstable(feature = "process_signal", issue = "0")]
+pub trait ChildExt {
+    /// Sends the signal `signal` to the child, as with `kill(2)`.
+    ///
+    /// `Child::kill` is equivalent to sending `SIGKILL`; this allows a gentler
+    /// `SIGTERM` or `SIGINT` to be sent instead so the child may clean up.
+    ///
+    /// Like `Child::kill`, this returns an `InvalidInput` error if the child
+    /// has already been waited on, as its pid may have been reused.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(process_signal)]
+    ///
+    /// use std::os::unix::process::ChildExt;
+    /// use std::process::Command;
+    ///
+    /// const SIGTERM: i32 = 15;
+    ///
+    /// let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+    /// child.send_signal(SIGTERM).unwrap();
+    /// child.wait().unwrap();
+    /// ```
+    #[unstable(feature = "process_signal", issue = "0")]
+    fn send_signal(&mut self, signal: i32) -> io::Result<()>;
+}
+
+#[unstable(feature = "process_signal", issue = "0")]
+impl ChildExt for process::Child {
+    fn send_signal(&mut self, signal: i32) -> io::Result<()> {
+        self.as_inner_mut().send_signal(signal)
+    }
 }
 
 /// Unix-specific extensions to `std::process::ExitStatus`
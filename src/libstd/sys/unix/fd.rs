@@ -12,6 +12,7 @@
 
 use prelude::v1::*;
 
+use cmp;
 use io::{self, Read};
 use libc::{self, c_int, size_t, c_void};
 use mem;
@@ -24,6 +25,11 @@ pub struct FileDesc {
     fd: c_int,
 }
 
+// `readv` and `writev` fail with `EINVAL` when given more than `IOV_MAX`
+// buffers. POSIX only guarantees 16, but every platform we support allows
+// 1024, so larger requests are truncated to a partial read or write.
+const MAX_IOV: usize = 1024;
+
 impl FileDesc {
     pub fn new(fd: c_int) -> FileDesc {
         FileDesc { fd: fd }
@@ -47,6 +53,15 @@ impl FileDesc {
         Ok(ret as usize)
     }
 
+    pub fn read_vectored(&self, bufs: &mut [io::IoSliceMut]) -> io::Result<usize> {
+        let ret = cvt(unsafe {
+            libc::readv(self.fd,
+                        bufs.as_ptr() as *const libc::iovec,
+                        cmp::min(bufs.len(), MAX_IOV) as c_int)
+        })?;
+        Ok(ret as usize)
+    }
+
     pub fn read_to_end(&self, buf: &mut Vec<u8>) -> io::Result<usize> {
         let mut me = self;
         (&mut me).read_to_end(buf)
@@ -61,6 +76,15 @@ impl FileDesc {
         Ok(ret as usize)
     }
 
+    pub fn write_vectored(&self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        let ret = cvt(unsafe {
+            libc::writev(self.fd,
+                         bufs.as_ptr() as *const libc::iovec,
+                         cmp::min(bufs.len(), MAX_IOV) as c_int)
+        })?;
+        Ok(ret as usize)
+    }
+
     #[cfg(not(any(target_env = "newlib", target_os = "solaris", target_os = "emscripten")))]
     pub fn set_cloexec(&self) {
         unsafe {
@@ -11,9 +11,11 @@
 use prelude::v1::*;
 use os::unix::prelude::*;
 
+use cmp;
 use ffi::{CString, CStr, OsString, OsStr};
 use fmt;
 use io::{self, Error, ErrorKind, SeekFrom};
+use io::prelude::*;
 use libc::{self, c_int, mode_t};
 use mem;
 use path::{Path, PathBuf};
@@ -89,6 +91,13 @@ pub struct FileType { mode: mode_t }
 
 pub struct DirBuilder { mode: mode_t }
 
+#[derive(Clone, Debug)]
+pub struct CopyOptions {
+    permissions: bool,
+    timestamps: bool,
+    xattrs: bool,
+}
+
 impl FileAttr {
     pub fn size(&self) -> u64 { self.stat.st_size as u64 }
     pub fn perm(&self) -> FilePermissions {
@@ -474,6 +483,72 @@ impl File {
         unsafe fn os_datasync(fd: c_int) -> c_int { libc::fsync(fd) }
     }
 
+    #[cfg(not(target_os = "solaris"))]
+    pub fn lock(&self, exclusive: bool) -> io::Result<()> {
+        let op = if exclusive { libc::LOCK_EX } else { libc::LOCK_SH };
+        cvt_r(|| unsafe { libc::flock(self.0.raw(), op) })?;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "solaris"))]
+    pub fn try_lock(&self, exclusive: bool) -> io::Result<bool> {
+        let op = if exclusive { libc::LOCK_EX } else { libc::LOCK_SH };
+        match cvt_r(|| unsafe { libc::flock(self.0.raw(), op | libc::LOCK_NB) }) {
+            Ok(_) => Ok(true),
+            Err(ref e) if e.raw_os_error() == Some(libc::EWOULDBLOCK) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(not(target_os = "solaris"))]
+    pub fn unlock(&self) -> io::Result<()> {
+        cvt_r(|| unsafe { libc::flock(self.0.raw(), libc::LOCK_UN) })?;
+        Ok(())
+    }
+
+    // Solaris has no `flock`, so lock the whole file with `fcntl` instead.
+    // Unlike `flock` locks these belong to the process rather than to the
+    // open file description: a process never conflicts with its own locks,
+    // and closing any descriptor of the file releases them.
+    #[cfg(target_os = "solaris")]
+    pub fn lock(&self, exclusive: bool) -> io::Result<()> {
+        let kind = if exclusive { solaris::F_WRLCK } else { solaris::F_RDLCK };
+        self.fcntl_lock(solaris::F_SETLKW, kind)
+    }
+
+    #[cfg(target_os = "solaris")]
+    pub fn try_lock(&self, exclusive: bool) -> io::Result<bool> {
+        let kind = if exclusive { solaris::F_WRLCK } else { solaris::F_RDLCK };
+        match self.fcntl_lock(solaris::F_SETLK, kind) {
+            Ok(()) => Ok(true),
+            Err(ref e) if e.raw_os_error() == Some(libc::EACCES) ||
+                          e.raw_os_error() == Some(libc::EAGAIN) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    #[cfg(target_os = "solaris")]
+    pub fn unlock(&self) -> io::Result<()> {
+        self.fcntl_lock(solaris::F_SETLK, solaris::F_UNLCK)
+    }
+
+    #[cfg(target_os = "solaris")]
+    fn fcntl_lock(&self, cmd: c_int, kind: libc::c_short) -> io::Result<()> {
+        let lock = solaris::flock {
+            l_type: kind,
+            l_whence: libc::SEEK_SET as libc::c_short,
+            l_start: 0,
+            // A length of zero extends the lock to the end of the file,
+            // however large it grows.
+            l_len: 0,
+            l_sysid: 0,
+            l_pid: 0,
+            l_pad: [0; 4],
+        };
+        cvt_r(|| unsafe { libc::fcntl(self.0.raw(), cmd, &lock) })?;
+        Ok(())
+    }
+
     pub fn truncate(&self, size: u64) -> io::Result<()> {
         #[cfg(target_os = "android")]
         return ::sys::android::ftruncate64(self.0.raw(), size);
@@ -517,6 +592,20 @@ impl File {
     pub fn into_fd(self) -> FileDesc { self.0 }
 }
 
+impl CopyOptions {
+    pub fn new() -> CopyOptions {
+        CopyOptions {
+            permissions: true,
+            timestamps: false,
+            xattrs: false,
+        }
+    }
+
+    pub fn permissions(&mut self, permissions: bool) { self.permissions = permissions; }
+    pub fn timestamps(&mut self, timestamps: bool) { self.timestamps = timestamps; }
+    pub fn xattrs(&mut self, xattrs: bool) { self.xattrs = xattrs; }
+}
+
 impl DirBuilder {
     pub fn new() -> DirBuilder {
         DirBuilder { mode: 0o777 }
@@ -741,18 +830,362 @@ pub fn canonicalize(p: &Path) -> io::Result<PathBuf> {
     Ok(PathBuf::from(OsString::from_vec(buf)))
 }
 
-pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
-    use fs::{File, set_permissions};
+pub fn copy(from: &Path, to: &Path, opts: &CopyOptions) -> io::Result<u64> {
+    use fs::File;
     if !from.is_file() {
         return Err(Error::new(ErrorKind::InvalidInput,
                               "the source path is not an existing regular file"))
     }
 
-    let mut reader = File::open(from)?;
-    let mut writer = File::create(to)?;
-    let perm = reader.metadata()?.permissions();
+    let reader = File::open(from)?;
+    let writer = File::create(to)?;
+    let attr = reader.as_inner().file_attr()?;
 
-    let ret = io::copy(&mut reader, &mut writer)?;
-    set_permissions(to, perm)?;
+    let ret = if is_sparse(&attr) {
+        copy_sparse(&reader, &writer, attr.size())?
+    } else {
+        copy_range(&reader, &writer, u64::max_value())?
+    };
+    // Attributes go first, as the permissions may forbid setting them, and
+    // the timestamps last, as everything else may touch them.
+    if opts.xattrs {
+        copy_xattrs(reader.as_raw_fd(), writer.as_raw_fd())?;
+    }
+    if opts.permissions {
+        cvt_r(|| unsafe { libc::fchmod(writer.as_raw_fd(), attr.perm().mode) })?;
+    }
+    if opts.timestamps {
+        set_times(writer.as_raw_fd(), &attr)?;
+    }
     Ok(ret)
 }
+
+// Whether a file takes up less room on disk than its length, which means
+// that some of it is holes.
+fn is_sparse(attr: &FileAttr) -> bool {
+    (attr.stat.st_blocks as u64).saturating_mul(512) < attr.size()
+}
+
+// Copies up to `len` bytes, stopping early at the end of `reader`, from the
+// current position of `reader` to that of `writer`.
+//
+// `copy_file_range` (Linux 4.5) lets the file system share or copy the
+// blocks itself, and `sendfile` (Linux 2.6.33 for regular files) at least
+// keeps the data in the kernel. Both are given up on for the errors they
+// return when they can't handle a pair of files, or if they copy nothing at
+// all, before anything is copied, and we then fall back to reading and
+// writing.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn copy_range(reader: &::fs::File, writer: &::fs::File, len: u64) -> io::Result<u64> {
+    weak!(fn copy_file_range(c_int, *mut i64, c_int, *mut i64, libc::size_t,
+                             libc::c_uint) -> libc::ssize_t);
+
+    // The most either call copies at once.
+    const MAX_CHUNK: u64 = 0x7fff_f000;
+
+    fn unsupported(e: &io::Error) -> bool {
+        match e.raw_os_error() {
+            Some(libc::ENOSYS) | Some(libc::EXDEV) | Some(libc::EINVAL) |
+            Some(libc::EPERM) | Some(libc::EOPNOTSUPP) => true,
+            _ => false,
+        }
+    }
+
+    let (from, to) = (reader.as_raw_fd(), writer.as_raw_fd());
+    let mut use_copy_file_range = copy_file_range.get().is_some();
+    let mut use_sendfile = true;
+    let mut copied = 0;
+    while copied < len {
+        let chunk = cmp::min(len - copied, MAX_CHUNK) as libc::size_t;
+        let ret = if use_copy_file_range {
+            let f = copy_file_range.get().unwrap();
+            cvt_r(|| unsafe {
+                f(from, ptr::null_mut(), to, ptr::null_mut(), chunk, 0)
+            })
+        } else if use_sendfile {
+            cvt_r(|| unsafe { libc::sendfile(to, from, ptr::null_mut(), chunk) })
+        } else {
+            let mut reader = reader.take(len - copied);
+            let mut writer = writer;
+            return io::copy(&mut reader, &mut writer).map(|n| copied + n)
+        };
+        match ret {
+            // Files of procfs or sysfs say they're empty, and the calls
+            // believe them, whereas reading gives their contents.
+            Ok(0) if copied == 0 && use_copy_file_range => use_copy_file_range = false,
+            Ok(0) if copied == 0 && use_sendfile => use_sendfile = false,
+            Ok(0) => break,
+            Ok(n) => copied += n as u64,
+            Err(ref e) if copied == 0 && use_copy_file_range && unsupported(e) => {
+                use_copy_file_range = false;
+            }
+            Err(ref e) if copied == 0 && use_sendfile && unsupported(e) => {
+                use_sendfile = false;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(copied)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn copy_range(reader: &::fs::File, mut writer: &::fs::File, len: u64) -> io::Result<u64> {
+    io::copy(&mut reader.take(len), &mut writer)
+}
+
+// Copies a sparse file of length `len`, leaving holes where `reader` has
+// them. Returns the length of the copy.
+//
+// `SEEK_DATA` and `SEEK_HOLE` (Linux 3.1) tell where the data is. Older
+// kernels reject them, and we then look for blocks of zeros ourselves.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn copy_sparse(reader: &::fs::File, writer: &::fs::File, len: u64) -> io::Result<u64> {
+    const SEEK_DATA: c_int = 3;
+    const SEEK_HOLE: c_int = 4;
+
+    let fd = reader.as_raw_fd();
+    let mut pos = 0;
+    while pos < len {
+        let data = match cvt(unsafe { lseek64(fd, pos as off64_t, SEEK_DATA) }) {
+            Ok(data) => data as u64,
+            // There is nothing but a hole from `pos` on.
+            Err(ref e) if e.raw_os_error() == Some(libc::ENXIO) => break,
+            Err(ref e) if pos == 0 && e.raw_os_error() == Some(libc::EINVAL) => {
+                return copy_skipping_zeros(reader, writer)
+            }
+            Err(e) => return Err(e),
+        };
+        let hole = cvt(unsafe { lseek64(fd, data as off64_t, SEEK_HOLE) })? as u64;
+        (&*reader).seek(SeekFrom::Start(data))?;
+        (&*writer).seek(SeekFrom::Start(data))?;
+        copy_range(reader, writer, hole - data)?;
+        pos = hole;
+    }
+    // The copy may end in a hole too.
+    writer.set_len(len)?;
+    Ok(len)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn copy_sparse(reader: &::fs::File, writer: &::fs::File, _len: u64) -> io::Result<u64> {
+    copy_skipping_zeros(reader, writer)
+}
+
+// Copies `reader` to `writer`, seeking over the blocks of zeros instead of
+// writing them so that they become holes. Returns the length of the copy.
+fn copy_skipping_zeros(mut reader: &::fs::File, mut writer: &::fs::File) -> io::Result<u64> {
+    const BLOCK_SIZE: usize = 4096;
+
+    let mut buf = vec![0; 16 * BLOCK_SIZE];
+    let mut copied = 0;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for block in buf[..n].chunks(BLOCK_SIZE) {
+            if block.iter().all(|&b| b == 0) {
+                writer.seek(SeekFrom::Current(block.len() as i64))?;
+            } else {
+                writer.write_all(block)?;
+            }
+        }
+        copied += n as u64;
+    }
+    writer.set_len(copied)?;
+    Ok(copied)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android",
+          target_os = "macos", target_os = "ios"))]
+fn copy_xattrs(from: c_int, to: c_int) -> io::Result<()> {
+    // Calls `f` with a buffer of the size it asks for, which is what it
+    // returns when given none, until the value fits.
+    fn read_sized<F>(mut f: F) -> io::Result<Vec<u8>>
+        where F: FnMut(*mut u8, usize) -> libc::ssize_t
+    {
+        loop {
+            let len = cvt(f(ptr::null_mut(), 0))? as usize;
+            let mut buf = Vec::with_capacity(len);
+            match cvt(f(buf.as_mut_ptr(), len)) {
+                Ok(n) => {
+                    unsafe { buf.set_len(n as usize); }
+                    return Ok(buf)
+                }
+                // The value grew in between.
+                Err(ref e) if e.raw_os_error() == Some(libc::ERANGE) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    let names = match read_sized(|buf, len| unsafe { xattr::list(from, buf, len) }) {
+        Ok(names) => names,
+        // The file system doesn't support extended attributes.
+        Err(ref e) if e.raw_os_error() == Some(libc::ENOTSUP) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let name = CString::new(name).unwrap();
+        let value = match read_sized(|buf, len| unsafe {
+            xattr::get(from, name.as_ptr(), buf, len)
+        }) {
+            Ok(value) => value,
+            // The attribute was removed in between.
+            Err(ref e) if e.raw_os_error() == Some(xattr::ENOATTR) => continue,
+            Err(e) => return Err(e),
+        };
+        match cvt(unsafe { xattr::set(to, name.as_ptr(), value.as_ptr(), value.len()) }) {
+            Ok(_) => {}
+            // Only privileged processes may set attributes outside of the
+            // `user` namespace, and the others are copied regardless.
+            Err(ref e) if e.raw_os_error() == Some(libc::EPERM) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android",
+              target_os = "macos", target_os = "ios")))]
+fn copy_xattrs(_from: c_int, _to: c_int) -> io::Result<()> {
+    Err(Error::new(ErrorKind::Other,
+                   "copying extended attributes is not supported on this platform"))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod xattr {
+    use libc::{self, c_char, c_int, c_void, size_t, ssize_t};
+
+    pub const ENOATTR: c_int = libc::ENODATA;
+
+    extern {
+        fn flistxattr(fd: c_int, list: *mut c_char, size: size_t) -> ssize_t;
+        fn fgetxattr(fd: c_int, name: *const c_char, value: *mut c_void,
+                     size: size_t) -> ssize_t;
+        fn fsetxattr(fd: c_int, name: *const c_char, value: *const c_void,
+                     size: size_t, flags: c_int) -> c_int;
+    }
+
+    pub unsafe fn list(fd: c_int, buf: *mut u8, len: usize) -> ssize_t {
+        flistxattr(fd, buf as *mut c_char, len)
+    }
+
+    pub unsafe fn get(fd: c_int, name: *const c_char, buf: *mut u8, len: usize) -> ssize_t {
+        fgetxattr(fd, name, buf as *mut c_void, len)
+    }
+
+    pub unsafe fn set(fd: c_int, name: *const c_char, value: *const u8, len: usize) -> c_int {
+        fsetxattr(fd, name, value as *const c_void, len, 0)
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+mod xattr {
+    use libc::{c_char, c_int, c_void, size_t, ssize_t};
+
+    pub const ENOATTR: c_int = 93;
+
+    extern {
+        fn flistxattr(fd: c_int, list: *mut c_char, size: size_t,
+                      options: c_int) -> ssize_t;
+        fn fgetxattr(fd: c_int, name: *const c_char, value: *mut c_void,
+                     size: size_t, position: u32, options: c_int) -> ssize_t;
+        fn fsetxattr(fd: c_int, name: *const c_char, value: *const c_void,
+                     size: size_t, position: u32, options: c_int) -> c_int;
+    }
+
+    pub unsafe fn list(fd: c_int, buf: *mut u8, len: usize) -> ssize_t {
+        flistxattr(fd, buf as *mut c_char, len, 0)
+    }
+
+    pub unsafe fn get(fd: c_int, name: *const c_char, buf: *mut u8, len: usize) -> ssize_t {
+        fgetxattr(fd, name, buf as *mut c_void, len, 0, 0)
+    }
+
+    pub unsafe fn set(fd: c_int, name: *const c_char, value: *const u8, len: usize) -> c_int {
+        fsetxattr(fd, name, value as *const c_void, len, 0, 0)
+    }
+}
+
+// Sets the access and modification times of `fd` to those in `attr`.
+#[cfg(any(target_os = "ios", target_os = "macos"))]
+fn set_times(fd: c_int, attr: &FileAttr) -> io::Result<()> {
+    extern {
+        fn futimes(fd: c_int, times: *const libc::timeval) -> c_int;
+    }
+    let times = [
+        libc::timeval {
+            tv_sec: attr.stat.st_atime,
+            tv_usec: (attr.stat.st_atime_nsec / 1000) as libc::suseconds_t,
+        },
+        libc::timeval {
+            tv_sec: attr.stat.st_mtime,
+            tv_usec: (attr.stat.st_mtime_nsec / 1000) as libc::suseconds_t,
+        },
+    ];
+    cvt(unsafe { futimes(fd, times.as_ptr()) })?;
+    Ok(())
+}
+
+#[cfg(target_os = "netbsd")]
+fn set_times(fd: c_int, attr: &FileAttr) -> io::Result<()> {
+    extern {
+        fn futimens(fd: c_int, times: *const libc::timespec) -> c_int;
+    }
+    let times = [
+        libc::timespec {
+            tv_sec: attr.stat.st_atime as libc::time_t,
+            tv_nsec: attr.stat.st_atimensec as libc::c_long,
+        },
+        libc::timespec {
+            tv_sec: attr.stat.st_mtime as libc::time_t,
+            tv_nsec: attr.stat.st_mtimensec as libc::c_long,
+        },
+    ];
+    cvt(unsafe { futimens(fd, times.as_ptr()) })?;
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "ios", target_os = "macos", target_os = "netbsd")))]
+fn set_times(fd: c_int, attr: &FileAttr) -> io::Result<()> {
+    extern {
+        fn futimens(fd: c_int, times: *const libc::timespec) -> c_int;
+    }
+    let times = [
+        libc::timespec {
+            tv_sec: attr.stat.st_atime as libc::time_t,
+            tv_nsec: attr.stat.st_atime_nsec as libc::c_long,
+        },
+        libc::timespec {
+            tv_sec: attr.stat.st_mtime as libc::time_t,
+            tv_nsec: attr.stat.st_mtime_nsec as libc::c_long,
+        },
+    ];
+    cvt(unsafe { futimens(fd, times.as_ptr()) })?;
+    Ok(())
+}
+
+#[cfg(target_os = "solaris")]
+mod solaris {
+    use libc;
+
+    #[repr(C)]
+    pub struct flock {
+        pub l_type: libc::c_short,
+        pub l_whence: libc::c_short,
+        pub l_start: libc::off_t,
+        pub l_len: libc::off_t,
+        pub l_sysid: libc::c_int,
+        pub l_pid: libc::pid_t,
+        pub l_pad: [libc::c_long; 4],
+    }
+
+    pub const F_RDLCK: libc::c_short = 1;
+    pub const F_WRLCK: libc::c_short = 2;
+    pub const F_UNLCK: libc::c_short = 3;
+    pub const F_SETLK: libc::c_int = 6;
+    pub const F_SETLKW: libc::c_int = 7;
+}
@@ -0,0 +1,184 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Bindings to inotify(7), which `os::linux::fs::Watcher` builds on.
+
+use prelude::v1::*;
+use os::unix::prelude::*;
+
+use cmp;
+use ffi::{CString, OsString};
+use io;
+use libc::{self, c_char, c_int};
+use mem;
+use path::Path;
+use ptr;
+use sys::fd::FileDesc;
+use sys::cvt;
+use time::{Duration, Instant};
+
+pub const IN_MODIFY: u32 = 0x00000002;
+pub const IN_ATTRIB: u32 = 0x00000004;
+pub const IN_MOVED_FROM: u32 = 0x00000040;
+pub const IN_MOVED_TO: u32 = 0x00000080;
+pub const IN_CREATE: u32 = 0x00000100;
+pub const IN_DELETE: u32 = 0x00000200;
+pub const IN_DELETE_SELF: u32 = 0x00000400;
+pub const IN_MOVE_SELF: u32 = 0x00000800;
+pub const IN_Q_OVERFLOW: u32 = 0x00004000;
+pub const IN_IGNORED: u32 = 0x00008000;
+pub const IN_ONLYDIR: u32 = 0x01000000;
+pub const IN_DONT_FOLLOW: u32 = 0x02000000;
+pub const IN_ISDIR: u32 = 0x40000000;
+
+#[repr(C)]
+struct inotify_event {
+    wd: c_int,
+    mask: u32,
+    cookie: u32,
+    len: u32,
+}
+
+extern {
+    fn inotify_init() -> c_int;
+    fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int;
+    fn inotify_rm_watch(fd: c_int, wd: c_int) -> c_int;
+}
+
+pub struct Inotify(FileDesc);
+
+/// An event as read from the kernel, its name being that of the entry of
+/// the watched directory it is about, if any.
+pub struct Event {
+    pub wd: c_int,
+    pub mask: u32,
+    pub cookie: u32,
+    pub name: Option<OsString>,
+}
+
+impl Inotify {
+    pub fn new() -> io::Result<Inotify> {
+        // `inotify_init1` appeared in Linux 2.6.27, so as with `pipe2` the
+        // close-on-exec flag has to be set separately on older kernels.
+        weak! { fn inotify_init1(c_int) -> c_int }
+        if let Some(init) = inotify_init1.get() {
+            match cvt(unsafe { init(libc::O_CLOEXEC) }) {
+                Ok(fd) => return Ok(Inotify(FileDesc::new(fd))),
+                Err(ref e) if e.raw_os_error() == Some(libc::ENOSYS) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let fd = FileDesc::new(cvt(unsafe { inotify_init() })?);
+        fd.set_cloexec();
+        Ok(Inotify(fd))
+    }
+
+    pub fn add_watch(&self, path: &Path, mask: u32) -> io::Result<c_int> {
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        cvt(unsafe { inotify_add_watch(self.0.raw(), path.as_ptr(), mask) })
+    }
+
+    pub fn rm_watch(&self, wd: c_int) -> io::Result<()> {
+        cvt(unsafe { inotify_rm_watch(self.0.raw(), wd) })?;
+        Ok(())
+    }
+
+    /// Reads the events that are ready, waiting for some for at most
+    /// `timeout` if given. No events are returned if the time runs out.
+    pub fn read(&self, timeout: Option<Duration>) -> io::Result<Vec<Event>> {
+        if let Some(timeout) = timeout {
+            if !self.wait(timeout)? {
+                return Ok(Vec::new())
+            }
+        }
+
+        // Room for at least one event with the longest name there can be.
+        let mut buf = [0u8; 4096];
+        let len;
+        loop {
+            match self.0.read(&mut buf) {
+                Ok(n) => { len = n; break }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let header = mem::size_of::<inotify_event>();
+        let mut events = Vec::new();
+        let mut pos = 0;
+        while pos + header <= len {
+            // The buffer isn't aligned for the events in it.
+            let event = unsafe {
+                let mut event: inotify_event = mem::zeroed();
+                ptr::copy_nonoverlapping(buf[pos..].as_ptr(),
+                                         &mut event as *mut _ as *mut u8,
+                                         header);
+                event
+            };
+            let name = &buf[pos + header..cmp::min(pos + header + event.len as usize, len)];
+            // The name is padded with nuls.
+            let name = match name.iter().position(|&b| b == 0) {
+                Some(end) => &name[..end],
+                None => name,
+            };
+            events.push(Event {
+                wd: event.wd,
+                mask: event.mask,
+                cookie: event.cookie,
+                name: if name.is_empty() {
+                    None
+                } else {
+                    Some(OsString::from_vec(name.to_vec()))
+                },
+            });
+            pos += header + event.len as usize;
+        }
+        Ok(events)
+    }
+
+    // Waits for events to be ready for at most `timeout`, returning whether
+    // they are. A timeout too long to be represented is as good as none.
+    fn wait(&self, timeout: Duration) -> io::Result<bool> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut pfd = libc::pollfd {
+            fd: self.0.raw(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        loop {
+            let ms = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    let left = if now >= deadline {
+                        Duration::new(0, 0)
+                    } else {
+                        deadline.duration_since(now)
+                    };
+                    // Round up, so as not to wake up before the deadline.
+                    let ms = left.as_secs().saturating_mul(1000).saturating_add(
+                        (left.subsec_nanos() as u64 + 999_999) / 1_000_000);
+                    cmp::min(ms, c_int::max_value() as u64) as c_int
+                }
+                None => -1,
+            };
+            match cvt(unsafe { libc::poll(&mut pfd, 1, ms) }) {
+                Ok(0) if deadline.map_or(true, |d| Instant::now() < d) => {}
+                Ok(0) => return Ok(false),
+                Ok(_) => return Ok(true),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl AsRawFd for Inotify {
+    fn as_raw_fd(&self) -> RawFd { self.0.raw() }
+}
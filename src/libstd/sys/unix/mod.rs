@@ -10,7 +10,7 @@
 
 #![allow(missing_docs, bad_style)]
 
-use io::{self, ErrorKind};
+use io::ErrorKind;
 use libc;
 use num::One;
 use ops::Neg;
@@ -31,12 +31,16 @@ use ops::Neg;
 #[macro_use]
 pub mod weak;
 
+pub mod alloc;
 pub mod android;
 pub mod backtrace;
 pub mod condvar;
 pub mod ext;
 pub mod fd;
 pub mod fs;
+#[cfg(target_os = "linux")]
+pub mod inotify;
+pub mod io;
 pub mod mutex;
 pub mod net;
 pub mod os;
@@ -123,16 +127,16 @@ pub fn decode_error_kind(errno: i32) -> ErrorKind {
     }
 }
 
-pub fn cvt<T: One + PartialEq + Neg<Output=T>>(t: T) -> io::Result<T> {
+pub fn cvt<T: One + PartialEq + Neg<Output=T>>(t: T) -> ::io::Result<T> {
     let one: T = T::one();
     if t == -one {
-        Err(io::Error::last_os_error())
+        Err(::io::Error::last_os_error())
     } else {
         Ok(t)
     }
 }
 
-pub fn cvt_r<T, F>(mut f: F) -> io::Result<T>
+pub fn cvt_r<T, F>(mut f: F) -> ::io::Result<T>
     where T: One + PartialEq + Neg<Output=T>, F: FnMut() -> T
 {
     loop {
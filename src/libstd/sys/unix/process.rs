@@ -11,6 +11,7 @@
 use prelude::v1::*;
 use os::unix::prelude::*;
 
+use cmp;
 use collections::hash_map::{HashMap, Entry};
 use env;
 use ffi::{OsString, OsStr, CString, CStr};
@@ -23,6 +24,8 @@ use sys::fd::FileDesc;
 use sys::fs::{File, OpenOptions};
 use sys::pipe::{self, AnonPipe};
 use sys::{self, cvt, cvt_r};
+use thread;
+use time::{Duration, Instant};
 
 ////////////////////////////////////////////////////////////////////////////////
 // Command
@@ -56,6 +59,10 @@ pub struct Command {
     uid: Option<uid_t>,
     gid: Option<gid_t>,
     session_leader: bool,
+    pgroup: Option<pid_t>,
+    groups: Option<Vec<gid_t>>,
+    rlimits: Vec<(c_int, libc::rlimit)>,
+    fds: Vec<FdMapping>,
     saw_nul: bool,
     closures: Vec<Box<FnMut() -> io::Result<()> + Send + Sync>>,
     stdin: Option<Stdio>,
@@ -79,6 +86,15 @@ struct ChildPipes {
     stderr: ChildStdio,
 }
 
+// an extra descriptor to install in the child; `tmp` is scratch space for
+// `do_exec`, which can't allocate, to park `src` while the mappings are
+// shuffled into place
+struct FdMapping {
+    src: c_int,
+    dst: c_int,
+    tmp: c_int,
+}
+
 enum ChildStdio {
     Inherit,
     Explicit(c_int),
@@ -106,6 +122,10 @@ impl Command {
             uid: None,
             gid: None,
             session_leader: false,
+            pgroup: None,
+            groups: None,
+            rlimits: Vec::new(),
+            fds: Vec::new(),
             saw_nul: saw_nul,
             closures: Vec::new(),
             stdin: None,
@@ -200,6 +220,21 @@ impl Command {
     pub fn session_leader(&mut self, session_leader: bool) {
         self.session_leader = session_leader;
     }
+    pub fn process_group(&mut self, pgroup: pid_t) {
+        self.pgroup = Some(pgroup);
+    }
+    pub fn groups(&mut self, groups: &[gid_t]) {
+        self.groups = Some(groups.to_vec());
+    }
+    pub fn rlimit(&mut self, resource: c_int, soft: u64, hard: u64) {
+        self.rlimits.push((resource, libc::rlimit {
+            rlim_cur: soft as libc::rlim_t,
+            rlim_max: hard as libc::rlim_t,
+        }));
+    }
+    pub fn fd(&mut self, src: c_int, dst: c_int) {
+        self.fds.push(FdMapping { src: src, dst: dst, tmp: -1 });
+    }
 
     pub fn before_exec(&mut self,
                        f: Box<FnMut() -> io::Result<()> + Send + Sync>) {
@@ -352,6 +387,30 @@ impl Command {
             t!(cvt_r(|| libc::dup2(fd, libc::STDERR_FILENO)));
         }
 
+        // Extra descriptors are first duplicated above every target number so
+        // that installing one mapping can't clobber the source of another,
+        // then moved into place. `dup2` leaves the new descriptors without
+        // CLOEXEC, so they survive the exec.
+        if !self.fds.is_empty() {
+            let floor = self.fds.iter().fold(0, |m, f| cmp::max(m, f.dst)) + 1;
+            for fd in self.fds.iter_mut() {
+                fd.tmp = t!(cvt(libc::fcntl(fd.src, libc::F_DUPFD, floor)));
+            }
+            for fd in self.fds.iter() {
+                t!(cvt_r(|| libc::dup2(fd.tmp, fd.dst)));
+                libc::close(fd.tmp);
+            }
+        }
+
+        for &(resource, ref limit) in self.rlimits.iter() {
+            t!(cvt(libc::setrlimit(resource, limit)));
+        }
+
+        // Supplementary groups have to be set while we still have the
+        // privileges to do so, so before switching uid.
+        if let Some(ref groups) = self.groups {
+            t!(cvt(libc::setgroups(groups.len() as _, groups.as_ptr())));
+        }
         if let Some(u) = self.gid {
             t!(cvt(libc::setgid(u as gid_t)));
         }
@@ -362,8 +421,11 @@ impl Command {
             // groups that enable us to do super-user things. This will
             // fail if we aren't root, so don't bother checking the
             // return value, this is just done as an optimistic
-            // privilege dropping function.
-            let _ = libc::setgroups(0, ptr::null());
+            // privilege dropping function. An explicit list of groups was
+            // already installed above, though, so leave that alone.
+            if self.groups.is_none() {
+                let _ = libc::setgroups(0, ptr::null());
+            }
 
             t!(cvt(libc::setuid(u as uid_t)));
         }
@@ -373,6 +435,9 @@ impl Command {
             // error, but ignore it anyway.
             let _ = libc::setsid();
         }
+        if let Some(pgroup) = self.pgroup {
+            t!(cvt(libc::setpgid(0, pgroup)));
+        }
         if let Some(ref cwd) = self.cwd {
             t!(cvt(libc::chdir(cwd.as_ptr())));
         }
@@ -573,14 +638,18 @@ impl Process {
     }
 
     pub fn kill(&mut self) -> io::Result<()> {
+        self.send_signal(libc::SIGKILL)
+    }
+
+    pub fn send_signal(&mut self, signal: c_int) -> io::Result<()> {
         // If we've already waited on this process then the pid can be recycled
-        // and used for another process, and we probably shouldn't be killing
+        // and used for another process, and we probably shouldn't be signaling
         // random processes, so just return an error.
         if self.status.is_some() {
             Err(Error::new(ErrorKind::InvalidInput,
-                           "invalid argument: can't kill an exited process"))
+                           "invalid argument: can't signal an exited process"))
         } else {
-            cvt(unsafe { libc::kill(self.pid, libc::SIGKILL) }).map(|_| ())
+            cvt(unsafe { libc::kill(self.pid, signal) }).map(|_| ())
         }
     }
 
@@ -593,6 +662,46 @@ impl Process {
         self.status = Some(ExitStatus(status));
         Ok(ExitStatus(status))
     }
+
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status))
+        }
+        let mut status = 0 as c_int;
+        let pid = cvt_r(|| unsafe {
+            libc::waitpid(self.pid, &mut status, libc::WNOHANG)
+        })?;
+        if pid == 0 {
+            Ok(None)
+        } else {
+            self.status = Some(ExitStatus(status));
+            Ok(Some(ExitStatus(status)))
+        }
+    }
+
+    pub fn wait_timeout(&mut self, dur: Duration)
+                        -> io::Result<Option<ExitStatus>> {
+        // There's no portable way to block on a child with a timeout short of
+        // installing a SIGCHLD handler, which a library can't do without
+        // stepping on the application's toes, so poll with a backoff instead.
+        // A timeout too large to be represented as a deadline is as good as
+        // waiting forever.
+        let deadline = Instant::now().checked_add(dur);
+        let mut delay = Duration::from_millis(1);
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status))
+            }
+            let now = Instant::now();
+            let sleep = match deadline {
+                Some(deadline) if now >= deadline => return Ok(None),
+                Some(deadline) => cmp::min(delay, deadline - now),
+                None => delay,
+            };
+            thread::sleep(sleep);
+            delay = cmp::min(delay * 2, Duration::from_millis(50));
+        }
+    }
 }
 
 #[cfg(test)]
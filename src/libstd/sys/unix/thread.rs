@@ -18,6 +18,8 @@ use io;
 use libc;
 use mem;
 use ptr;
+#[cfg(target_os = "openbsd")]
+use sys::cvt;
 use sys::os;
 use time::Duration;
 
@@ -163,6 +165,245 @@ impl Drop for Thread {
     }
 }
 
+#[cfg(target_os = "linux")]
+pub fn available_parallelism() -> io::Result<usize> {
+    let cpus = match affinity(unsafe { libc::pthread_self() }) {
+        Ok(ref cpus) if !cpus.is_empty() => cpus.len(),
+        // More CPUs than fit in the set, or no affinity support at all.
+        _ => online_cpus()?,
+    };
+    Ok(match cgroups::quota() {
+        Some(quota) => cmp::min(cpus, quota),
+        None => cpus,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "openbsd")))]
+pub fn available_parallelism() -> io::Result<usize> {
+    online_cpus()
+}
+
+#[cfg(not(target_os = "openbsd"))]
+fn online_cpus() -> io::Result<usize> {
+    match unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) } {
+        -1 => Err(io::Error::last_os_error()),
+        0 => Err(io::Error::new(io::ErrorKind::Other,
+                                "the number of CPUs is unknown")),
+        n => Ok(n as usize),
+    }
+}
+
+#[cfg(target_os = "openbsd")]
+pub fn available_parallelism() -> io::Result<usize> {
+    let mut cpus: libc::c_uint = 0;
+    let mut cpus_size = mem::size_of_val(&cpus);
+    let mut mib = [libc::CTL_HW, libc::HW_NCPU, 0, 0];
+
+    unsafe {
+        cvt(libc::sysctl(mib.as_mut_ptr(),
+                         2,
+                         &mut cpus as *mut _ as *mut _,
+                         &mut cpus_size as *mut _ as *mut _,
+                         ptr::null_mut(),
+                         0))?;
+    }
+    match cpus {
+        0 => Err(io::Error::new(io::ErrorKind::Other,
+                                "the number of CPUs is unknown")),
+        n => Ok(n as usize),
+    }
+}
+
+// The number of CPUs glibc's `cpu_set_t` has room for, which is what the
+// kernel expects unless it was built for more.
+#[cfg(target_os = "linux")]
+const CPU_SETSIZE: usize = 1024;
+
+#[cfg(target_os = "linux")]
+extern {
+    fn pthread_setaffinity_np(thread: libc::pthread_t,
+                              cpusetsize: libc::size_t,
+                              cpuset: *const libc::c_ulong) -> libc::c_int;
+    fn pthread_getaffinity_np(thread: libc::pthread_t,
+                              cpusetsize: libc::size_t,
+                              cpuset: *mut libc::c_ulong) -> libc::c_int;
+}
+
+// A `cpu_set_t` is an array of `unsigned long`s, with CPU `n` being bit
+// `n % bits` of word `n / bits`.
+#[cfg(target_os = "linux")]
+fn ulong_bits() -> usize {
+    mem::size_of::<libc::c_ulong>() * 8
+}
+
+#[cfg(target_os = "linux")]
+pub fn set_affinity(thread: libc::pthread_t, cpus: &[usize]) -> io::Result<()> {
+    let bits = ulong_bits();
+    let mut set = vec![0 as libc::c_ulong; CPU_SETSIZE / bits];
+    for &cpu in cpus {
+        if cpu >= CPU_SETSIZE {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "CPU number out of range"))
+        }
+        set[cpu / bits] |= 1 << (cpu % bits);
+    }
+    let size = set.len() * mem::size_of::<libc::c_ulong>();
+    match unsafe { pthread_setaffinity_np(thread, size, set.as_ptr()) } {
+        0 => Ok(()),
+        e => Err(io::Error::from_raw_os_error(e)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn affinity(thread: libc::pthread_t) -> io::Result<Vec<usize>> {
+    let bits = ulong_bits();
+    let mut set = vec![0 as libc::c_ulong; CPU_SETSIZE / bits];
+    let size = set.len() * mem::size_of::<libc::c_ulong>();
+    match unsafe { pthread_getaffinity_np(thread, size, set.as_mut_ptr()) } {
+        0 => {}
+        e => return Err(io::Error::from_raw_os_error(e)),
+    }
+    Ok((0..CPU_SETSIZE).filter(|&cpu| set[cpu / bits] & (1 << (cpu % bits)) != 0)
+                       .collect())
+}
+
+// Container runtimes limit the CPU time of a container with a cgroup quota
+// rather than with its affinity mask, so a process in a container with a
+// quota of two CPUs can still see, and be scheduled on, all of the host's.
+#[cfg(target_os = "linux")]
+mod cgroups {
+    use prelude::v1::*;
+
+    use cmp;
+    use fs::File;
+    use io::{self, Read};
+    use path::{Path, PathBuf};
+
+    // The number of CPUs the cgroup quotas allow the process to use, or
+    // `None` if it isn't limited or the limit can't be determined.
+    pub fn quota() -> Option<usize> {
+        let cgroups = match read("/proc/self/cgroup") {
+            Ok(cgroups) => cgroups,
+            Err(_) => return None,
+        };
+        let mut quota = None;
+        for line in cgroups.lines() {
+            // Each line is "hierarchy-ID:controller-list:cgroup-path", with a
+            // hierarchy ID of 0 and no controllers for cgroup v2.
+            let fields = line.splitn(3, ':').collect::<Vec<_>>();
+            if fields.len() != 3 {
+                continue
+            }
+            let limit = if fields[0] == "0" && fields[1].is_empty() {
+                find_mount("cgroup2", None).and_then(|(mount, root)| {
+                    smallest_limit(&mount, &root, fields[2], quota_v2)
+                })
+            } else if fields[1].split(',').any(|c| c == "cpu") {
+                find_mount("cgroup", Some("cpu")).and_then(|(mount, root)| {
+                    smallest_limit(&mount, &root, fields[2], quota_v1)
+                })
+            } else {
+                continue
+            };
+            quota = min(quota, limit);
+        }
+        quota
+    }
+
+    fn min(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+        match (a, b) {
+            (Some(a), Some(b)) => Some(cmp::min(a, b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    // Quotas of ancestors apply too, so this looks at every directory from
+    // that of `group` up to the root of the hierarchy.
+    fn smallest_limit(mount: &Path, root: &str, group: &str,
+                      limit: fn(&Path) -> Option<usize>) -> Option<usize> {
+        // The hierarchy may be mounted from some cgroup other than its root,
+        // as it usually is in containers.
+        let mut dir = match Path::new(group).strip_prefix(root) {
+            Ok(relative) => mount.join(relative),
+            Err(_) => return None,
+        };
+        let mut smallest = None;
+        loop {
+            smallest = min(smallest, limit(&dir));
+            if dir.as_path() == mount || !dir.pop() {
+                return smallest
+            }
+        }
+    }
+
+    // cgroup v2 has "$MAX $PERIOD" in `cpu.max`, with a `$MAX` of "max" if
+    // there is no limit.
+    fn quota_v2(dir: &Path) -> Option<usize> {
+        let max = match read(dir.join("cpu.max")) {
+            Ok(max) => max,
+            Err(_) => return None,
+        };
+        let mut fields = max.split_whitespace().map(|f| f.parse::<u64>().ok());
+        match (fields.next(), fields.next()) {
+            (Some(Some(quota)), Some(Some(period))) => cpus(quota, period),
+            _ => None,
+        }
+    }
+
+    // cgroup v1 has separate files, with a quota of -1 if there is no limit.
+    fn quota_v1(dir: &Path) -> Option<usize> {
+        let quota = read(dir.join("cpu.cfs_quota_us")).ok()
+                        .and_then(|q| q.trim().parse::<i64>().ok());
+        let period = read(dir.join("cpu.cfs_period_us")).ok()
+                         .and_then(|p| p.trim().parse::<u64>().ok());
+        match (quota, period) {
+            (Some(quota), Some(period)) if quota > 0 => cpus(quota as u64, period),
+            _ => None,
+        }
+    }
+
+    fn cpus(quota: u64, period: u64) -> Option<usize> {
+        if period == 0 {
+            return None
+        }
+        Some(cmp::max(1, quota / period) as usize)
+    }
+
+    // Finds the mount point of a filesystem of type `fstype`, and the path
+    // within the filesystem it is mounted from. A line of mountinfo reads
+    // "36 35 98:0 /root /mount/point rw master:1 - fstype source options".
+    fn find_mount(fstype: &str, option: Option<&str>) -> Option<(PathBuf, String)> {
+        let info = match read("/proc/self/mountinfo") {
+            Ok(info) => info,
+            Err(_) => return None,
+        };
+        for line in info.lines() {
+            let halves = line.splitn(2, " - ").collect::<Vec<_>>();
+            if halves.len() != 2 {
+                continue
+            }
+            let mount = halves[0].split(' ').collect::<Vec<_>>();
+            let fs = halves[1].split(' ').collect::<Vec<_>>();
+            if mount.len() < 5 || fs.len() < 3 || fs[0] != fstype {
+                continue
+            }
+            if let Some(option) = option {
+                if !fs[2].split(',').any(|o| o == option) {
+                    continue
+                }
+            }
+            return Some((PathBuf::from(mount[4]), mount[3].to_string()))
+        }
+        None
+    }
+
+    fn read<P: AsRef<Path>>(path: P) -> io::Result<String> {
+        let mut s = String::new();
+        File::open(path)?.read_to_string(&mut s)?;
+        Ok(s)
+    }
+}
+
 #[cfg(all(not(all(target_os = "linux", not(target_env = "musl"))),
           not(target_os = "freebsd"),
           not(target_os = "macos"),
@@ -56,10 +56,13 @@ mod inner {
         }
 
         pub fn add_duration(&self, other: &Duration) -> Instant {
-            Instant {
-                t: self.t.checked_add(dur2intervals(other))
-                       .expect("overflow when adding duration to instant"),
-            }
+            self.checked_add_duration(other)
+                .expect("overflow when adding duration to instant")
+        }
+
+        pub fn checked_add_duration(&self, other: &Duration) -> Option<Instant> {
+            checked_dur2intervals(other).and_then(|intervals| self.t.checked_add(intervals))
+                                        .map(|t| Instant { t: t })
         }
 
         pub fn sub_duration(&self, other: &Duration) -> Instant {
@@ -184,11 +187,14 @@ mod inner {
     }
 
     fn dur2intervals(dur: &Duration) -> u64 {
+        checked_dur2intervals(dur).expect("overflow converting duration to nanoseconds")
+    }
+
+    fn checked_dur2intervals(dur: &Duration) -> Option<u64> {
         let info = info();
-        let nanos = dur.as_secs().checked_mul(NSEC_PER_SEC).and_then(|nanos| {
+        dur.as_secs().checked_mul(NSEC_PER_SEC).and_then(|nanos| {
             nanos.checked_add(dur.subsec_nanos() as u64)
-        }).expect("overflow converting duration to nanoseconds");
-        mul_div_u64(nanos, info.denom as u64, info.numer as u64)
+        }).map(|nanos| mul_div_u64(nanos, info.denom as u64, info.numer as u64))
     }
 
     fn info() -> &'static libc::mach_timebase_info {
@@ -255,6 +261,10 @@ mod inner {
             Instant { t: self.t.add_duration(other) }
         }
 
+        pub fn checked_add_duration(&self, other: &Duration) -> Option<Instant> {
+            self.t.checked_add_duration(other).map(|t| Instant { t: t })
+        }
+
         pub fn sub_duration(&self, other: &Duration) -> Instant {
             Instant { t: self.t.sub_duration(other) }
         }
@@ -341,23 +351,37 @@ mod inner {
         }
 
         fn add_duration(&self, other: &Duration) -> Timespec {
-            let secs = (self.t.tv_sec as i64).checked_add(other.as_secs() as i64);
-            let mut secs = secs.expect("overflow when adding duration to time");
+            self.checked_add_duration(other).expect("overflow when adding duration to time")
+        }
+
+        fn checked_add_duration(&self, other: &Duration) -> Option<Timespec> {
+            if other.as_secs() > i64::max_value() as u64 {
+                return None
+            }
+            let mut secs = match (self.t.tv_sec as i64).checked_add(other.as_secs() as i64) {
+                Some(secs) => secs,
+                None => return None,
+            };
 
             // Nano calculations can't overflow because nanos are <1B which fit
             // in a u32.
             let mut nsec = other.subsec_nanos() + self.t.tv_nsec as u32;
             if nsec >= NSEC_PER_SEC as u32 {
                 nsec -= NSEC_PER_SEC as u32;
-                secs = secs.checked_add(1).expect("overflow when adding \
-                                                   duration to time");
+                secs = match secs.checked_add(1) {
+                    Some(secs) => secs,
+                    None => return None,
+                };
             }
-            Timespec {
+            if secs > libc::time_t::max_value() as i64 {
+                return None
+            }
+            Some(Timespec {
                 t: libc::timespec {
                     tv_sec: secs as libc::time_t,
                     tv_nsec: nsec as libc::c_long,
                 },
-            }
+            })
         }
 
         fn sub_duration(&self, other: &Duration) -> Timespec {
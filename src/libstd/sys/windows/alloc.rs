@@ -0,0 +1,91 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The system allocator behind `std::heap::System`, mirroring the
+//! implementation in `alloc_system`.
+
+use sys::c;
+
+#[cfg(target_arch = "x86")]
+const MIN_ALIGN: usize = 8;
+#[cfg(target_arch = "x86_64")]
+const MIN_ALIGN: usize = 16;
+
+#[repr(C)]
+struct Header(*mut u8);
+
+unsafe fn get_header<'a>(ptr: *mut u8) -> &'a mut Header {
+    &mut *(ptr as *mut Header).offset(-1)
+}
+
+unsafe fn align_ptr(ptr: *mut u8, align: usize) -> *mut u8 {
+    let aligned = ptr.offset((align - (ptr as usize & (align - 1))) as isize);
+    *get_header(aligned) = Header(ptr);
+    aligned
+}
+
+pub unsafe fn allocate(size: usize, align: usize) -> *mut u8 {
+    if align <= MIN_ALIGN {
+        c::HeapAlloc(c::GetProcessHeap(), 0, size as c::SIZE_T) as *mut u8
+    } else {
+        let ptr = c::HeapAlloc(c::GetProcessHeap(), 0, (size + align) as c::SIZE_T) as *mut u8;
+        if ptr.is_null() {
+            return ptr;
+        }
+        align_ptr(ptr, align)
+    }
+}
+
+pub unsafe fn reallocate(ptr: *mut u8, _old_size: usize, size: usize, align: usize) -> *mut u8 {
+    if align <= MIN_ALIGN {
+        c::HeapReAlloc(c::GetProcessHeap(), 0, ptr as c::LPVOID, size as c::SIZE_T) as *mut u8
+    } else {
+        let header = get_header(ptr);
+        let new = c::HeapReAlloc(c::GetProcessHeap(),
+                                 0,
+                                 header.0 as c::LPVOID,
+                                 (size + align) as c::SIZE_T) as *mut u8;
+        if new.is_null() {
+            return new;
+        }
+        align_ptr(new, align)
+    }
+}
+
+pub unsafe fn reallocate_inplace(ptr: *mut u8,
+                                 old_size: usize,
+                                 size: usize,
+                                 align: usize)
+                                 -> usize {
+    if align <= MIN_ALIGN {
+        let new = c::HeapReAlloc(c::GetProcessHeap(),
+                                 c::HEAP_REALLOC_IN_PLACE_ONLY,
+                                 ptr as c::LPVOID,
+                                 size as c::SIZE_T) as *mut u8;
+        if new.is_null() {
+            old_size
+        } else {
+            size
+        }
+    } else {
+        old_size
+    }
+}
+
+pub unsafe fn deallocate(ptr: *mut u8, _old_size: usize, align: usize) {
+    if align <= MIN_ALIGN {
+        let err = c::HeapFree(c::GetProcessHeap(), 0, ptr as c::LPVOID);
+        debug_assert!(err != 0);
+    } else {
+        let header = get_header(ptr);
+        let err = c::HeapFree(c::GetProcessHeap(), 0, header.0 as c::LPVOID);
+        debug_assert!(err != 0);
+    }
+}
@@ -83,6 +83,11 @@ pub struct FilePermissions { attrs: c::DWORD }
 
 pub struct DirBuilder;
 
+// `CopyFileEx` always copies the attributes, the timestamps and the alternate
+// data streams, so there is nothing to choose.
+#[derive(Clone, Debug)]
+pub struct CopyOptions;
+
 impl Iterator for ReadDir {
     type Item = io::Result<DirEntry>;
     fn next(&mut self) -> Option<io::Result<DirEntry>> {
@@ -272,6 +277,36 @@ impl File {
 
     pub fn datasync(&self) -> io::Result<()> { self.fsync() }
 
+    pub fn lock(&self, exclusive: bool) -> io::Result<()> {
+        let flags = if exclusive { c::LOCKFILE_EXCLUSIVE_LOCK } else { 0 };
+        self.lock_file(flags)
+    }
+
+    pub fn try_lock(&self, exclusive: bool) -> io::Result<bool> {
+        let flags = if exclusive { c::LOCKFILE_EXCLUSIVE_LOCK } else { 0 };
+        match self.lock_file(flags | c::LOCKFILE_FAIL_IMMEDIATELY) {
+            Ok(()) => Ok(true),
+            Err(ref e) if e.raw_os_error() ==
+                          Some(c::ERROR_LOCK_VIOLATION as i32) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Locks the whole file, however large it gets.
+    fn lock_file(&self, flags: c::DWORD) -> io::Result<()> {
+        unsafe {
+            let mut overlapped: c::OVERLAPPED = mem::zeroed();
+            cvt(c::LockFileEx(self.handle.raw(), flags, 0, !0, !0,
+                              &mut overlapped))?;
+        }
+        Ok(())
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        cvt(unsafe { c::UnlockFile(self.handle.raw(), 0, 0, !0, !0) })?;
+        Ok(())
+    }
+
     pub fn truncate(&self, size: u64) -> io::Result<()> {
         let mut info = c::FILE_END_OF_FILE_INFO {
             EndOfFile: size as c::LARGE_INTEGER,
@@ -512,6 +547,14 @@ impl FileType {
     }
 }
 
+impl CopyOptions {
+    pub fn new() -> CopyOptions { CopyOptions }
+
+    pub fn permissions(&mut self, _permissions: bool) {}
+    pub fn timestamps(&mut self, _timestamps: bool) {}
+    pub fn xattrs(&mut self, _xattrs: bool) {}
+}
+
 impl DirBuilder {
     pub fn new() -> DirBuilder { DirBuilder }
 
@@ -672,7 +715,7 @@ pub fn canonicalize(p: &Path) -> io::Result<PathBuf> {
     get_path(&f)
 }
 
-pub fn copy(from: &Path, to: &Path) -> io::Result<u64> {
+pub fn copy(from: &Path, to: &Path, _opts: &CopyOptions) -> io::Result<u64> {
     unsafe extern "system" fn callback(
         _TotalFileSize: c::LARGE_INTEGER,
         TotalBytesTransferred: c::LARGE_INTEGER,
@@ -24,6 +24,7 @@ use sync::Once;
 use sys::c;
 use sys;
 use sys_common::{self, AsInner, FromInner, IntoInner};
+use sys_common::io::{default_read_vectored, default_write_vectored};
 use sys_common::io::read_to_end_uninitialized;
 use sys_common::net;
 use time::Duration;
@@ -148,11 +149,25 @@ impl Socket {
         }
     }
 
+    pub fn read_vectored(&self, bufs: &mut [io::IoSliceMut]) -> io::Result<usize> {
+        default_read_vectored(|b| self.read(b), bufs)
+    }
+
     pub fn read_to_end(&self, buf: &mut Vec<u8>) -> io::Result<usize> {
         let mut me = self;
         (&mut me).read_to_end(buf)
     }
 
+    pub fn write_vectored(&self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        default_write_vectored(|buf| {
+            let len = cmp::min(buf.len(), i32::max_value() as usize) as i32;
+            let ret = cvt(unsafe {
+                c::send(self.0, buf.as_ptr() as *const c_void, len, 0)
+            })?;
+            Ok(ret as usize)
+        }, bufs)
+    }
+
     pub fn set_timeout(&self, dur: Option<Duration>,
                        kind: c_int) -> io::Result<()> {
         let timeout = match dur {
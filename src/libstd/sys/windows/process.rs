@@ -32,6 +32,7 @@ use sys::pipe::{self, AnonPipe};
 use sys::stdio;
 use sys::{self, cvt};
 use sys_common::{AsInner, FromInner};
+use time::Duration;
 
 ////////////////////////////////////////////////////////////////////////////////
 // Command
@@ -320,6 +321,28 @@ impl Process {
         }
     }
 
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.wait_ms(0)
+    }
+
+    pub fn wait_timeout(&mut self, dur: Duration)
+                        -> io::Result<Option<ExitStatus>> {
+        self.wait_ms(super::dur2timeout(dur))
+    }
+
+    fn wait_ms(&mut self, ms: c::DWORD) -> io::Result<Option<ExitStatus>> {
+        unsafe {
+            match c::WaitForSingleObject(self.handle.raw(), ms) {
+                c::WAIT_OBJECT_0 => {}
+                c::WAIT_TIMEOUT => return Ok(None),
+                _ => return Err(Error::last_os_error()),
+            }
+            let mut status = 0;
+            cvt(c::GetExitCodeProcess(self.handle.raw(), &mut status))?;
+            Ok(Some(ExitStatus(status)))
+        }
+    }
+
     pub fn handle(&self) -> &Handle { &self.handle }
 
     pub fn into_handle(self) -> Handle { self.handle }
@@ -84,6 +84,19 @@ impl Thread {
     pub fn into_handle(self) -> Handle { self.handle }
 }
 
+pub fn available_parallelism() -> io::Result<usize> {
+    let cpus = unsafe {
+        let mut info: c::SYSTEM_INFO = mem::zeroed();
+        c::GetSystemInfo(&mut info);
+        info.dwNumberOfProcessors as usize
+    };
+    match cpus {
+        0 => Err(io::Error::new(io::ErrorKind::Other,
+                                "the number of CPUs is unknown")),
+        n => Ok(n),
+    }
+}
+
 #[cfg_attr(test, allow(dead_code))]
 pub mod guard {
     pub unsafe fn current() -> Option<usize> { None }
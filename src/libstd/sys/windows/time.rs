@@ -67,16 +67,23 @@ impl Instant {
     }
 
     pub fn add_duration(&self, other: &Duration) -> Instant {
+        self.checked_add_duration(other).expect("overflow when adding duration to time")
+    }
+
+    pub fn checked_add_duration(&self, other: &Duration) -> Option<Instant> {
         let freq = frequency() as u64;
-        let t = other.as_secs().checked_mul(freq).and_then(|i| {
+        other.as_secs().checked_mul(freq).and_then(|i| {
             (self.t as u64).checked_add(i)
         }).and_then(|i| {
             i.checked_add(mul_div_u64(other.subsec_nanos() as u64, freq,
                                       NANOS_PER_SEC))
-        }).expect("overflow when adding duration to time");
-        Instant {
-            t: t as c::LARGE_INTEGER,
-        }
+        }).and_then(|t| {
+            if t > c::LARGE_INTEGER::max_value() as u64 {
+                None
+            } else {
+                Some(Instant { t: t as c::LARGE_INTEGER })
+            }
+        })
     }
 
     pub fn sub_duration(&self, other: &Duration) -> Instant {
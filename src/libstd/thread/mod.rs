@@ -72,6 +72,31 @@
 //! value produced by the child thread, or `Err` of the value given to
 //! a call to `panic!` if the child panicked.
 //!
+//! ## Scoped threads
+//!
+//! Threads created with `spawn` may outlive their parent, so they can only
+//! borrow `'static` data. The `scope` function instead waits for all of the
+//! threads spawned within it before returning, which lets them borrow from
+//! the parent's stack:
+//!
+//! ```rust
+//! #![feature(thread_scope)]
+//! use std::thread;
+//!
+//! let data = vec![1, 2, 3];
+//! thread::scope(|s| {
+//!     s.spawn(|| println!("{:?}", data));
+//! });
+//! ```
+//!
+//! ## Thread pools
+//!
+//! Spawning a thread per task is wasteful when there are many small tasks.
+//! A `ThreadPool` runs them on a fixed set of worker threads instead, one per
+//! CPU by default, and its `scope` method spawns tasks which may borrow from
+//! the parent's stack, just like `scope` does for threads. The `par` module
+//! builds parallel iterators on top of it.
+//!
 //! ## Configuring threads
 //!
 //! A new thread can be configured before it is spawned via the `Builder` type,
@@ -200,6 +225,26 @@ pub use self::scoped_tls::ScopedKey;
 #[unstable(feature = "libstd_thread_internals", issue = "0")]
 #[doc(hidden)] pub use self::scoped_tls::__KeyInner as __ScopedKeyInner;
 
+////////////////////////////////////////////////////////////////////////////////
+// Scoped threads
+////////////////////////////////////////////////////////////////////////////////
+
+mod scope;
+
+#[unstable(feature = "thread_scope", issue = "0")]
+pub use self::scope::{scope, Scope, ScopedJoinHandle};
+use self::scope::ScopeData;
+
+////////////////////////////////////////////////////////////////////////////////
+// Thread pools
+////////////////////////////////////////////////////////////////////////////////
+
+mod pool;
+pub mod par;
+
+#[unstable(feature = "thread_pool", issue = "0")]
+pub use self::pool::{ThreadPool, PoolScope};
+
 ////////////////////////////////////////////////////////////////////////////////
 // Builder
 ////////////////////////////////////////////////////////////////////////////////
@@ -255,6 +300,23 @@ impl Builder {
     #[stable(feature = "rust1", since = "1.0.0")]
     pub fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<T>> where
         F: FnOnce() -> T, F: Send + 'static, T: Send + 'static
+    {
+        unsafe { self.spawn_unchecked(f, None).map(JoinHandle) }
+    }
+
+    // Spawns a thread without requiring `F` and `T` to be `'static`.
+    //
+    // The caller has to ensure that the thread is done with anything `f`
+    // borrows before it goes away. If a scope is given, it is told about the
+    // thread once the thread is done with its packet, which is after `f` has
+    // been dropped, and after the result (or panic payload) too unless the
+    // handle still holds it. The handle can't outlive the scope, so `scope`
+    // relies on this to know when all of its threads are finished with the
+    // parent's stack, even if a handle was leaked.
+    unsafe fn spawn_unchecked<'a, F, T>(self, f: F,
+                                        scope: Option<Arc<ScopeData>>)
+                                        -> io::Result<JoinInner<T>> where
+        F: FnOnce() -> T, F: Send + 'a, T: Send + 'a
     {
         let Builder { name, stack_size } = self;
 
@@ -263,8 +325,10 @@ impl Builder {
         let my_thread = Thread::new(name);
         let their_thread = my_thread.clone();
 
-        let my_packet : Arc<UnsafeCell<Option<Result<T>>>>
-            = Arc::new(UnsafeCell::new(None));
+        let my_packet = Arc::new(Packet {
+            scope: scope,
+            result: UnsafeCell::new(None),
+        });
         let their_packet = my_packet.clone();
 
         let main = move || {
@@ -278,19 +342,38 @@ impl Builder {
                     let ptr = &mut output;
                     unwind::try(move || *ptr = Some(f()))
                 };
-                *their_packet.get() = Some(try_result.map(|()| {
+                let panicked = try_result.is_err();
+                *their_packet.result.get() = Some(try_result.map(|()| {
                     output.unwrap()
                 }));
+                let scope = their_packet.scope.clone();
+                drop(their_packet);
+                if let Some(scope) = scope {
+                    scope.decrement_num_running_threads(panicked);
+                }
             }
         };
 
-        Ok(JoinHandle(JoinInner {
-            native: unsafe {
-                Some(imp::Thread::new(stack_size, Box::new(main))?)
-            },
+        if let Some(ref scope) = my_packet.scope {
+            scope.increment_num_running_threads();
+        }
+
+        let native = match imp::Thread::new(stack_size, Box::new(main)) {
+            Ok(native) => native,
+            Err(e) => {
+                // The thread never ran, so it won't tell the scope it's done.
+                if let Some(ref scope) = my_packet.scope {
+                    scope.decrement_num_running_threads(false);
+                }
+                return Err(e)
+            }
+        };
+
+        Ok(JoinInner {
+            native: Some(native),
             thread: my_thread,
-            packet: Packet(my_packet),
-        }))
+            packet: my_packet,
+        })
     }
 }
 
@@ -445,6 +528,39 @@ pub fn park_timeout(dur: Duration) {
     *guard = false;
 }
 
+/// Returns an estimate of the number of threads the program can usefully run
+/// in parallel.
+///
+/// This is usually the number of CPUs, but where the platform restricts
+/// which CPUs a process may run on, or how much CPU time it may use, those
+/// restrictions are taken into account. On Linux, that means both the
+/// affinity mask of the calling thread and the CPU quota of the process's
+/// cgroup, so a program in a container is sized to the container rather
+/// than to the host.
+///
+/// The value may change while the program runs, as the restrictions do, so
+/// it is best used as a hint when sizing a thread pool.
+///
+/// # Errors
+///
+/// Returns an error if the number can't be determined. Callers usually fall
+/// back to 1 then.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(available_parallelism)]
+///
+/// use std::thread;
+///
+/// let threads = thread::available_parallelism().unwrap_or(1);
+/// assert!(threads > 0);
+/// ```
+#[unstable(feature = "available_parallelism", issue = "0")]
+pub fn available_parallelism() -> io::Result<usize> {
+    imp::available_parallelism()
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Thread
 ////////////////////////////////////////////////////////////////////////////////
@@ -524,35 +640,42 @@ impl thread_info::NewThread for Thread {
 pub type Result<T> = ::result::Result<T, Box<Any + Send + 'static>>;
 
 // This packet is used to communicate the return value between the child thread
-// and the parent thread. Memory is shared through the `Arc` within and there's
-// no need for a mutex here because synchronization happens with `join()` (the
-// parent thread never reads this packet until the child has exited).
+// and the parent thread. Memory is shared through an `Arc` and there's no need
+// for a mutex here because synchronization happens with `join()` (the parent
+// thread never reads this packet until the child has exited).
 //
 // This packet itself is then stored into a `JoinInner` which in turns is placed
-// in `JoinHandle` and `JoinGuard`. Due to the usage of `UnsafeCell` we need to
-// manually worry about impls like Send and Sync. The type `T` should
+// in `JoinHandle` and `ScopedJoinHandle`. Due to the usage of `UnsafeCell` we
+// need to manually worry about impls like Send and Sync. The type `T` should
 // already always be Send (otherwise the thread could not have been created) and
-// this type is inherently Sync because no methods take &self. Regardless,
-// however, we add inheriting impls for Send/Sync to this type to ensure it's
-// Send/Sync and that future modifications will still appropriately classify it.
-struct Packet<T>(Arc<UnsafeCell<Option<Result<T>>>>);
+// the packet is only ever accessed by one thread at a time, so sharing it
+// through the `Arc` is fine as long as `T` can be sent.
+struct Packet<T> {
+    scope: Option<Arc<ScopeData>>,
+    result: UnsafeCell<Option<Result<T>>>,
+}
 
 unsafe impl<T: Send> Send for Packet<T> {}
-unsafe impl<T: Sync> Sync for Packet<T> {}
+unsafe impl<T: Send> Sync for Packet<T> {}
 
 /// Inner representation for JoinHandle
 struct JoinInner<T> {
     native: Option<imp::Thread>,
     thread: Thread,
-    packet: Packet<T>,
+    packet: Arc<Packet<T>>,
 }
 
 impl<T> JoinInner<T> {
     fn join(&mut self) -> Result<T> {
         self.native.take().unwrap().join();
-        unsafe {
-            (*self.packet.0.get()).take().unwrap()
+        let result = unsafe {
+            (*self.packet.result.get()).take().unwrap()
+        };
+        // A panic which is joined on is no longer the scope's to report.
+        if let (&Err(_), Some(ref scope)) = (&result, self.packet.scope.as_ref()) {
+            scope.panic_joined();
         }
+        result
     }
 }
 
@@ -616,6 +739,11 @@ mod tests {
     // !!! These tests are dangerous. If something is buggy, they will hang, !!!
     // !!! instead of exiting cleanly. This might wedge the buildbots.       !!!
 
+    #[test]
+    fn test_available_parallelism() {
+        assert!(thread::available_parallelism().unwrap() > 0);
+    }
+
     #[test]
     fn test_unnamed_thread() {
         thread::spawn(move|| {
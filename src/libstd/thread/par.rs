@@ -0,0 +1,616 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parallel iterators.
+//!
+//! A parallel iterator is the counterpart of an `Iterator` whose items are
+//! processed on a thread pool. Slices get one through the `par_iter` and
+//! `par_iter_mut` methods of `ParallelSlice`, and integer ranges through
+//! `IntoParallelIterator::into_par_iter`. Adapters like `map` and `filter`
+//! and consumers like `fold` and `collect` work much like their sequential
+//! namesakes, except that the closures may be called from several threads at
+//! once, so they are `Fn` and `Sync` rather than `FnMut`.
+//!
+//! The input is split in halves recursively, with `ThreadPool::join`, until
+//! there are a few pieces per worker. Each piece is then processed like an
+//! ordinary iterator, and the results of the pieces are combined in order.
+//!
+//! Parallel iterators run on the pool of the worker thread they are used
+//! from, so `ThreadPool::install` picks the pool to use. Everywhere else
+//! they run on a global pool with a worker per CPU, which is started when
+//! first needed.
+//!
+//! # Examples
+//!
+//! ```
+//! #![feature(parallel_iterator)]
+//!
+//! use std::thread::par::{IntoParallelIterator, ParallelIterator, ParallelSlice};
+//!
+//! let squares = (0..1000u32).into_par_iter()
+//!                           .map(|x| x * x)
+//!                           .collect::<Vec<_>>();
+//! assert_eq!(squares[999], 998_001);
+//!
+//! let evens = squares.par_iter().filter(|&&x| x % 2 == 0).count();
+//! assert_eq!(evens, 500);
+//! ```
+
+#![unstable(feature = "parallel_iterator", issue = "0")]
+
+use prelude::v1::*;
+
+use cmp;
+use ops::Range;
+use slice;
+use sync::Arc;
+use super::pool::{self, Registry};
+
+/// A parallel version of `Iterator`.
+///
+/// See the module documentation for details.
+pub trait ParallelIterator: Sized + Send {
+    /// The type of the elements being iterated over.
+    type Item: Send;
+
+    /// Feeds the items of this iterator to `consumer`, which is how all the
+    /// other methods are implemented.
+    #[doc(hidden)]
+    fn drive<C>(self, consumer: C) -> C::Result where C: Consumer<Self::Item>;
+
+    /// Takes a closure and creates a parallel iterator which calls that
+    /// closure on each element.
+    fn map<F, R>(self, f: F) -> Map<Self, F>
+        where F: Fn(Self::Item) -> R, F: Sync + Send, R: Send
+    {
+        Map { base: self, f: f }
+    }
+
+    /// Creates a parallel iterator which uses a closure to determine if an
+    /// element should be yielded.
+    fn filter<P>(self, predicate: P) -> Filter<Self, P>
+        where P: Fn(&Self::Item) -> bool, P: Sync + Send
+    {
+        Filter { base: self, predicate: predicate }
+    }
+
+    /// Calls a closure on each element.
+    fn for_each<F>(self, f: F) where F: Fn(Self::Item), F: Sync {
+        self.drive(ForEachConsumer { f: &f })
+    }
+
+    /// Folds every element into an accumulator.
+    ///
+    /// Each piece of the input is folded separately, starting from a fresh
+    /// value returned by `identity`, and then the accumulators of adjacent
+    /// pieces are combined with `reduce_op`. For the result to be
+    /// deterministic, `reduce_op` has to be associative, and the value
+    /// returned by `identity` has to be neutral to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(parallel_iterator)]
+    ///
+    /// use std::thread::par::{ParallelIterator, ParallelSlice};
+    ///
+    /// let words = ["parallel", "fold", "example"];
+    /// let letters = words.par_iter().fold(|| 0, |n, w| n + w.len(), |a, b| a + b);
+    /// assert_eq!(letters, 19);
+    /// ```
+    fn fold<T, ID, F, R>(self, identity: ID, fold_op: F, reduce_op: R) -> T
+        where T: Send,
+              ID: Fn() -> T, ID: Sync,
+              F: Fn(T, Self::Item) -> T, F: Sync,
+              R: Fn(T, T) -> T, R: Sync
+    {
+        self.drive(FoldConsumer {
+            identity: &identity,
+            fold_op: &fold_op,
+            reduce_op: &reduce_op,
+        })
+    }
+
+    /// Reduces the elements to a single one with an associative operation,
+    /// starting every piece of the input from the value returned by
+    /// `identity`.
+    fn reduce<ID, OP>(self, identity: ID, op: OP) -> Self::Item
+        where ID: Fn() -> Self::Item, ID: Sync,
+              OP: Fn(Self::Item, Self::Item) -> Self::Item, OP: Sync
+    {
+        self.fold(&identity, &op, &op)
+    }
+
+    /// Counts the elements.
+    fn count(self) -> usize {
+        self.fold(|| 0, |n, _| n + 1, |a, b| a + b)
+    }
+
+    /// Transforms the parallel iterator into a collection.
+    ///
+    /// The order of the elements is kept, as if the iterator had been
+    /// processed sequentially.
+    fn collect<C>(self) -> C where C: FromParallelIterator<Self::Item> {
+        C::from_par_iter(self)
+    }
+}
+
+/// Conversion into a `ParallelIterator`.
+pub trait IntoParallelIterator {
+    /// The type of the elements being iterated over.
+    type Item: Send;
+
+    /// Which kind of parallel iterator are we turning this into?
+    type IntoIter: ParallelIterator<Item = Self::Item>;
+
+    /// Creates a parallel iterator from a value.
+    fn into_par_iter(self) -> Self::IntoIter;
+}
+
+/// Conversion from a `ParallelIterator`.
+pub trait FromParallelIterator<T: Send> {
+    /// Creates a value from a parallel iterator.
+    fn from_par_iter<I>(par_iter: I) -> Self where I: ParallelIterator<Item = T>;
+}
+
+/// Parallel iteration over slices.
+pub trait ParallelSlice<T> {
+    /// Returns a parallel iterator over the slice.
+    fn par_iter(&self) -> Iter<T> where T: Sync;
+
+    /// Returns a parallel iterator that allows modifying each value.
+    fn par_iter_mut(&mut self) -> IterMut<T> where T: Send;
+}
+
+/// The receiving end of a parallel iterator.
+///
+/// A consumer is split along with the input, each part being fed a piece of
+/// it sequentially, and then the results of adjacent pieces are combined.
+#[doc(hidden)]
+pub trait Consumer<Item>: Send + Sized {
+    type Result: Send;
+
+    /// Returns a consumer for another piece of the input.
+    fn split(&self) -> Self;
+
+    /// Consumes one piece of the input.
+    fn consume<I>(self, iter: I) -> Self::Result where I: Iterator<Item = Item>;
+
+    /// Combines the results of two adjacent pieces, `left` coming first.
+    fn reduce(&self, left: Self::Result, right: Self::Result) -> Self::Result;
+}
+
+// The splittable source of a parallel iterator.
+trait Producer: Send + Sized {
+    type Item;
+    type IntoIter: Iterator<Item = Self::Item>;
+
+    fn len(&self) -> usize;
+    fn split_at(self, index: usize) -> (Self, Self);
+    fn into_iter(self) -> Self::IntoIter;
+}
+
+fn bridge<P, C>(producer: P, consumer: C) -> C::Result
+    where P: Producer, C: Consumer<P::Item>
+{
+    let registry = pool::current_registry();
+    // A few pieces per worker even out the load when some pieces take longer
+    // than others, without making the pieces so small that splitting them
+    // up costs more than it gains.
+    let min_len = cmp::max(1, producer.len() / (registry.num_threads() * 4));
+    bridge_in(&registry, producer, consumer, min_len)
+}
+
+fn bridge_in<P, C>(registry: &Arc<Registry>, producer: P, consumer: C,
+                   min_len: usize) -> C::Result
+    where P: Producer, C: Consumer<P::Item>
+{
+    let len = producer.len();
+    if len / 2 < min_len {
+        return consumer.consume(producer.into_iter())
+    }
+    let (left, right) = producer.split_at(len / 2);
+    let (left_consumer, right_consumer) = (consumer.split(), consumer.split());
+    let (l, r) = pool::join_in(registry,
+                               || bridge_in(registry, left, left_consumer, min_len),
+                               || bridge_in(registry, right, right_consumer, min_len));
+    consumer.reduce(l, r)
+}
+
+/// Parallel iterator over a slice, created by `ParallelSlice::par_iter`.
+pub struct Iter<'a, T: 'a> {
+    slice: &'a [T],
+}
+
+/// Parallel iterator over a mutable slice, created by
+/// `ParallelSlice::par_iter_mut`.
+pub struct IterMut<'a, T: 'a> {
+    slice: &'a mut [T],
+}
+
+/// Parallel iterator over a range of integers, created by
+/// `IntoParallelIterator::into_par_iter`.
+pub struct RangeIter<T> {
+    range: Range<T>,
+}
+
+impl<T> ParallelSlice<T> for [T] {
+    fn par_iter(&self) -> Iter<T> where T: Sync {
+        Iter { slice: self }
+    }
+
+    fn par_iter_mut(&mut self) -> IterMut<T> where T: Send {
+        IterMut { slice: self }
+    }
+}
+
+impl<'a, T: Sync> IntoParallelIterator for &'a [T] {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_par_iter(self) -> Iter<'a, T> {
+        self.par_iter()
+    }
+}
+
+impl<'a, T: Send> IntoParallelIterator for &'a mut [T] {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_par_iter(self) -> IterMut<'a, T> {
+        self.par_iter_mut()
+    }
+}
+
+impl<'a, T: Sync> IntoParallelIterator for &'a Vec<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_par_iter(self) -> Iter<'a, T> {
+        self.par_iter()
+    }
+}
+
+impl<'a, T: Send> IntoParallelIterator for &'a mut Vec<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_par_iter(self) -> IterMut<'a, T> {
+        self.par_iter_mut()
+    }
+}
+
+impl<'a, T: Sync> ParallelIterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn drive<C>(self, consumer: C) -> C::Result where C: Consumer<Self::Item> {
+        bridge(self, consumer)
+    }
+}
+
+impl<'a, T: Sync> Producer for Iter<'a, T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn len(&self) -> usize {
+        self.slice.len()
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (l, r) = self.slice.split_at(index);
+        (Iter { slice: l }, Iter { slice: r })
+    }
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.slice.iter()
+    }
+}
+
+impl<'a, T: Send> ParallelIterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn drive<C>(self, consumer: C) -> C::Result where C: Consumer<Self::Item> {
+        bridge(self, consumer)
+    }
+}
+
+impl<'a, T: Send> Producer for IterMut<'a, T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn len(&self) -> usize {
+        self.slice.len()
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (l, r) = self.slice.split_at_mut(index);
+        (IterMut { slice: l }, IterMut { slice: r })
+    }
+
+    fn into_iter(self) -> slice::IterMut<'a, T> {
+        self.slice.iter_mut()
+    }
+}
+
+// Only for the types whose ranges are `ExactSizeIterator`s, as their length
+// always fits in a `usize`.
+macro_rules! range_impl {
+    ($($t:ty)*) => ($(
+        impl IntoParallelIterator for Range<$t> {
+            type Item = $t;
+            type IntoIter = RangeIter<$t>;
+
+            fn into_par_iter(self) -> RangeIter<$t> {
+                RangeIter { range: self }
+            }
+        }
+
+        impl ParallelIterator for RangeIter<$t> {
+            type Item = $t;
+
+            fn drive<C>(self, consumer: C) -> C::Result
+                where C: Consumer<Self::Item>
+            {
+                bridge(self, consumer)
+            }
+        }
+
+        impl Producer for RangeIter<$t> {
+            type Item = $t;
+            type IntoIter = Range<$t>;
+
+            fn len(&self) -> usize {
+                self.range.len()
+            }
+
+            fn split_at(self, index: usize) -> (Self, Self) {
+                let mid = self.range.start.wrapping_add(index as $t);
+                (RangeIter { range: self.range.start..mid },
+                 RangeIter { range: mid..self.range.end })
+            }
+
+            fn into_iter(self) -> Range<$t> {
+                self.range
+            }
+        }
+    )*)
+}
+
+range_impl!(usize u8 u16 u32 isize i8 i16 i32);
+
+/// A parallel iterator that maps the values of `base` with `f`, created by
+/// `ParallelIterator::map`.
+pub struct Map<I, F> {
+    base: I,
+    f: F,
+}
+
+impl<I, F, R> ParallelIterator for Map<I, F>
+    where I: ParallelIterator, F: Fn(I::Item) -> R, F: Sync + Send, R: Send
+{
+    type Item = R;
+
+    fn drive<C>(self, consumer: C) -> C::Result where C: Consumer<Self::Item> {
+        let Map { base, f } = self;
+        base.drive(MapConsumer { base: consumer, f: &f })
+    }
+}
+
+struct MapConsumer<'f, C, F: 'f> {
+    base: C,
+    f: &'f F,
+}
+
+impl<'f, T, R, C, F> Consumer<T> for MapConsumer<'f, C, F>
+    where C: Consumer<R>, F: Fn(T) -> R, F: Sync
+{
+    type Result = C::Result;
+
+    fn split(&self) -> Self {
+        MapConsumer { base: self.base.split(), f: self.f }
+    }
+
+    fn consume<I>(self, iter: I) -> C::Result where I: Iterator<Item = T> {
+        self.base.consume(iter.map(self.f))
+    }
+
+    fn reduce(&self, left: C::Result, right: C::Result) -> C::Result {
+        self.base.reduce(left, right)
+    }
+}
+
+/// A parallel iterator that filters the values of `base` with `predicate`,
+/// created by `ParallelIterator::filter`.
+pub struct Filter<I, P> {
+    base: I,
+    predicate: P,
+}
+
+impl<I, P> ParallelIterator for Filter<I, P>
+    where I: ParallelIterator, P: Fn(&I::Item) -> bool, P: Sync + Send
+{
+    type Item = I::Item;
+
+    fn drive<C>(self, consumer: C) -> C::Result where C: Consumer<Self::Item> {
+        let Filter { base, predicate } = self;
+        base.drive(FilterConsumer { base: consumer, predicate: &predicate })
+    }
+}
+
+struct FilterConsumer<'p, C, P: 'p> {
+    base: C,
+    predicate: &'p P,
+}
+
+impl<'p, T, C, P> Consumer<T> for FilterConsumer<'p, C, P>
+    where C: Consumer<T>, P: Fn(&T) -> bool, P: Sync
+{
+    type Result = C::Result;
+
+    fn split(&self) -> Self {
+        FilterConsumer { base: self.base.split(), predicate: self.predicate }
+    }
+
+    fn consume<I>(self, iter: I) -> C::Result where I: Iterator<Item = T> {
+        let predicate = self.predicate;
+        self.base.consume(iter.filter(move |x| predicate(x)))
+    }
+
+    fn reduce(&self, left: C::Result, right: C::Result) -> C::Result {
+        self.base.reduce(left, right)
+    }
+}
+
+struct ForEachConsumer<'f, F: 'f> {
+    f: &'f F,
+}
+
+impl<'f, T, F> Consumer<T> for ForEachConsumer<'f, F> where F: Fn(T), F: Sync {
+    type Result = ();
+
+    fn split(&self) -> Self {
+        ForEachConsumer { f: self.f }
+    }
+
+    fn consume<I>(self, iter: I) where I: Iterator<Item = T> {
+        for item in iter {
+            (self.f)(item);
+        }
+    }
+
+    fn reduce(&self, _: (), _: ()) {}
+}
+
+struct FoldConsumer<'a, ID: 'a, F: 'a, R: 'a> {
+    identity: &'a ID,
+    fold_op: &'a F,
+    reduce_op: &'a R,
+}
+
+impl<'a, T, Item, ID, F, R> Consumer<Item> for FoldConsumer<'a, ID, F, R>
+    where T: Send,
+          ID: Fn() -> T, ID: Sync,
+          F: Fn(T, Item) -> T, F: Sync,
+          R: Fn(T, T) -> T, R: Sync
+{
+    type Result = T;
+
+    fn split(&self) -> Self {
+        FoldConsumer {
+            identity: self.identity,
+            fold_op: self.fold_op,
+            reduce_op: self.reduce_op,
+        }
+    }
+
+    fn consume<I>(self, iter: I) -> T where I: Iterator<Item = Item> {
+        iter.fold((self.identity)(), self.fold_op)
+    }
+
+    fn reduce(&self, left: T, right: T) -> T {
+        (self.reduce_op)(left, right)
+    }
+}
+
+struct CollectConsumer;
+
+impl<T: Send> Consumer<T> for CollectConsumer {
+    type Result = Vec<T>;
+
+    fn split(&self) -> Self {
+        CollectConsumer
+    }
+
+    fn consume<I>(self, iter: I) -> Vec<T> where I: Iterator<Item = T> {
+        iter.collect()
+    }
+
+    fn reduce(&self, mut left: Vec<T>, mut right: Vec<T>) -> Vec<T> {
+        left.append(&mut right);
+        left
+    }
+}
+
+impl<T: Send> FromParallelIterator<T> for Vec<T> {
+    fn from_par_iter<I>(par_iter: I) -> Vec<T> where I: ParallelIterator<Item = T> {
+        par_iter.drive(CollectConsumer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use cmp;
+    use sync::atomic::{AtomicUsize, Ordering};
+    use thread::ThreadPool;
+    use super::{IntoParallelIterator, ParallelIterator, ParallelSlice};
+
+    #[test]
+    fn test_map_collect_keeps_order() {
+        let v = (0..10_000usize).into_par_iter().map(|x| x * 2).collect::<Vec<_>>();
+        assert_eq!(v, (0..10_000).map(|x| x * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_filter() {
+        let v = (0..1000i32).into_par_iter().filter(|x| x % 3 == 0).collect::<Vec<_>>();
+        assert_eq!(v, (0..1000).filter(|x| x % 3 == 0).collect::<Vec<_>>());
+        assert_eq!((0..1000u32).into_par_iter().filter(|x| x % 3 == 0).count(), 334);
+    }
+
+    #[test]
+    fn test_fold_and_reduce() {
+        let v = (1..1001).collect::<Vec<u64>>();
+        assert_eq!(v.par_iter().fold(|| 0u64, |a, b| a + b, |a, b| a + b), 500_500);
+        assert_eq!(v.par_iter().map(|&x| x).reduce(|| 0, |a, b| a + b), 500_500);
+        assert_eq!(v.par_iter().map(|&x| x).reduce(|| 0, |a, b| cmp::max(a, b)), 1000);
+    }
+
+    #[test]
+    fn test_par_iter_mut() {
+        let mut v = (0..1000).collect::<Vec<i32>>();
+        v.par_iter_mut().for_each(|x| *x *= 2);
+        assert_eq!(v, (0..1000).map(|x| x * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_for_each() {
+        let count = AtomicUsize::new(0);
+        (0..500usize).into_par_iter().for_each(|x| {
+            count.fetch_add(x, Ordering::SeqCst);
+        });
+        assert_eq!(count.load(Ordering::SeqCst), 124_750);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!((5..5u8).into_par_iter().count(), 0);
+        let v: Vec<i32> = Vec::new();
+        assert!(v.par_iter().map(|&x| x).collect::<Vec<_>>().is_empty());
+    }
+
+    #[test]
+    fn test_negative_range() {
+        let v = (-100..100i8).into_par_iter().collect::<Vec<_>>();
+        assert_eq!(v, (-100..100).collect::<Vec<i8>>());
+    }
+
+    #[test]
+    fn test_install() {
+        let pool = ThreadPool::with_threads(2).unwrap();
+        let v = pool.install(|| {
+            (0..100u32).into_par_iter()
+                       .map(|x| (0..x).into_par_iter().count())
+                       .collect::<Vec<_>>()
+        });
+        assert_eq!(v, (0..100).collect::<Vec<usize>>());
+    }
+}
@@ -0,0 +1,603 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A work-stealing thread pool.
+//!
+//! Every worker has a deque of tasks. Tasks spawned by a worker are pushed
+//! onto the back of its own deque and popped off the back again, so a worker
+//! keeps working on whatever it touched last, while idle workers steal from
+//! the front of the others' deques, where the oldest and usually biggest
+//! pieces of work are. Tasks spawned from outside the pool go to a queue
+//! shared by all workers.
+//!
+//! A worker which has to wait for the tasks of a scope keeps running other
+//! tasks in the meantime. Without that, nested scopes (and with them nested
+//! `join`s and parallel iterators) would deadlock once every worker waits.
+
+use prelude::v1::*;
+
+use alloc::boxed::FnBox;
+use any::Any;
+use cell::Cell;
+use collections::VecDeque;
+use fmt;
+use io;
+use marker::PhantomData;
+use mem;
+use panic::{self, AssertUnwindSafe};
+use sync::{Arc, Condvar, Mutex, Once};
+use sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use super::{available_parallelism, Builder};
+
+type Job = Box<FnBox() + Send>;
+
+thread_local! {
+    // The registry of the pool the current thread is a worker of, and the
+    // worker's index in it. The pointer is to the `Arc` on the worker's
+    // stack, which lives as long as the thread.
+    static WORKER: Cell<Option<(*const Arc<Registry>, usize)>> = Cell::new(None)
+}
+
+/// A pool of threads which tasks can be spawned on.
+///
+/// The pool balances its load by work stealing: each worker has a queue of
+/// its own, and workers that run out of tasks take them from the others.
+/// Tasks that panic don't take their worker down with them.
+///
+/// Dropping the pool lets the workers exit once every task spawned on it has
+/// run. It doesn't wait for that to happen.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(thread_pool)]
+///
+/// use std::thread::ThreadPool;
+///
+/// let pool = ThreadPool::new().unwrap();
+/// let mut v = vec![1, 2, 3, 4];
+///
+/// pool.scope(|s| {
+///     for x in &mut v {
+///         s.spawn(move |_| *x *= 2);
+///     }
+/// });
+///
+/// assert_eq!(v, [2, 4, 6, 8]);
+/// ```
+#[unstable(feature = "thread_pool", issue = "0")]
+pub struct ThreadPool {
+    registry: Arc<Registry>,
+}
+
+/// A scope in which tasks borrowing from the enclosing stack frame can be
+/// spawned on a thread pool.
+///
+/// See `ThreadPool::scope` for details.
+#[unstable(feature = "thread_pool", issue = "0")]
+pub struct PoolScope<'env> {
+    registry: Arc<Registry>,
+    pending: AtomicUsize,
+    panic: Mutex<Option<Box<Any + Send>>>,
+    // Invariant for the same reason as in `thread::Scope`.
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+// The state shared between a pool, its workers and its scopes.
+pub struct Registry {
+    // Tasks spawned from outside the pool.
+    injector: Mutex<VecDeque<Job>>,
+    // One deque per worker.
+    deques: Vec<Mutex<VecDeque<Job>>>,
+    // Counts the events which may let a sleeping thread make progress: a task
+    // being pushed, the last task of a scope finishing, and termination.
+    events: Mutex<usize>,
+    wakeup: Condvar,
+    terminate: AtomicBool,
+}
+
+impl Registry {
+    fn new(threads: usize) -> io::Result<Arc<Registry>> {
+        let registry = Arc::new(Registry {
+            injector: Mutex::new(VecDeque::new()),
+            deques: (0..threads).map(|_| Mutex::new(VecDeque::new())).collect(),
+            events: Mutex::new(0),
+            wakeup: Condvar::new(),
+            terminate: AtomicBool::new(false),
+        });
+        for index in 0..threads {
+            let worker = registry.clone();
+            if let Err(e) = Builder::new().spawn(move || worker_main(worker, index)) {
+                registry.terminate();
+                return Err(e)
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn num_threads(&self) -> usize {
+        self.deques.len()
+    }
+
+    // The index of the current thread if it is a worker of this registry.
+    fn worker_index(&self) -> Option<usize> {
+        WORKER.with(|worker| match worker.get() {
+            Some((registry, index)) if unsafe { &**registry as *const Registry } ==
+                                       self as *const Registry => Some(index),
+            _ => None,
+        })
+    }
+
+    fn push(&self, job: Job) {
+        match self.worker_index() {
+            Some(index) => self.deques[index].lock().unwrap().push_back(job),
+            None => self.injector.lock().unwrap().push_back(job),
+        }
+        self.notify();
+    }
+
+    fn notify(&self) {
+        let mut events = self.events.lock().unwrap();
+        *events = events.wrapping_add(1);
+        self.wakeup.notify_all();
+    }
+
+    fn terminate(&self) {
+        self.terminate.store(true, Ordering::SeqCst);
+        self.notify();
+    }
+
+    fn find_work(&self, index: usize) -> Option<Job> {
+        if let Some(job) = self.deques[index].lock().unwrap().pop_back() {
+            return Some(job)
+        }
+        if let Some(job) = self.injector.lock().unwrap().pop_front() {
+            return Some(job)
+        }
+        let n = self.deques.len();
+        for i in 1..n {
+            if let Some(job) = self.deques[(index + i) % n].lock().unwrap().pop_front() {
+                return Some(job)
+            }
+        }
+        None
+    }
+
+    // Blocks until an event newer than `seen` happens.
+    fn sleep(&self, seen: usize) {
+        let mut events = self.events.lock().unwrap();
+        while *events == seen {
+            events = self.wakeup.wait(events).unwrap();
+        }
+    }
+
+    // Returns once `done` returns true. Workers of this registry run tasks
+    // while they wait; other threads just sleep.
+    fn wait_until<F: Fn() -> bool>(&self, done: F) {
+        let index = self.worker_index();
+        loop {
+            // Read before checking, so that an event between the check and
+            // going to sleep isn't missed.
+            let seen = *self.events.lock().unwrap();
+            if done() {
+                return
+            }
+            if let Some(index) = index {
+                if let Some(job) = self.find_work(index) {
+                    run(job);
+                    continue
+                }
+            }
+            self.sleep(seen);
+        }
+    }
+}
+
+fn worker_main(registry: Arc<Registry>, index: usize) {
+    WORKER.with(|worker| worker.set(Some((&registry as *const _, index))));
+    loop {
+        let seen = *registry.events.lock().unwrap();
+        if let Some(job) = registry.find_work(index) {
+            run(job);
+            continue
+        }
+        if registry.terminate.load(Ordering::SeqCst) {
+            break
+        }
+        registry.sleep(seen);
+    }
+    WORKER.with(|worker| worker.set(None));
+}
+
+fn run(job: Job) {
+    // The panic has been reported by the panic handler already, and scoped
+    // tasks catch their own panics to hand them to their scope.
+    let _ = panic::catch_unwind(AssertUnwindSafe(move || job()));
+}
+
+// The registry parallel iterators use: that of the pool the current thread
+// is a worker of, or else that of a global pool with a worker per CPU.
+pub fn current_registry() -> Arc<Registry> {
+    static INIT: Once = Once::new();
+    static mut GLOBAL: *const Arc<Registry> = 0 as *const _;
+
+    if let Some((registry, _)) = WORKER.with(|worker| worker.get()) {
+        return unsafe { (*registry).clone() }
+    }
+    unsafe {
+        INIT.call_once(|| {
+            let registry = Registry::new(available_parallelism().unwrap_or(1))
+                                    .expect("failed to spawn the global thread pool");
+            GLOBAL = Box::into_raw(Box::new(registry));
+        });
+        (*GLOBAL).clone()
+    }
+}
+
+pub fn scope_in<'env, F, R>(registry: &Arc<Registry>, f: F) -> R
+    where F: FnOnce(&PoolScope<'env>) -> R
+{
+    let scope = PoolScope {
+        registry: registry.clone(),
+        pending: AtomicUsize::new(0),
+        panic: Mutex::new(None),
+        env: PhantomData,
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+
+    // Wait until all the tasks are finished with the environment.
+    registry.wait_until(|| scope.pending.load(Ordering::Acquire) == 0);
+
+    let task_panic = scope.panic.lock().unwrap().take();
+    match (result, task_panic) {
+        (Err(e), _) | (Ok(_), Some(e)) => panic::resume_unwind(e),
+        (Ok(result), None) => result,
+    }
+}
+
+pub fn join_in<A, B, RA, RB>(registry: &Arc<Registry>, a: A, b: B) -> (RA, RB)
+    where A: FnOnce() -> RA, A: Send, B: FnOnce() -> RB, B: Send,
+          RA: Send, RB: Send
+{
+    let mut rb = None;
+    let ra = scope_in(registry, |s| {
+        // On a worker, `b` goes on the back of its own deque, so unless it
+        // is stolen in the meantime it's the first thing the worker runs
+        // when it waits for the scope after finishing `a`.
+        s.spawn(|_| rb = Some(b()));
+        a()
+    });
+    (ra, rb.unwrap())
+}
+
+impl ThreadPool {
+    /// Creates a thread pool with as many workers as
+    /// `thread::available_parallelism` suggests.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a worker thread can't be spawned.
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn new() -> io::Result<ThreadPool> {
+        ThreadPool::with_threads(available_parallelism().unwrap_or(1))
+    }
+
+    /// Creates a thread pool with `threads` workers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a worker thread can't be spawned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is 0.
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn with_threads(threads: usize) -> io::Result<ThreadPool> {
+        assert!(threads > 0, "a thread pool needs at least one thread");
+        Registry::new(threads).map(|registry| ThreadPool { registry: registry })
+    }
+
+    /// Returns the number of workers in the pool.
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn threads(&self) -> usize {
+        self.registry.num_threads()
+    }
+
+    /// Spawns a task on the pool.
+    ///
+    /// Nothing is returned to wait for the task with; use a channel, or
+    /// `scope` if the caller has to wait for it anyway.
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn spawn<F>(&self, f: F) where F: FnOnce(), F: Send + 'static {
+        self.registry.push(Box::new(f));
+    }
+
+    /// Creates a scope for spawning tasks that may borrow non-`'static`
+    /// data.
+    ///
+    /// The closure is passed a `PoolScope`, whose `spawn` method puts tasks
+    /// on the pool that may borrow anything that outlives the call to
+    /// `scope`. Before `scope` returns, all of those tasks have run.
+    ///
+    /// The closure itself runs on the calling thread. If that is one of the
+    /// pool's workers, it runs other tasks while it waits for those of the
+    /// scope, so scopes may be nested freely.
+    ///
+    /// # Panics
+    ///
+    /// If the closure or any of the tasks panic, the first of those panics
+    /// is propagated once all tasks are done.
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn scope<'env, F, R>(&self, f: F) -> R
+        where F: FnOnce(&PoolScope<'env>) -> R
+    {
+        scope_in(&self.registry, f)
+    }
+
+    /// Runs `a` and `b`, potentially in parallel, and returns both results.
+    ///
+    /// `a` runs on the calling thread and `b` is spawned on the pool. This is
+    /// the building block of divide-and-conquer algorithms: called from
+    /// within the pool, the second half of the work is only handed to
+    /// another worker if one is idle and steals it.
+    ///
+    /// # Panics
+    ///
+    /// If either closure panics, the panic is propagated once both are done.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(thread_pool)]
+    ///
+    /// use std::thread::ThreadPool;
+    ///
+    /// fn sum(pool: &ThreadPool, v: &[u64]) -> u64 {
+    ///     if v.len() <= 1024 {
+    ///         return v.iter().fold(0, |a, b| a + b)
+    ///     }
+    ///     let (l, r) = v.split_at(v.len() / 2);
+    ///     let (l, r) = pool.join(|| sum(pool, l), || sum(pool, r));
+    ///     l + r
+    /// }
+    ///
+    /// let pool = ThreadPool::new().unwrap();
+    /// let v = (0..100_000).collect::<Vec<u64>>();
+    /// assert_eq!(sum(&pool, &v), 4_999_950_000);
+    /// ```
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn join<A, B, RA, RB>(&self, a: A, b: B) -> (RA, RB)
+        where A: FnOnce() -> RA, A: Send, B: FnOnce() -> RB, B: Send,
+              RA: Send, RB: Send
+    {
+        join_in(&self.registry, a, b)
+    }
+
+    /// Runs `f` on one of the pool's workers and waits for its result.
+    ///
+    /// Parallel iterators run on the pool of the worker they are used from,
+    /// and on a global pool everywhere else, so this is how to run them on a
+    /// particular pool.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics, the panic is propagated.
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn install<F, R>(&self, f: F) -> R
+        where F: FnOnce() -> R, F: Send, R: Send
+    {
+        if self.registry.worker_index().is_some() {
+            return f()
+        }
+        let mut result = None;
+        self.scope(|s| s.spawn(|_| result = Some(f())));
+        result.unwrap()
+    }
+}
+
+#[unstable(feature = "thread_pool", issue = "0")]
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.registry.terminate();
+    }
+}
+
+#[unstable(feature = "thread_pool", issue = "0")]
+impl fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ThreadPool")
+         .field("threads", &self.threads())
+         .finish()
+    }
+}
+
+impl<'env> PoolScope<'env> {
+    /// Spawns a task on the pool within the scope.
+    ///
+    /// Unlike `ThreadPool::spawn`, the closure may borrow anything that
+    /// outlives the scope. It is passed the scope, so that it can spawn
+    /// further tasks in it.
+    #[unstable(feature = "thread_pool", issue = "0")]
+    pub fn spawn<'scope, F>(&'scope self, f: F)
+        where F: FnOnce(&PoolScope<'env>), F: Send + 'env
+    {
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        let job: Box<FnBox() + Send + 'scope> = Box::new(move || {
+            if let Err(e) = panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+                let mut slot = self.panic.lock().unwrap();
+                if slot.is_none() {
+                    *slot = Some(e);
+                }
+            }
+            // The scope may be gone as soon as `pending` drops to zero, so
+            // hold on to the registry to notify it.
+            let registry = self.registry.clone();
+            if self.pending.fetch_sub(1, Ordering::Release) == 1 {
+                registry.notify();
+            }
+        });
+        // The scope doesn't return before the task has run, so it won't
+        // outlive anything it borrows.
+        let job = unsafe {
+            mem::transmute::<Box<FnBox() + Send + 'scope>, Job>(job)
+        };
+        self.registry.push(job);
+    }
+}
+
+#[unstable(feature = "thread_pool", issue = "0")]
+impl<'env> fmt::Debug for PoolScope<'env> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PoolScope")
+         .field("pending", &self.pending.load(Ordering::Relaxed))
+         .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use panic;
+    use sync::atomic::{AtomicUsize, Ordering};
+    use sync::mpsc::channel;
+    use thread::{self, ThreadPool};
+    use time::Duration;
+
+    #[test]
+    fn test_spawn() {
+        let pool = ThreadPool::with_threads(2).unwrap();
+        let (tx, rx) = channel();
+        for i in 0..10 {
+            let tx = tx.clone();
+            pool.spawn(move || tx.send(i).unwrap());
+        }
+        drop(tx);
+        let mut v = rx.iter().collect::<Vec<_>>();
+        v.sort();
+        assert_eq!(v, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_spawn_panic_keeps_worker() {
+        let pool = ThreadPool::with_threads(1).unwrap();
+        let (tx, rx) = channel();
+        pool.spawn(|| panic!());
+        pool.spawn(move || tx.send(()).unwrap());
+        rx.recv().unwrap();
+    }
+
+    #[test]
+    fn test_scope_borrow() {
+        let pool = ThreadPool::with_threads(4).unwrap();
+        let mut v = vec![1, 2, 3, 4];
+        let total = AtomicUsize::new(0);
+        pool.scope(|s| {
+            for x in &mut v {
+                let total = &total;
+                s.spawn(move |_| {
+                    *x *= 2;
+                    total.fetch_add(*x, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(v, [2, 4, 6, 8]);
+        assert_eq!(total.load(Ordering::SeqCst), 20);
+    }
+
+    #[test]
+    fn test_scope_waits() {
+        let pool = ThreadPool::with_threads(2).unwrap();
+        let done = AtomicUsize::new(0);
+        pool.scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|_| {
+                    thread::sleep(Duration::from_millis(20));
+                    done.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(done.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_nested_spawn() {
+        fn count<'a>(s: &super::PoolScope<'a>, n: usize, counter: &'a AtomicUsize) {
+            counter.fetch_add(1, Ordering::SeqCst);
+            if n > 0 {
+                s.spawn(move |s| count(s, n - 1, counter));
+                s.spawn(move |s| count(s, n - 1, counter));
+            }
+        }
+
+        let pool = ThreadPool::with_threads(3).unwrap();
+        let counter = AtomicUsize::new(0);
+        pool.scope(|s| count(s, 6, &counter));
+        assert_eq!(counter.load(Ordering::SeqCst), 127);
+    }
+
+    #[test]
+    fn test_scope_panic() {
+        let pool = ThreadPool::with_threads(2).unwrap();
+        let done = AtomicUsize::new(0);
+        let r = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            pool.scope(|s| {
+                s.spawn(|_| panic!());
+                s.spawn(|_| {
+                    thread::sleep(Duration::from_millis(20));
+                    done.fetch_add(1, Ordering::SeqCst);
+                });
+            });
+        }));
+        assert!(r.is_err());
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_join() {
+        fn fib(pool: &ThreadPool, n: u32) -> u32 {
+            if n < 2 {
+                return n
+            }
+            let (a, b) = pool.join(|| fib(pool, n - 1), || fib(pool, n - 2));
+            a + b
+        }
+
+        let pool = ThreadPool::with_threads(4).unwrap();
+        assert_eq!(fib(&pool, 15), 610);
+        assert_eq!(pool.install(|| fib(&pool, 15)), 610);
+    }
+
+    #[test]
+    fn test_nested_join_on_one_thread() {
+        // The only worker has to run `b` itself while waiting for it.
+        let pool = ThreadPool::with_threads(1).unwrap();
+        let r = pool.install(|| {
+            pool.join(|| pool.join(|| 1, || 2), || pool.join(|| 3, || 4))
+        });
+        assert_eq!(r, ((1, 2), (3, 4)));
+    }
+
+    #[test]
+    fn test_join_panic() {
+        let pool = ThreadPool::with_threads(2).unwrap();
+        let r = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            pool.join(|| 1, || -> i32 { panic!() })
+        }));
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_threads() {
+        assert_eq!(ThreadPool::with_threads(3).unwrap().threads(), 3);
+        assert!(ThreadPool::new().unwrap().threads() > 0);
+    }
+}
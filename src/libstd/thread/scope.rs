@@ -0,0 +1,332 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Scoped threads, which may borrow from the stack of the thread that spawns
+//! them.
+//!
+//! Soundness doesn't rest on a guard's destructor being run, which is what
+//! sank the old `thread::scoped`: the `Scope` is owned by `scope` itself,
+//! which waits for every thread spawned in it before returning, whether or
+//! not the handles were joined, leaked or dropped. A thread counts as done
+//! once it has returned, not once its handle is gone, so leaking a handle
+//! only leaks the thread's result.
+
+use prelude::v1::*;
+
+use io;
+use marker::PhantomData;
+use panic::{self, AssertUnwindSafe};
+use sync::Arc;
+use sync::atomic::{AtomicUsize, Ordering};
+use super::{current, park, Builder, JoinInner, Result, Thread};
+
+/// A scope in which threads borrowing from the enclosing stack frame can be
+/// spawned.
+///
+/// See the `scope` function for details.
+#[unstable(feature = "thread_scope", issue = "0")]
+pub struct Scope<'env> {
+    data: Arc<ScopeData>,
+    // `'env` is invariant so that a `&Scope<'env>` can't be turned into a
+    // scope for a shorter lifetime, such as that of a local of the closure
+    // passed to `scope`, which is dropped before the threads are waited for.
+    env: PhantomData<&'env mut &'env ()>,
+}
+
+/// An owned permission to join on a scoped thread (block on its termination).
+///
+/// Unlike `JoinHandle`, dropping this does not let the thread run on past the
+/// scope; `scope` waits for it regardless.
+#[unstable(feature = "thread_scope", issue = "0")]
+pub struct ScopedJoinHandle<'scope, T> {
+    inner: JoinInner<T>,
+    scope: PhantomData<&'scope ()>,
+}
+
+// Shared between a scope and the packets of its threads.
+pub struct ScopeData {
+    num_running_threads: AtomicUsize,
+    // Threads which panicked and haven't been joined (yet).
+    num_unhandled_panics: AtomicUsize,
+    main_thread: Thread,
+}
+
+impl ScopeData {
+    pub fn increment_num_running_threads(&self) {
+        // As with `Arc`, overflowing the count would be catastrophic, and
+        // can only happen by leaking an absurd number of packets.
+        if self.num_running_threads.fetch_add(1, Ordering::Relaxed) > usize::max_value() / 2 {
+            panic!("too many running threads in thread scope");
+        }
+    }
+
+    pub fn decrement_num_running_threads(&self, panicked: bool) {
+        if panicked {
+            self.num_unhandled_panics.fetch_add(1, Ordering::Relaxed);
+        }
+        if self.num_running_threads.fetch_sub(1, Ordering::Release) == 1 {
+            self.main_thread.unpark();
+        }
+    }
+
+    // Called when the panic of a thread is returned by `join`, which is only
+    // after the thread has reported it above.
+    pub fn panic_joined(&self) {
+        self.num_unhandled_panics.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Creates a scope for spawning threads that may borrow non-`'static` data.
+///
+/// The closure is passed a `Scope`, whose `spawn` method starts threads that
+/// may borrow anything that outlives the call to `scope`. Before `scope`
+/// returns, every thread spawned in the scope is waited for, including those
+/// whose handles were not joined.
+///
+/// # Panics
+///
+/// If the closure panics, the panic is propagated once all threads are done.
+/// Otherwise, if a thread that was not joined panicked, `scope` panics after
+/// all threads are done. Panics of joined threads are returned by `join`
+/// as usual.
+///
+/// # Examples
+///
+/// ```
+/// #![feature(thread_scope)]
+///
+/// use std::thread;
+///
+/// let mut a = vec![1, 2, 3];
+/// let mut x = 0;
+///
+/// thread::scope(|s| {
+///     s.spawn(|| {
+///         println!("hello from the first scoped thread");
+///         // We can borrow `a` here.
+///         println!("{:?}", a);
+///     });
+///     s.spawn(|| {
+///         println!("hello from the second scoped thread");
+///         // We can even mutably borrow `x` here,
+///         // because no other threads are using it.
+///         x += a[0] + a[2];
+///     });
+///     println!("hello from the main thread");
+/// });
+///
+/// // After the scope, we can modify and access our variables again:
+/// a.push(4);
+/// assert_eq!(x, a.len());
+/// ```
+#[unstable(feature = "thread_scope", issue = "0")]
+pub fn scope<'env, F, T>(f: F) -> T
+    where F: FnOnce(&Scope<'env>) -> T
+{
+    let scope = Scope {
+        data: Arc::new(ScopeData {
+            num_running_threads: AtomicUsize::new(0),
+            num_unhandled_panics: AtomicUsize::new(0),
+            main_thread: current(),
+        }),
+        env: PhantomData,
+    };
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&scope)));
+
+    // Wait until all the threads are finished with the environment.
+    while scope.data.num_running_threads.load(Ordering::Acquire) != 0 {
+        park();
+    }
+
+    match result {
+        Err(e) => panic::resume_unwind(e),
+        Ok(_) if scope.data.num_unhandled_panics.load(Ordering::Relaxed) != 0 => {
+            panic!("a scoped thread panicked")
+        }
+        Ok(result) => result,
+    }
+}
+
+impl<'env> Scope<'env> {
+    /// Spawns a new thread within the scope, returning a `ScopedJoinHandle`
+    /// for it.
+    ///
+    /// Unlike `thread::spawn`, the closure may borrow anything that outlives
+    /// the scope. The thread is joined automatically when the scope ends if
+    /// it hasn't been joined through its handle before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the OS fails to create a thread; use `Builder::spawn_scoped`
+    /// to recover from such errors.
+    #[unstable(feature = "thread_scope", issue = "0")]
+    pub fn spawn<'scope, F, T>(&'scope self, f: F) -> ScopedJoinHandle<'scope, T>
+        where F: FnOnce() -> T, F: Send + 'env, T: Send + 'env
+    {
+        Builder::new().spawn_scoped(self, f).expect("failed to spawn thread")
+    }
+}
+
+impl Builder {
+    /// Spawns a new thread within `scope` with this configuration, and
+    /// returns a `ScopedJoinHandle` for it.
+    ///
+    /// # Errors
+    ///
+    /// Unlike `Scope::spawn`, this method yields an `io::Result` to capture
+    /// any failure to create the thread at the OS level.
+    #[unstable(feature = "thread_scope", issue = "0")]
+    pub fn spawn_scoped<'scope, 'env, F, T>(self, scope: &'scope Scope<'env>,
+                                            f: F)
+                                            -> io::Result<ScopedJoinHandle<'scope, T>>
+        where F: FnOnce() -> T, F: Send + 'env, T: Send + 'env
+    {
+        let inner = unsafe {
+            self.spawn_unchecked(f, Some(scope.data.clone()))?
+        };
+        Ok(ScopedJoinHandle { inner: inner, scope: PhantomData })
+    }
+}
+
+impl<'scope, T> ScopedJoinHandle<'scope, T> {
+    /// Extracts a handle to the underlying thread
+    #[unstable(feature = "thread_scope", issue = "0")]
+    pub fn thread(&self) -> &Thread {
+        &self.inner.thread
+    }
+
+    /// Waits for the associated thread to finish.
+    ///
+    /// If the child thread panics, `Err` is returned with the parameter given
+    /// to `panic`, and the panic is not reported again by the scope.
+    #[unstable(feature = "thread_scope", issue = "0")]
+    pub fn join(mut self) -> Result<T> {
+        self.inner.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prelude::v1::*;
+
+    use mem;
+    use panic;
+    use sync::atomic::{AtomicUsize, Ordering};
+    use thread;
+    use time::Duration;
+
+    #[test]
+    fn test_borrow() {
+        let mut a = vec![1, 2, 3];
+        let mut x = 0;
+        thread::scope(|s| {
+            s.spawn(|| assert_eq!(a.len(), 3));
+            s.spawn(|| x += a[0] + a[2]);
+        });
+        a.push(4);
+        assert_eq!(x, a.len());
+    }
+
+    #[test]
+    fn test_join_value() {
+        let v = vec![1, 2, 3, 4];
+        let sum = thread::scope(|s| {
+            let (l, r) = v.split_at(2);
+            let l = s.spawn(move || l.iter().fold(0, |a, b| a + b));
+            let r = s.spawn(move || r.iter().fold(0, |a, b| a + b));
+            l.join().unwrap() + r.join().unwrap()
+        });
+        assert_eq!(sum, 10);
+    }
+
+    #[test]
+    fn test_waits_for_unjoined() {
+        let done = AtomicUsize::new(0);
+        thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    thread::sleep(Duration::from_millis(20));
+                    done.fetch_add(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(done.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_joined_panic_is_not_propagated() {
+        let r = thread::scope(|s| {
+            s.spawn(|| -> () { panic!() }).join()
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_unjoined_panic_is_propagated() {
+        let r = panic::catch_unwind(|| {
+            thread::scope(|s| {
+                s.spawn(|| -> () { panic!() });
+            });
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_body_panic_waits_for_threads() {
+        let done = AtomicUsize::new(0);
+        let r = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            thread::scope(|s| {
+                s.spawn(|| {
+                    thread::sleep(Duration::from_millis(20));
+                    done.fetch_add(1, Ordering::SeqCst);
+                });
+                panic!();
+            });
+        }));
+        assert!(r.is_err());
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_leaked_handle() {
+        let done = AtomicUsize::new(0);
+        thread::scope(|s| {
+            mem::forget(s.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                done.fetch_add(1, Ordering::SeqCst);
+            }));
+        });
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_leaked_handle_panic_is_propagated() {
+        let r = panic::catch_unwind(|| {
+            thread::scope(|s| {
+                mem::forget(s.spawn(|| -> () { panic!() }));
+            });
+        });
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_builder() {
+        let x = 5;
+        thread::scope(|s| {
+            let h = thread::Builder::new().name("scoped".to_string())
+                                          .spawn_scoped(s, || {
+                assert_eq!(thread::current().name(), Some("scoped"));
+                x + 1
+            }).unwrap();
+            assert_eq!(h.join().unwrap(), 6);
+        });
+    }
+}
@@ -161,6 +161,28 @@ impl Instant {
     pub fn elapsed(&self) -> Duration {
         Instant::now() - *self
     }
+
+    /// Returns the instant `duration` after this one, or `None` if it can't
+    /// be represented, in which case adding `duration` would panic.
+    ///
+    /// This is useful to compute deadlines from user-supplied timeouts,
+    /// which may be arbitrarily large.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(time_checked_add)]
+    ///
+    /// use std::time::{Duration, Instant};
+    ///
+    /// let now = Instant::now();
+    /// assert!(now.checked_add(Duration::from_secs(1)).is_some());
+    /// assert_eq!(now.checked_add(Duration::from_secs(u64::max_value())), None);
+    /// ```
+    #[unstable(feature = "time_checked_add", issue = "0")]
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_add_duration(&duration).map(Instant)
+    }
 }
 
 #[stable(feature = "time2", since = "1.8.0")]
@@ -361,6 +383,15 @@ mod tests {
         assert!(b >= a);
     }
 
+    #[test]
+    fn instant_checked_add() {
+        let a = Instant::now();
+        let second = Duration::new(1, 0);
+        assert_eq!(a.checked_add(second), Some(a + second));
+        assert_eq!(a.checked_add(Duration::new(u64::max_value(), 0)), None);
+        assert_eq!(a.checked_add(Duration::new(0, 0)), Some(a));
+    }
+
     #[test]
     fn instant_elapsed() {
         let a = Instant::now();
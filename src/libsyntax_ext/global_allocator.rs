@@ -0,0 +1,136 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The `#[global_allocator]` attribute.
+//!
+//! Tagging a `static` with `#[global_allocator]` generates the
+//! `__rust_allocate` family of symbols that `liballoc` links against, each
+//! forwarding to the `std::heap::GlobalAlloc` implementation of the static.
+//! The first shim is marked `#[rustc_global_allocator]` so that the crate
+//! reader knows not to inject a default allocator crate, and the dependency
+//! format calculation can reject conflicting allocators.
+
+use syntax::ast::{self, ItemKind, MetaItem, MetaItemKind};
+use syntax::codemap::Span;
+use syntax::ext::base::{Annotatable, ExtCtxt};
+use syntax::ext::build::AstBuilder;
+use syntax::parse::token::intern_and_get_ident;
+
+pub fn expand(cx: &mut ExtCtxt,
+              span: Span,
+              mitem: &MetaItem,
+              item: &Annotatable,
+              push: &mut FnMut(Annotatable)) {
+    match mitem.node {
+        MetaItemKind::Word(_) => {}
+        _ => {
+            cx.span_err(mitem.span, "malformed `#[global_allocator]` attribute, \
+                                     expected `#[global_allocator]`");
+            return
+        }
+    }
+
+    let name = match *item {
+        Annotatable::Item(ref item) => match item.node {
+            ItemKind::Static(_, ast::Mutability::Immutable, _) => item.ident,
+            _ => {
+                cx.span_err(item.span, "`#[global_allocator]` can only be applied \
+                                        to an immutable `static` item");
+                return
+            }
+        },
+        _ => {
+            cx.span_err(span, "`#[global_allocator]` can only be applied \
+                               to an immutable `static` item");
+            return
+        }
+    };
+
+    if cx.crate_root != Some("std") {
+        cx.span_err(span, "`#[global_allocator]` requires the standard library");
+        return
+    }
+
+    let shims = vec![
+        quote_item!(cx,
+            #[no_mangle]
+            #[allow(private_no_mangle_fns)]
+            #[doc(hidden)]
+            pub extern "C" fn __rust_allocate(size: usize, align: usize) -> *mut u8 {
+                unsafe { ::std::heap::GlobalAlloc::alloc(&$name, size, align) }
+            }
+        ),
+        quote_item!(cx,
+            #[no_mangle]
+            #[allow(private_no_mangle_fns)]
+            #[doc(hidden)]
+            pub extern "C" fn __rust_deallocate(ptr: *mut u8, old_size: usize, align: usize) {
+                unsafe { ::std::heap::GlobalAlloc::dealloc(&$name, ptr, old_size, align) }
+            }
+        ),
+        quote_item!(cx,
+            #[no_mangle]
+            #[allow(private_no_mangle_fns)]
+            #[doc(hidden)]
+            pub extern "C" fn __rust_reallocate(ptr: *mut u8,
+                                                old_size: usize,
+                                                size: usize,
+                                                align: usize)
+                                                -> *mut u8 {
+                unsafe {
+                    ::std::heap::GlobalAlloc::realloc(&$name, ptr, old_size, size, align)
+                }
+            }
+        ),
+        quote_item!(cx,
+            #[no_mangle]
+            #[allow(private_no_mangle_fns)]
+            #[doc(hidden)]
+            pub extern "C" fn __rust_reallocate_inplace(ptr: *mut u8,
+                                                        old_size: usize,
+                                                        size: usize,
+                                                        align: usize)
+                                                        -> usize {
+                unsafe {
+                    ::std::heap::GlobalAlloc::realloc_in_place(&$name, ptr, old_size,
+                                                               size, align)
+                }
+            }
+        ),
+        quote_item!(cx,
+            #[no_mangle]
+            #[allow(private_no_mangle_fns)]
+            #[doc(hidden)]
+            pub extern "C" fn __rust_usable_size(size: usize, align: usize) -> usize {
+                ::std::heap::GlobalAlloc::usable_size(&$name, size, align)
+            }
+        ),
+    ];
+
+    // The marker is internal, so give it a span inside this expansion to
+    // get past the `rustc_attrs` feature gate.
+    let marker_span = Span { expn_id: cx.backtrace(), ..span };
+    let marker = cx.attribute(marker_span,
+                              cx.meta_word(marker_span,
+                                           intern_and_get_ident("rustc_global_allocator")));
+
+    let mut marker = Some(marker);
+    for shim in shims {
+        let shim = shim.expect("failed to parse global allocator shim");
+        let shim = match marker.take() {
+            Some(marker) => shim.map(|mut item| {
+                item.attrs.push(marker);
+                item
+            }),
+            None => shim,
+        };
+        push(Annotatable::Item(shim));
+    }
+}
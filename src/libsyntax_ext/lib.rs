@@ -19,6 +19,7 @@
        html_root_url = "https://doc.rust-lang.org/nightly/")]
 #![cfg_attr(not(stage0), deny(warnings))]
 
+#![feature(quote)]
 #![feature(rustc_private)]
 #![feature(staged_api)]
 
@@ -27,7 +28,7 @@ extern crate fmt_macros;
 #[macro_use]
 extern crate syntax;
 
-use syntax::ext::base::{MacroExpanderFn, NormalTT};
+use syntax::ext::base::{MacroExpanderFn, MultiDecorator, NormalTT};
 use syntax::ext::base::{SyntaxEnv, SyntaxExtension};
 use syntax::parse::token::intern;
 
@@ -38,6 +39,7 @@ mod concat;
 mod concat_idents;
 mod env;
 mod format;
+mod global_allocator;
 mod log_syntax;
 mod trace_macros;
 
@@ -65,6 +67,8 @@ pub fn register_builtins(env: &mut SyntaxEnv) {
     env.insert(intern("format_args"),
                // format_args uses `unstable` things internally.
                NormalTT(Box::new(format::expand_format_args), None, true));
+    env.insert(intern("global_allocator"),
+               MultiDecorator(Box::new(global_allocator::expand)));
     env.insert(intern("log_syntax"),
                builtin_normal_expander(log_syntax::expand_syntax_ext));
     env.insert(intern("trace_macros"),
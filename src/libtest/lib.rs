@@ -34,6 +34,7 @@
 #![cfg_attr(not(stage0), deny(warnings))]
 
 #![feature(asm)]
+#![feature(available_parallelism)]
 #![feature(box_syntax)]
 #![feature(fnbox)]
 #![feature(libc)]
@@ -75,8 +76,8 @@ use std::time::{Instant, Duration};
 pub mod test {
     pub use {Bencher, TestName, TestResult, TestDesc, TestDescAndFn, TestOpts, TrFailed,
              TrIgnored, TrOk, Metric, MetricMap, StaticTestFn, StaticTestName, DynTestName,
-             DynTestFn, run_test, test_main, test_main_static, filter_tests, parse_opts,
-             StaticBenchFn, ShouldPanic};
+             DynTestFn, run_test, test_main, test_main_static, filter_tests, shuffle_tests,
+             parse_opts, StaticBenchFn, ShouldPanic};
 }
 
 pub mod stats;
@@ -290,7 +291,12 @@ pub enum ColorConfig {
 }
 
 pub struct TestOpts {
+    pub list: bool,
     pub filter: Option<String>,
+    pub filter_exact: bool,
+    pub skip: Vec<String>,
+    pub shard: Option<(usize, usize)>,
+    pub shuffle_seed: Option<u64>,
     pub run_ignored: bool,
     pub run_tests: bool,
     pub bench_benchmarks: bool,
@@ -304,7 +310,12 @@ impl TestOpts {
     #[cfg(test)]
     fn new() -> TestOpts {
         TestOpts {
+            list: false,
             filter: None,
+            filter_exact: false,
+            skip: vec![],
+            shard: None,
+            shuffle_seed: None,
             run_ignored: false,
             run_tests: false,
             bench_benchmarks: false,
@@ -325,6 +336,16 @@ fn optgroups() -> Vec<getopts::OptGroup> {
       getopts::optflag("", "test", "Run tests and not benchmarks"),
       getopts::optflag("", "bench", "Run benchmarks instead of tests"),
       getopts::optflag("h", "help", "Display this message (longer with --help)"),
+      getopts::optflag("", "list", "List all tests and benchmarks without running them"),
+      getopts::optflag("", "exact", "Exactly match filters rather than by substring"),
+      getopts::optmulti("", "skip", "Skip tests whose names contain FILTER (this flag can \
+                                     be used multiple times)", "FILTER"),
+      getopts::optopt("", "shard", "Only run the I-th of N deterministic shards of the \
+                                    test suite (1 <= I <= N)", "I/N"),
+      getopts::optflag("", "shuffle", "Run tests in a random order; the seed is printed so \
+                                       that the order can be replayed"),
+      getopts::optopt("", "shuffle-seed", "Run tests in the random order given by SEED \
+                                           (implies --shuffle)", "SEED"),
       getopts::optopt("", "logfile", "Write logs to the specified file instead \
                           of stdout", "PATH"),
       getopts::optflag("", "nocapture", "don't capture stdout/stderr of each \
@@ -341,7 +362,14 @@ fn usage(binary: &str) {
     println!(r#"{usage}
 
 The FILTER string is tested against the name of all tests, and only those
-tests whose names contain the filter are run.
+tests whose names contain the filter are run. With --exact, only tests whose
+names are exactly equal to the filter are run; this also applies to --skip.
+
+Tests can be split across several machines with --shard I/N: the filtered
+tests are sorted by name and every machine running with the same N gets a
+disjoint, deterministic subset. Running with --shuffle randomizes the order in
+which tests are started and prints the seed used; pass it back with
+--shuffle-seed SEED to reproduce the same order.
 
 By default, all tests are run in parallel. This can be altered with the
 RUST_TEST_THREADS environment variable when running tests (set it to 1).
@@ -388,6 +416,39 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
 
     let run_ignored = matches.opt_present("ignored");
     let quiet = matches.opt_present("quiet");
+    let list = matches.opt_present("list");
+    let filter_exact = matches.opt_present("exact");
+    let skip = matches.opt_strs("skip");
+
+    let shard = match matches.opt_str("shard") {
+        None => None,
+        Some(s) => {
+            match parse_shard(&s) {
+                Some(shard) => Some(shard),
+                None => {
+                    return Some(Err(format!("argument for --shard must be of the form I/N \
+                                             with 1 <= I <= N (was {})",
+                                            s)))
+                }
+            }
+        }
+    };
+
+    let shuffle_seed = match matches.opt_str("shuffle-seed") {
+        Some(s) => {
+            match s.parse::<u64>() {
+                Ok(seed) => Some(seed),
+                Err(e) => {
+                    return Some(Err(format!("argument for --shuffle-seed must be an unsigned \
+                                             integer (was {}): {}",
+                                            s,
+                                            e)))
+                }
+            }
+        }
+        None if matches.opt_present("shuffle") => Some(random_seed()),
+        None => None,
+    };
 
     let logfile = matches.opt_str("logfile");
     let logfile = logfile.map(|s| PathBuf::from(&s));
@@ -416,7 +477,12 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     };
 
     let test_opts = TestOpts {
+        list: list,
         filter: filter,
+        filter_exact: filter_exact,
+        skip: skip,
+        shard: shard,
+        shuffle_seed: shuffle_seed,
         run_ignored: run_ignored,
         run_tests: run_tests,
         bench_benchmarks: bench_benchmarks,
@@ -429,6 +495,27 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     Some(Ok(test_opts))
 }
 
+// Parses the argument to `--shard`, which is of the form `I/N` with the shard
+// index `I` being 1-based.
+fn parse_shard(s: &str) -> Option<(usize, usize)> {
+    let mut parts = s.splitn(2, '/');
+    let index = parts.next().and_then(|i| i.trim().parse::<usize>().ok());
+    let total = parts.next().and_then(|n| n.trim().parse::<usize>().ok());
+    match (index, total) {
+        (Some(i), Some(n)) if 1 <= i && i <= n => Some((i, n)),
+        _ => None,
+    }
+}
+
+// Picks a seed for `--shuffle` when none was given on the command line. This
+// doesn't need to be high quality, it only needs to differ between runs.
+fn random_seed() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::new(0, 0));
+    since_epoch.as_secs() ^ ((since_epoch.subsec_nanos() as u64) << 32)
+}
+
 #[derive(Clone, PartialEq)]
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary,
@@ -608,6 +695,13 @@ impl<T: Write> ConsoleTestState<T> {
         }
     }
 
+    pub fn write_log_line(&mut self, s: &str) -> io::Result<()> {
+        match self.log_out {
+            None => Ok(()),
+            Some(ref mut o) => o.write_all(s.as_bytes()),
+        }
+    }
+
     pub fn write_failures(&mut self) -> io::Result<()> {
         self.write_plain("\nfailures:\n")?;
         let mut failures = Vec::new();
@@ -700,8 +794,52 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
     output
 }
 
+// List the tests to console, and optionally to logfile. Filters are honored.
+pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<()> {
+    let mut st = ConsoleTestState::new(opts, None::<io::Stdout>)?;
+
+    let mut ntest = 0;
+    let mut nbench = 0;
+
+    for test in filter_tests(opts, tests) {
+        let fntype = match test.testfn {
+            StaticTestFn(..) | DynTestFn(..) => {
+                ntest += 1;
+                "test"
+            }
+            StaticBenchFn(..) | DynBenchFn(..) | StaticMetricFn(..) | DynMetricFn(..) => {
+                nbench += 1;
+                "bench"
+            }
+        };
+
+        st.write_plain(&format!("{}: {}\n", test.desc.name, fntype))?;
+        st.write_log_line(&format!("{} {}\n", fntype, test.desc.name))?;
+    }
+
+    fn plural(count: u32, s: &str) -> String {
+        match count {
+            1 => format!("{} {}", 1, s),
+            n => format!("{} {}s", n, s),
+        }
+    }
+
+    if !opts.quiet {
+        if ntest != 0 || nbench != 0 {
+            st.write_plain("\n")?;
+        }
+        st.write_plain(&format!("{}, {}\n", plural(ntest, "test"), plural(nbench, "benchmark")))?;
+    }
+
+    Ok(())
+}
+
 // A simple console test runner
 pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Result<bool> {
+    if opts.list {
+        list_tests_console(opts, tests)?;
+        return Ok(true);
+    }
 
     fn callback<T: Write>(event: &TestEvent, st: &mut ConsoleTestState<T>) -> io::Result<()> {
         match (*event).clone() {
@@ -739,6 +877,11 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
     }
 
     let mut st = ConsoleTestState::new(opts, None::<io::Stdout>)?;
+    if let Some(seed) = opts.shuffle_seed {
+        st.write_plain(&format!("shuffling tests with seed {} (replay with --shuffle-seed {})\n",
+                                seed,
+                                seed))?;
+    }
     fn len_if_padded(t: &TestDescAndFn) -> usize {
         match t.testfn.padding() {
             PadNone => 0,
@@ -840,6 +983,9 @@ fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F) ->
     where F: FnMut(TestEvent) -> io::Result<()>
 {
     let mut filtered_tests = filter_tests(opts, tests);
+    if let Some(seed) = opts.shuffle_seed {
+        shuffle_tests(seed, &mut filtered_tests);
+    }
     if !opts.bench_benchmarks {
         filtered_tests = convert_benchmarks_to_tests(filtered_tests);
     }
@@ -904,7 +1050,7 @@ fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F) ->
 
 #[allow(deprecated)]
 fn get_concurrency() -> usize {
-    return match env::var("RUST_TEST_THREADS") {
+    match env::var("RUST_TEST_THREADS") {
         Ok(s) => {
             let opt_n: Option<usize> = s.parse().ok();
             match opt_n {
@@ -915,108 +1061,37 @@ fn get_concurrency() -> usize {
                 }
             }
         }
-        Err(..) => num_cpus(),
-    };
-
-    #[cfg(windows)]
-    #[allow(bad_style)]
-    fn num_cpus() -> usize {
-        #[repr(C)]
-        struct SYSTEM_INFO {
-            wProcessorArchitecture: u16,
-            wReserved: u16,
-            dwPageSize: u32,
-            lpMinimumApplicationAddress: *mut u8,
-            lpMaximumApplicationAddress: *mut u8,
-            dwActiveProcessorMask: *mut u8,
-            dwNumberOfProcessors: u32,
-            dwProcessorType: u32,
-            dwAllocationGranularity: u32,
-            wProcessorLevel: u16,
-            wProcessorRevision: u16,
-        }
-        extern "system" {
-            fn GetSystemInfo(info: *mut SYSTEM_INFO) -> i32;
-        }
-        unsafe {
-            let mut sysinfo = std::mem::zeroed();
-            GetSystemInfo(&mut sysinfo);
-            sysinfo.dwNumberOfProcessors as usize
-        }
-    }
-
-    #[cfg(any(target_os = "linux",
-              target_os = "macos",
-              target_os = "ios",
-              target_os = "android",
-              target_os = "solaris",
-              target_os = "emscripten"))]
-    fn num_cpus() -> usize {
-        unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) as usize }
-    }
-
-    #[cfg(any(target_os = "freebsd",
-              target_os = "dragonfly",
-              target_os = "bitrig",
-              target_os = "netbsd"))]
-    fn num_cpus() -> usize {
-        let mut cpus: libc::c_uint = 0;
-        let mut cpus_size = std::mem::size_of_val(&cpus);
-
-        unsafe {
-            cpus = libc::sysconf(libc::_SC_NPROCESSORS_ONLN) as libc::c_uint;
-        }
-        if cpus < 1 {
-            let mut mib = [libc::CTL_HW, libc::HW_NCPU, 0, 0];
-            unsafe {
-                libc::sysctl(mib.as_mut_ptr(),
-                             2,
-                             &mut cpus as *mut _ as *mut _,
-                             &mut cpus_size as *mut _ as *mut _,
-                             0 as *mut _,
-                             0);
-            }
-            if cpus < 1 {
-                cpus = 1;
-            }
-        }
-        cpus as usize
-    }
-
-    #[cfg(target_os = "openbsd")]
-    fn num_cpus() -> usize {
-        let mut cpus: libc::c_uint = 0;
-        let mut cpus_size = std::mem::size_of_val(&cpus);
-        let mut mib = [libc::CTL_HW, libc::HW_NCPU, 0, 0];
-
-        unsafe {
-            libc::sysctl(mib.as_mut_ptr(),
-                         2,
-                         &mut cpus as *mut _ as *mut _,
-                         &mut cpus_size as *mut _ as *mut _,
-                         0 as *mut _,
-                         0);
-        }
-        if cpus < 1 {
-            cpus = 1;
-        }
-        cpus as usize
+        Err(..) => thread::available_parallelism().unwrap_or(1),
     }
 }
 
 pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     let mut filtered = tests;
 
+    let matches_filter = |test: &TestDescAndFn, filter: &str| {
+        let name = test.desc.name.as_slice();
+        if opts.filter_exact {
+            name == filter
+        } else {
+            name.contains(filter)
+        }
+    };
+
     // Remove tests that don't match the test filter
     filtered = match opts.filter {
         None => filtered,
         Some(ref filter) => {
             filtered.into_iter()
-                    .filter(|test| test.desc.name.as_slice().contains(&filter[..]))
+                    .filter(|test| matches_filter(test, filter))
                     .collect()
         }
     };
 
+    // Remove tests that match any of the skip filters
+    filtered = filtered.into_iter()
+                       .filter(|test| !opts.skip.iter().any(|sf| matches_filter(test, sf)))
+                       .collect();
+
     // Maybe pull out the ignored test and unignore them
     filtered = if !opts.run_ignored {
         filtered
@@ -1038,9 +1113,42 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
     // Sort the tests alphabetically
     filtered.sort_by(|t1, t2| t1.desc.name.as_slice().cmp(t2.desc.name.as_slice()));
 
+    // Keep only this machine's share of the tests. This happens after sorting
+    // so that every shard sees the same order and the shards are disjoint.
+    filtered = match opts.shard {
+        None => filtered,
+        Some((index, total)) => {
+            filtered.into_iter()
+                    .enumerate()
+                    .filter(|&(i, _)| i % total == index - 1)
+                    .map(|(_, test)| test)
+                    .collect()
+        }
+    };
+
     filtered
 }
 
+/// Shuffles `tests` into an order determined entirely by `seed`, so that a
+/// run with `--shuffle-seed` reproduces the order of an earlier `--shuffle`.
+pub fn shuffle_tests(seed: u64, tests: &mut [TestDescAndFn]) {
+    // A splitmix64 generator: tiny, and good enough to pick a permutation.
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+
+    // Fisher-Yates
+    for i in (1..tests.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        tests.swap(i, j);
+    }
+}
+
 pub fn convert_benchmarks_to_tests(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     // convert benchmarks to tests, if we're not benchmarking them
     tests.into_iter()
@@ -1354,8 +1462,9 @@ pub mod bench {
 
 #[cfg(test)]
 mod tests {
-    use test::{TrFailed, TrIgnored, TrOk, filter_tests, parse_opts, TestDesc, TestDescAndFn,
-               TestOpts, run_test, MetricMap, StaticTestName, DynTestName, DynTestFn, ShouldPanic};
+    use test::{TrFailed, TrIgnored, TrOk, filter_tests, shuffle_tests, parse_opts, TestDesc,
+               TestDescAndFn, TestOpts, run_test, MetricMap, StaticTestName, DynTestName,
+               DynTestFn, ShouldPanic};
     use std::sync::mpsc::channel;
 
     #[test]
@@ -1510,6 +1619,103 @@ mod tests {
         assert!(!filtered[0].desc.ignore);
     }
 
+    fn named_tests(names: &[&'static str]) -> Vec<TestDescAndFn> {
+        names.iter()
+             .map(|name| {
+                 TestDescAndFn {
+                     desc: TestDesc {
+                         name: StaticTestName(*name),
+                         ignore: false,
+                         should_panic: ShouldPanic::No,
+                     },
+                     testfn: DynTestFn(Box::new(move || {})),
+                 }
+             })
+             .collect()
+    }
+
+    fn names_of(tests: &[TestDescAndFn]) -> Vec<String> {
+        tests.iter().map(|t| t.desc.name.to_string()).collect()
+    }
+
+    #[test]
+    pub fn exact_filter_and_skip() {
+        let names = ["base", "base::test", "base::test1", "base::test2"];
+
+        let mut opts = TestOpts::new();
+        opts.filter = Some("base::test".to_string());
+        assert_eq!(filter_tests(&opts, named_tests(&names)).len(), 3);
+
+        opts.filter_exact = true;
+        assert_eq!(names_of(&filter_tests(&opts, named_tests(&names))),
+                   vec!["base::test".to_string()]);
+
+        let mut opts = TestOpts::new();
+        opts.skip = vec!["test1".to_string(), "test2".to_string()];
+        assert_eq!(names_of(&filter_tests(&opts, named_tests(&names))),
+                   vec!["base".to_string(), "base::test".to_string()]);
+
+        opts.filter_exact = true;
+        opts.skip = vec!["test1".to_string(), "base::test2".to_string()];
+        assert_eq!(filter_tests(&opts, named_tests(&names)).len(), 3);
+    }
+
+    #[test]
+    pub fn parse_shard_option() {
+        let parse = |shard: &str| {
+            let args = vec!["progname".to_string(), "--shard".to_string(), shard.to_string()];
+            match parse_opts(&args) {
+                Some(Ok(o)) => Some(o.shard),
+                Some(Err(_)) => None,
+                None => panic!("unexpected help"),
+            }
+        };
+        assert_eq!(parse("1/1"), Some(Some((1, 1))));
+        assert_eq!(parse("3/8"), Some(Some((3, 8))));
+        assert_eq!(parse("0/8"), None);
+        assert_eq!(parse("9/8"), None);
+        assert_eq!(parse("3"), None);
+        assert_eq!(parse("a/b"), None);
+    }
+
+    #[test]
+    pub fn shards_are_disjoint_and_complete() {
+        let names = ["a", "b", "c", "d", "e", "f", "g"];
+        let mut seen = Vec::new();
+        for i in 1..4 {
+            let mut opts = TestOpts::new();
+            opts.shard = Some((i, 3));
+            let shard = names_of(&filter_tests(&opts, named_tests(&names)));
+            assert!(shard.len() == 2 || shard.len() == 3);
+            seen.extend(shard);
+        }
+        seen.sort();
+        let expected: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    pub fn shuffle_is_replayable() {
+        let names = ["a", "b", "c", "d", "e", "f", "g", "h"];
+
+        let mut first = named_tests(&names);
+        shuffle_tests(1234, &mut first);
+        let mut second = named_tests(&names);
+        shuffle_tests(1234, &mut second);
+        assert_eq!(names_of(&first), names_of(&second));
+
+        let mut sorted = names_of(&first);
+        sorted.sort();
+        assert_eq!(sorted, names_of(&named_tests(&names)));
+
+        let args = vec!["progname".to_string(), "--shuffle-seed".to_string(), "42".to_string()];
+        let opts = match parse_opts(&args) {
+            Some(Ok(o)) => o,
+            _ => panic!("Malformed arg in shuffle_is_replayable"),
+        };
+        assert_eq!(opts.shuffle_seed, Some(42));
+    }
+
     #[test]
     pub fn sort_tests() {
         let mut opts = TestOpts::new();
@@ -20,5 +20,6 @@ extern crate b; //~ ERROR: found possibly newer version of crate `a` which `b` d
 //~| NOTE: perhaps this crate needs to be recompiled
 //~| NOTE: crate `a` path #1:
 //~| NOTE: crate `b` path #1:
+//~| HELP: `b` was compiled against a different version of `a` than the one already loaded
 
 fn main() {}
@@ -0,0 +1,37 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// The destructors of `Vec` and `BTreeMap` are blind to what their parameters
+// borrow, but free memory through their allocator, so the allocator may not
+// borrow anything that could die before the collection.
+
+#![feature(allocator_api)]
+
+use std::collections::BTreeMap;
+use std::heap::Heap;
+
+fn vec() {
+    let mut v;
+    let mut heap = Heap;
+    v = Vec::new_in(&mut heap); //~ ERROR does not live long enough
+    v.push(String::new());
+}
+
+fn btree_map() {
+    let mut m;
+    let mut heap = Heap;
+    m = BTreeMap::new_in(&mut heap); //~ ERROR does not live long enough
+    m.insert(0, String::new());
+}
+
+fn main() {
+    vec();
+    btree_map();
+}
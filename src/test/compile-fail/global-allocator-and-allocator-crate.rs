@@ -0,0 +1,23 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// no-prefer-dynamic
+// error-pattern: the `#[global_allocator]` in this crate conflicts with the allocator crate
+
+#![feature(alloc_system)]
+
+extern crate alloc_system;
+
+use std::heap::System;
+
+#[global_allocator]
+static GLOBAL: System = System;
+
+fn main() {}
@@ -0,0 +1,35 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![allow(dead_code)]
+
+pub fn x() -> u32 {
+    22
+}
+
+#[cfg(rpass1)]
+pub fn y() -> u32 {
+    1
+}
+
+#[cfg(rpass2)]
+pub fn y() -> u32 {
+    2
+}
+
+#[cfg(rpass1)]
+fn private_helper() -> u32 {
+    1
+}
+
+#[cfg(rpass2)]
+fn private_helper() -> u32 {
+    2
+}
@@ -0,0 +1,44 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:extern_crate_items_aux.rs
+// revisions: rpass1 rpass2
+
+#![allow(warnings)]
+#![feature(rustc_attrs)]
+
+// Here the upstream crate changes the body of its public fn `y` and of a
+// private fn. Only the items which use `y` have to be type-checked again;
+// the hashes in the metadata of the upstream crate tell us that `x` is
+// unchanged.
+
+extern crate extern_crate_items_aux as aux;
+
+fn main() {
+    assert_eq!(x::use_x(), 22);
+    #[cfg(rpass1)]
+    assert_eq!(y::use_y(), 1);
+    #[cfg(rpass2)]
+    assert_eq!(y::use_y(), 2);
+}
+
+mod x {
+    #[rustc_clean(label="TypeckItemBody", cfg="rpass2")]
+    pub fn use_x() -> u32 {
+        ::aux::x()
+    }
+}
+
+mod y {
+    #[rustc_dirty(label="TypeckItemBody", cfg="rpass2")]
+    pub fn use_y() -> u32 {
+        ::aux::y()
+    }
+}
@@ -0,0 +1,60 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Check that the object files of codegen units are only re-used as long as
+// the code translated into them is unchanged.
+
+// revisions: rpass1 rpass2 rpass3
+// compile-flags: -C codegen-units=4
+
+// Four codegen units give the crate root and each module its own.
+
+#![allow(warnings)]
+#![feature(rustc_attrs)]
+
+fn main() {
+    #[cfg(any(rpass1, rpass3))]
+    assert_eq!(x::x(), 1);
+    #[cfg(rpass2)]
+    assert_eq!(x::x(), 2);
+
+    assert_eq!(y::y(), 3);
+    assert_eq!(z::z(), 4);
+}
+
+#[rustc_partition_translated(cfg="rpass2")]
+#[rustc_partition_translated(cfg="rpass3")]
+mod x {
+    #[cfg(any(rpass1, rpass3))]
+    pub fn x() -> i32 {
+        1
+    }
+
+    #[cfg(rpass2)]
+    pub fn x() -> i32 {
+        2
+    }
+}
+
+#[rustc_partition_reused(cfg="rpass2")]
+#[rustc_partition_reused(cfg="rpass3")]
+mod y {
+    pub fn y() -> i32 {
+        3
+    }
+}
+
+#[rustc_partition_reused(cfg="rpass2")]
+#[rustc_partition_reused(cfg="rpass3")]
+mod z {
+    pub fn z() -> i32 {
+        ::y::y() + 1
+    }
+}
@@ -262,7 +262,7 @@ fn compile_program(input: &str, sysroot: PathBuf)
                 .filter_map(|(_, p)| p).collect();
 
             assert_eq!(trans.modules.len(), 1);
-            let llmod = trans.modules[0].llmod;
+            let llmod = trans.modules[0].llvm.llmod;
 
             // Workaround because raw pointers do not impl Send
             let modp = llmod as usize;
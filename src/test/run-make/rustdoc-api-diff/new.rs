@@ -0,0 +1,43 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_name = "foo"]
+
+pub mod inner {
+    pub struct Thing;
+}
+
+// Renaming an argument doesn't change the API.
+pub fn frobnicate(input: u32) -> u32 {
+    input
+}
+
+pub trait Tr {
+    fn tr(&self);
+}
+
+impl Tr for *const u8 {
+    fn tr(&self) {}
+}
+
+// Reordering impls doesn't change the API.
+impl<T: Clone> Wrapper<T> {
+    pub fn cloned(&self) -> T {
+        self.0.clone()
+    }
+}
+
+pub struct Wrapper<T>(pub T);
+
+impl<T> Wrapper<T> {
+    pub fn get(self) -> T {
+        self.0
+    }
+}
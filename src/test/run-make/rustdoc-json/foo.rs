@@ -0,0 +1,23 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_name = "foo"]
+
+/// A trait for things that can be frobnicated.
+pub trait Frobnicate<T> where T: Clone {
+    /// Frobnicates its argument.
+    fn frobnicate(&self, t: T) -> T;
+}
+
+pub struct Widget;
+
+impl Frobnicate<u32> for Widget {
+    fn frobnicate(&self, t: u32) -> u32 { t + 1 }
+}
@@ -0,0 +1,80 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// no-prefer-dynamic
+
+// An instrumented global allocator that charges every allocation to the
+// subsystem that is currently running.
+
+#![feature(test)]
+
+extern crate test;
+
+use std::heap::{GlobalAlloc, System};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+
+const RUNTIME: usize = 0;
+const PARSER: usize = 1;
+const RENDERER: usize = 2;
+
+static CURRENT: AtomicUsize = ATOMIC_USIZE_INIT;
+static ALLOCS: [AtomicUsize; 3] = [ATOMIC_USIZE_INIT, ATOMIC_USIZE_INIT, ATOMIC_USIZE_INIT];
+static BYTES: [AtomicUsize; 3] = [ATOMIC_USIZE_INIT, ATOMIC_USIZE_INIT, ATOMIC_USIZE_INIT];
+
+struct PerSubsystem;
+
+unsafe impl GlobalAlloc for PerSubsystem {
+    unsafe fn alloc(&self, size: usize, align: usize) -> *mut u8 {
+        let current = CURRENT.load(Ordering::SeqCst);
+        ALLOCS[current].fetch_add(1, Ordering::SeqCst);
+        BYTES[current].fetch_add(size, Ordering::SeqCst);
+        System.alloc(size, align)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, size: usize, align: usize) {
+        System.dealloc(ptr, size, align)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PerSubsystem = PerSubsystem;
+
+fn allocs(subsystem: usize) -> usize {
+    ALLOCS[subsystem].load(Ordering::SeqCst)
+}
+
+fn bytes(subsystem: usize) -> usize {
+    BYTES[subsystem].load(Ordering::SeqCst)
+}
+
+fn main() {
+    assert_eq!(allocs(PARSER), 0);
+    assert_eq!(allocs(RENDERER), 0);
+
+    CURRENT.store(PARSER, Ordering::SeqCst);
+    let mut v: Vec<u64> = Vec::with_capacity(16);
+    test::black_box(&mut v);
+    CURRENT.store(RENDERER, Ordering::SeqCst);
+    let mut b = Box::new([0u8; 100]);
+    test::black_box(&mut b);
+    CURRENT.store(RUNTIME, Ordering::SeqCst);
+
+    assert_eq!(allocs(PARSER), 1);
+    assert_eq!(bytes(PARSER), 16 * 8);
+    assert_eq!(allocs(RENDERER), 1);
+    assert_eq!(bytes(RENDERER), 100);
+
+    // Growing goes through the default `realloc`, which allocates anew.
+    CURRENT.store(PARSER, Ordering::SeqCst);
+    v.reserve(32);
+    CURRENT.store(RUNTIME, Ordering::SeqCst);
+    assert_eq!(allocs(PARSER), 2);
+    assert!(v.capacity() >= 32);
+}
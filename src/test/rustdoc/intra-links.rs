@@ -0,0 +1,58 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_name = "foo"]
+
+// @has foo/index.html '//a[@href="../foo/struct.ThisType.html"]' 'ThisType'
+// @has - '//a[@href="../foo/struct.ThisType.html#method.this_method"]' 'ThisType::this_method'
+// @has - '//a[@href="../foo/enum.ThisEnum.html#variant.ThisVariant"]' 'ThisEnum::ThisVariant'
+// @has - '//a[@href="../foo/fn.this_function.html"]' 'this_function()'
+// @has - '//a[@href="../foo/nested/struct.Nested.html"]' 'nested::Nested'
+// @has - '//a[@href="../foo/macro.this_macro!.html"]' 'this_macro!'
+// @has - '//a[@href="http://example.com"]' 'explicit'
+// @has - '//a[@href="https://doc.rust-lang.org/nightly/core/option/enum.Option.html"]' 'Option'
+//! Links to [`ThisType`], [`ThisType::this_method`], [`ThisEnum::ThisVariant`],
+//! [`this_function()`], [nested::Nested] and [`this_macro!`], but [explicit]
+//! links are kept as they are. Names from the prelude such as [`Option`] can
+//! be linked to as well.
+//!
+//! [explicit]: http://example.com
+
+#[macro_export]
+macro_rules! this_macro {
+    () => {};
+}
+
+pub struct ThisType;
+
+impl ThisType {
+    pub fn this_method(&self) {}
+}
+
+pub enum ThisEnum {
+    ThisVariant,
+}
+
+pub fn this_function() {}
+
+pub mod nested {
+    use ThisType;
+    use super::this_function as renamed;
+
+    // @has foo/nested/struct.Nested.html '//a[@href="../../foo/struct.ThisType.html"]' 'ThisType'
+    // @has - '//a[@href="../../foo/fn.this_function.html"]' 'renamed()'
+    // @has - '//a[@href="../../foo/fn.this_function.html"]' 'foo::this_function'
+    // @has - '//a[@href="../../foo/macro.this_macro!.html"]' 'this_macro!'
+    // @!has - '//a' 'ThisEnum'
+    /// Links back to the crate root through the imports of [`ThisType`] and
+    /// [`renamed()`], and through [`foo::this_function`] and [`this_macro!`].
+    /// [`ThisEnum`] isn't in scope here.
+    pub struct Nested;
+}
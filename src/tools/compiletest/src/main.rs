@@ -15,6 +15,7 @@
 #![feature(test)]
 #![feature(question_mark)]
 #![feature(libc)]
+#![feature(fs_walk_dir)]
 
 #![deny(warnings)]
 
@@ -287,7 +288,12 @@ pub fn run_tests(config: &Config) {
 
 pub fn test_opts(config: &Config) -> test::TestOpts {
     test::TestOpts {
+        list: false,
         filter: config.filter.clone(),
+        filter_exact: false,
+        skip: vec![],
+        shard: None,
+        shuffle_seed: None,
         run_ignored: config.run_ignored,
         quiet: config.quiet,
         logfile: config.logfile.clone(),
@@ -305,66 +311,36 @@ pub fn make_tests(config: &Config) -> Vec<test::TestDescAndFn> {
     debug!("making tests from {:?}",
            config.src_base.display());
     let mut tests = Vec::new();
-    collect_tests_from_dir(config,
-                           &config.src_base,
-                           &config.src_base,
-                           &PathBuf::new(),
-                           &mut tests)
-        .unwrap();
+    collect_tests_from_dir(config, &config.src_base, &mut tests).unwrap();
     tests
 }
 
 fn collect_tests_from_dir(config: &Config,
                           base: &Path,
-                          dir: &Path,
-                          relative_dir_path: &Path,
                           tests: &mut Vec<test::TestDescAndFn>)
                           -> io::Result<()> {
-    // Ignore directories that contain a file
-    // `compiletest-ignore-dir`.
-    for file in fs::read_dir(dir)? {
-        let file = file?;
-        let name = file.file_name();
-        if name == *"compiletest-ignore-dir" {
-            return Ok(());
-        }
-        if name == *"Makefile" && config.mode == Mode::RunMake {
-            let paths = TestPaths {
-                file: dir.to_path_buf(),
-                base: base.to_path_buf(),
-                relative_dir: relative_dir_path.parent().unwrap().to_path_buf(),
-            };
-            tests.push(make_test(config, &paths));
-            return Ok(())
-        }
+    if !enter_dir(config, base, base, Path::new(""), tests) {
+        return Ok(())
     }
 
-    // If we find a test foo/bar.rs, we have to build the
-    // output directory `$build/foo` so we can write
-    // `$build/foo/bar` into it. We do this *now* in this
-    // sequential loop because otherwise, if we do it in the
-    // tests themselves, they race for the privilege of
-    // creating the directories and sometimes fail randomly.
-    let build_dir = config.build_base.join(&relative_dir_path);
-    fs::create_dir_all(&build_dir).unwrap();
-
-    // Add each `.rs` file as a test, and recurse further on any
-    // subdirectories we find, except for `aux` directories.
-    let dirs = fs::read_dir(dir)?;
-    for file in dirs {
+    // Add each `.rs` file as a test, and search any subdirectories we find,
+    // except for `aux` directories.
+    let mut walk = fs::WalkOptions::new().follow_links(true).walk(base)?;
+    while let Some(file) = walk.next() {
         let file = file?;
         let file_path = file.path();
         let file_name = file.file_name();
+        let relative_file_path = file_path.strip_prefix(base).unwrap().to_path_buf();
         if is_test(&file_name) {
             debug!("found test file: {:?}", file_path.display());
             let paths = TestPaths {
                 file: file_path,
                 base: base.to_path_buf(),
-                relative_dir: relative_dir_path.to_path_buf(),
+                relative_dir: relative_file_path.parent().unwrap().to_path_buf(),
             };
-            tests.push(make_test(config, &paths))
+            tests.push(make_test(config, &paths));
+            walk.skip_dir();
         } else if file_path.is_dir() {
-            let relative_file_path = relative_dir_path.join(file.file_name());
             if &file_name == "auxiliary" {
                 // `aux` directories contain other crates used for
                 // cross-crate tests. Don't search them for tests, but
@@ -373,13 +349,12 @@ fn collect_tests_from_dir(config: &Config,
                 // sometimes.
                 let build_dir = config.build_base.join(&relative_file_path);
                 fs::create_dir_all(&build_dir).unwrap();
+                walk.skip_dir();
             } else {
                 debug!("found directory: {:?}", file_path.display());
-                collect_tests_from_dir(config,
-                                       base,
-                                       &file_path,
-                                       &relative_file_path,
-                                       tests)?;
+                if !enter_dir(config, base, &file_path, &relative_file_path, tests) {
+                    walk.skip_dir();
+                }
             }
         } else {
             debug!("found other file/directory: {:?}", file_path.display());
@@ -388,6 +363,39 @@ fn collect_tests_from_dir(config: &Config,
     Ok(())
 }
 
+// Returns whether the tests in `dir` should be searched for: not if it
+// contains a file `compiletest-ignore-dir`, nor if it is a run-make test
+// itself, which is then added.
+fn enter_dir(config: &Config,
+             base: &Path,
+             dir: &Path,
+             relative_dir_path: &Path,
+             tests: &mut Vec<test::TestDescAndFn>)
+             -> bool {
+    if dir.join("compiletest-ignore-dir").exists() {
+        return false
+    }
+    if config.mode == Mode::RunMake && dir.join("Makefile").exists() {
+        let paths = TestPaths {
+            file: dir.to_path_buf(),
+            base: base.to_path_buf(),
+            relative_dir: relative_dir_path.parent().unwrap().to_path_buf(),
+        };
+        tests.push(make_test(config, &paths));
+        return false
+    }
+
+    // If we find a test foo/bar.rs, we have to build the
+    // output directory `$build/foo` so we can write
+    // `$build/foo/bar` into it. We do this *now* in this
+    // sequential loop because otherwise, if we do it in the
+    // tests themselves, they race for the privilege of
+    // creating the directories and sometimes fail randomly.
+    let build_dir = config.build_base.join(relative_dir_path);
+    fs::create_dir_all(&build_dir).unwrap();
+    true
+}
+
 pub fn is_test(file_name: &OsString) -> bool {
     let file_name = file_name.to_str().unwrap();
 
@@ -1183,7 +1183,14 @@ actual:\n\
 
         for rel_ab in &self.props.aux_builds {
             let aux_testpaths = self.compute_aux_test_paths(rel_ab);
-            let aux_props = TestProps::from_file(&aux_testpaths.file);
+            let mut aux_props = TestProps::from_file(&aux_testpaths.file);
+            // Auxiliary crates of incremental tests are rebuilt for every
+            // revision of the test, so they can be configured by revision
+            // as well.
+            if let (Incremental, Some(revision)) = (self.config.mode, self.revision) {
+                aux_props.compile_flags.extend(vec![format!("--cfg"),
+                                                    format!("{}", revision)]);
+            }
             let mut crate_type = if aux_props.no_prefer_dynamic {
                 Vec::new()
             } else {